@@ -0,0 +1,11 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|page: &str| {
+    let _ = furaffinity_rs::gallery::parse_listing(page);
+    let _ = furaffinity_rs::journal::parse_journals(page);
+    let _ = furaffinity_rs::parse_front_page(page);
+    let _ = furaffinity_rs::profile::parse_profile("example", page);
+    let _ = furaffinity_rs::journal::parse_journal(furaffinity_rs::JournalId(1), page);
+});
@@ -0,0 +1,10 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|page: &str| {
+    if let Ok(Some(sub)) = furaffinity_rs::parse_submission(furaffinity_rs::SubmissionId(1), page) {
+        let _ = sub.nav_links();
+    }
+    let _ = furaffinity_rs::comment::parse_comments(page);
+});
@@ -0,0 +1,9 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|link: &str| {
+    if let Some(url) = furaffinity_rs::url::FaUrl::parse(link) {
+        let _ = url.canonical();
+    }
+});
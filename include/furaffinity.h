@@ -0,0 +1,33 @@
+/* C interface to furaffinity-rs, built with the `ffi` feature. */
+
+#ifndef FURAFFINITY_H
+#define FURAFFINITY_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* Message of the last error on this thread, or NULL. Owned by the library. */
+const char *fa_last_error(void);
+
+/* Release a string returned by the library. */
+void fa_string_free(char *s);
+
+/* Parse a submission page into JSON. Returns 1 and sets `out` if parsed, 0 if
+ * the submission does not exist, or -1 on error. */
+int fa_parse_submission(uint64_t id, const char *page, char **out);
+
+/* Perceptual hash of an image. Returns 0 and sets `out`, or -1 on error. */
+int fa_hash_image(const uint8_t *data, size_t len, int64_t *out);
+
+/* Number of bits that differ between two perceptual hashes. */
+uint32_t fa_hash_distance(int64_t a, int64_t b);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif
@@ -0,0 +1,199 @@
+//! Finding the links and forms used to change things on FurAffinity, such as
+//! favoriting a submission or watching a user.
+//!
+//! FurAffinity adds a key tied to the session to each of these, so they must
+//! come from a page loaded with the same cookies.
+
+use crate::Error;
+use lazy_static::lazy_static;
+use scraper::Selector;
+
+lazy_static! {
+    static ref FAV: Selector = Selector::parse(r#"a[href^="/fav/"]"#).unwrap();
+    static ref UNFAV: Selector = Selector::parse(r#"a[href^="/unfav/"]"#).unwrap();
+    static ref WATCH: Selector = Selector::parse(r#"a[href^="/watch/"]"#).unwrap();
+    static ref UNWATCH: Selector = Selector::parse(r#"a[href^="/unwatch/"]"#).unwrap();
+    static ref COMMENT_FORM: Selector =
+        Selector::parse(r#"form#add_comment_form, form[name="reply_form"]"#).unwrap();
+    static ref HIDDEN_INPUT: Selector = Selector::parse(r#"input[type="hidden"][name]"#).unwrap();
+    static ref TEXTAREA: Selector = Selector::parse("textarea[name]").unwrap();
+}
+
+/// Links on a submission or profile for changing if it is favorited or
+/// watched. Only the links for the current state are shown, so a submission
+/// that was already favorited only has an unfavorite link.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct ActionLinks {
+    pub fav: Option<String>,
+    pub unfav: Option<String>,
+    pub watch: Option<String>,
+    pub unwatch: Option<String>,
+}
+
+/// The form for commenting on a submission, with the hidden fields it must
+/// be sent with.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct CommentForm {
+    /// Where the form is sent, as it appears in the page.
+    pub action: String,
+    pub fields: Vec<(String, String)>,
+    /// Name of the field for the comment's text.
+    pub text_field: String,
+}
+
+impl CommentForm {
+    /// Fields to send to post a comment.
+    pub fn params(&self, text: &str) -> Vec<(String, String)> {
+        let mut params = self.fields.clone();
+        params.push((self.text_field.clone(), text.to_string()));
+
+        params
+    }
+}
+
+/// Parse the favorite and watch links on a page.
+pub fn parse_action_links(page: &str) -> ActionLinks {
+    let document = scraper::Html::parse_document(page);
+
+    let link = |selector: &Selector| {
+        document
+            .select(selector)
+            .find_map(|link| link.value().attr("href"))
+            .map(str::to_string)
+    };
+
+    ActionLinks {
+        fav: link(&FAV),
+        unfav: link(&UNFAV),
+        watch: link(&WATCH),
+        unwatch: link(&UNWATCH),
+    }
+}
+
+/// Parse the form for commenting on a submission.
+pub fn parse_comment_form(page: &str) -> Result<CommentForm, Error> {
+    let document = scraper::Html::parse_document(page);
+
+    let form = document
+        .select(&COMMENT_FORM)
+        .next()
+        .ok_or_else(|| Error::parse("comment form"))?;
+
+    let action = form
+        .value()
+        .attr("action")
+        .filter(|action| !action.is_empty())
+        .ok_or_else(|| Error::parse("comment form action"))?
+        .to_string();
+
+    let fields = form
+        .select(&HIDDEN_INPUT)
+        .filter_map(|input| {
+            let value = input.value();
+            Some((
+                value.attr("name")?.to_string(),
+                value.attr("value").unwrap_or_default().to_string(),
+            ))
+        })
+        .collect();
+
+    let text_field = form
+        .select(&TEXTAREA)
+        .find_map(|textarea| textarea.value().attr("name"))
+        .ok_or_else(|| Error::parse("comment text field"))?
+        .to_string();
+
+    Ok(CommentForm {
+        action,
+        fields,
+        text_field,
+    })
+}
+
+#[cfg(all(test, feature = "client"))]
+mod tests {
+    use super::*;
+    use crate::test_utils::{MockServer, PROFILE, SUBMISSION};
+    use crate::SubmissionId;
+
+    const LOGGED_IN: &str = r#"<a id="my-username" href="/user/user/">~user</a>"#;
+
+    const COMMENT_FORM: &str = r#"<form id="add_comment_form" action="/view/31209021/" method="post">
+        <input type="hidden" name="action" value="reply">
+        <input type="hidden" name="key" value="abc123">
+        <textarea name="reply" id="JSMessage"></textarea>
+        <input type="submit" value="Post Comment">
+    </form>"#;
+
+    #[tokio::test]
+    async fn test_actions() {
+        let server = MockServer::start().await.unwrap();
+        server.route(
+            "/view/31209021",
+            200,
+            SUBMISSION.replace(
+                "</body>",
+                &format!(
+                    r#"{}<a href="/fav/31209021/?key=abc123">+Fav</a>{}</body>"#,
+                    LOGGED_IN, COMMENT_FORM
+                ),
+            ),
+        );
+        server.route("/fav/31209021/?key=abc123", 200, SUBMISSION);
+        server.route("/view/31209021/", 200, SUBMISSION);
+        server.route(
+            "/user/deadrussiansoul/",
+            200,
+            PROFILE.replace(
+                "</body>",
+                &format!(
+                    r#"{}<a href="/unwatch/deadrussiansoul/?key=abc123">-Watch</a></body>"#,
+                    LOGGED_IN
+                ),
+            ),
+        );
+        server.route("/unwatch/deadrussiansoul/?key=abc123", 200, PROFILE);
+
+        let fa = server.client();
+
+        fa.fav_submission(SubmissionId(31209021)).await.unwrap();
+        assert_eq!(
+            server.requests().last().map(String::as_str),
+            Some("/fav/31209021/?key=abc123")
+        );
+
+        // Only links for the current state are shown, so there is nothing
+        // to do when already unfavorited or watched.
+        let requests = server.requests().len();
+        fa.unfav_submission(SubmissionId(31209021)).await.unwrap();
+        fa.watch_user("Dead_Russian_Soul").await.unwrap();
+        assert_eq!(server.requests().len(), requests + 2);
+
+        fa.unwatch_user("deadrussiansoul").await.unwrap();
+        assert_eq!(
+            server.requests().last().map(String::as_str),
+            Some("/unwatch/deadrussiansoul/?key=abc123")
+        );
+
+        assert_eq!(
+            parse_comment_form(COMMENT_FORM).unwrap().text_field,
+            "reply"
+        );
+        fa.post_comment(SubmissionId(31209021), "Lovely fox!")
+            .await
+            .unwrap();
+        assert!(server
+            .request_bodies()
+            .contains(&"action=reply&key=abc123&reply=Lovely+fox%21".to_string()));
+
+        server.route("/view/31209021", 200, SUBMISSION);
+        assert!(matches!(
+            fa.fav_submission(SubmissionId(31209021)).await.unwrap_err(),
+            Error::LoginRequired(_)
+        ));
+    }
+}
@@ -0,0 +1,83 @@
+//! Reporting parts of pages that did not parse as expected.
+//!
+//! Anomalies do not stop a page from being parsed, but a rising number of
+//! them can mean FurAffinity changed its pages.
+
+use std::sync::Arc;
+
+/// Something unexpected found while parsing a page.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Anomaly {
+    /// Name of the field, such as `ext` or `comment.posted_at`.
+    pub field: &'static str,
+    pub kind: AnomalyKind,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub enum AnomalyKind {
+    /// The usual markup was missing, so a fallback was used.
+    Fallback,
+    /// An optional field was missing.
+    Missing,
+    /// A field was present but could not be parsed.
+    Invalid,
+}
+
+impl Anomaly {
+    pub(crate) fn new(field: &'static str, kind: AnomalyKind) -> Self {
+        Self { field, kind }
+    }
+}
+
+/// Called with the URL of the page and each anomaly found on it.
+pub type AnomalyHook = Arc<dyn Fn(&str, &Anomaly) + Send + Sync>;
+
+#[cfg(all(test, feature = "client"))]
+mod tests {
+    use super::*;
+    use crate::test_utils::{MockServer, SUBMISSION};
+    use crate::{GetSubmissionOptions, SubmissionId};
+    use std::sync::Mutex;
+
+    #[tokio::test]
+    async fn test_anomaly_hook() {
+        let server = MockServer::start().await.unwrap();
+        server.route("/view/31209021", 200, SUBMISSION);
+        server.route(
+            "/view/31209022",
+            200,
+            SUBMISSION.replace(r#"style="width:97%""#, ""),
+        );
+
+        let reported: Arc<Mutex<Vec<(String, Anomaly)>>> = Default::default();
+        let fa = server.client().with_anomaly_hook({
+            let reported = reported.clone();
+            move |url, anomaly| {
+                reported
+                    .lock()
+                    .unwrap()
+                    .push((url.to_string(), anomaly.clone()))
+            }
+        });
+        let options = GetSubmissionOptions::new().with_comments(true);
+
+        fa.get_submission_with(SubmissionId(31209021), &options)
+            .await
+            .unwrap();
+        assert!(reported.lock().unwrap().is_empty());
+
+        fa.get_submission_with(SubmissionId(31209022), &options)
+            .await
+            .unwrap();
+        assert_eq!(
+            *reported.lock().unwrap(),
+            vec![(
+                format!("{}/view/31209022", server.url()),
+                Anomaly::new("comment.depth", AnomalyKind::Fallback)
+            )]
+        );
+    }
+}
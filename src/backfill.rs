@@ -0,0 +1,91 @@
+//! Loading historical submissions across a range of IDs.
+
+use crate::checkpoint::{Checkpoint, CheckpointState, FileCheckpoint};
+use crate::poller::Polled;
+use crate::{Error, FurAffinity, SubmissionId};
+use futures::Stream;
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// Walks every submission ID in a range, waiting between each request.
+///
+/// Progress can be saved to a checkpoint after each ID, so that a backfill
+/// can resume where it left off if it is interrupted.
+pub struct Backfill<'a> {
+    fa: &'a FurAffinity,
+    next_id: SubmissionId,
+    end_id: SubmissionId,
+    delay: Duration,
+    checkpoint: Option<Box<dyn Checkpoint + Send + 'a>>,
+}
+
+impl<'a> Backfill<'a> {
+    /// Create a new backfill for all IDs from `start_id` to `end_id`,
+    /// inclusive.
+    pub fn new(fa: &'a FurAffinity, start_id: SubmissionId, end_id: SubmissionId) -> Self {
+        Self {
+            fa,
+            next_id: start_id,
+            end_id,
+            delay: Duration::from_secs(1),
+            checkpoint: None,
+        }
+    }
+
+    /// How long to wait between loading each submission. Defaults to one
+    /// second.
+    pub fn with_delay(mut self, delay: Duration) -> Self {
+        self.delay = delay;
+        self
+    }
+
+    /// Save progress after each ID. If the checkpoint has a saved state, the
+    /// backfill resumes from it.
+    pub fn with_checkpoint<C: Checkpoint + Send + 'a>(mut self, checkpoint: C) -> Self {
+        self.checkpoint = Some(Box::new(checkpoint));
+        self
+    }
+
+    /// Save progress to a file after each ID, as with
+    /// [`Backfill::with_checkpoint`] and a [`FileCheckpoint`].
+    pub fn with_checkpoint_file<P: Into<PathBuf>>(self, path: P) -> Self {
+        self.with_checkpoint(FileCheckpoint::new(path))
+    }
+
+    /// Stream of the outcome of loading each ID. Errors are from reading or
+    /// saving the checkpoint.
+    pub fn submissions(self) -> impl Stream<Item = Result<Polled, Error>> + 'a {
+        futures::stream::unfold((self, false), |(mut backfill, started)| async move {
+            if !started {
+                if let Some(checkpoint) = &mut backfill.checkpoint {
+                    match checkpoint.load() {
+                        Ok(Some(state)) => backfill.next_id = state.next_id,
+                        Ok(None) => (),
+                        Err(err) => return Some((Err(err), (backfill, true))),
+                    }
+                }
+            } else {
+                tokio::time::sleep(backfill.delay).await;
+            }
+
+            if backfill.next_id > backfill.end_id {
+                return None;
+            }
+
+            let id = backfill.next_id;
+            let polled = Polled::load(backfill.fa, id).await;
+            backfill.next_id += 1;
+
+            let state = CheckpointState {
+                next_id: backfill.next_id,
+                ..Default::default()
+            };
+            let polled = match &mut backfill.checkpoint {
+                Some(checkpoint) => checkpoint.save(&state).map(|_| polled),
+                None => Ok(polled),
+            };
+
+            Some((polled, (backfill, true)))
+        })
+    }
+}
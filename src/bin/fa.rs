@@ -0,0 +1,130 @@
+//! Command line access to FurAffinity, built with the `cli` feature.
+//!
+//! Cookies and the user agent are read from the `FA_COOKIE_A`,
+//! `FA_COOKIE_B`, and `FA_USER_AGENT` environment variables, or can be given
+//! as options.
+
+use clap::{Parser, Subcommand};
+use furaffinity_rs::download::{Download, Downloader};
+use furaffinity_rs::hash::to_hex;
+use furaffinity_rs::poller::{Polled, Poller};
+use furaffinity_rs::{Error, FurAffinity, SubmissionId};
+use futures::StreamExt;
+use std::path::PathBuf;
+
+#[derive(Parser)]
+#[command(name = "fa", about = "Load submissions from FurAffinity")]
+struct Args {
+    #[arg(long, env = "FA_COOKIE_A", hide_env_values = true)]
+    cookie_a: String,
+    #[arg(long, env = "FA_COOKIE_B", hide_env_values = true)]
+    cookie_b: String,
+    #[arg(long, env = "FA_USER_AGENT", default_value = "furaffinity-rs")]
+    user_agent: String,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Print a submission as JSON.
+    Get { id: SubmissionId },
+    /// Download a submission's file and print its hashes.
+    Hash { id: SubmissionId },
+    /// Download every file in a user's gallery.
+    Gallery {
+        username: String,
+        /// Directory to save files in.
+        #[arg(long, default_value = ".")]
+        out: PathBuf,
+    },
+    /// Print new submissions as JSON Lines as they are uploaded.
+    Watch {
+        /// ID to start from, instead of the latest submission.
+        #[arg(long)]
+        start: Option<SubmissionId>,
+    },
+}
+
+#[tokio::main]
+async fn main() {
+    let args = Args::parse();
+    let fa = FurAffinity::new(args.cookie_a, args.cookie_b, args.user_agent, None);
+
+    if let Err(err) = run(&fa, args.command).await {
+        eprintln!("{}", err);
+        std::process::exit(1);
+    }
+}
+
+async fn run(fa: &FurAffinity, command: Command) -> Result<(), Error> {
+    match command {
+        Command::Get { id } => {
+            let sub = fa.get_submission(id).await?;
+            println!("{}", serde_json::to_string_pretty(&sub).unwrap());
+        }
+        Command::Hash { id } => {
+            let sub = match fa.get_submission(id).await? {
+                Some(sub) => fa.calc_image_hash(sub).await?,
+                None => return Err(Error::Deleted),
+            };
+
+            println!("hash\t{}", sub.hash.unwrap_or_default());
+            println!("hash_num\t{}", sub.hash_num.unwrap_or_default());
+            println!("sha256\t{}", to_hex(&sub.file_sha256.unwrap_or_default()));
+            println!("md5\t{}", to_hex(&sub.file_md5.unwrap_or_default()));
+        }
+        Command::Gallery { username, out } => {
+            let mut downloader = Downloader::open(fa, out).await?;
+
+            for page in 1.. {
+                let previews = fa.get_gallery(&username, page).await?;
+                if previews.is_empty() {
+                    break;
+                }
+
+                for preview in previews {
+                    let sub = match fa.get_submission(preview.id).await? {
+                        Some(sub) => sub,
+                        None => continue,
+                    };
+
+                    match downloader.download(&sub).await? {
+                        Download::Saved(path) => println!("saved {}", path.display()),
+                        Download::Duplicate(path) => println!("skipped {}", path.display()),
+                    }
+                }
+            }
+        }
+        Command::Watch { start } => {
+            let start = match start {
+                Some(start) => start,
+                None => fa.latest_id().await?.next(),
+            };
+
+            let subs = Poller::new(fa, start).submissions();
+            futures::pin_mut!(subs);
+
+            while let Some(polled) = subs.next().await {
+                let polled = match polled {
+                    Ok(polled) => polled,
+                    Err(err) => {
+                        eprintln!("polling failed: {}", err);
+                        continue;
+                    }
+                };
+
+                match polled {
+                    Polled::Submission(sub) => {
+                        println!("{}", serde_json::to_string(&sub).unwrap())
+                    }
+                    Polled::Missing(id) => eprintln!("submission {} is missing", id),
+                    Polled::Failed(id, err) => eprintln!("submission {} failed: {}", id, err),
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
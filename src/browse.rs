@@ -0,0 +1,205 @@
+//! Browsing the newest submissions with filters, at `/browse/`.
+//!
+//! Filter values come from the options on the browse page, which can be
+//! loaded with [`FurAffinity::get_taxonomy`](crate::FurAffinity::get_taxonomy).
+
+use crate::{Error, Rating};
+use lazy_static::lazy_static;
+use scraper::Selector;
+
+lazy_static! {
+    static ref NAV_LINK: Selector = Selector::parse(
+        ".navigation a[href], .navigation form[action], .pagination a[href], .pagination form[action]"
+    )
+    .unwrap();
+    static ref PAGE_NUMBER: regex::Regex =
+        regex::Regex::new(r"(?:/browse/(\d+)|[?&]page=(\d+))").unwrap();
+}
+
+/// Filters for browsing, and which page to load.
+///
+/// Filters that are not set include everything.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct BrowseOptions {
+    page: u32,
+    category: Option<String>,
+    kind: Option<String>,
+    species: Option<String>,
+    gender: Option<String>,
+    ratings: Vec<Rating>,
+}
+
+impl BrowseOptions {
+    pub fn new() -> Self {
+        Self {
+            page: 1,
+            ..Default::default()
+        }
+    }
+
+    /// Load another page, starting at 1.
+    pub fn with_page(mut self, page: u32) -> Self {
+        self.page = page.max(1);
+        self
+    }
+
+    /// Only include a category, by its value in
+    /// [`Taxonomy::categories`](crate::search::Taxonomy::categories).
+    pub fn with_category<T: Into<String>>(mut self, category: T) -> Self {
+        self.category = Some(category.into());
+        self
+    }
+
+    /// Only include a type, by its value in
+    /// [`Taxonomy::types`](crate::search::Taxonomy::types).
+    pub fn with_type<T: Into<String>>(mut self, kind: T) -> Self {
+        self.kind = Some(kind.into());
+        self
+    }
+
+    /// Only include a species, by its value in
+    /// [`Taxonomy::species`](crate::search::Taxonomy::species).
+    pub fn with_species<T: Into<String>>(mut self, species: T) -> Self {
+        self.species = Some(species.into());
+        self
+    }
+
+    /// Only include a gender, by its value in
+    /// [`Taxonomy::genders`](crate::search::Taxonomy::genders).
+    pub fn with_gender<T: Into<String>>(mut self, gender: T) -> Self {
+        self.gender = Some(gender.into());
+        self
+    }
+
+    /// Only include submissions with these ratings.
+    pub fn with_ratings(mut self, ratings: &[Rating]) -> Self {
+        self.ratings = ratings.to_vec();
+        self
+    }
+
+    pub fn page(&self) -> u32 {
+        self.page.max(1)
+    }
+
+    /// Query string parameters for the browse page.
+    pub fn params(&self) -> Vec<(String, String)> {
+        let mut params = vec![("page".to_string(), self.page().to_string())];
+
+        let filters = [
+            ("cat", &self.category),
+            ("atype", &self.kind),
+            ("species", &self.species),
+            ("gender", &self.gender),
+        ];
+        for (name, value) in filters.iter() {
+            if let Some(value) = value {
+                params.push((name.to_string(), value.clone()));
+            }
+        }
+
+        let ratings: &[Rating] = if self.ratings.is_empty() {
+            &[Rating::General, Rating::Mature, Rating::Adult]
+        } else {
+            &self.ratings
+        };
+        for rating in ratings {
+            let name = match rating {
+                Rating::General => "rating_general",
+                Rating::Mature => "rating_mature",
+                Rating::Adult => "rating_adult",
+            };
+            params.push((name.to_string(), "1".to_string()));
+        }
+
+        params
+    }
+}
+
+/// A page of browse results.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct BrowsePage {
+    pub submissions: Vec<crate::gallery::SubmissionPreview>,
+    /// If the page was loaded as a guest, so submissions may be missing.
+    pub guest: bool,
+    /// Number of the previous page, if the page links to one.
+    pub prev_page: Option<u32>,
+    /// Number of the next page, if the page links to one.
+    pub next_page: Option<u32>,
+}
+
+/// Parse a page of browse results, including the links to the pages around
+/// it.
+pub fn parse_browse(page: &str) -> Result<BrowsePage, Error> {
+    let listing = crate::gallery::parse_listing(page)?;
+    let document = scraper::Html::parse_document(page);
+
+    let mut prev_page = None;
+    let mut next_page = None;
+    for link in document.select(&NAV_LINK) {
+        let value = link.value();
+        let target = match value.attr("href").or_else(|| value.attr("action")) {
+            Some(target) => target,
+            None => continue,
+        };
+
+        let number = PAGE_NUMBER
+            .captures(target)
+            .and_then(|captures| captures.get(1).or_else(|| captures.get(2)))
+            .and_then(|number| number.as_str().parse().ok());
+
+        let text = crate::join_text_nodes(link).to_lowercase();
+        if text.contains("next") {
+            next_page = next_page.or(number);
+        } else if text.contains("back") || text.contains("prev") {
+            prev_page = prev_page.or(number);
+        }
+    }
+
+    Ok(BrowsePage {
+        submissions: listing.submissions,
+        guest: listing.guest,
+        prev_page,
+        next_page,
+    })
+}
+
+#[cfg(all(test, feature = "client"))]
+mod tests {
+    use super::*;
+    use crate::test_utils::{MockServer, GALLERY};
+
+    #[tokio::test]
+    async fn test_browse() {
+        let server = MockServer::start().await.unwrap();
+        let page = GALLERY.replace(
+            "</body>",
+            r#"<div class="navigation">
+                <form action="/browse/1/" method="get"><button type="submit">Back 48</button></form>
+                <form action="/browse/3/" method="get"><button type="submit">Next 48</button></form>
+            </div></body>"#,
+        );
+        server.route(
+            "/browse/?page=2&cat=2&species=6008&rating_general=1&rating_mature=1",
+            200,
+            page,
+        );
+
+        let options = BrowseOptions::new()
+            .with_page(2)
+            .with_category("2")
+            .with_species("6008")
+            .with_ratings(&[Rating::General, Rating::Mature]);
+        let page = server.client().browse(&options).await.unwrap();
+
+        assert_eq!(page.submissions.len(), 2);
+        assert_eq!(page.prev_page, Some(1));
+        assert_eq!(page.next_page, Some(3));
+
+        let last = parse_browse(GALLERY).unwrap();
+        assert_eq!(last.next_page, None);
+    }
+}
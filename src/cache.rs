@@ -0,0 +1,425 @@
+//! Caching pages, hashes, and progress, optionally shared between instances
+//! through Redis.
+
+use crate::hash::HashConfig;
+use crate::Error;
+use futures::future::BoxFuture;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// A key-value store for cached data.
+///
+/// Values may be evicted at any time, so everything stored must be possible
+/// to recreate.
+pub trait CacheBackend: Send + Sync {
+    /// Get a value, if it is cached and hasn't expired.
+    fn get<'a>(&'a self, key: &'a str) -> BoxFuture<'a, Result<Option<Vec<u8>>, Error>>;
+
+    /// Store a value, expiring after `ttl` if set.
+    fn set<'a>(
+        &'a self,
+        key: &'a str,
+        value: &'a [u8],
+        ttl: Option<Duration>,
+    ) -> BoxFuture<'a, Result<(), Error>>;
+
+    /// Remove a value.
+    fn delete<'a>(&'a self, key: &'a str) -> BoxFuture<'a, Result<(), Error>>;
+}
+
+/// Key for the cached contents of a page.
+pub fn page_key(url: &str) -> String {
+    format!("fa:page:{}", url)
+}
+
+/// Key for the perceptual hash of a file with a SHA-256 digest, calculated
+/// with a hash configuration.
+pub fn hash_key(config: &HashConfig, sha256: &[u8]) -> String {
+    format!(
+        "fa:hash:{}:{}",
+        config.fingerprint(),
+        crate::hash::to_hex(sha256)
+    )
+}
+
+/// Key for a page kept for conditional requests.
+pub fn validated_page_key(url: &str) -> String {
+    format!("fa:validated:{}", url)
+}
+
+/// Key for a saved checkpoint.
+pub fn checkpoint_key(name: &str) -> String {
+    format!("fa:checkpoint:{}", name)
+}
+
+/// Get the perceptual hash memoized for a file's SHA-256 digest.
+pub async fn get_hash(
+    cache: &dyn CacheBackend,
+    config: &HashConfig,
+    sha256: &[u8],
+) -> Result<Option<i64>, Error> {
+    use std::convert::TryInto;
+
+    let value = cache.get(&hash_key(config, sha256)).await?;
+
+    Ok(value
+        .and_then(|value| value.as_slice().try_into().ok())
+        .map(i64::from_be_bytes))
+}
+
+/// Memoize the perceptual hash of a file by its SHA-256 digest.
+pub async fn set_hash(
+    cache: &dyn CacheBackend,
+    config: &HashConfig,
+    sha256: &[u8],
+    hash: i64,
+) -> Result<(), Error> {
+    cache
+        .set(&hash_key(config, sha256), &hash.to_be_bytes(), None)
+        .await
+}
+
+/// A page with the `ETag` and `Last-Modified` headers it was sent with, so
+/// it can be loaded again only if it changed.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ValidatedPage {
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+    pub body: String,
+}
+
+impl ValidatedPage {
+    /// If the page can be validated, instead of always being loaded again.
+    pub fn has_validators(&self) -> bool {
+        self.etag.is_some() || self.last_modified.is_some()
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        // Header values can't contain newlines, so they separate the fields.
+        format!(
+            "{}\n{}\n{}",
+            self.etag.as_deref().unwrap_or_default(),
+            self.last_modified.as_deref().unwrap_or_default(),
+            self.body
+        )
+        .into_bytes()
+    }
+
+    fn from_bytes(value: &[u8]) -> Option<Self> {
+        let value = std::str::from_utf8(value).ok()?;
+        let mut fields = value.splitn(3, '\n');
+
+        let mut header = || {
+            Some(fields.next()?)
+                .filter(|value| !value.is_empty())
+                .map(str::to_string)
+        };
+        let etag = header();
+        let last_modified = header();
+
+        Some(Self {
+            etag,
+            last_modified,
+            body: fields.next()?.to_string(),
+        })
+    }
+}
+
+/// Get the page kept for conditional requests to a URL.
+pub async fn get_validated_page(
+    cache: &dyn CacheBackend,
+    url: &str,
+) -> Result<Option<ValidatedPage>, Error> {
+    let value = cache.get(&validated_page_key(url)).await?;
+
+    Ok(value.and_then(|value| ValidatedPage::from_bytes(&value)))
+}
+
+/// Keep a page for conditional requests to a URL.
+pub async fn set_validated_page(
+    cache: &dyn CacheBackend,
+    url: &str,
+    page: &ValidatedPage,
+) -> Result<(), Error> {
+    cache
+        .set(&validated_page_key(url), &page.to_bytes(), None)
+        .await
+}
+
+#[derive(Debug)]
+struct Entry {
+    value: Vec<u8>,
+    expires: Option<Instant>,
+}
+
+impl Entry {
+    fn is_live(&self, now: Instant) -> bool {
+        self.expires.is_none_or(|expires| expires > now)
+    }
+}
+
+/// Caches values in memory, for a single instance.
+#[derive(Debug, Default)]
+pub struct MemoryCache {
+    values: Mutex<HashMap<String, Entry>>,
+}
+
+impl MemoryCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Remove all expired values.
+    pub fn purge_expired(&self) {
+        let now = Instant::now();
+
+        self.values
+            .lock()
+            .unwrap()
+            .retain(|_, entry| entry.is_live(now));
+    }
+}
+
+impl CacheBackend for MemoryCache {
+    fn get<'a>(&'a self, key: &'a str) -> BoxFuture<'a, Result<Option<Vec<u8>>, Error>> {
+        let now = Instant::now();
+
+        let value = self
+            .values
+            .lock()
+            .unwrap()
+            .get(key)
+            .filter(|entry| entry.is_live(now))
+            .map(|entry| entry.value.clone());
+
+        Box::pin(futures::future::ready(Ok(value)))
+    }
+
+    fn set<'a>(
+        &'a self,
+        key: &'a str,
+        value: &'a [u8],
+        ttl: Option<Duration>,
+    ) -> BoxFuture<'a, Result<(), Error>> {
+        let expires = ttl.map(|ttl| Instant::now() + ttl);
+
+        self.values.lock().unwrap().insert(
+            key.to_string(),
+            Entry {
+                value: value.to_vec(),
+                expires,
+            },
+        );
+
+        Box::pin(futures::future::ready(Ok(())))
+    }
+
+    fn delete<'a>(&'a self, key: &'a str) -> BoxFuture<'a, Result<(), Error>> {
+        self.values.lock().unwrap().remove(key);
+
+        Box::pin(futures::future::ready(Ok(())))
+    }
+}
+
+#[cfg(feature = "redis")]
+pub use self::redis_cache::{RedisCache, RedisCheckpoint};
+
+#[cfg(feature = "redis")]
+mod redis_cache {
+    use super::{checkpoint_key, CacheBackend};
+    use crate::checkpoint::{Checkpoint, CheckpointState};
+    use crate::{Error, SubmissionId};
+    use futures::future::BoxFuture;
+    use redis::AsyncCommands;
+    use std::time::Duration;
+
+    /// Caches values in Redis, shared by every instance using the same
+    /// server.
+    #[derive(Clone)]
+    pub struct RedisCache {
+        conn: redis::aio::MultiplexedConnection,
+    }
+
+    impl RedisCache {
+        /// Connect to a Redis server, such as `redis://127.0.0.1/`.
+        pub async fn connect(url: &str) -> Result<Self, Error> {
+            let client = redis::Client::open(url)?;
+            let conn = client.get_multiplexed_async_connection().await?;
+
+            Ok(Self { conn })
+        }
+    }
+
+    impl CacheBackend for RedisCache {
+        fn get<'a>(&'a self, key: &'a str) -> BoxFuture<'a, Result<Option<Vec<u8>>, Error>> {
+            let mut conn = self.conn.clone();
+
+            Box::pin(async move { Ok(conn.get(key).await?) })
+        }
+
+        fn set<'a>(
+            &'a self,
+            key: &'a str,
+            value: &'a [u8],
+            ttl: Option<Duration>,
+        ) -> BoxFuture<'a, Result<(), Error>> {
+            let mut conn = self.conn.clone();
+
+            Box::pin(async move {
+                match ttl {
+                    Some(ttl) => conn.pset_ex(key, value, ttl.as_millis() as u64).await?,
+                    None => conn.set(key, value).await?,
+                }
+
+                Ok(())
+            })
+        }
+
+        fn delete<'a>(&'a self, key: &'a str) -> BoxFuture<'a, Result<(), Error>> {
+            let mut conn = self.conn.clone();
+
+            Box::pin(async move { Ok(conn.del(key).await?) })
+        }
+    }
+
+    /// Saves poller or backfill progress to Redis, so another instance can
+    /// resume it.
+    ///
+    /// The next ID and pending retries are stored in a hash, with the next
+    /// ID in the `next_id` field and each pending ID as a field holding its
+    /// attempts.
+    ///
+    /// Checkpoints are loaded and saved with a blocking connection. On a
+    /// multi-threaded Tokio runtime this is done with
+    /// [`tokio::task::block_in_place`], so other tasks keep running, but on a
+    /// current thread runtime it blocks the runtime until Redis responds.
+    pub struct RedisCheckpoint {
+        conn: redis::Connection,
+        key: String,
+    }
+
+    impl RedisCheckpoint {
+        /// Connect to a Redis server, storing the checkpoint under a name.
+        pub fn connect(url: &str, name: &str) -> Result<Self, Error> {
+            let client = redis::Client::open(url)?;
+
+            Ok(Self {
+                conn: client.get_connection()?,
+                key: checkpoint_key(name),
+            })
+        }
+    }
+
+    impl Checkpoint for RedisCheckpoint {
+        fn load(&mut self) -> Result<Option<CheckpointState>, Error> {
+            use redis::Commands;
+            use std::collections::HashMap;
+
+            let mut fields: HashMap<String, i64> = blocking(|| self.conn.hgetall(&self.key))?;
+
+            let next_id = match fields.remove("next_id") {
+                Some(next_id) => SubmissionId(next_id as u64),
+                None => return Ok(None),
+            };
+
+            let pending = fields
+                .into_iter()
+                .filter_map(|(id, attempts)| Some((id.parse().ok()?, attempts as u32)))
+                .collect();
+
+            Ok(Some(CheckpointState { next_id, pending }))
+        }
+
+        fn save(&mut self, state: &CheckpointState) -> Result<(), Error> {
+            let mut fields = vec![("next_id".to_string(), state.next_id.to_i64())];
+            fields.extend(
+                state
+                    .pending
+                    .iter()
+                    .map(|(id, attempts)| (id.to_string(), *attempts as i64)),
+            );
+
+            blocking(|| {
+                redis::pipe()
+                    .atomic()
+                    .del(&self.key)
+                    .hset_multiple(&self.key, &fields)
+                    .query::<()>(&mut self.conn)
+            })?;
+
+            Ok(())
+        }
+    }
+
+    /// Run a blocking Redis command, moving other tasks off of this thread
+    /// when running on a multi-threaded runtime.
+    fn blocking<T, F: FnOnce() -> T>(f: F) -> T {
+        use tokio::runtime::{Handle, RuntimeFlavor};
+
+        match Handle::try_current() {
+            Ok(handle) if handle.runtime_flavor() == RuntimeFlavor::MultiThread => {
+                tokio::task::block_in_place(f)
+            }
+            _ => f(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_memory_cache() {
+        let cache = MemoryCache::new();
+
+        cache.set("a", b"first", None).await.unwrap();
+        cache
+            .set("b", b"second", Some(Duration::from_secs(0)))
+            .await
+            .unwrap();
+
+        assert_eq!(cache.get("a").await.unwrap(), Some(b"first".to_vec()));
+        assert_eq!(cache.get("b").await.unwrap(), None);
+
+        cache.purge_expired();
+        assert_eq!(cache.values.lock().unwrap().len(), 1);
+
+        cache.delete("a").await.unwrap();
+        assert_eq!(cache.get("a").await.unwrap(), None);
+
+        let sha256 = [0xab; 32];
+        let config = HashConfig::default();
+        let other = HashConfig {
+            width: 16,
+            height: 16,
+            ..Default::default()
+        };
+        assert_eq!(get_hash(&cache, &config, &sha256).await.unwrap(), None);
+        set_hash(&cache, &config, &sha256, -42).await.unwrap();
+        assert_eq!(get_hash(&cache, &config, &sha256).await.unwrap(), Some(-42));
+        assert_eq!(get_hash(&cache, &other, &sha256).await.unwrap(), None);
+        assert_eq!(
+            hash_key(&config, &[0x0f, 0xa0]),
+            "fa:hash:gradient-8x8-dct-first:0fa0"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_validated_page() {
+        let cache = MemoryCache::new();
+        let page = ValidatedPage {
+            etag: None,
+            last_modified: Some("Thu, 01 Apr 2021 14:00:30 GMT".into()),
+            body: "<html>\n</html>".into(),
+        };
+
+        set_validated_page(&cache, "/view/1", &page).await.unwrap();
+        assert_eq!(
+            get_validated_page(&cache, "/view/1").await.unwrap(),
+            Some(page)
+        );
+        assert_eq!(get_validated_page(&cache, "/view/2").await.unwrap(), None);
+    }
+}
@@ -0,0 +1,145 @@
+//! Formatting submissions as captions for chat platforms.
+
+use crate::description::escape_markdown;
+use crate::{Rating, Submission};
+
+/// Formats submissions as short captions for Telegram and Discord.
+///
+/// Captions include the rating as an emoji, the title linked to the
+/// submission, the artist, and the start of the description as plain text.
+#[derive(Clone, Debug)]
+pub struct CaptionFormatter {
+    max_description: usize,
+}
+
+impl Default for CaptionFormatter {
+    fn default() -> Self {
+        Self {
+            max_description: 200,
+        }
+    }
+}
+
+impl CaptionFormatter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Longest description to include, in characters, before it is cut off
+    /// with an ellipsis. Set to 0 to leave out the description. Defaults to
+    /// 200.
+    pub fn with_max_description(mut self, max_description: usize) -> Self {
+        self.max_description = max_description;
+        self
+    }
+
+    /// Format a caption using Telegram's HTML formatting.
+    pub fn telegram_html(&self, sub: &Submission) -> String {
+        let mut caption = format!(
+            r#"{} <a href="{}"><b>{}</b></a> by {}"#,
+            rating_emoji(&sub.rating),
+            escape_html(&sub.url()),
+            escape_html(&sub.title),
+            escape_html(&sub.artist),
+        );
+
+        if let Some(description) = self.description(sub) {
+            caption.push_str("\n\n");
+            caption.push_str(&escape_html(&description));
+        }
+
+        caption
+    }
+
+    /// Format a caption using Discord's Markdown.
+    pub fn discord_markdown(&self, sub: &Submission) -> String {
+        let mut caption = format!(
+            "{} **[{}](<{}>)** by **{}**",
+            rating_emoji(&sub.rating),
+            escape_markdown(&sub.title),
+            sub.url(),
+            escape_markdown(&sub.artist),
+        );
+
+        if let Some(description) = self.description(sub) {
+            caption.push_str("\n> ");
+            caption.push_str(&escape_markdown(&description));
+        }
+
+        caption
+    }
+
+    /// The description as plain text, shortened to the maximum length.
+    fn description(&self, sub: &Submission) -> Option<String> {
+        if self.max_description == 0 {
+            return None;
+        }
+
+        let text = crate::fragment_text(&sub.description);
+
+        if text.is_empty() {
+            return None;
+        }
+
+        if text.chars().count() <= self.max_description {
+            return Some(text);
+        }
+
+        let mut trimmed: String = text.chars().take(self.max_description).collect();
+        trimmed.truncate(trimmed.trim_end().len());
+        trimmed.push('…');
+
+        Some(trimmed)
+    }
+}
+
+fn rating_emoji(rating: &Rating) -> &'static str {
+    match rating {
+        Rating::General => "🟢",
+        Rating::Mature => "🟡",
+        Rating::Adult => "🔞",
+    }
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::test_submission;
+
+    #[test]
+    fn test_captions() {
+        let sub = Submission {
+            title: "<Fox> & *Friends*".into(),
+            rating: Rating::Adult,
+            description: "Commissioned <b>by</b> a friend.<br>\n  Thanks!".into(),
+            ..test_submission("", 1617235200, &[])
+        };
+
+        let formatter = CaptionFormatter::new();
+
+        assert_eq!(
+            formatter.telegram_html(&sub),
+            "🔞 <a href=\"https://www.furaffinity.net/view/41234567/\"><b>&lt;Fox&gt; &amp; *Friends*</b></a> by Example\n\n\
+             Commissioned by a friend. Thanks!"
+        );
+        assert_eq!(
+            formatter.discord_markdown(&sub),
+            "🔞 **[<Fox\\> & \\*Friends\\*](<https://www.furaffinity.net/view/41234567/>)** by **Example**\n\
+             > Commissioned by a friend. Thanks!"
+        );
+
+        let short = CaptionFormatter::new().with_max_description(13);
+        assert!(short.telegram_html(&sub).ends_with("\n\nCommissioned…"));
+        assert!(!CaptionFormatter::new()
+            .with_max_description(0)
+            .discord_markdown(&sub)
+            .contains('\n'));
+    }
+}
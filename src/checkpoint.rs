@@ -0,0 +1,158 @@
+//! Saving the progress of pollers and backfills so they can resume after a
+//! restart.
+
+use crate::{Error, SubmissionId};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+/// Progress of a poller or backfill.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct CheckpointState {
+    /// The next ID that hasn't been loaded yet.
+    pub next_id: SubmissionId,
+    /// IDs waiting to be retried, with how many attempts have been made.
+    pub pending: BTreeMap<SubmissionId, u32>,
+}
+
+/// Somewhere to save progress.
+///
+/// Checkpoints are saved after every ID, so implementations should be
+/// quick.
+pub trait Checkpoint {
+    /// Load the last saved state, if any was saved.
+    fn load(&mut self) -> Result<Option<CheckpointState>, Error>;
+
+    /// Save the current state, replacing anything saved before.
+    fn save(&mut self, state: &CheckpointState) -> Result<(), Error>;
+}
+
+/// Saves progress to a text file.
+///
+/// The first line is the next ID, followed by a line with the ID and
+/// attempts for each pending retry.
+#[derive(Clone, Debug)]
+pub struct FileCheckpoint {
+    path: PathBuf,
+}
+
+impl FileCheckpoint {
+    pub fn new<P: Into<PathBuf>>(path: P) -> Self {
+        Self { path: path.into() }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Checkpoint for FileCheckpoint {
+    fn load(&mut self) -> Result<Option<CheckpointState>, Error> {
+        let contents = match std::fs::read_to_string(&self.path) {
+            Ok(contents) => contents,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(err) => return Err(err.into()),
+        };
+
+        let mut lines = contents.lines();
+
+        let next_id = lines
+            .next()
+            .ok_or_else(|| Error::new("checkpoint was empty", false))?
+            .trim()
+            .parse()?;
+
+        let mut pending = BTreeMap::new();
+        for line in lines.filter(|line| !line.trim().is_empty()) {
+            let mut parts = line.split_whitespace();
+            let id = parts.next().unwrap_or_default().parse()?;
+            let attempts = parts.next().unwrap_or("0").parse()?;
+            pending.insert(id, attempts);
+        }
+
+        Ok(Some(CheckpointState { next_id, pending }))
+    }
+
+    fn save(&mut self, state: &CheckpointState) -> Result<(), Error> {
+        let mut contents = format!("{}\n", state.next_id);
+        for (id, attempts) in &state.pending {
+            contents.push_str(&format!("{} {}\n", id, attempts));
+        }
+
+        // Replace the file in one step so a crash can't leave it half written.
+        let temp = self.path.with_extension("tmp");
+
+        std::fs::write(&temp, contents)?;
+        std::fs::rename(&temp, &self.path)?;
+
+        Ok(())
+    }
+}
+
+/// Keeps progress in memory.
+///
+/// Clones share the same state, so one clone can be given to a poller and
+/// another kept to see its progress.
+#[derive(Clone, Debug, Default)]
+pub struct MemoryCheckpoint {
+    state: Arc<Mutex<Option<CheckpointState>>>,
+}
+
+impl MemoryCheckpoint {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The last saved state.
+    pub fn state(&self) -> Option<CheckpointState> {
+        self.state.lock().unwrap().clone()
+    }
+}
+
+impl Checkpoint for MemoryCheckpoint {
+    fn load(&mut self) -> Result<Option<CheckpointState>, Error> {
+        Ok(self.state())
+    }
+
+    fn save(&mut self, state: &CheckpointState) -> Result<(), Error> {
+        *self.state.lock().unwrap() = Some(state.clone());
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_file_checkpoint() {
+        let path = std::env::temp_dir().join(format!("fa-checkpoint-{}", std::process::id()));
+        let mut checkpoint = FileCheckpoint::new(&path);
+
+        assert_eq!(checkpoint.load().unwrap(), None);
+
+        let state = CheckpointState {
+            next_id: SubmissionId(12345),
+            pending: vec![(SubmissionId(12340), 2), (SubmissionId(12343), 1)]
+                .into_iter()
+                .collect(),
+        };
+        checkpoint.save(&state).unwrap();
+        assert_eq!(checkpoint.load().unwrap(), Some(state));
+
+        // Checkpoints with only an ID are still read.
+        std::fs::write(&path, "12345").unwrap();
+        assert_eq!(
+            checkpoint.load().unwrap(),
+            Some(CheckpointState {
+                next_id: SubmissionId(12345),
+                pending: BTreeMap::new(),
+            })
+        );
+
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,189 @@
+//! FurAffinity's clock, for turning the local times shown on pages into
+//! UTC.
+//!
+//! Pages show times in the site's local time without an offset.
+//! [`parse_date`](crate::parse_date) assumes US Eastern time, including
+//! daylight saving time. Comparing the time shown in the footer of a page to
+//! the `Date` header it was sent with gives the real offset, in case the
+//! site's clock is set differently.
+
+use crate::Error;
+use lazy_static::lazy_static;
+
+lazy_static! {
+    static ref SERVER_TIME: regex::Regex = regex::Regex::new(
+        r"Server (?:Local )?Time:\s*([A-Za-z]{3} \d{1,2}\w*, \d{4} \d{1,2}:\d{2} [AP]M)"
+    )
+    .unwrap();
+    static ref RELATIVE_DATE: regex::Regex =
+        regex::Regex::new(r"^(an?|\d+) (second|minute|hour|day|week|month|year)s? ago$").unwrap();
+}
+
+/// Standard offset of the site's timezone, outside of daylight saving time.
+pub const DEFAULT_OFFSET_SECS: i32 = -5 * 3600;
+
+/// Timezone assumed for times on pages.
+pub const SITE_TIMEZONE: chrono_tz::Tz = chrono_tz::America::New_York;
+
+/// Offset of the site's timezone at a time.
+pub fn site_offset(at: chrono::DateTime<chrono::Utc>) -> chrono::FixedOffset {
+    use chrono::{Offset, TimeZone};
+
+    SITE_TIMEZONE
+        .offset_from_utc_datetime(&at.naive_utc())
+        .fix()
+}
+
+/// Interpret a time shown on a page as the site's local time. Times repeated
+/// when daylight saving time ends are taken as the first of the two, and
+/// times skipped when it starts use the standard offset.
+pub fn from_site_local(date: chrono::NaiveDateTime) -> chrono::DateTime<chrono::Utc> {
+    use chrono::TimeZone;
+
+    match SITE_TIMEZONE.from_local_datetime(&date).earliest() {
+        Some(date) => date.with_timezone(&chrono::Utc),
+        None => chrono::Utc
+            .from_utc_datetime(&(date - chrono::Duration::seconds(DEFAULT_OFFSET_SECS.into()))),
+    }
+}
+
+/// The site's current time and offset from UTC.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct ServerTime {
+    /// Current time, from the response's `Date` header.
+    pub now: chrono::DateTime<chrono::Utc>,
+    /// Offset of the times shown on pages.
+    pub offset: chrono::FixedOffset,
+}
+
+impl ServerTime {
+    /// Work out the site's clock from when a page was sent and the page
+    /// itself. Without a server time in the footer the offset is the site's
+    /// timezone's.
+    pub fn from_page(now: chrono::DateTime<chrono::Utc>, page: &str) -> Result<Self, Error> {
+        let offset_secs = match SERVER_TIME.captures(page) {
+            Some(captures) => {
+                let shown = crate::parse_date(&captures[1])?;
+                let offset =
+                    (shown - now).num_seconds() + i64::from(site_offset(shown).local_minus_utc());
+                // The footer only shows minutes and the header may be a
+                // little behind it, so round to the nearest quarter hour.
+                ((offset as f64 / 900.0).round() as i32) * 900
+            }
+            None => site_offset(now).local_minus_utc(),
+        };
+
+        let offset = chrono::FixedOffset::east_opt(offset_secs)
+            .ok_or_else(|| Error::new("server offset out of range", false))?;
+
+        Ok(Self { now, offset })
+    }
+
+    /// Parse a date shown on a page with this offset.
+    pub fn parse_date(&self, date: &str) -> Result<chrono::DateTime<chrono::Utc>, Error> {
+        crate::parse_date(date).map(|date| self.correct(date))
+    }
+
+    /// Fix a date parsed with [`parse_date`](crate::parse_date) for how far
+    /// this offset is from the site's timezone at the time it was measured.
+    pub fn correct(&self, date: chrono::DateTime<chrono::Utc>) -> chrono::DateTime<chrono::Utc> {
+        date + chrono::Duration::seconds(i64::from(
+            site_offset(self.now).local_minus_utc() - self.offset.local_minus_utc(),
+        ))
+    }
+
+    /// Parse a relative date, such as `5 days ago`, from the site's current
+    /// time. Months and years are treated as 30 and 365 days.
+    pub fn parse_relative(&self, date: &str) -> Option<chrono::DateTime<chrono::Utc>> {
+        let date = date.trim().to_lowercase();
+        if date == "just now" {
+            return Some(self.now);
+        }
+
+        let captures = RELATIVE_DATE.captures(&date)?;
+        let count: i64 = match &captures[1] {
+            "a" | "an" => 1,
+            count => count.parse().ok()?,
+        };
+
+        let seconds = match &captures[2] {
+            "second" => 1,
+            "minute" => 60,
+            "hour" => 3600,
+            "day" => 86400,
+            "week" => 7 * 86400,
+            "month" => 30 * 86400,
+            _ => 365 * 86400,
+        };
+
+        Some(self.now - chrono::Duration::seconds(count.checked_mul(seconds)?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_server_time() {
+        let now = chrono::Utc.with_ymd_and_hms(2021, 4, 1, 14, 0, 30).unwrap();
+        let page = r#"<div class="footer">Server Time: Apr 1st, 2021 10:00 AM</div>"#;
+
+        let time = ServerTime::from_page(now, page).unwrap();
+        assert_eq!(time.offset.local_minus_utc(), -4 * 3600);
+        assert_eq!(
+            time.parse_date("Apr 1st, 2021 09:00 AM").unwrap(),
+            chrono::Utc.with_ymd_and_hms(2021, 4, 1, 13, 0, 0).unwrap()
+        );
+        assert_eq!(
+            time.parse_relative("2 hours ago"),
+            Some(now - chrono::Duration::hours(2))
+        );
+        assert_eq!(
+            time.parse_relative("a day ago"),
+            Some(now - chrono::Duration::days(1))
+        );
+        assert_eq!(time.parse_relative("yesterday"), None);
+
+        let time = ServerTime::from_page(now, "<html></html>").unwrap();
+        assert_eq!(time.offset.local_minus_utc(), -4 * 3600);
+        assert_eq!(
+            time.parse_date("Apr 1st, 2021 09:00 AM").unwrap(),
+            crate::parse_date("Apr 1st, 2021 09:00 AM").unwrap()
+        );
+
+        // A clock an hour ahead of the site's timezone.
+        let page = r#"<div class="footer">Server Time: Apr 1st, 2021 11:00 AM</div>"#;
+        let time = ServerTime::from_page(now, page).unwrap();
+        assert_eq!(
+            time.parse_date("Jan 1st, 2021 09:00 AM").unwrap(),
+            chrono::Utc.with_ymd_and_hms(2021, 1, 1, 13, 0, 0).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_site_local() {
+        let date =
+            |date: &str| chrono::NaiveDateTime::parse_from_str(date, "%Y-%m-%d %H:%M").unwrap();
+
+        assert_eq!(
+            from_site_local(date("2021-01-01 09:00")),
+            chrono::Utc.with_ymd_and_hms(2021, 1, 1, 14, 0, 0).unwrap()
+        );
+        assert_eq!(
+            from_site_local(date("2021-07-01 09:00")),
+            chrono::Utc.with_ymd_and_hms(2021, 7, 1, 13, 0, 0).unwrap()
+        );
+        // Repeated when clocks go back.
+        assert_eq!(
+            from_site_local(date("2021-11-07 01:30")),
+            chrono::Utc.with_ymd_and_hms(2021, 11, 7, 5, 30, 0).unwrap()
+        );
+        // Skipped when clocks go forward.
+        assert_eq!(
+            from_site_local(date("2021-03-14 02:30")),
+            chrono::Utc.with_ymd_and_hms(2021, 3, 14, 7, 30, 0).unwrap()
+        );
+    }
+}
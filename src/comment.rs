@@ -0,0 +1,292 @@
+//! Parsing comments on submissions and journals.
+
+use crate::anomaly::{Anomaly, AnomalyKind};
+use crate::url::FaUrl;
+use crate::{parse_date, Error};
+use lazy_static::lazy_static;
+use scraper::Selector;
+
+lazy_static! {
+    static ref COMMENT_CONTAINER: Selector = Selector::parse(".comment_container").unwrap();
+    static ref COMMENT_ANCHOR: Selector = Selector::parse(r#"a[id^="cid:"]"#).unwrap();
+    static ref COMMENT_USERNAME: Selector = Selector::parse(".comment_username").unwrap();
+    static ref COMMENT_USER_LINK: Selector = Selector::parse(r#"a[href^="/user/"]"#).unwrap();
+    static ref COMMENT_POSTED_AT: Selector = Selector::parse(".popup_date").unwrap();
+    static ref COMMENT_TEXT: Selector = Selector::parse(".comment_text").unwrap();
+    static ref COMMENT_AVATAR: Selector = Selector::parse("img.comment_useravatar").unwrap();
+    static ref CONTAINER_WIDTH: regex::Regex = regex::Regex::new(r"width:\s*([\d.]+)%").unwrap();
+}
+
+/// A comment on a submission or journal.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Comment {
+    pub id: i64,
+    /// The comment this is a reply to.
+    pub parent_id: Option<i64>,
+    /// How many replies deep the comment is, starting at 0.
+    pub depth: u32,
+    /// Display name of the commenter, or `None` if the comment was hidden or
+    /// deleted.
+    pub author: Option<String>,
+    /// Username of the commenter as it appears in their profile's URL.
+    pub author_username: Option<String>,
+    /// Link to the commenter's avatar.
+    pub avatar_url: Option<String>,
+    pub posted_at: Option<chrono::DateTime<chrono::Utc>>,
+    /// Comment HTML, or the notice shown in place of a hidden comment.
+    pub text: String,
+}
+
+impl Comment {
+    /// If the comment was hidden or deleted.
+    pub fn is_hidden(&self) -> bool {
+        self.author.is_none()
+    }
+}
+
+/// A link to a comment, like
+/// `https://www.furaffinity.net/view/31209021/#cid:152000001`.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct CommentLink {
+    /// The submission or journal the comment is on.
+    pub page: FaUrl,
+    pub id: i64,
+}
+
+impl CommentLink {
+    /// Parse a link to a comment on a submission or journal. Returns `None`
+    /// if it is not a FurAffinity link or has no `#cid:` anchor.
+    pub fn parse(link: &str) -> Option<Self> {
+        let (page, anchor) = link.trim().split_once('#')?;
+        let id = anchor.strip_prefix("cid:")?.parse().ok()?;
+
+        match FaUrl::parse(page)? {
+            page @ FaUrl::View(_) | page @ FaUrl::Journal(_) => Some(Self { page, id }),
+            _ => None,
+        }
+    }
+}
+
+/// A comment along with the replies to it, and the replies to those.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct CommentThread {
+    pub comment: Comment,
+    /// Every reply below the comment, in the order they are shown.
+    pub replies: Vec<Comment>,
+}
+
+/// Find a comment and its replies among the comments from a page, or `None`
+/// if the comment is not there.
+pub fn thread(comments: Vec<Comment>, id: i64) -> Option<CommentThread> {
+    let mut comments = comments.into_iter().skip_while(|comment| comment.id != id);
+    let comment = comments.next()?;
+
+    let replies = comments
+        .take_while(|reply| reply.depth > comment.depth)
+        .collect();
+
+    Some(CommentThread { comment, replies })
+}
+
+/// Group comments from a page into threads, one for each comment that isn't
+/// a reply.
+pub fn threads(comments: Vec<Comment>) -> Vec<CommentThread> {
+    let mut threads: Vec<CommentThread> = Vec::new();
+
+    for comment in comments {
+        match threads.last_mut() {
+            Some(thread) if comment.depth > thread.comment.depth => thread.replies.push(comment),
+            _ => threads.push(CommentThread {
+                comment,
+                replies: Vec::new(),
+            }),
+        }
+    }
+
+    threads
+}
+
+/// Parse the comments on a submission or journal page, in the order they are
+/// shown.
+pub fn parse_comments(page: &str) -> Result<Vec<Comment>, Error> {
+    let document = scraper::Html::parse_document(page);
+
+    parse_comments_document(&document, &mut Vec::new())
+}
+
+pub(crate) fn parse_comments_document(
+    document: &scraper::Html,
+    anomalies: &mut Vec<Anomaly>,
+) -> Result<Vec<Comment>, Error> {
+    let mut comments: Vec<Comment> = Vec::new();
+    // Widths of the comments that could be parents of the next comment,
+    // replies being narrower than the comments they reply to.
+    let mut parents: Vec<(f32, i64, u32)> = Vec::new();
+
+    for container in document.select(&COMMENT_CONTAINER) {
+        let id = container
+            .select(&COMMENT_ANCHOR)
+            .next()
+            .and_then(|anchor| anchor.value().id())
+            .and_then(|id| id.strip_prefix("cid:"))
+            .ok_or_else(|| Error::parse("comment id"))?
+            .parse()?;
+
+        let width = match container
+            .value()
+            .attr("style")
+            .and_then(|style| CONTAINER_WIDTH.captures(style))
+            .and_then(|captures| captures[1].parse().ok())
+        {
+            Some(width) => width,
+            None => {
+                anomalies.push(Anomaly::new("comment.depth", AnomalyKind::Fallback));
+                100.0
+            }
+        };
+
+        while parents
+            .last()
+            .map(|(parent_width, _, _)| *parent_width <= width)
+            .unwrap_or(false)
+        {
+            parents.pop();
+        }
+        let (parent_id, depth) = match parents.last() {
+            Some((_, parent_id, parent_depth)) => (Some(*parent_id), parent_depth + 1),
+            None => (None, 0),
+        };
+        parents.push((width, id, depth));
+
+        let username = container.select(&COMMENT_USERNAME).next();
+
+        let author = username.map(crate::join_text_nodes);
+        let author_username = username
+            .and_then(|username| username.select(&COMMENT_USER_LINK).next())
+            .and_then(|link| link.value().attr("href"))
+            .and_then(|href| href.split('/').nth(2))
+            .map(String::from);
+
+        let avatar_url = container
+            .select(&COMMENT_AVATAR)
+            .next()
+            .and_then(|img| img.value().attr("src"))
+            .and_then(FaUrl::parse)
+            .map(|url| url.canonical());
+
+        let posted_at = container
+            .select(&COMMENT_POSTED_AT)
+            .next()
+            .and_then(|date| date.value().attr("title"))
+            .map(parse_date)
+            .transpose()?;
+
+        if author.is_some() && posted_at.is_none() {
+            anomalies.push(Anomaly::new("comment.posted_at", AnomalyKind::Missing));
+        }
+
+        let text = container
+            .select(&COMMENT_TEXT)
+            .next()
+            .map(|text| text.inner_html().trim().to_string())
+            .unwrap_or_default();
+
+        comments.push(Comment {
+            id,
+            parent_id,
+            depth,
+            author,
+            author_username,
+            avatar_url,
+            posted_at,
+            text,
+        });
+    }
+
+    Ok(comments)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_comments() {
+        let comments = parse_comments(crate::test_utils::SUBMISSION).unwrap();
+
+        assert_eq!(comments.len(), 3);
+
+        assert_eq!(comments[0].id, 152000001);
+        assert_eq!(comments[0].author.as_deref(), Some("Berry_Picker"));
+        assert_eq!(comments[0].author_username.as_deref(), Some("berrypicker"));
+        assert_eq!(comments[0].text, "So cute!");
+        assert_eq!(comments[0].parent_id, None);
+
+        assert_eq!(comments[1].parent_id, Some(152000001));
+        assert_eq!(comments[1].depth, 1);
+        assert!(comments[1].posted_at > comments[0].posted_at);
+
+        assert_eq!(comments[2].parent_id, None);
+        assert!(comments[2].is_hidden());
+        assert_eq!(comments[2].posted_at, None);
+
+        assert_eq!(
+            comments[0].avatar_url.as_deref(),
+            Some("https://a.furaffinity.net/1555431800/berrypicker.gif")
+        );
+        assert_eq!(comments[2].avatar_url, None);
+    }
+
+    #[test]
+    fn test_threads() {
+        let comments = parse_comments(crate::test_utils::SUBMISSION).unwrap();
+        let threads = threads(comments);
+
+        assert_eq!(threads.len(), 2);
+        assert_eq!(threads[0].comment.id, 152000001);
+        assert_eq!(threads[0].replies.len(), 1);
+        assert!(threads[1].comment.is_hidden());
+        assert!(threads[1].replies.is_empty());
+    }
+
+    #[test]
+    fn test_comment_link() {
+        let link =
+            CommentLink::parse("https://www.furaffinity.net/view/31209021/#cid:152000001").unwrap();
+        assert_eq!(link.page, FaUrl::View(crate::SubmissionId(31209021)));
+        assert_eq!(link.id, 152000001);
+
+        let link = CommentLink::parse("furaffinity.net/journal/10000001/#cid:1").unwrap();
+        assert_eq!(link.page, FaUrl::Journal(crate::JournalId(10000001)));
+
+        assert_eq!(
+            CommentLink::parse("https://www.furaffinity.net/view/31209021/"),
+            None
+        );
+        assert_eq!(
+            CommentLink::parse("https://www.furaffinity.net/user/example/#cid:1"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_thread() {
+        let comments = parse_comments(crate::test_utils::SUBMISSION).unwrap();
+
+        let first = thread(comments.clone(), 152000001).unwrap();
+        assert_eq!(first.comment.id, 152000001);
+        assert_eq!(first.replies.len(), 1);
+        assert_eq!(first.replies[0].id, 152000002);
+
+        let reply = thread(comments.clone(), 152000002).unwrap();
+        assert!(reply.replies.is_empty());
+
+        assert_eq!(thread(comments, 1), None);
+    }
+}
@@ -0,0 +1,262 @@
+//! Parsing a user's commission info, from `/commissions/{slug}/`.
+
+use crate::username::Artist;
+use crate::{Error, SubmissionId};
+use lazy_static::lazy_static;
+use scraper::Selector;
+
+lazy_static! {
+    static ref NOTICE: Selector =
+        Selector::parse("section.notice-message .redirect-message, .error-message-box").unwrap();
+    static ref STATUS: Selector = Selector::parse(".commission-status").unwrap();
+    static ref INFO: Selector = Selector::parse(".commission-info .section-body").unwrap();
+    static ref TYPE: Selector = Selector::parse(".commission-type").unwrap();
+    static ref TYPE_NAME: Selector = Selector::parse(".commission-type-name, h3").unwrap();
+    static ref TYPE_PRICE: Selector = Selector::parse(".commission-type-price").unwrap();
+    static ref TYPE_DESCRIPTION: Selector =
+        Selector::parse(".commission-type-description").unwrap();
+    static ref TYPE_EXAMPLE: Selector = Selector::parse(r#"a[href^="/view/"]"#).unwrap();
+    static ref EXAMPLE_ID: regex::Regex = regex::Regex::new(r"/view/(\d+)").unwrap();
+    static ref PRICE: regex::Regex =
+        regex::Regex::new(r"([$€£¥])?\s*(\d[\d,]*)(?:\.(\d{1,2}))?\s*([A-Z]{3})?").unwrap();
+}
+
+/// A user's commission info.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct CommissionInfo {
+    pub artist: Artist,
+    /// If the user is taking commissions, when the page says.
+    pub accepting: Option<bool>,
+    /// HTML of the user's terms and other details.
+    pub info: String,
+    pub types: Vec<CommissionType>,
+}
+
+/// A kind of commission a user offers, such as a sketch or reference sheet.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct CommissionType {
+    pub name: String,
+    pub price: Option<Price>,
+    /// HTML of the description.
+    pub description: String,
+    /// Submissions linked as examples.
+    pub examples: Vec<SubmissionId>,
+}
+
+/// The price of a commission type.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Price {
+    /// As shown on the page, like `$20.00 USD`.
+    pub text: String,
+    /// Amount in hundredths of the currency, if it could be read.
+    pub cents: Option<u64>,
+    /// ISO 4217 code of the currency, if shown or known from its symbol.
+    pub currency: Option<String>,
+}
+
+impl Price {
+    /// Parse a price as shown on the page.
+    pub fn parse(text: &str) -> Self {
+        let text = text.trim().to_string();
+
+        let captures = match PRICE.captures(&text) {
+            Some(captures) => captures,
+            None => {
+                return Self {
+                    text,
+                    cents: None,
+                    currency: None,
+                }
+            }
+        };
+
+        let cents = captures[2]
+            .replace(',', "")
+            .parse::<u64>()
+            .ok()
+            .and_then(|whole| {
+                let fraction = match captures.get(3).map(|m| m.as_str()) {
+                    Some(fraction) if fraction.len() == 1 => fraction.parse::<u64>().ok()? * 10,
+                    Some(fraction) => fraction.parse().ok()?,
+                    None => 0,
+                };
+
+                whole.checked_mul(100)?.checked_add(fraction)
+            });
+
+        let currency = captures
+            .get(4)
+            .map(|code| code.as_str())
+            .or_else(|| match captures.get(1)?.as_str() {
+                "$" => Some("USD"),
+                "€" => Some("EUR"),
+                "£" => Some("GBP"),
+                "¥" => Some("JPY"),
+                _ => None,
+            })
+            .map(str::to_string);
+
+        Self {
+            text,
+            cents,
+            currency,
+        }
+    }
+}
+
+/// Parse a user's commission info page. Returns `None` if the user does not
+/// exist or their commission info can't be viewed.
+pub fn parse_commissions(slug: &str, page: &str) -> Result<Option<CommissionInfo>, Error> {
+    let slug = crate::username::slug(slug);
+    let document = scraper::Html::parse_document(page);
+
+    if document.select(&NOTICE).next().is_some() {
+        return Ok(None);
+    }
+
+    let artist = crate::profile::parse_owner(&document, Some(&slug))?;
+
+    let accepting = document
+        .select(&STATUS)
+        .next()
+        .map(|status| crate::join_text_nodes(status).to_lowercase())
+        .and_then(|status| {
+            if status.contains("closed") {
+                Some(false)
+            } else if status.contains("open") {
+                Some(true)
+            } else {
+                None
+            }
+        });
+
+    let info = document
+        .select(&INFO)
+        .next()
+        .map(|info| info.inner_html().trim().to_string())
+        .unwrap_or_default();
+
+    let types = document
+        .select(&TYPE)
+        .map(|kind| {
+            let name = kind
+                .select(&TYPE_NAME)
+                .next()
+                .map(crate::join_text_nodes)
+                .filter(|name| !name.is_empty())
+                .ok_or_else(|| Error::parse("commission type name"))?;
+
+            let price = kind
+                .select(&TYPE_PRICE)
+                .next()
+                .map(crate::join_text_nodes)
+                .filter(|price| !price.is_empty())
+                .map(|price| Price::parse(&price));
+
+            let description = kind
+                .select(&TYPE_DESCRIPTION)
+                .next()
+                .map(|description| description.inner_html().trim().to_string())
+                .unwrap_or_default();
+
+            let examples = kind
+                .select(&TYPE_EXAMPLE)
+                .filter_map(|link| EXAMPLE_ID.captures(link.value().attr("href")?))
+                .filter_map(|captures| captures[1].parse().ok().map(SubmissionId))
+                .collect();
+
+            Ok(CommissionType {
+                name,
+                price,
+                description,
+                examples,
+            })
+        })
+        .collect::<Result<_, Error>>()?;
+
+    Ok(Some(CommissionInfo {
+        artist,
+        accepting,
+        info,
+        types,
+    }))
+}
+
+#[cfg(all(test, feature = "client"))]
+mod tests {
+    use super::*;
+    use crate::test_utils::MockServer;
+
+    const COMMISSIONS: &str = r#"<html><body><div id="page-commissions">
+        <userpage-nav-header>
+            <userpage-nav-avatar><a href="/user/deadrussiansoul/"><img src="//a.furaffinity.net/1555431700/deadrussiansoul.gif"></a></userpage-nav-avatar>
+            <userpage-nav-user-details><username>~Dead_Russian_Soul</username></userpage-nav-user-details>
+        </userpage-nav-header>
+        <div class="commission-status">Commissions: Open</div>
+        <section class="commission-info">
+            <div class="section-header"><h2>Commission Info</h2></div>
+            <div class="section-body">Payment up front through <b>PayPal</b>.</div>
+        </section>
+        <section class="commission-types">
+            <div class="commission-type">
+                <h3 class="commission-type-name">Sketch</h3>
+                <span class="commission-type-price">$20.00 USD</span>
+                <div class="commission-type-description">A rough pencil sketch.</div>
+                <div class="commission-type-examples">
+                    <a href="/view/31209021/"><img src="//t.furaffinity.net/31209021@200-1555431774.jpg"></a>
+                </div>
+            </div>
+            <div class="commission-type">
+                <h3 class="commission-type-name">Painting</h3>
+                <span class="commission-type-price">€1,250.5</span>
+            </div>
+        </section>
+    </div></body></html>"#;
+
+    #[tokio::test]
+    async fn test_commissions() {
+        let server = MockServer::start().await.unwrap();
+        server.route("/commissions/deadrussiansoul/", 200, COMMISSIONS);
+        let fa = server.client();
+
+        let info = fa
+            .get_commissions("Dead_Russian_Soul")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(info.artist.slug, "deadrussiansoul");
+        assert_eq!(info.accepting, Some(true));
+        assert_eq!(info.info, "Payment up front through <b>PayPal</b>.");
+
+        assert_eq!(info.types.len(), 2);
+        assert_eq!(info.types[0].name, "Sketch");
+        assert_eq!(
+            info.types[0].price,
+            Some(Price {
+                text: "$20.00 USD".into(),
+                cents: Some(2000),
+                currency: Some("USD".into()),
+            })
+        );
+        assert_eq!(info.types[0].description, "A rough pencil sketch.");
+        assert_eq!(info.types[0].examples, vec![SubmissionId(31209021)]);
+
+        let price = info.types[1].price.as_ref().unwrap();
+        assert_eq!(price.cents, Some(125050));
+        assert_eq!(price.currency.as_deref(), Some("EUR"));
+        assert_eq!(Price::parse("Ask for a quote").cents, None);
+
+        server.route(
+            "/commissions/example/",
+            200,
+            r#"<section class="notice-message"><div class="redirect-message">This user cannot be found.</div></section>"#,
+        );
+        assert_eq!(fa.get_commissions("example").await.unwrap(), None);
+    }
+}
@@ -0,0 +1,133 @@
+//! Running several kinds of crawling against one request budget.
+
+use crate::poller::Polled;
+use crate::rate_limit::RateLimiter;
+use crate::{Error, FurAffinity, SubmissionId};
+use futures::Stream;
+use std::collections::VecDeque;
+use std::time::Duration;
+use tokio::time::Instant;
+
+/// A submission loaded by a [`Coordinator`], by the kind of work that
+/// loaded it.
+#[derive(Debug)]
+pub enum Crawled {
+    /// A newly uploaded submission.
+    Live(Polled),
+    /// A submission that was queued to be loaded again.
+    Refreshed(Polled),
+    /// A historical submission from the backfill range.
+    Backfilled(Polled),
+}
+
+/// Schedules live polling, refreshes, and backfill so that together they
+/// never make requests faster than a single rate limit.
+///
+/// Live submissions are always loaded first, then refreshes, and backfill
+/// only uses the budget left over.
+pub struct Coordinator<'a> {
+    fa: &'a FurAffinity,
+    limiter: RateLimiter,
+    poll_interval: Duration,
+
+    live_next: Option<SubmissionId>,
+    latest: Option<SubmissionId>,
+    next_poll: Option<Instant>,
+    refresh: VecDeque<SubmissionId>,
+    backfill: Option<(SubmissionId, SubmissionId)>,
+}
+
+impl<'a> Coordinator<'a> {
+    /// Create a new coordinator, making requests no faster than the limiter
+    /// allows. The limiter may be shared with other work.
+    pub fn new(fa: &'a FurAffinity, limiter: RateLimiter) -> Self {
+        Self {
+            fa,
+            limiter,
+            poll_interval: Duration::from_secs(60),
+            live_next: None,
+            latest: None,
+            next_poll: None,
+            refresh: VecDeque::new(),
+            backfill: None,
+        }
+    }
+
+    /// Load new submissions as they are uploaded, starting with the given
+    /// ID, checking for new uploads on an interval.
+    pub fn with_live(mut self, start_id: SubmissionId, poll_interval: Duration) -> Self {
+        self.live_next = Some(start_id);
+        self.poll_interval = poll_interval;
+        self
+    }
+
+    /// Load all submissions from `start_id` to `end_id`, inclusive.
+    pub fn with_backfill(mut self, start_id: SubmissionId, end_id: SubmissionId) -> Self {
+        self.backfill = Some((start_id, end_id));
+        self
+    }
+
+    /// Queue submissions to be loaded again.
+    pub fn with_refresh<I: IntoIterator<Item = SubmissionId>>(mut self, ids: I) -> Self {
+        self.refresh.extend(ids);
+        self
+    }
+
+    /// Stream of loaded submissions. Errors are from checking the latest ID,
+    /// and crawling continues after them.
+    ///
+    /// Without live polling, the stream ends once all refreshes and backfill
+    /// are done.
+    pub fn crawl(self) -> impl Stream<Item = Result<Crawled, Error>> + 'a {
+        futures::stream::unfold(self, |mut coordinator| async move {
+            let crawled = coordinator.next().await?;
+            Some((crawled, coordinator))
+        })
+    }
+
+    async fn next(&mut self) -> Option<Result<Crawled, Error>> {
+        loop {
+            let now = Instant::now();
+
+            if self.live_next.is_some() && self.next_poll.is_none_or(|next| next <= now) {
+                self.next_poll = Some(now + self.poll_interval);
+
+                self.limiter.wait().await;
+                match self.fa.latest_id().await {
+                    Ok(latest) => self.latest = Some(latest),
+                    Err(err) => return Some(Err(err)),
+                }
+            }
+
+            if let (Some(id), Some(latest)) = (self.live_next, self.latest) {
+                if id <= latest {
+                    self.live_next = Some(id + 1);
+                    return Some(Ok(Crawled::Live(self.load(id).await)));
+                }
+            }
+
+            if let Some(id) = self.refresh.pop_front() {
+                return Some(Ok(Crawled::Refreshed(self.load(id).await)));
+            }
+
+            if let Some((id, end_id)) = self.backfill {
+                if id <= end_id {
+                    self.backfill = Some((id + 1, end_id));
+                    return Some(Ok(Crawled::Backfilled(self.load(id).await)));
+                }
+            }
+
+            match self.next_poll {
+                Some(next_poll) if self.live_next.is_some() => {
+                    tokio::time::sleep_until(next_poll).await
+                }
+                _ => return None,
+            }
+        }
+    }
+
+    async fn load(&self, id: SubmissionId) -> Polled {
+        self.limiter.wait().await;
+        Polled::load(self.fa, id).await
+    }
+}
@@ -0,0 +1,257 @@
+//! Finding files that have already been seen.
+
+use crate::hash::HashDistance;
+use crate::{Submission, SubmissionId};
+use std::collections::HashMap;
+
+/// A submission found to match one in a [`DedupIndex`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub enum Duplicate {
+    /// A submission with exactly the same file.
+    Exact(SubmissionId),
+    /// A submission with a perceptual hash within the requested distance.
+    Similar { id: SubmissionId, distance: u32 },
+}
+
+struct Node {
+    hash: i64,
+    ids: Vec<SubmissionId>,
+    children: HashMap<u32, usize>,
+}
+
+/// A bk-tree of perceptual hashes, for finding hashes within a distance
+/// without comparing against every hash.
+#[derive(Default)]
+pub struct BkTree {
+    nodes: Vec<Node>,
+}
+
+impl BkTree {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a hash belonging to a submission.
+    pub fn insert(&mut self, hash: i64, id: SubmissionId) {
+        if self.nodes.is_empty() {
+            self.nodes.push(Node {
+                hash,
+                ids: vec![id],
+                children: HashMap::new(),
+            });
+
+            return;
+        }
+
+        let mut index = 0;
+        loop {
+            let node = &mut self.nodes[index];
+            let distance = hash.distance(&node.hash).unwrap_or_default();
+
+            if distance == 0 {
+                if !node.ids.contains(&id) {
+                    node.ids.push(id);
+                }
+
+                return;
+            }
+
+            match node.children.get(&distance) {
+                Some(child) => index = *child,
+                None => {
+                    let child = self.nodes.len();
+                    self.nodes[index].children.insert(distance, child);
+                    self.nodes.push(Node {
+                        hash,
+                        ids: vec![id],
+                        children: HashMap::new(),
+                    });
+
+                    return;
+                }
+            }
+        }
+    }
+
+    /// Find the IDs of submissions with hashes within `max_distance` bits of
+    /// the given hash, closest first.
+    pub fn find(&self, hash: i64, max_distance: u32) -> Vec<(SubmissionId, u32)> {
+        let mut found = Vec::new();
+        let mut pending = if self.nodes.is_empty() {
+            vec![]
+        } else {
+            vec![0]
+        };
+
+        while let Some(index) = pending.pop() {
+            let node = &self.nodes[index];
+            let distance = hash.distance(&node.hash).unwrap_or_default();
+
+            if distance <= max_distance {
+                found.extend(node.ids.iter().map(|id| (*id, distance)));
+            }
+
+            let range = distance.saturating_sub(max_distance)..=distance + max_distance;
+            pending.extend(
+                node.children
+                    .iter()
+                    .filter(|(child_distance, _)| range.contains(child_distance))
+                    .map(|(_, child)| *child),
+            );
+        }
+
+        found.sort_by_key(|(id, distance)| (*distance, *id));
+        found
+    }
+
+    /// Number of distinct hashes in the tree.
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+}
+
+/// An in-memory index of the files of submissions, by SHA-256 digest and
+/// perceptual hash.
+#[derive(Default)]
+pub struct DedupIndex {
+    sha256: HashMap<Vec<u8>, Vec<SubmissionId>>,
+    hashes: BkTree,
+}
+
+impl DedupIndex {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a file's digest and hash, either of which may be unknown.
+    pub fn insert(&mut self, id: SubmissionId, sha256: Option<&[u8]>, hash: Option<i64>) {
+        if let Some(sha256) = sha256 {
+            let ids = self.sha256.entry(sha256.to_vec()).or_default();
+            if !ids.contains(&id) {
+                ids.push(id);
+            }
+        }
+
+        if let Some(hash) = hash {
+            self.hashes.insert(hash, id);
+        }
+    }
+
+    /// Add a hashed submission.
+    pub fn insert_submission(&mut self, sub: &Submission) {
+        self.insert(sub.id, sub.file_sha256.as_deref(), sub.hash_num);
+    }
+
+    /// IDs of submissions with a file matching a SHA-256 digest.
+    pub fn find_sha256(&self, sha256: &[u8]) -> &[SubmissionId] {
+        self.sha256
+            .get(sha256)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    /// IDs of submissions with a perceptual hash within `max_distance` bits,
+    /// closest first.
+    pub fn find_similar(&self, hash: i64, max_distance: u32) -> Vec<(SubmissionId, u32)> {
+        self.hashes.find(hash, max_distance)
+    }
+
+    /// Check if a hashed submission's file has been seen before, preferring
+    /// exact matches. The submission itself is never a match.
+    pub fn seen(&self, sub: &Submission, max_distance: u32) -> Option<Duplicate> {
+        let exact = sub.file_sha256.as_ref().and_then(|sha256| {
+            self.find_sha256(sha256)
+                .iter()
+                .find(|id| **id != sub.id)
+                .copied()
+        });
+
+        if let Some(id) = exact {
+            return Some(Duplicate::Exact(id));
+        }
+
+        let hash = sub.hash_num?;
+        self.find_similar(hash, max_distance)
+            .into_iter()
+            .find(|(id, _distance)| *id != sub.id)
+            .map(|(id, distance)| Duplicate::Similar { id, distance })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::test_submission;
+
+    #[test]
+    fn test_bk_tree() {
+        let mut tree = BkTree::new();
+        for (id, hash) in [
+            (1, 0b0000),
+            (2, 0b0001),
+            (3, 0b0111),
+            (4, 0b1111),
+            (5, 0b0001),
+        ] {
+            tree.insert(hash, SubmissionId(id));
+        }
+
+        assert_eq!(tree.len(), 4);
+        assert_eq!(
+            tree.find(0b0001, 0),
+            vec![(SubmissionId(2), 0), (SubmissionId(5), 0)]
+        );
+        assert_eq!(
+            tree.find(0b0011, 1),
+            vec![
+                (SubmissionId(2), 1),
+                (SubmissionId(3), 1),
+                (SubmissionId(5), 1)
+            ]
+        );
+        assert_eq!(tree.find(0b0000, 4).len(), 5);
+    }
+
+    #[test]
+    fn test_dedup_index() {
+        let mut index = DedupIndex::new();
+
+        let original = Submission {
+            file_sha256: Some(vec![0xab; 32]),
+            hash_num: Some(0b1111),
+            ..test_submission("Sketch", 1617235200, &[])
+        };
+        index.insert_submission(&original);
+        assert_eq!(index.seen(&original, 2), None);
+
+        let reupload = Submission {
+            id: SubmissionId(41234600),
+            ..original.clone()
+        };
+        assert_eq!(
+            index.seen(&reupload, 2),
+            Some(Duplicate::Exact(SubmissionId(41234567)))
+        );
+
+        let edited = Submission {
+            id: SubmissionId(41234601),
+            file_sha256: Some(vec![0xcd; 32]),
+            hash_num: Some(0b1101),
+            ..original.clone()
+        };
+        assert_eq!(
+            index.seen(&edited, 2),
+            Some(Duplicate::Similar {
+                id: SubmissionId(41234567),
+                distance: 1
+            })
+        );
+        assert_eq!(index.seen(&edited, 0), None);
+    }
+}
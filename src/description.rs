@@ -0,0 +1,413 @@
+//! Converting descriptions from FurAffinity's HTML to plain text or Markdown,
+//! and finding the users and pages they link to.
+//!
+//! The title block some pages include is left out, and links to users made
+//! with `:iconname:` or `:linkname:` become their username.
+
+use crate::url::FaUrl;
+use crate::username::Artist;
+use crate::{JournalId, SubmissionId};
+use lazy_static::lazy_static;
+use scraper::{ElementRef, Node, Selector};
+
+lazy_static! {
+    static ref LINK: Selector = Selector::parse("a[href]").unwrap();
+    static ref MENTION: regex::Regex =
+        regex::Regex::new(r":(?:icon|link)([A-Za-z0-9\-_.~]+):").unwrap();
+}
+
+const SITE: &str = "https://www.furaffinity.net";
+
+/// Classes of elements that aren't part of the description itself.
+const SKIPPED_CLASSES: &[&str] = &["submission-title", "submission-id-container"];
+
+/// Users, submissions, and journals a description links to, in the order
+/// they first appear.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct References {
+    /// Users linked with `:iconname:`, `:linkname:`, `@name`, or a link to
+    /// their profile.
+    pub users: Vec<Artist>,
+    pub submissions: Vec<SubmissionId>,
+    pub journals: Vec<JournalId>,
+}
+
+impl References {
+    fn add_user(&mut self, user: Artist) {
+        if !self.users.iter().any(|existing| existing.slug == user.slug) {
+            self.users.push(user);
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Format {
+    Text,
+    Markdown,
+}
+
+/// Convert a description to plain text, keeping line breaks.
+pub fn to_text(html: &str) -> String {
+    convert(html, Format::Text)
+}
+
+/// Convert a description to Markdown, keeping bold, italic, strikethrough,
+/// quotes, and links.
+pub fn to_markdown(html: &str) -> String {
+    convert(html, Format::Markdown)
+}
+
+/// Find the users, submissions, and journals a description links to,
+/// including the previous, first, and next links of a series.
+///
+/// Mentions that FurAffinity left as text, such as `:iconname:` in a quote,
+/// are included too.
+pub fn references(html: &str) -> References {
+    let fragment = scraper::Html::parse_fragment(html);
+    let mut references = References::default();
+
+    for link in fragment.select(&LINK) {
+        if is_skipped(link) {
+            continue;
+        }
+
+        let href = match link.value().attr("href") {
+            Some(href) => absolute_url(href),
+            None => continue,
+        };
+
+        match FaUrl::parse(&href) {
+            Some(FaUrl::User(slug)) => {
+                let name = match user_name(link) {
+                    name if crate::username::is_valid(&name) => name,
+                    _ => slug,
+                };
+
+                references.add_user(Artist::parse(&name, Some(&href)));
+            }
+            Some(FaUrl::View(id)) if !references.submissions.contains(&id) => {
+                references.submissions.push(id)
+            }
+            Some(FaUrl::Journal(id)) if !references.journals.contains(&id) => {
+                references.journals.push(id)
+            }
+            _ => (),
+        }
+    }
+
+    for text in fragment.root_element().text() {
+        for mention in MENTION.captures_iter(text) {
+            references.add_user(Artist::parse(&mention[1], None));
+        }
+    }
+
+    references
+}
+
+fn convert(html: &str, format: Format) -> String {
+    let fragment = scraper::Html::parse_fragment(html);
+
+    let mut out = String::new();
+    render_children(fragment.root_element(), format, &mut out);
+
+    tidy(&out)
+}
+
+fn render_children(elem: ElementRef, format: Format, out: &mut String) {
+    for child in elem.children() {
+        match child.value() {
+            Node::Text(text) => push_text(text, format, out),
+            Node::Element(_) => {
+                if let Some(child) = ElementRef::wrap(child) {
+                    render_element(child, format, out);
+                }
+            }
+            _ => (),
+        }
+    }
+}
+
+fn render_element(elem: ElementRef, format: Format, out: &mut String) {
+    let value = elem.value();
+    if has_skipped_class(elem) {
+        return;
+    }
+
+    let markdown = format == Format::Markdown;
+
+    match value.name() {
+        "script" | "style" | "img" => (),
+        "br" => out.push('\n'),
+        "hr" if markdown => out.push_str("\n\n---\n\n"),
+        "hr" => out.push('\n'),
+        "b" | "strong" if markdown => wrap(elem, "**", out),
+        "i" | "em" if markdown => wrap(elem, "*", out),
+        "s" | "strike" | "del" if markdown => wrap(elem, "~~", out),
+        "code" if markdown => wrap(elem, "`", out),
+        "a" => render_link(elem, format, out),
+        "blockquote" => render_quote(elem, format, out),
+        "span" if value.classes().any(|class| class == "bbcode_quote") => {
+            render_quote(elem, format, out)
+        }
+        "p" | "div" | "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => {
+            out.push('\n');
+            render_children(elem, format, out);
+            out.push('\n');
+        }
+        _ => render_children(elem, format, out),
+    }
+}
+
+/// Surround an element's contents with a Markdown marker, keeping
+/// surrounding whitespace outside of the marker so it stays valid.
+fn wrap(elem: ElementRef, marker: &str, out: &mut String) {
+    let mut inner = String::new();
+    render_children(elem, Format::Markdown, &mut inner);
+
+    let trimmed = inner.trim();
+    if trimmed.is_empty() {
+        out.push_str(&inner);
+        return;
+    }
+
+    let start = inner.len() - inner.trim_start().len();
+    let end = inner.trim_end().len();
+
+    out.push_str(&inner[..start]);
+    out.push_str(marker);
+    out.push_str(trimmed);
+    out.push_str(marker);
+    out.push_str(&inner[end..]);
+}
+
+fn render_link(elem: ElementRef, format: Format, out: &mut String) {
+    let value = elem.value();
+    let href = value.attr("href").map(absolute_url);
+    let is_user = value
+        .classes()
+        .any(|class| class == "iconusername" || class == "linkusername");
+
+    let mut text = String::new();
+    if is_user {
+        text = user_name(elem);
+
+        if format == Format::Markdown {
+            text = escape_markdown(&text);
+        }
+    } else {
+        render_children(elem, format, &mut text);
+    }
+
+    let href = match href {
+        Some(href) if format == Format::Markdown => href,
+        _ => {
+            out.push_str(&text);
+            return;
+        }
+    };
+
+    let href = match FaUrl::parse(&href) {
+        Some(url) if is_user => url.canonical(),
+        _ => href,
+    };
+
+    let label = text.trim();
+    if label.is_empty() || label == escape_markdown(&href) {
+        out.push('<');
+        out.push_str(&href);
+        out.push('>');
+    } else {
+        out.push('[');
+        out.push_str(label);
+        out.push_str("](");
+        out.push_str(&href.replace(')', "%29"));
+        out.push(')');
+    }
+}
+
+/// Name shown for a link to a user. Links with only an icon have the name on
+/// the image.
+fn user_name(link: ElementRef) -> String {
+    let text = crate::join_text_nodes(link).replace('\u{a0}', " ");
+    let text = text.trim();
+    if !text.is_empty() {
+        return text.to_string();
+    }
+
+    link.descendants()
+        .filter_map(|node| node.value().as_element())
+        .find_map(|img| img.attr("title").or_else(|| img.attr("alt")))
+        .unwrap_or_default()
+        .trim()
+        .to_string()
+}
+
+fn has_skipped_class(elem: ElementRef) -> bool {
+    elem.value()
+        .classes()
+        .any(|class| SKIPPED_CLASSES.contains(&class))
+}
+
+/// If an element is in, or is, one that isn't part of the description.
+fn is_skipped(elem: ElementRef) -> bool {
+    std::iter::once(elem)
+        .chain(elem.ancestors().filter_map(ElementRef::wrap))
+        .any(has_skipped_class)
+}
+
+fn render_quote(elem: ElementRef, format: Format, out: &mut String) {
+    let mut inner = String::new();
+    render_children(elem, format, &mut inner);
+    let inner = tidy(&inner);
+
+    out.push('\n');
+    for line in inner.lines() {
+        match format {
+            Format::Markdown if line.is_empty() => out.push('>'),
+            Format::Markdown => {
+                out.push_str("> ");
+                out.push_str(line);
+            }
+            Format::Text => out.push_str(line),
+        }
+        out.push('\n');
+    }
+}
+
+/// Add text as a browser would show it, with runs of whitespace collapsed.
+fn push_text(text: &str, format: Format, out: &mut String) {
+    let mut collapsed = String::with_capacity(text.len());
+    let mut space = false;
+
+    for c in text.chars() {
+        if c.is_whitespace() {
+            space = true;
+            continue;
+        }
+
+        if space {
+            collapsed.push(' ');
+            space = false;
+        }
+        collapsed.push(c);
+    }
+    if space {
+        collapsed.push(' ');
+    }
+
+    match format {
+        Format::Text => out.push_str(&collapsed),
+        Format::Markdown => out.push_str(&escape_markdown(&collapsed)),
+    }
+}
+
+/// Trim each line and allow at most one blank line in a row.
+fn tidy(text: &str) -> String {
+    let mut lines: Vec<&str> = Vec::new();
+
+    for line in text.lines().map(str::trim) {
+        if line.is_empty() && lines.last().is_none_or(|last| last.is_empty()) {
+            continue;
+        }
+        lines.push(line);
+    }
+
+    while lines.last() == Some(&"") {
+        lines.pop();
+    }
+
+    lines.join("\n")
+}
+
+fn absolute_url(href: &str) -> String {
+    if href.starts_with("//") {
+        format!("https:{}", href)
+    } else if href.starts_with('/') {
+        format!("{}{}", SITE, href)
+    } else {
+        href.to_string()
+    }
+}
+
+/// Escape characters that have a meaning in Markdown.
+pub(crate) fn escape_markdown(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+
+    for c in text.chars() {
+        if matches!(
+            c,
+            '\\' | '*' | '_' | '~' | '`' | '|' | '>' | '[' | ']' | '(' | ')' | '#'
+        ) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DESCRIPTION: &str = r#"
+        <div class="submission-title"><h2><p>Bilberry fox</p></h2></div>
+        <div class="section-body">
+            Fox eating <b>bilberries </b>with <a href="/user/example/" class="iconusername"><img src="//a.furaffinity.net/20190101/example.gif" title="Example" alt="Example">&nbsp;Example</a>!<br>
+            <br>
+            Drawn for <a href="/user/friend/" class="iconusername"><img src="//a.furaffinity.net/20190101/friend.gif" title="Friend" alt="Friend"></a>, see <a href="https://example.com/a_b" class="auto_link">https://example.com/a_b</a>
+            <span class="bbcode_quote">Lovely <i>fox</i></span>
+            <s>old</s> <a href="/view/31209021/">prev</a>
+        </div>"#;
+
+    #[test]
+    fn test_references() {
+        let page = DESCRIPTION.replace(
+            "</div>",
+            r#"<a href="/user/example">again</a> <a href="/journal/10123456/">journal</a>
+            :iconquoted_user: <a href="/user/deadrussiansoul/">my gallery</a></div>"#,
+        );
+        let references = references(&page);
+
+        let users: Vec<_> = references
+            .users
+            .iter()
+            .map(|user| (user.slug.as_str(), user.display_name.as_str()))
+            .collect();
+        assert_eq!(
+            users,
+            vec![
+                ("example", "Example"),
+                ("friend", "Friend"),
+                ("deadrussiansoul", "deadrussiansoul"),
+                ("quoteduser", "quoted_user"),
+            ]
+        );
+        assert_eq!(references.submissions, vec![SubmissionId(31209021)]);
+        assert_eq!(references.journals, vec![JournalId(10123456)]);
+    }
+
+    #[test]
+    fn test_to_text() {
+        assert_eq!(
+            to_text(DESCRIPTION),
+            "Fox eating bilberries with Example!\n\n\
+             Drawn for Friend, see https://example.com/a_b\n\
+             Lovely fox\n\
+             old prev"
+        );
+    }
+
+    #[test]
+    fn test_to_markdown() {
+        assert_eq!(
+            to_markdown(DESCRIPTION),
+            "Fox eating **bilberries** with [Example](https://www.furaffinity.net/user/example/)!\n\n\
+             Drawn for [Friend](https://www.furaffinity.net/user/friend/), see <https://example.com/a_b>\n\
+             > Lovely *fox*\n\
+             ~~old~~ [prev](https://www.furaffinity.net/view/31209021/)"
+        );
+    }
+}
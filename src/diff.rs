@@ -0,0 +1,124 @@
+//! Finding what changed between two versions of a submission.
+
+use crate::{file_timestamp, Rating, Submission};
+
+/// A value that changed, along with what it was before.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Change<T> {
+    pub old: T,
+    pub new: T,
+}
+
+/// Everything that changed between two versions of a submission.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct SubmissionDiff {
+    pub title: Option<Change<String>>,
+    pub description: Option<Change<String>>,
+    pub rating: Option<Change<Rating>>,
+    pub tags_added: Vec<String>,
+    pub tags_removed: Vec<String>,
+    /// If the submission's file was replaced with a different one.
+    pub file_replaced: bool,
+}
+
+impl SubmissionDiff {
+    /// If nothing changed.
+    pub fn is_empty(&self) -> bool {
+        self == &Self::default()
+    }
+}
+
+/// Find what changed between two versions of a submission.
+///
+/// Files are compared by their SHA-256 digests if both versions were hashed,
+/// otherwise by the upload timestamp in their URLs.
+pub fn diff_submissions(old: &Submission, new: &Submission) -> SubmissionDiff {
+    SubmissionDiff {
+        title: change(&old.title, &new.title),
+        description: change(&old.description, &new.description),
+        rating: change(&old.rating, &new.rating),
+        tags_added: missing_from(&new.tags, &old.tags),
+        tags_removed: missing_from(&old.tags, &new.tags),
+        file_replaced: file_replaced(old, new),
+    }
+}
+
+fn change<T: Clone + PartialEq>(old: &T, new: &T) -> Option<Change<T>> {
+    if old == new {
+        return None;
+    }
+
+    Some(Change {
+        old: old.clone(),
+        new: new.clone(),
+    })
+}
+
+/// Items in `items` that are not in `other`.
+pub(crate) fn missing_from(items: &[String], other: &[String]) -> Vec<String> {
+    items
+        .iter()
+        .filter(|item| !other.contains(item))
+        .cloned()
+        .collect()
+}
+
+fn file_replaced(old: &Submission, new: &Submission) -> bool {
+    if let (Some(old), Some(new)) = (&old.file_sha256, &new.file_sha256) {
+        return old != new;
+    }
+
+    let (old_url, new_url) = (old.content.url(), new.content.url());
+
+    match (file_timestamp(old_url), file_timestamp(new_url)) {
+        (Some(old), Some(new)) => old != new,
+        _ => old_url != new_url,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::test_submission;
+
+    #[test]
+    fn test_diff_submissions() {
+        let old = test_submission("Sketch", 1617235200, &["fox", "sketch"]);
+
+        assert!(diff_submissions(&old, &old).is_empty());
+
+        let new = test_submission("Finished", 1617321600, &["fox", "colored"]);
+        let diff = diff_submissions(&old, &new);
+
+        assert_eq!(
+            diff.title,
+            Some(Change {
+                old: "Sketch".into(),
+                new: "Finished".into()
+            })
+        );
+        assert_eq!(diff.description, None);
+        assert_eq!(diff.rating, None);
+        assert_eq!(diff.tags_added, vec!["colored".to_string()]);
+        assert_eq!(diff.tags_removed, vec!["sketch".to_string()]);
+        assert!(diff.file_replaced);
+    }
+
+    #[test]
+    fn test_file_replaced_by_digest() {
+        let old = Submission {
+            file_sha256: Some(vec![1; 32]),
+            ..test_submission("Sketch", 1617235200, &[])
+        };
+        let new = Submission {
+            file_sha256: Some(vec![1; 32]),
+            ..test_submission("Sketch", 1617321600, &[])
+        };
+
+        assert!(!diff_submissions(&old, &new).file_replaced);
+    }
+}
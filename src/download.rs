@@ -0,0 +1,358 @@
+//! Saving submission files to disk.
+
+use crate::file_store::{self, FileStore};
+use crate::{Error, FurAffinity, Submission, SubmissionId};
+use lazy_static::lazy_static;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::io::AsyncWriteExt;
+
+lazy_static! {
+    static ref TEMPLATE_FIELD: regex::Regex = regex::Regex::new(r"\{(\w+)\}").unwrap();
+}
+
+/// Name of the manifest file, kept in the root of the download directory.
+pub const MANIFEST_NAME: &str = "manifest.tsv";
+
+/// What happened when downloading a submission's file.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub enum Download {
+    /// The file was saved to this path.
+    Saved(PathBuf),
+    /// A file with the same contents was already saved to this path.
+    Duplicate(PathBuf),
+}
+
+/// A file that was saved, as recorded in the manifest.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct ManifestEntry {
+    pub sha256: Vec<u8>,
+    pub id: SubmissionId,
+    /// Path of the file, relative to the download directory.
+    pub path: PathBuf,
+}
+
+impl ManifestEntry {
+    fn parse(line: &str) -> Option<Self> {
+        let mut parts = line.splitn(3, '\t');
+
+        Some(Self {
+            sha256: from_hex(parts.next()?)?,
+            id: parts.next()?.parse().ok()?,
+            path: parts.next()?.into(),
+        })
+    }
+
+    fn to_line(&self) -> String {
+        format!(
+            "{}\t{}\t{}\n",
+            crate::hash::to_hex(&self.sha256),
+            self.id,
+            self.path.display()
+        )
+    }
+}
+
+/// Saves submission files into a directory, skipping files that have
+/// already been saved.
+///
+/// Every saved file is recorded in a manifest in the root of the directory,
+/// as tab separated lines of the file's SHA-256 digest in hex, the
+/// submission ID, and the file's path.
+pub struct Downloader<'a> {
+    fa: &'a FurAffinity,
+    root: PathBuf,
+    template: String,
+    saved: HashMap<Vec<u8>, PathBuf>,
+    file_store: Option<Arc<dyn FileStore>>,
+}
+
+impl<'a> Downloader<'a> {
+    /// Open a download directory, creating it if needed and loading the
+    /// manifest of files that have already been saved.
+    pub async fn open<P: Into<PathBuf>>(
+        fa: &'a FurAffinity,
+        root: P,
+    ) -> Result<Downloader<'a>, Error> {
+        let root = root.into();
+        tokio::fs::create_dir_all(&root).await?;
+
+        let saved = match tokio::fs::read_to_string(root.join(MANIFEST_NAME)).await {
+            Ok(manifest) => manifest
+                .lines()
+                .filter_map(ManifestEntry::parse)
+                .map(|entry| (entry.sha256, entry.path))
+                .collect(),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => HashMap::new(),
+            Err(err) => return Err(err.into()),
+        };
+
+        Ok(Self {
+            fa,
+            root,
+            template: "{artist}/{id}_{filename}".to_string(),
+            saved,
+            file_store: None,
+        })
+    }
+
+    /// Template for the path of saved files, relative to the download
+    /// directory. Defaults to `{artist}/{id}_{filename}`.
+    ///
+    /// Available fields are `{id}`, `{artist}`, `{title}`, `{filename}`,
+    /// `{ext}`, and `{rating}`. Path separators are removed from values.
+    pub fn with_template<T: Into<String>>(mut self, template: T) -> Self {
+        self.template = template.into();
+        self
+    }
+
+    /// Also keep saved files in a store. Submissions with a digest that is
+    /// in the store are copied from it instead of being downloaded.
+    pub fn with_file_store(mut self, store: Arc<dyn FileStore>) -> Self {
+        self.file_store = Some(store);
+        self
+    }
+
+    /// Save a submission's file.
+    ///
+    /// If the submission already has a digest that was saved, the file is
+    /// not downloaded again. Otherwise it is downloaded and only saved if no
+    /// file with the same contents exists.
+    pub async fn download(&mut self, sub: &Submission) -> Result<Download, Error> {
+        if let Some(path) = sub
+            .file_sha256
+            .as_ref()
+            .and_then(|sha256| self.saved.get(sha256))
+        {
+            return Ok(Download::Duplicate(self.root.join(path)));
+        }
+
+        let temp = self.root.join(format!(".{}.download", sub.id));
+        let sha256 = match self.fetch_to(sub, &temp).await {
+            Ok(sha256) => sha256,
+            Err(err) => {
+                let _ = tokio::fs::remove_file(&temp).await;
+                return Err(err);
+            }
+        };
+
+        if let Some(path) = self.saved.get(&sha256) {
+            tokio::fs::remove_file(&temp).await?;
+            return Ok(Download::Duplicate(self.root.join(path)));
+        }
+
+        let path = PathBuf::from(render_template(&self.template, sub));
+        let dest = self.root.join(&path);
+
+        if let Some(parent) = dest.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        tokio::fs::rename(&temp, &dest).await?;
+
+        let entry = ManifestEntry {
+            sha256,
+            id: sub.id,
+            path,
+        };
+
+        let mut manifest = tokio::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(self.root.join(MANIFEST_NAME))
+            .await?;
+        manifest.write_all(entry.to_line().as_bytes()).await?;
+
+        self.saved.insert(entry.sha256, entry.path);
+
+        Ok(Download::Saved(dest))
+    }
+
+    /// Write a submission's file to a path from the store if it's there,
+    /// otherwise downloading it. Returns the file's SHA-256 digest.
+    async fn fetch_to(&self, sub: &Submission, path: &Path) -> Result<Vec<u8>, Error> {
+        let store = match &self.file_store {
+            Some(store) => store.as_ref(),
+            None => return self.download_to(sub, path).await,
+        };
+
+        if let Some(sha256) = &sub.file_sha256 {
+            if let Some(data) = store.get(sha256).await? {
+                tokio::fs::write(path, data).await?;
+                return Ok(sha256.clone());
+            }
+        }
+
+        let sha256 = self.download_to(sub, path).await?;
+        let data = tokio::fs::read(path).await?;
+        file_store::put_if_missing(store, &sha256, &data).await?;
+
+        Ok(sha256)
+    }
+
+    /// Stream a submission's file to a path, returning its SHA-256 digest.
+    async fn download_to(&self, sub: &Submission, path: &Path) -> Result<Vec<u8>, Error> {
+        let resp = self.fa.load_file(sub.content.url()).await?;
+
+        if !resp.status().is_success() {
+            return Err(Error::from_status(resp.status().as_u16()));
+        }
+
+        let mut file = tokio::fs::File::create(path).await?;
+        let file = self.fa.read_file(resp, None, Some(&mut file)).await?;
+        file.check_complete()?;
+
+        Ok(file.digests.sha256)
+    }
+}
+
+/// Fill in a path template with the details of a submission.
+fn render_template(template: &str, sub: &Submission) -> String {
+    TEMPLATE_FIELD
+        .replace_all(template, |captures: &regex::Captures| {
+            let value = match &captures[1] {
+                "id" => sub.id.to_string(),
+                "artist" => sub.artist.clone(),
+                "title" => sub.title.clone(),
+                "filename" => sub.filename.clone(),
+                "ext" => sub.ext.clone(),
+                "rating" => sub.rating.serialize(),
+                _ => return captures[0].to_string(),
+            };
+
+            sanitize(&value)
+        })
+        .into_owned()
+}
+
+/// Make a value safe to use as part of a path, so it can't create extra
+/// directories or refer to a parent directory.
+fn sanitize(value: &str) -> String {
+    let value: String = value
+        .chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect();
+
+    match value.trim_start_matches('.') {
+        "" => "_".to_string(),
+        value => value.to_string(),
+    }
+}
+
+fn from_hex(hex: &str) -> Option<Vec<u8>> {
+    if hex.len() % 2 != 0 || !hex.is_ascii() {
+        return None;
+    }
+
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::test_submission;
+    use crate::{test_utils, Content};
+
+    #[test]
+    fn test_render_template() {
+        let sub = Submission {
+            artist: "../Example".into(),
+            ..test_submission("Sketch/WIP", 1617235200, &[])
+        };
+
+        assert_eq!(
+            render_template("{artist}/{id}_{filename}", &sub),
+            "_Example/41234567_1617235200.example_image.png"
+        );
+        assert_eq!(
+            render_template("{rating}/{title}.{ext}", &sub),
+            "g/Sketch_WIP.png"
+        );
+        assert_eq!(
+            render_template("{unknown}/{id}", &sub),
+            "{unknown}/41234567"
+        );
+    }
+
+    #[test]
+    fn test_manifest_entry() {
+        let entry = ManifestEntry {
+            sha256: vec![0x0f, 0xa0],
+            id: SubmissionId(41234567),
+            path: "Example/41234567_image.png".into(),
+        };
+
+        let line = entry.to_line();
+        assert_eq!(line, "0fa0\t41234567\tExample/41234567_image.png\n");
+        assert_eq!(ManifestEntry::parse(line.trim_end()), Some(entry));
+        assert_eq!(ManifestEntry::parse("zz\t1\tpath"), None);
+    }
+
+    #[tokio::test]
+    async fn test_download() {
+        let server = test_utils::MockServer::start().await.unwrap();
+        server.route("/art/example/1/first.png", 200, "first");
+        server.route("/art/example/2/copy.png", 200, "first");
+        server.route("/art/example/3/second.png", 200, "second");
+        let fa = server.client();
+
+        let sub = |id, path: &str| Submission {
+            id: SubmissionId(id),
+            content: Content::Image(format!("{}/art/example/{}.png", server.url(), path)),
+            ..test_submission("Sketch", 1617235200, &[])
+        };
+
+        let root = std::env::temp_dir().join(format!("fa-download-{}", std::process::id()));
+        let mut downloader = Downloader::open(&fa, &root)
+            .await
+            .unwrap()
+            .with_template("{id}.{ext}");
+
+        let first = downloader.download(&sub(1, "1/first")).await.unwrap();
+        assert_eq!(first, Download::Saved(root.join("1.png")));
+        assert_eq!(std::fs::read(root.join("1.png")).unwrap(), b"first");
+
+        // Same contents under a different submission are not saved again.
+        let copy = downloader.download(&sub(2, "2/copy")).await.unwrap();
+        assert_eq!(copy, Download::Duplicate(root.join("1.png")));
+        assert!(!root.join("2.png").exists());
+
+        let second = downloader.download(&sub(3, "3/second")).await.unwrap();
+        assert_eq!(second, Download::Saved(root.join("3.png")));
+
+        let first_sha256 = crate::hash::digest_file(&Default::default(), b"first").sha256;
+        let manifest = std::fs::read_to_string(root.join(MANIFEST_NAME)).unwrap();
+        let lines: Vec<&str> = manifest.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(
+            lines[0],
+            format!("{}\t1\t1.png", crate::hash::to_hex(&first_sha256))
+        );
+
+        // Reopening loads the manifest, so a known digest isn't downloaded.
+        let requests = server.requests().len();
+        let mut downloader = Downloader::open(&fa, &root).await.unwrap();
+        let known = Submission {
+            file_sha256: Some(first_sha256),
+            ..sub(4, "1/first")
+        };
+        assert_eq!(
+            downloader.download(&known).await.unwrap(),
+            Download::Duplicate(root.join("1.png"))
+        );
+        assert_eq!(server.requests().len(), requests);
+
+        std::fs::remove_dir_all(root).unwrap();
+    }
+}
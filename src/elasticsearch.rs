@@ -0,0 +1,113 @@
+//! Indexing submissions in Elasticsearch or OpenSearch.
+//!
+//! [`mapping`] provides the index mapping, and [`document`] converts a
+//! submission to a document for that mapping. Documents should be indexed
+//! with the submission ID as their `_id`, so re-indexing a submission
+//! replaces it.
+//!
+//! | Field         | Type                                 |
+//! |---------------|--------------------------------------|
+//! | `id`          | `long`                               |
+//! | `artist`      | `keyword`, lowercased                |
+//! | `title`       | `text`, with a `title.raw` `keyword` |
+//! | `rating`      | `keyword`, one of `g`, `m`, or `a`   |
+//! | `content_type`| `keyword`, such as `image` or `text` |
+//! | `url`         | `keyword`, not indexed               |
+//! | `ext`         | `keyword`                            |
+//! | `posted_at`   | `date`                               |
+//! | `tags`        | `keyword`, lowercased                |
+//! | `description` | `text`, without HTML                 |
+//! | `hash_num`    | `long`                               |
+//! | `file_sha256` | `keyword`, lowercase hex             |
+
+use crate::Submission;
+use serde_json::{json, Value};
+
+/// The index mapping for submission documents.
+pub fn mapping() -> Value {
+    json!({
+        "settings": {
+            "analysis": {
+                "normalizer": {
+                    "lowercase": {
+                        "type": "custom",
+                        "filter": ["lowercase"]
+                    }
+                }
+            }
+        },
+        "mappings": {
+            "dynamic": "strict",
+            "properties": {
+                "id": { "type": "long" },
+                "artist": { "type": "keyword", "normalizer": "lowercase" },
+                "title": {
+                    "type": "text",
+                    "fields": {
+                        "raw": { "type": "keyword" }
+                    }
+                },
+                "rating": { "type": "keyword" },
+                "content_type": { "type": "keyword" },
+                "url": { "type": "keyword", "index": false },
+                "ext": { "type": "keyword" },
+                "posted_at": { "type": "date" },
+                "tags": { "type": "keyword", "normalizer": "lowercase" },
+                "description": { "type": "text" },
+                "hash_num": { "type": "long" },
+                "file_sha256": { "type": "keyword" }
+            }
+        }
+    })
+}
+
+/// Convert a submission to a document for the index mapping.
+pub fn document(sub: &Submission) -> Value {
+    let file_sha256 = sub.file_sha256.as_deref().map(crate::hash::to_hex);
+
+    json!({
+        "id": sub.id,
+        "artist": sub.artist,
+        "title": sub.title,
+        "rating": sub.rating.code(),
+        "content_type": sub.content.kind(),
+        "url": sub.content.url(),
+        "ext": sub.ext,
+        "posted_at": sub.posted_at.to_rfc3339(),
+        "tags": sub.tags,
+        "description": crate::fragment_text(&sub.description),
+        "hash_num": sub.hash_num,
+        "file_sha256": file_sha256,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::test_submission;
+
+    #[test]
+    fn test_document() {
+        let sub = Submission {
+            description: "A <b>fox</b><br>\n  in the snow".into(),
+            file_sha256: Some(vec![0xab, 0x01]),
+            ..test_submission("Snow", 1617285600, &["fox", "winter"])
+        };
+
+        let doc = document(&sub);
+
+        assert_eq!(doc["id"], 41234567);
+        assert_eq!(doc["rating"], "g");
+        assert_eq!(doc["tags"], json!(["fox", "winter"]));
+        assert_eq!(doc["description"], "A fox in the snow");
+        assert_eq!(doc["file_sha256"], "ab01");
+        assert_eq!(doc["hash_num"], Value::Null);
+
+        // Every field in the document must be in the strict mapping.
+        let properties = &mapping()["mappings"]["properties"];
+        assert_eq!(properties["id"]["type"], "long");
+        for field in doc.as_object().unwrap().keys() {
+            assert!(properties.get(field).is_some(), "{} is not mapped", field);
+        }
+    }
+}
@@ -0,0 +1,121 @@
+//! Sharing what pollers and watchers find with multiple consumers.
+
+use crate::poller::Polled;
+use crate::watcher::{NewJournal, NewSubmission};
+use crate::{Error, Submission, SubmissionId};
+use futures::{Stream, StreamExt};
+use std::sync::Arc;
+use tokio::sync::broadcast;
+
+/// Something that was found while watching FurAffinity.
+#[derive(Clone, Debug)]
+pub enum Event {
+    /// A submission was loaded.
+    NewSubmission(Arc<Submission>),
+    /// There was no submission that could be viewed with this ID.
+    SubmissionRemoved(SubmissionId),
+    /// Loading a submission failed.
+    SubmissionFailed(SubmissionId, Arc<Error>),
+    /// A submission appeared in a watched user's gallery.
+    GallerySubmission(NewSubmission),
+    /// A watched user posted a journal.
+    NewJournal(NewJournal),
+    /// Something being forwarded had an error, but continued.
+    Error(Arc<Error>),
+}
+
+impl From<Polled> for Event {
+    fn from(polled: Polled) -> Self {
+        match polled {
+            Polled::Submission(sub) => Event::NewSubmission(Arc::new(*sub)),
+            Polled::Missing(id) => Event::SubmissionRemoved(id),
+            Polled::Failed(id, err) => Event::SubmissionFailed(id, Arc::new(err)),
+        }
+    }
+}
+
+impl From<NewSubmission> for Event {
+    fn from(sub: NewSubmission) -> Self {
+        Event::GallerySubmission(sub)
+    }
+}
+
+impl From<NewJournal> for Event {
+    fn from(journal: NewJournal) -> Self {
+        Event::NewJournal(journal)
+    }
+}
+
+/// Publishes events to every subscriber.
+///
+/// Subscribers that fall more than the bus's capacity behind miss the
+/// oldest events, as with [`tokio::sync::broadcast`].
+#[derive(Clone)]
+pub struct EventBus {
+    sender: broadcast::Sender<Event>,
+}
+
+impl EventBus {
+    /// Create a new bus, holding up to `capacity` events for subscribers
+    /// that haven't received them yet.
+    pub fn new(capacity: usize) -> Self {
+        let (sender, _receiver) = broadcast::channel(capacity);
+
+        Self { sender }
+    }
+
+    /// Receive all events published after subscribing.
+    pub fn subscribe(&self) -> broadcast::Receiver<Event> {
+        self.sender.subscribe()
+    }
+
+    /// Publish an event, returning how many subscribers will receive it.
+    pub fn publish<E: Into<Event>>(&self, event: E) -> usize {
+        self.sender.send(event.into()).unwrap_or(0)
+    }
+
+    /// Publish everything from a stream, such as a poller or watcher, until
+    /// it ends.
+    pub async fn forward<S, T>(&self, stream: S)
+    where
+        S: Stream<Item = Result<T, Error>>,
+        T: Into<Event>,
+    {
+        futures::pin_mut!(stream);
+
+        while let Some(item) = stream.next().await {
+            match item {
+                Ok(item) => self.publish(item),
+                Err(err) => self.publish(Event::Error(Arc::new(err))),
+            };
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_event_bus() {
+        let bus = EventBus::new(16);
+        assert_eq!(bus.publish(Polled::Missing(SubmissionId(1))), 0);
+
+        let mut first = bus.subscribe();
+        let mut second = bus.subscribe();
+
+        let polled = vec![
+            Ok(Polled::Missing(SubmissionId(2))),
+            Err(Error::new("latest id unavailable", true)),
+        ];
+        bus.forward(futures::stream::iter(polled)).await;
+
+        for receiver in [&mut first, &mut second].iter_mut() {
+            assert!(matches!(
+                receiver.recv().await,
+                Ok(Event::SubmissionRemoved(SubmissionId(2)))
+            ));
+            assert!(matches!(receiver.recv().await, Ok(Event::Error(_))));
+        }
+    }
+}
@@ -0,0 +1,514 @@
+//! Exporting submissions to files for use by other tools.
+//!
+//! Submissions are written as JSON Lines, one [`SubmissionRecord`] per line,
+//! or as CSV with only the most commonly used metadata. With the `parquet`
+//! feature, metadata can also be written to Parquet.
+//!
+//! [`to_json`], [`to_json_array`], and [`write_csv_rows`] use the same
+//! formats for submissions that were already collected, such as a batch
+//! from a poller.
+
+use crate::{Submission, SubmissionId};
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+
+/// Version of the [`SubmissionRecord`] schema, included in every record.
+///
+/// The version is increased whenever a field is removed or changes meaning.
+/// Fields may be added without changing the version.
+pub const SCHEMA_VERSION: u32 = 2;
+
+/// An exported submission.
+///
+/// Digests are base64, as are perceptual hashes of their big endian bytes,
+/// and dates are RFC 3339 in UTC. Optional fields are `null` when they were
+/// not calculated.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SubmissionRecord {
+    pub schema_version: u32,
+    pub id: SubmissionId,
+    pub title: String,
+    pub artist: String,
+    /// One of `g`, `m`, or `a`.
+    pub rating: String,
+    /// Kind of content, such as `image` or `text`.
+    pub content_type: String,
+    pub url: String,
+    pub filename: String,
+    pub ext: String,
+    pub posted_at: String,
+    pub tags: Vec<String>,
+    /// Description HTML.
+    pub description: String,
+    pub hash: Option<String>,
+    pub hash_num: Option<i64>,
+    pub file_size: Option<usize>,
+    pub file_sha256: Option<String>,
+    pub file_md5: Option<String>,
+    pub file_blake3: Option<String>,
+    pub file_mime: Option<String>,
+    /// Base64 of the file's contents, if included in the export.
+    pub file: Option<String>,
+    /// Where the file was saved, if it was saved separately from the export.
+    pub file_path: Option<String>,
+}
+
+impl SubmissionRecord {
+    /// Create a record for a submission, optionally including the contents
+    /// of its file.
+    pub fn new(sub: &Submission, include_file: bool) -> Self {
+        Self {
+            schema_version: SCHEMA_VERSION,
+            id: sub.id,
+            title: sub.title.clone(),
+            artist: sub.artist.clone(),
+            rating: sub.rating.serialize(),
+            content_type: sub.content.kind().to_string(),
+            url: sub.content.url().to_string(),
+            filename: sub.filename.clone(),
+            ext: sub.ext.clone(),
+            posted_at: sub.posted_at.to_rfc3339(),
+            tags: sub.tags.clone(),
+            description: sub.description.clone(),
+            hash: sub.hash.clone(),
+            hash_num: sub.hash_num,
+            file_size: sub.file_size,
+            file_sha256: sub.file_sha256.as_deref().map(base64::encode),
+            file_md5: sub.file_md5.as_deref().map(base64::encode),
+            file_blake3: sub.file_blake3.as_deref().map(base64::encode),
+            file_mime: sub.file_mime.clone(),
+            file: sub
+                .file
+                .as_deref()
+                .filter(|_| include_file)
+                .map(base64::encode),
+            file_path: None,
+        }
+    }
+
+    /// Record where the submission's file was saved.
+    pub fn with_file_path<P: Into<String>>(mut self, path: P) -> Self {
+        self.file_path = Some(path.into());
+        self
+    }
+}
+
+/// Writes submissions as JSON Lines.
+pub struct JsonLinesExporter<W> {
+    writer: W,
+    include_files: bool,
+}
+
+impl<W: Write> JsonLinesExporter<W> {
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer,
+            include_files: false,
+        }
+    }
+
+    /// Include the contents of files, if they were retained, as base64.
+    pub fn with_files(mut self, include_files: bool) -> Self {
+        self.include_files = include_files;
+        self
+    }
+
+    /// Write a submission.
+    pub fn write(&mut self, sub: &Submission) -> std::io::Result<()> {
+        self.write_record(&SubmissionRecord::new(sub, self.include_files))
+    }
+
+    /// Write a record, such as one with a file path set.
+    pub fn write_record(&mut self, record: &SubmissionRecord) -> std::io::Result<()> {
+        serde_json::to_writer(&mut self.writer, record)?;
+        self.writer.write_all(b"\n")
+    }
+
+    /// Get the underlying writer.
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+/// A submission as a JSON [`SubmissionRecord`], without the file.
+pub fn to_json(sub: &Submission) -> serde_json::Result<String> {
+    serde_json::to_string(&SubmissionRecord::new(sub, false))
+}
+
+/// Submissions as a JSON array of [`SubmissionRecord`]s, without files.
+pub fn to_json_array<'a, I>(subs: I) -> serde_json::Result<String>
+where
+    I: IntoIterator<Item = &'a Submission>,
+{
+    let records: Vec<_> = subs
+        .into_iter()
+        .map(|sub| SubmissionRecord::new(sub, false))
+        .collect();
+
+    serde_json::to_string(&records)
+}
+
+/// Append rows of submission metadata to a CSV writer, in the same columns
+/// as a [`CsvExporter`].
+///
+/// The writer adds a header row before the first row it writes, unless it
+/// was built without headers, such as when appending to an existing file.
+pub fn write_csv_rows<'a, W, I>(writer: &mut csv::Writer<W>, subs: I) -> csv::Result<()>
+where
+    W: Write,
+    I: IntoIterator<Item = &'a Submission>,
+{
+    for sub in subs {
+        writer.serialize(CsvRow::new(sub))?;
+    }
+
+    Ok(())
+}
+
+/// A row of submission metadata for CSV exports.
+#[derive(Serialize)]
+struct CsvRow<'a> {
+    id: SubmissionId,
+    artist: &'a str,
+    title: &'a str,
+    rating: String,
+    tags: String,
+    posted_at: String,
+    sha256: Option<String>,
+    hash_num: Option<i64>,
+}
+
+impl<'a> CsvRow<'a> {
+    fn new(sub: &'a Submission) -> Self {
+        Self {
+            id: sub.id,
+            artist: &sub.artist,
+            title: &sub.title,
+            rating: sub.rating.serialize(),
+            tags: sub.tags.join(" "),
+            posted_at: sub.posted_at.to_rfc3339(),
+            sha256: sub.file_sha256.as_deref().map(base64::encode),
+            hash_num: sub.hash_num,
+        }
+    }
+}
+
+/// Writes submission metadata as CSV, with a header row.
+///
+/// Columns are `id`, `artist`, `title`, `rating`, `tags`, `posted_at`,
+/// `sha256`, and `hash_num`, formatted the same as a [`SubmissionRecord`].
+/// Tags are separated by spaces.
+pub struct CsvExporter<W: Write> {
+    writer: csv::Writer<W>,
+}
+
+impl<W: Write> CsvExporter<W> {
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer: csv::Writer::from_writer(writer),
+        }
+    }
+
+    /// Write a submission.
+    pub fn write(&mut self, sub: &Submission) -> csv::Result<()> {
+        self.writer.serialize(CsvRow::new(sub))
+    }
+
+    /// Flush any buffered rows and get the underlying writer.
+    pub fn into_inner(self) -> std::io::Result<W> {
+        self.writer.into_inner().map_err(|err| err.into_error())
+    }
+}
+
+/// Writes submission metadata to Parquet, for analysis of large datasets.
+///
+/// Rows are buffered and written in batches. Dates are second precision
+/// timestamps in UTC, tags are a list of strings, and digests are binary.
+/// Files are never included.
+#[cfg(feature = "parquet")]
+pub struct ParquetExporter<W: Write + Send> {
+    writer: parquet::arrow::ArrowWriter<W>,
+    columns: ParquetColumns,
+    batch_size: usize,
+}
+
+#[cfg(feature = "parquet")]
+#[derive(Default)]
+struct ParquetColumns {
+    id: arrow_array::builder::Int64Builder,
+    artist: arrow_array::builder::StringBuilder,
+    title: arrow_array::builder::StringBuilder,
+    rating: arrow_array::builder::StringBuilder,
+    url: arrow_array::builder::StringBuilder,
+    ext: arrow_array::builder::StringBuilder,
+    posted_at: arrow_array::builder::TimestampSecondBuilder,
+    tags: arrow_array::builder::ListBuilder<arrow_array::builder::StringBuilder>,
+    hash_num: arrow_array::builder::Int64Builder,
+    file_size: arrow_array::builder::UInt64Builder,
+    file_sha256: arrow_array::builder::BinaryBuilder,
+    file_mime: arrow_array::builder::StringBuilder,
+}
+
+#[cfg(feature = "parquet")]
+impl ParquetColumns {
+    fn schema() -> arrow_schema::SchemaRef {
+        use arrow_schema::{DataType, Field, Schema, TimeUnit};
+
+        std::sync::Arc::new(Schema::new(vec![
+            Field::new("id", DataType::Int64, false),
+            Field::new("artist", DataType::Utf8, false),
+            Field::new("title", DataType::Utf8, false),
+            Field::new("rating", DataType::Utf8, false),
+            Field::new("url", DataType::Utf8, false),
+            Field::new("ext", DataType::Utf8, false),
+            Field::new(
+                "posted_at",
+                DataType::Timestamp(TimeUnit::Second, Some("UTC".into())),
+                false,
+            ),
+            Field::new(
+                "tags",
+                DataType::List(std::sync::Arc::new(Field::new(
+                    "item",
+                    DataType::Utf8,
+                    true,
+                ))),
+                false,
+            ),
+            Field::new("hash_num", DataType::Int64, true),
+            Field::new("file_size", DataType::UInt64, true),
+            Field::new("file_sha256", DataType::Binary, true),
+            Field::new("file_mime", DataType::Utf8, true),
+        ]))
+    }
+
+    fn append(&mut self, sub: &Submission) {
+        self.id.append_value(sub.id.to_i64());
+        self.artist.append_value(&sub.artist);
+        self.title.append_value(&sub.title);
+        self.rating.append_value(sub.rating.code());
+        self.url.append_value(sub.content.url());
+        self.ext.append_value(&sub.ext);
+        self.posted_at.append_value(sub.posted_at.timestamp());
+        for tag in &sub.tags {
+            self.tags.values().append_value(tag);
+        }
+        self.tags.append(true);
+        self.hash_num.append_option(sub.hash_num);
+        self.file_size
+            .append_option(sub.file_size.map(|size| size as u64));
+        self.file_sha256.append_option(sub.file_sha256.as_deref());
+        self.file_mime.append_option(sub.file_mime.as_deref());
+    }
+
+    fn len(&self) -> usize {
+        use arrow_array::builder::ArrayBuilder;
+
+        self.id.len()
+    }
+
+    fn finish(&mut self) -> parquet::errors::Result<arrow_array::RecordBatch> {
+        use arrow_array::ArrayRef;
+        use std::sync::Arc;
+
+        let columns: Vec<ArrayRef> = vec![
+            Arc::new(self.id.finish()),
+            Arc::new(self.artist.finish()),
+            Arc::new(self.title.finish()),
+            Arc::new(self.rating.finish()),
+            Arc::new(self.url.finish()),
+            Arc::new(self.ext.finish()),
+            Arc::new(self.posted_at.finish().with_timezone("UTC")),
+            Arc::new(self.tags.finish()),
+            Arc::new(self.hash_num.finish()),
+            Arc::new(self.file_size.finish()),
+            Arc::new(self.file_sha256.finish()),
+            Arc::new(self.file_mime.finish()),
+        ];
+
+        Ok(arrow_array::RecordBatch::try_new(Self::schema(), columns)?)
+    }
+}
+
+#[cfg(feature = "parquet")]
+impl<W: Write + Send> ParquetExporter<W> {
+    pub fn new(writer: W) -> parquet::errors::Result<Self> {
+        Ok(Self {
+            writer: parquet::arrow::ArrowWriter::try_new(writer, ParquetColumns::schema(), None)?,
+            columns: ParquetColumns::default(),
+            batch_size: 10_000,
+        })
+    }
+
+    /// How many rows to buffer before writing them. Defaults to 10,000.
+    pub fn with_batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size.max(1);
+        self
+    }
+
+    /// Write a submission.
+    pub fn write(&mut self, sub: &Submission) -> parquet::errors::Result<()> {
+        self.columns.append(sub);
+
+        if self.columns.len() >= self.batch_size {
+            self.flush()?;
+        }
+
+        Ok(())
+    }
+
+    fn flush(&mut self) -> parquet::errors::Result<()> {
+        if self.columns.len() == 0 {
+            return Ok(());
+        }
+
+        let batch = self.columns.finish()?;
+        self.writer.write(&batch)
+    }
+
+    /// Write any buffered rows and the file footer, and get the underlying
+    /// writer.
+    pub fn finish(mut self) -> parquet::errors::Result<W> {
+        self.flush()?;
+        self.writer.into_inner()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::test_submission;
+
+    #[test]
+    fn test_json_lines() {
+        let sub = Submission {
+            file_sha256: Some(vec![0xab; 32]),
+            file: Some(b"file".to_vec()),
+            ..test_submission("Sketch", 1617235200, &["fox"])
+        };
+
+        let mut exporter = JsonLinesExporter::new(Vec::new());
+        exporter.write(&sub).unwrap();
+        exporter
+            .write_record(&SubmissionRecord::new(&sub, true).with_file_path("example/41234567.png"))
+            .unwrap();
+
+        let output = String::from_utf8(exporter.into_inner()).unwrap();
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(lines.len(), 2);
+
+        let record: SubmissionRecord = serde_json::from_str(lines[0]).unwrap();
+        assert_eq!(record.schema_version, SCHEMA_VERSION);
+        assert_eq!(record.id, SubmissionId(41234567));
+        assert_eq!(record.rating, "g");
+        assert_eq!(record.tags, vec!["fox".to_string()]);
+        assert_eq!(record.posted_at, "2021-04-01T13:00:00+00:00");
+        assert_eq!(
+            record.file_sha256.as_deref(),
+            Some("q6urq6urq6urq6urq6urq6urq6urq6urq6urq6urq6s=")
+        );
+        assert_eq!(record.file, None);
+
+        let record: SubmissionRecord = serde_json::from_str(lines[1]).unwrap();
+        assert_eq!(record.file.as_deref(), Some("ZmlsZQ=="));
+        assert_eq!(record.file_path.as_deref(), Some("example/41234567.png"));
+    }
+
+    #[test]
+    fn test_csv() {
+        let sub = Submission {
+            hash_num: Some(-42),
+            ..test_submission("Sketch, colored", 1617235200, &["fox", "sketch"])
+        };
+
+        let mut exporter = CsvExporter::new(Vec::new());
+        exporter.write(&sub).unwrap();
+
+        let output = String::from_utf8(exporter.into_inner().unwrap()).unwrap();
+
+        assert_eq!(
+            output,
+            "id,artist,title,rating,tags,posted_at,sha256,hash_num\n\
+             41234567,Example,\"Sketch, colored\",g,fox sketch,2021-04-01T13:00:00+00:00,,-42\n"
+        );
+    }
+
+    #[test]
+    fn test_batch_helpers() {
+        let subs = vec![
+            Submission {
+                file_sha256: Some(vec![0xab; 32]),
+                file: Some(b"file".to_vec()),
+                ..test_submission("Sketch", 1617235200, &["fox"])
+            },
+            test_submission("Painting", 1617235200, &["paint"]),
+        ];
+
+        let record: SubmissionRecord = serde_json::from_str(&to_json(&subs[0]).unwrap()).unwrap();
+        assert_eq!(record, SubmissionRecord::new(&subs[0], false));
+
+        let records: Vec<SubmissionRecord> =
+            serde_json::from_str(&to_json_array(&subs).unwrap()).unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[1].title, "Painting");
+        assert_eq!(records[0].file, None);
+
+        let mut writer = csv::WriterBuilder::new()
+            .has_headers(false)
+            .from_writer(Vec::new());
+        write_csv_rows(&mut writer, &subs[1..]).unwrap();
+        let output = String::from_utf8(writer.into_inner().unwrap()).unwrap();
+        assert_eq!(
+            output,
+            "41234567,Example,Painting,g,paint,2021-04-01T13:00:00+00:00,,\n"
+        );
+    }
+
+    #[cfg(feature = "parquet")]
+    #[test]
+    fn test_parquet() {
+        use arrow_array::{Array, Int64Array, ListArray, StringArray};
+        use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+
+        let path = std::env::temp_dir().join(format!("fa-export-{}.parquet", std::process::id()));
+
+        let mut exporter = ParquetExporter::new(std::fs::File::create(&path).unwrap())
+            .unwrap()
+            .with_batch_size(1);
+        exporter
+            .write(&test_submission("Sketch", 1617235200, &["fox", "sketch"]))
+            .unwrap();
+        exporter
+            .write(&Submission {
+                hash_num: Some(-42),
+                ..test_submission("Colored", 1617235300, &[])
+            })
+            .unwrap();
+        exporter.finish().unwrap();
+
+        let reader = ParquetRecordBatchReaderBuilder::try_new(std::fs::File::open(&path).unwrap())
+            .unwrap()
+            .with_batch_size(10)
+            .build()
+            .unwrap();
+        let batches: Vec<_> = reader.map(|batch| batch.unwrap()).collect();
+        assert_eq!(batches.len(), 1);
+
+        let batch = &batches[0];
+        assert_eq!(batch.num_rows(), 2);
+        let titles = batch.column_by_name("title").unwrap();
+        let titles = titles.as_any().downcast_ref::<StringArray>().unwrap();
+        assert_eq!(titles.value(1), "Colored");
+
+        let hashes = batch.column_by_name("hash_num").unwrap();
+        let hashes = hashes.as_any().downcast_ref::<Int64Array>().unwrap();
+        assert!(hashes.is_null(0));
+        assert_eq!(hashes.value(1), -42);
+
+        let tags = batch.column_by_name("tags").unwrap();
+        let tags = tags.as_any().downcast_ref::<ListArray>().unwrap();
+        assert_eq!(tags.value(0).len(), 2);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+}
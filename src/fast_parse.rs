@@ -0,0 +1,397 @@
+//! Parsing submission pages with tl, enabled with the `fast-parse` feature.
+//!
+//! tl only tokenizes the page instead of building a tree the way a browser
+//! would, which is much faster but only supports simple selectors. Pages are
+//! parsed into the same [`Submission`] as with scraper, and the description
+//! is serialized by html5ever so its HTML is the same too.
+
+use crate::anomaly::{Anomaly, AnomalyKind};
+use crate::info::{Folder, SubmissionInfo};
+use crate::{
+    file_url, parse_date, Content, Error, ParseFields, Rating, Submission, SubmissionBuilder,
+    SubmissionId, SubmissionStats, AI_LABEL,
+};
+use tl::{HTMLTag, Node, NodeHandle, Parser, VDom};
+
+pub(crate) fn parse_submission(
+    id: SubmissionId,
+    page: &str,
+    fields: ParseFields,
+    anomalies: &mut Vec<Anomaly>,
+) -> Result<Option<Submission>, Error> {
+    let dom = tl::parse(page, tl::ParserOptions::default()).map_err(|_err| Error::parse("page"))?;
+    let parser = dom.parser();
+
+    let title_system_error = select(&dom, "title")
+        .first()
+        .map(|elem| text(parser, elem) == "System Error")
+        .unwrap_or(false);
+
+    if title_system_error {
+        return Ok(None);
+    }
+
+    if !select(&dom, ".error-message-box").is_empty()
+        || !select(
+            &dom,
+            "div#standardpage section.notice-message p.link-override",
+        )
+        .is_empty()
+    {
+        return Ok(None);
+    }
+
+    let title = match select(&dom, ".submission-title h2 p").first() {
+        Some(title) => text(parser, title),
+        None => return Err(Error::parse("title")),
+    };
+
+    let (artist, artist_info) = match select(&dom, ".submission-id-sub-container")
+        .into_iter()
+        .find_map(|container| title_link(parser, container))
+    {
+        Some(artist) => {
+            let name = text(parser, artist);
+            let info = crate::username::Artist::parse(&name, attr(artist, "href").as_deref());
+
+            (name, info)
+        }
+        None => return Err(Error::parse("artist")),
+    };
+
+    let download = select(&dom, ".download a")
+        .first()
+        .and_then(|link| attr(link, "href"))
+        .and_then(|href| file_url(&href))
+        .and_then(|(url, url_ext, filename)| {
+            Content::from_download(url).map(|content| (content, url_ext, filename))
+        });
+
+    let (content, url_ext, filename) = {
+        if let Some(download) = download {
+            download
+        } else if let Some(url) = select(&dom, "#submissionImg").first() {
+            let (url, url_ext, filename) = attr(url, "src")
+                .and_then(|src| file_url(&src))
+                .ok_or_else(|| Error::parse("image url"))?;
+
+            (Content::Image(url), url_ext, filename)
+        } else if let Some(url) = select(&dom, "#flash_embed").first() {
+            let (url, url_ext, filename) = attr(url, "data")
+                .and_then(|data| file_url(&data))
+                .ok_or_else(|| Error::parse("flash url"))?;
+
+            (Content::Flash(url), url_ext, filename)
+        } else {
+            return Err(Error::parse("content type"));
+        }
+    };
+
+    let image = select(&dom, "#submissionImg").first().copied();
+    let image_url = |name| {
+        image
+            .and_then(|image| attr(image, name))
+            .and_then(|src| file_url(&src))
+            .map(|(url, _, _)| url)
+    };
+    let preview_url = image_url("data-preview-src");
+    let fullview_url = image_url("data-fullview-src");
+    let avatar_url = select(&dom, "img")
+        .into_iter()
+        .find_map(|img| crate::url::avatar_src(&attr(img, "src")?, &artist_info.slug));
+
+    let rating = match select(&dom, ".stats-container .rating span.rating-box").first() {
+        Some(rating) => {
+            Rating::parse(&text(parser, rating)).ok_or_else(|| Error::parse("rating"))?
+        }
+        None => return Err(Error::parse("submission rating")),
+    };
+
+    let ai_generated = select(&dom, ".info .category-name,.type-name")
+        .into_iter()
+        .any(|category| AI_LABEL.is_match(&text(parser, category)));
+
+    let count = |selector: &str| {
+        select(&dom, selector)
+            .first()
+            .map(|count| SubmissionStats::parse_count(&text(parser, count)))
+            .transpose()
+            .map(Option::unwrap_or_default)
+    };
+    let stats = SubmissionStats {
+        views: count(".stats-container .views .font-large")?,
+        favorites: count(".stats-container .favorites .font-large")?,
+        comments: count(".stats-container .comments .font-large")?,
+    };
+
+    let mut info = SubmissionInfo::default();
+    if let Some(category) = select(&dom, ".info .category-name").first() {
+        info.set("Category", &text(parser, category));
+    }
+    if let Some(kind) = select(&dom, ".info .type-name").first() {
+        info.set("Type", &text(parser, kind));
+    }
+    for row in select(&dom, ".info div") {
+        let label = match first(parser, row, "strong.highlight") {
+            Some(label) => text(parser, label),
+            None => continue,
+        };
+        if label == "Category" {
+            continue;
+        }
+
+        if let Some(value) = first(parser, row, "span") {
+            info.set(&label, &text(parser, value));
+        }
+    }
+    info.folders = select(&dom, ".folder-list-container a")
+        .into_iter()
+        .filter_map(|link| Folder::parse(&attr(link, "href")?, text(parser, link)))
+        .collect();
+
+    let posted_at =
+        match select(&dom, ".submission-id-sub-container strong span.popup_date").first() {
+            Some(posted_at) => attr(posted_at, "title").ok_or_else(|| Error::parse("posted at"))?,
+            None => return Err(Error::parse("posted at")),
+        };
+
+    let tags: Vec<String> = if fields.contains(ParseFields::TAGS) {
+        select(&dom, "section.tags-row a")
+            .into_iter()
+            .map(|tag| text(parser, tag))
+            .collect()
+    } else {
+        Vec::new()
+    };
+
+    let description = if fields.contains(ParseFields::DESCRIPTION) {
+        match select(&dom, ".submission-content section").first() {
+            Some(description) => inner_html(page, parser, description),
+            None => return Err(Error::parse("description")),
+        }
+    } else {
+        String::new()
+    };
+
+    if !filename.contains('.') {
+        anomalies.push(Anomaly::new("ext", AnomalyKind::Missing));
+    }
+
+    let mut builder = SubmissionBuilder::new(id, content);
+    if let Some(preview_url) = preview_url {
+        builder = builder.with_preview_url(preview_url);
+    }
+    if let Some(fullview_url) = fullview_url {
+        builder = builder.with_fullview_url(fullview_url);
+    }
+    if let Some(avatar_url) = avatar_url {
+        builder = builder.with_avatar_url(avatar_url);
+    }
+
+    let sub = builder
+        .with_title(title)
+        .with_artist(artist)
+        .with_artist_info(artist_info)
+        .with_rating(rating)
+        .with_ai_generated(ai_generated)
+        .with_stats(stats)
+        .with_info(info)
+        .with_posted_at(parse_date(&posted_at)?)
+        .with_tags(tags)
+        .with_description(description)
+        .build();
+
+    Ok(Some(Submission {
+        ext: url_ext,
+        filename,
+        ..sub
+    }))
+}
+
+/// Elements matching a selector, in document order. Selectors are simple
+/// selectors separated by spaces, as tl does not support combinators.
+fn select<'a, 'b>(dom: &'b VDom<'a>, selector: &str) -> Vec<&'b HTMLTag<'a>> {
+    let parser = dom.parser();
+    let mut steps = selector.split(' ');
+
+    let mut handles: Vec<NodeHandle> = steps
+        .next()
+        .and_then(|step| dom.query_selector(step))
+        .into_iter()
+        .flatten()
+        .collect();
+
+    for step in steps {
+        handles = handles
+            .iter()
+            .filter_map(|handle| handle.get(parser).and_then(Node::as_tag))
+            .filter_map(|tag| tag.query_selector(parser, step))
+            .flatten()
+            .collect();
+        handles.sort();
+        handles.dedup();
+    }
+
+    handles
+        .into_iter()
+        .filter_map(|handle| handle.get(parser).and_then(Node::as_tag))
+        .collect()
+}
+
+/// The first element within a tag matching a simple selector.
+fn first<'a, 'b>(
+    parser: &'b Parser<'a>,
+    tag: &'b HTMLTag<'a>,
+    selector: &str,
+) -> Option<&'b HTMLTag<'a>> {
+    tag.query_selector(parser, selector)?
+        .next()?
+        .get(parser)?
+        .as_tag()
+}
+
+/// The first link directly after a `.submission-title`, like the selector
+/// `.submission-title + a`.
+fn title_link<'a, 'b>(parser: &'b Parser<'a>, parent: &'b HTMLTag<'a>) -> Option<&'b HTMLTag<'a>> {
+    let children: Vec<&HTMLTag> = parent
+        .children()
+        .top()
+        .iter()
+        .filter_map(|handle| handle.get(parser).and_then(Node::as_tag))
+        .collect();
+
+    children.iter().enumerate().find_map(|(index, child)| {
+        let link = children
+            .get(index + 1)
+            .filter(|next| has_class(child, "submission-title") && next.name() == "a");
+
+        link.copied().or_else(|| title_link(parser, child))
+    })
+}
+
+fn has_class(tag: &HTMLTag, class: &str) -> bool {
+    tag.attributes().is_class_member(class)
+}
+
+fn attr(tag: &HTMLTag, name: &str) -> Option<String> {
+    let value = tag.attributes().get(name)??.as_utf8_str();
+
+    Some(if value.contains('&') {
+        decode(&value)
+    } else {
+        value.into_owned()
+    })
+}
+
+/// Text of an element, like `join_text_nodes`.
+fn text(parser: &Parser, tag: &HTMLTag) -> String {
+    let text = tag.inner_text(parser);
+
+    if text.contains('&') {
+        decode(&text).trim().to_string()
+    } else {
+        text.trim().to_string()
+    }
+}
+
+/// Decode character references, which tl leaves as they appear in the page.
+fn decode(text: &str) -> String {
+    scraper::Html::parse_fragment(text)
+        .root_element()
+        .text()
+        .collect()
+}
+
+/// HTML inside an element, serialized the same way as scraper.
+///
+/// tl does not always find the end of elements containing self-closing tags
+/// like `<br/>`, so the element is taken from the page up to its matching end
+/// tag.
+fn inner_html(page: &str, parser: &Parser, tag: &HTMLTag) -> String {
+    let (start, _end) = tag.boundaries(parser);
+    let name = tag.name().as_utf8_str().to_ascii_lowercase();
+    let source = &page[start..];
+    let lower = source.to_ascii_lowercase();
+
+    let open = format!("<{}", name);
+    let close = format!("</{}", name);
+    let mut depth = 0;
+    let mut end = source.len();
+
+    for (index, _) in lower.match_indices('<') {
+        if lower[index..].starts_with(&close) {
+            depth -= 1;
+            if depth == 0 {
+                end = index;
+                break;
+            }
+        } else if lower[index..].starts_with(&open) {
+            depth += 1;
+        }
+    }
+
+    let fragment = scraper::Html::parse_fragment(&source[..end]);
+
+    fragment
+        .root_element()
+        .first_child()
+        .and_then(scraper::ElementRef::wrap)
+        .map(|elem| elem.inner_html())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils;
+
+    const SNOW: &str = r#"<html><head><title>Snow by Example</title></head><body>
+        <div class="submission-id-sub-container">
+            <div class="submission-title"><h2><p>Snow &amp; Ice</p></h2></div>
+            <a href="/user/example/"><strong>Example</strong></a>
+            <strong><span class="popup_date" title="Apr 1st, 2021 09:00 AM">5 years ago</span></strong>
+        </div>
+        <img id="submissionImg" src="//d.furaffinity.net/art/example/1617285600/1617285600.example_snow">
+        <div class="stats-container"><div class="rating"><span class="rating-box">General</span></div></div>
+        <div class="submission-content"><section>A fox<br/>in the <a href='/user/snow'>snow</a> &gt;_&lt;</section></div>
+        <section class="info"><span class="category-name">Artwork (Digital)</span> / <span class="type-name">AI Generated</span></section>
+        <section class="tags-row"><a>fox</a> <a>winter</a></section>
+    </body></html>"#;
+
+    #[test]
+    fn test_same_as_scraper() {
+        let pages = [
+            (SubmissionId(31209021), test_utils::SUBMISSION.to_string()),
+            (SubmissionId(34426892), test_utils::DELETED.to_string()),
+            (SubmissionId(34999322), test_utils::AGE_GATED.to_string()),
+            (SubmissionId(41234567), test_utils::STORY.to_string()),
+            (SubmissionId(41234568), test_utils::MUSIC.to_string()),
+            (SubmissionId(41234567), SNOW.to_string()),
+            (
+                SubmissionId(31209021),
+                test_utils::SUBMISSION.replace("Bilberry fox", "Bilberry &amp; fox"),
+            ),
+        ];
+
+        for (id, page) in pages.iter() {
+            for fields in [ParseFields::ALL, ParseFields::NONE] {
+                let mut anomalies = Vec::new();
+                let fast = parse_submission(*id, page, fields, &mut anomalies)
+                    .map_err(|err| err.to_string());
+
+                let mut expected_anomalies = Vec::new();
+                let expected = crate::parse_submission_document(
+                    *id,
+                    &scraper::Html::parse_document(page),
+                    crate::template::Template::Modern,
+                    fields,
+                    &mut expected_anomalies,
+                )
+                .map_err(|err| err.to_string());
+
+                assert_eq!(fast, expected, "{}", id);
+                assert_eq!(anomalies, expected_anomalies);
+            }
+        }
+    }
+}
@@ -0,0 +1,162 @@
+//! Building Atom feeds of submissions.
+
+use crate::gallery::SubmissionPreview;
+use crate::{Submission, SubmissionId};
+use atom_syndication::{Category, Content, Entry, Feed, FixedDateTime, Link, Person, Text};
+
+/// Something that can be included in a feed.
+pub trait FeedItem {
+    fn to_entry(&self) -> Entry;
+}
+
+impl FeedItem for Submission {
+    fn to_entry(&self) -> Entry {
+        let url = submission_url(self.id);
+        let posted_at = FixedDateTime::from(self.posted_at);
+
+        let image = match self.thumbnail_url(600) {
+            Some(thumbnail) => format!(
+                r#"<p><a href="{}"><img src="{}" alt="{}"></a></p>"#,
+                url,
+                thumbnail,
+                escape(&self.title)
+            ),
+            None => String::new(),
+        };
+
+        Entry {
+            title: Text::plain(self.title.clone()),
+            id: url.clone(),
+            updated: posted_at,
+            published: Some(posted_at),
+            authors: vec![artist(&self.artist)],
+            categories: self
+                .tags
+                .iter()
+                .map(|tag| Category {
+                    term: tag.clone(),
+                    ..Default::default()
+                })
+                .collect(),
+            links: vec![link(url)],
+            content: Some(Content {
+                value: Some(image + &self.description),
+                content_type: Some("html".to_string()),
+                ..Default::default()
+            }),
+            ..Default::default()
+        }
+    }
+}
+
+impl FeedItem for SubmissionPreview {
+    fn to_entry(&self) -> Entry {
+        let url = submission_url(self.id);
+        let uploaded_at = self.uploaded_at().map(FixedDateTime::from);
+
+        Entry {
+            title: Text::plain(self.title.clone()),
+            id: url.clone(),
+            updated: uploaded_at.unwrap_or_default(),
+            published: uploaded_at,
+            authors: vec![artist(&self.artist)],
+            links: vec![link(url.clone())],
+            content: Some(Content {
+                value: Some(format!(
+                    r#"<a href="{}"><img src="{}" alt="{}"></a>"#,
+                    url,
+                    self.thumbnail_url,
+                    escape(&self.title)
+                )),
+                content_type: Some("html".to_string()),
+                ..Default::default()
+            }),
+            ..Default::default()
+        }
+    }
+}
+
+/// Build a feed of items. The feed's ID is its link, and it was last
+/// updated when the newest item was.
+pub fn build_feed<'a, I, T>(title: &str, link_url: &str, items: I) -> Feed
+where
+    I: IntoIterator<Item = &'a T>,
+    T: FeedItem + 'a,
+{
+    let entries: Vec<Entry> = items.into_iter().map(FeedItem::to_entry).collect();
+
+    let updated = entries
+        .iter()
+        .map(|entry| entry.updated)
+        .max()
+        .unwrap_or_else(|| chrono::Utc::now().into());
+
+    Feed {
+        title: Text::plain(title),
+        id: link_url.to_string(),
+        updated,
+        links: vec![link(link_url.to_string())],
+        entries,
+        ..Default::default()
+    }
+}
+
+fn submission_url(id: SubmissionId) -> String {
+    crate::url::FaUrl::View(id).canonical()
+}
+
+fn artist(name: &str) -> Person {
+    Person {
+        name: name.to_string(),
+        uri: Some(crate::url::FaUrl::User(crate::username::slug(name)).canonical()),
+        ..Default::default()
+    }
+}
+
+fn link(href: String) -> Link {
+    Link {
+        href,
+        rel: "alternate".to_string(),
+        ..Default::default()
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::test_submission;
+
+    #[test]
+    fn test_build_feed() {
+        let sub = test_submission("Sketch \"WIP\"", 1617235200, &["fox"]);
+
+        let feed = build_feed(
+            "Example's gallery",
+            "https://www.furaffinity.net/gallery/example/",
+            &[sub],
+        );
+
+        assert_eq!(feed.updated, feed.entries[0].updated);
+
+        let entry = &feed.entries[0];
+        assert_eq!(entry.id, "https://www.furaffinity.net/view/41234567/");
+        assert_eq!(entry.authors[0].name, "Example");
+        assert_eq!(entry.categories[0].term, "fox");
+
+        let content = entry.content.as_ref().unwrap().value.as_deref().unwrap();
+        assert!(content.contains(
+            r#"<img src="https://t.furaffinity.net/41234567@600-1617235200.jpg" alt="Sketch &quot;WIP&quot;">"#
+        ));
+
+        let xml = feed.to_string();
+        assert!(xml.starts_with("<?xml"));
+        assert!(xml.contains("<id>https://www.furaffinity.net/view/41234567/</id>"));
+    }
+}
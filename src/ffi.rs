@@ -0,0 +1,154 @@
+//! A C interface to the parser and hashing, declared in
+//! `include/furaffinity.h`.
+//!
+//! Build a shared library with
+//! `cargo rustc --release --features ffi --crate-type cdylib`.
+//!
+//! Functions returning `int` return a negative value on error, after which
+//! [`fa_last_error`] describes what went wrong. Strings returned by this
+//! library must be released with [`fa_string_free`].
+
+use crate::hash::HashDistance;
+use std::cell::RefCell;
+use std::ffi::{CStr, CString};
+use std::os::raw::{c_char, c_int};
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+fn set_last_error<T: ToString>(err: T) {
+    let message = CString::new(err.to_string().replace('\0', ""))
+        .expect("nul bytes were removed from message");
+
+    LAST_ERROR.with(|last| *last.borrow_mut() = Some(message));
+}
+
+/// The message of the last error on this thread, or NULL if there was none.
+///
+/// The message is owned by the library and is valid until the next call on
+/// this thread.
+#[no_mangle]
+pub extern "C" fn fa_last_error() -> *const c_char {
+    LAST_ERROR.with(|last| {
+        last.borrow()
+            .as_ref()
+            .map(|message| message.as_ptr())
+            .unwrap_or(std::ptr::null())
+    })
+}
+
+/// Release a string returned by this library.
+///
+/// # Safety
+///
+/// `s` must be NULL or a string returned by this library that has not
+/// already been released.
+#[no_mangle]
+pub unsafe extern "C" fn fa_string_free(s: *mut c_char) {
+    if !s.is_null() {
+        drop(CString::from_raw(s));
+    }
+}
+
+/// Parse the HTML of a submission page into JSON, in the same format as the
+/// `serde` feature.
+///
+/// Returns 1 and sets `out` if the submission was parsed, 0 if it does not
+/// exist, or -1 on error.
+///
+/// # Safety
+///
+/// `page` must be a valid nul-terminated UTF-8 string and `out` must be a
+/// valid pointer.
+#[no_mangle]
+pub unsafe extern "C" fn fa_parse_submission(
+    id: u64,
+    page: *const c_char,
+    out: *mut *mut c_char,
+) -> c_int {
+    let page = match CStr::from_ptr(page).to_str() {
+        Ok(page) => page,
+        Err(err) => {
+            set_last_error(err);
+            return -1;
+        }
+    };
+
+    let sub = match crate::parse_submission(crate::SubmissionId(id), page) {
+        Ok(Some(sub)) => sub,
+        Ok(None) => return 0,
+        Err(err) => {
+            set_last_error(err);
+            return -1;
+        }
+    };
+
+    let json = serde_json::to_string(&sub).expect("submissions always serialize");
+    *out = CString::new(json)
+        .expect("JSON escapes nul bytes")
+        .into_raw();
+
+    1
+}
+
+/// Calculate the perceptual hash of an image.
+///
+/// Returns 0 and sets `out` on success, or -1 on error.
+///
+/// # Safety
+///
+/// `data` must point to `len` readable bytes and `out` must be a valid
+/// pointer.
+#[no_mangle]
+pub unsafe extern "C" fn fa_hash_image(data: *const u8, len: usize, out: *mut i64) -> c_int {
+    let data = std::slice::from_raw_parts(data, len);
+
+    let hash = match crate::hash::hash_image(data) {
+        Ok(hash) => hash,
+        Err(err) => {
+            set_last_error(err);
+            return -1;
+        }
+    };
+
+    match hash.to_i64() {
+        Some(hash) => {
+            *out = hash;
+            0
+        }
+        None => {
+            set_last_error("hash was not 8 bytes");
+            -1
+        }
+    }
+}
+
+/// Number of bits that differ between two perceptual hashes.
+#[no_mangle]
+pub extern "C" fn fa_hash_distance(a: i64, b: i64) -> u32 {
+    a.distance(&b).unwrap_or(u32::MAX)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ffi() {
+        assert_eq!(fa_hash_distance(0b1011, 0b0001), 2);
+
+        let mut hash = 0;
+        let ret = unsafe { fa_hash_image(b"not an image".as_ptr(), 12, &mut hash) };
+        assert_eq!(ret, -1);
+
+        let message = unsafe { CStr::from_ptr(fa_last_error()) };
+        assert!(!message.to_bytes().is_empty());
+
+        let page = CString::new("<html><head><title>System Error</title></head></html>").unwrap();
+        let mut out = std::ptr::null_mut();
+        let ret = unsafe { fa_parse_submission(1, page.as_ptr(), &mut out) };
+        assert_eq!(ret, 0);
+        assert!(out.is_null());
+    }
+}
@@ -0,0 +1,159 @@
+//! Types matching FuzzySearch, so hashed submissions can be loaded into
+//! existing reverse image search infrastructure.
+
+use crate::{file_timestamp, Rating, Submission};
+use serde::{Deserialize, Serialize};
+
+/// A row of FuzzySearch's FurAffinity `submission` table.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SubmissionRow {
+    pub id: i64,
+    pub artist: String,
+    pub url: String,
+    pub filename: String,
+    /// Perceptual hash bytes.
+    pub hash: Option<Vec<u8>>,
+    /// One of `g`, `m`, or `a`.
+    pub rating: String,
+    pub posted_at: chrono::DateTime<chrono::Utc>,
+    pub description: String,
+    pub hash_int: Option<i64>,
+    /// Upload timestamp from the file's URL.
+    pub file_id: Option<i32>,
+    pub file_size: Option<i32>,
+    pub file_sha256: Option<Vec<u8>>,
+}
+
+impl From<&Submission> for SubmissionRow {
+    fn from(sub: &Submission) -> Self {
+        use std::convert::TryInto;
+
+        Self {
+            id: sub.id.to_i64(),
+            artist: sub.artist.clone(),
+            url: sub.content.url().to_string(),
+            filename: sub.filename.clone(),
+            hash: sub.hash_num.map(|hash| hash.to_be_bytes().to_vec()),
+            rating: sub.rating.serialize(),
+            posted_at: sub.posted_at,
+            description: sub.description.clone(),
+            hash_int: sub.hash_num,
+            file_id: file_id(sub),
+            file_size: sub.file_size.and_then(|size| size.try_into().ok()),
+            file_sha256: sub.file_sha256.clone(),
+        }
+    }
+}
+
+impl From<Submission> for SubmissionRow {
+    fn from(sub: Submission) -> Self {
+        Self::from(&sub)
+    }
+}
+
+/// Rating as used by the FuzzySearch API.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ApiRating {
+    General,
+    Mature,
+    Adult,
+}
+
+impl From<&Rating> for ApiRating {
+    fn from(rating: &Rating) -> Self {
+        match rating {
+            Rating::General => ApiRating::General,
+            Rating::Mature => ApiRating::Mature,
+            Rating::Adult => ApiRating::Adult,
+        }
+    }
+}
+
+/// Site specific details of a file from the FuzzySearch API.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "site", content = "site_info")]
+pub enum SiteInfo {
+    FurAffinity { file_id: i32 },
+}
+
+/// A file as returned by the FuzzySearch API.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ApiFile {
+    pub id: i64,
+    pub site_id: i64,
+    pub site_id_str: String,
+    pub url: String,
+    pub filename: String,
+    pub artists: Option<Vec<String>>,
+    pub rating: Option<ApiRating>,
+    pub posted_at: Option<chrono::DateTime<chrono::Utc>>,
+    #[serde(flatten)]
+    pub site_info: Option<SiteInfo>,
+    pub hash: Option<i64>,
+    pub hash_str: Option<String>,
+    /// SHA-256 digest in lowercase hex.
+    pub sha256: Option<String>,
+}
+
+impl From<&Submission> for ApiFile {
+    fn from(sub: &Submission) -> Self {
+        Self {
+            id: sub.id.to_i64(),
+            site_id: sub.id.to_i64(),
+            site_id_str: sub.id.to_string(),
+            url: sub.content.url().to_string(),
+            filename: sub.filename.clone(),
+            artists: Some(vec![sub.artist.clone()]),
+            rating: Some(ApiRating::from(&sub.rating)),
+            posted_at: Some(sub.posted_at),
+            site_info: file_id(sub).map(|file_id| SiteInfo::FurAffinity { file_id }),
+            hash: sub.hash_num,
+            hash_str: sub.hash_num.map(|hash| hash.to_string()),
+            sha256: sub.file_sha256.as_deref().map(crate::hash::to_hex),
+        }
+    }
+}
+
+impl From<Submission> for ApiFile {
+    fn from(sub: Submission) -> Self {
+        Self::from(&sub)
+    }
+}
+
+fn file_id(sub: &Submission) -> Option<i32> {
+    file_timestamp(sub.content.url())?.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::test_submission;
+
+    #[test]
+    fn test_conversions() {
+        let sub = Submission {
+            hash_num: Some(-2),
+            file_size: Some(1024),
+            file_sha256: Some(vec![0xab; 32]),
+            ..test_submission("Sketch", 1617235200, &[])
+        };
+
+        let row = SubmissionRow::from(&sub);
+        assert_eq!(
+            row.hash,
+            Some(vec![0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xfe])
+        );
+        assert_eq!(row.id, 41234567);
+        assert_eq!(row.rating, "g");
+        assert_eq!(row.file_id, Some(1617235200));
+        assert_eq!(row.file_size, Some(1024));
+
+        let file = serde_json::to_value(ApiFile::from(sub)).unwrap();
+        assert_eq!(file["site"], "FurAffinity");
+        assert_eq!(file["site_info"]["file_id"], 1617235200);
+        assert_eq!(file["rating"], "general");
+        assert_eq!(file["hash_str"], "-2");
+        assert_eq!(file["sha256"], "ab".repeat(32));
+    }
+}
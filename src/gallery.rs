@@ -0,0 +1,453 @@
+//! Parsing listings of submissions, such as a user's gallery.
+
+use crate::{Error, Rating, SubmissionId};
+use futures::{Stream, StreamExt};
+use lazy_static::lazy_static;
+use scraper::Selector;
+
+lazy_static! {
+    static ref FIGURE: Selector = Selector::parse("section.gallery figure").unwrap();
+    static ref RECENT_FIGURE: Selector =
+        Selector::parse("#gallery-frontpage-submissions figure").unwrap();
+    static ref RECENT_WRITING: Selector =
+        Selector::parse("#gallery-frontpage-writing figure").unwrap();
+    static ref RECENT_MUSIC: Selector = Selector::parse("#gallery-frontpage-music figure").unwrap();
+    static ref RECENT_CRAFTS: Selector =
+        Selector::parse("#gallery-frontpage-crafts figure").unwrap();
+    static ref THUMBNAIL: Selector = Selector::parse("img").unwrap();
+    static ref CAPTION_LINK: Selector = Selector::parse("figcaption a").unwrap();
+    static ref THUMBNAIL_TIMESTAMP: regex::Regex = regex::Regex::new(r"@\d+-(\d+)\.").unwrap();
+    static ref FAVORITES_LINK: Selector =
+        Selector::parse(r#"form[action*="/favorites/"], a[href*="/favorites/"]"#).unwrap();
+    static ref FOLDER_LINK: Selector =
+        Selector::parse(r#".user-folders a[href*="/folder/"]"#).unwrap();
+    static ref FAVORITES_NEXT: regex::Regex =
+        regex::Regex::new(r"/favorites/[^/]+/(\d+/next)").unwrap();
+}
+
+/// The details of a submission shown in a listing.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct SubmissionPreview {
+    pub id: SubmissionId,
+    pub title: String,
+    /// Display name of the artist, or their username as it appears in URLs
+    /// on pages that don't show the name, like the front page.
+    pub artist: String,
+    pub thumbnail_url: String,
+    pub rating: Rating,
+}
+
+impl SubmissionPreview {
+    /// When the submission's file was uploaded, from the timestamp in its
+    /// thumbnail's URL.
+    pub fn uploaded_at(&self) -> Option<chrono::DateTime<chrono::Utc>> {
+        use chrono::TimeZone;
+
+        let timestamp = THUMBNAIL_TIMESTAMP
+            .captures(&self.thumbnail_url)?
+            .get(1)?
+            .as_str()
+            .parse()
+            .ok()?;
+
+        chrono::Utc.timestamp_opt(timestamp, 0).single()
+    }
+}
+
+/// The submissions shown on a listing page, such as a gallery or search
+/// results.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Listing {
+    pub submissions: Vec<SubmissionPreview>,
+    /// If the page was loaded as a guest. Guests are only shown submissions
+    /// rated General, so others may be missing from the listing.
+    pub guest: bool,
+}
+
+impl Listing {
+    /// If the listing is empty because there is nothing to show, rather
+    /// than because everything was hidden from a guest.
+    pub fn is_empty(&self) -> bool {
+        self.submissions.is_empty() && !self.guest
+    }
+
+    /// If every submission that could be listed was shown.
+    pub fn is_complete(&self) -> bool {
+        !self.guest
+    }
+}
+
+/// A page of a user's favorites, most recently added first.
+///
+/// Favorites pages aren't numbered. Each page links to the next with a token
+/// for where it starts, like `1234567890/next`.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Favorites {
+    pub listing: Listing,
+    /// Token for the next page, if there is one.
+    pub next_page: Option<String>,
+}
+
+/// Items that have a rating known before loading the full submission.
+pub trait Rated {
+    fn rating(&self) -> Rating;
+}
+
+impl Rated for SubmissionPreview {
+    fn rating(&self) -> Rating {
+        self.rating
+    }
+}
+
+#[cfg(feature = "client")]
+impl Rated for crate::watcher::NewSubmission {
+    fn rating(&self) -> Rating {
+        self.submission.rating
+    }
+}
+
+/// Filters for streams of previews, such as from
+/// [`FurAffinity::gallery_stream`](crate::FurAffinity::gallery_stream).
+///
+/// Filtering uses the rating shown in the listing, so submissions that are
+/// filtered out are never loaded. Errors are always passed through.
+pub trait PreviewStreamExt<T: Rated>: Stream<Item = Result<T, Error>> + Sized {
+    /// Only keep submissions rated General.
+    fn only_sfw(self) -> impl Stream<Item = Result<T, Error>> {
+        self.max_rating(Rating::General)
+    }
+
+    /// Only keep submissions rated at least as explicit as `rating`.
+    fn min_rating(self, rating: Rating) -> impl Stream<Item = Result<T, Error>> {
+        self.filter_rating(move |item| item >= rating)
+    }
+
+    /// Only keep submissions rated at most as explicit as `rating`.
+    fn max_rating(self, rating: Rating) -> impl Stream<Item = Result<T, Error>> {
+        self.filter_rating(move |item| item <= rating)
+    }
+
+    #[doc(hidden)]
+    fn filter_rating<F>(self, keep: F) -> impl Stream<Item = Result<T, Error>>
+    where
+        F: Fn(Rating) -> bool,
+    {
+        self.filter(move |item| {
+            let keep = match item {
+                Ok(item) => keep(item.rating()),
+                Err(_) => true,
+            };
+
+            futures::future::ready(keep)
+        })
+    }
+}
+
+impl<T: Rated, S: Stream<Item = Result<T, Error>>> PreviewStreamExt<T> for S {}
+
+/// Parse the submissions shown on a gallery page, newest first.
+pub fn parse_gallery(page: &str) -> Result<Vec<SubmissionPreview>, Error> {
+    let document = scraper::Html::parse_document(page);
+
+    document.select(&FIGURE).map(parse_figure).collect()
+}
+
+/// Parse the submissions shown on a listing page, and if it was loaded as a
+/// guest.
+pub fn parse_listing(page: &str) -> Result<Listing, Error> {
+    Ok(Listing {
+        submissions: parse_gallery(page)?,
+        guest: !crate::is_logged_in(page),
+    })
+}
+
+/// Parse a page of a user's favorites, along with the token for the next
+/// page.
+pub fn parse_favorites(page: &str) -> Result<Favorites, Error> {
+    let listing = parse_listing(page)?;
+
+    let document = scraper::Html::parse_document(page);
+    let next_page = document
+        .select(&FAVORITES_LINK)
+        .filter_map(|link| {
+            let value = link.value();
+            let target = value.attr("action").or_else(|| value.attr("href"))?;
+
+            Some(FAVORITES_NEXT.captures(target)?[1].to_string())
+        })
+        .next();
+
+    Ok(Favorites { listing, next_page })
+}
+
+/// Parse the list of folders in the sidebar of a gallery or scraps page.
+pub fn parse_folders(page: &str) -> Vec<crate::info::Folder> {
+    let document = scraper::Html::parse_document(page);
+
+    document
+        .select(&FOLDER_LINK)
+        .filter_map(|link| {
+            crate::info::Folder::parse(link.value().attr("href")?, crate::join_text_nodes(link))
+        })
+        .collect()
+}
+
+/// Parse the grid of recent submissions on the front page, newest first.
+pub fn parse_recent(page: &str) -> Result<Vec<SubmissionPreview>, Error> {
+    let document = scraper::Html::parse_document(page);
+
+    document.select(&RECENT_FIGURE).map(parse_figure).collect()
+}
+
+/// The recent uploads shown on the front page in each category, newest
+/// first.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct FrontPage {
+    pub artwork: Vec<SubmissionPreview>,
+    pub writing: Vec<SubmissionPreview>,
+    pub music: Vec<SubmissionPreview>,
+    pub crafts: Vec<SubmissionPreview>,
+}
+
+/// Parse every section of recent uploads on the front page. Sections that
+/// aren't shown, such as when hidden in the viewer's settings, are empty.
+pub fn parse_front_page_sections(page: &str) -> Result<FrontPage, Error> {
+    let document = scraper::Html::parse_document(page);
+
+    let section = |selector: &Selector| -> Result<Vec<_>, Error> {
+        document.select(selector).map(parse_figure).collect()
+    };
+
+    Ok(FrontPage {
+        artwork: section(&RECENT_FIGURE)?,
+        writing: section(&RECENT_WRITING)?,
+        music: section(&RECENT_MUSIC)?,
+        crafts: section(&RECENT_CRAFTS)?,
+    })
+}
+
+/// IDs between the oldest and newest submissions in a listing that are not
+/// in it, oldest first. On the front page, these are submissions that were
+/// deleted or hidden by the viewer's settings.
+pub fn missing_ids(submissions: &[SubmissionPreview]) -> Vec<SubmissionId> {
+    let ids: std::collections::HashSet<_> = submissions.iter().map(|sub| sub.id).collect();
+
+    let (oldest, newest) = match (ids.iter().min(), ids.iter().max()) {
+        (Some(oldest), Some(newest)) => (oldest.0, newest.0),
+        _ => return Vec::new(),
+    };
+
+    (oldest..newest)
+        .map(SubmissionId)
+        .filter(|id| !ids.contains(id))
+        .collect()
+}
+
+fn parse_figure(figure: scraper::ElementRef) -> Result<SubmissionPreview, Error> {
+    let id = figure
+        .value()
+        .id()
+        .and_then(|id| id.strip_prefix("sid-"))
+        .ok_or_else(|| Error::parse("submission id"))?
+        .parse()?;
+
+    let rating = figure
+        .value()
+        .classes()
+        .find_map(|class| match class {
+            "r-general" => Some(Rating::General),
+            "r-mature" => Some(Rating::Mature),
+            "r-adult" => Some(Rating::Adult),
+            _ => None,
+        })
+        .ok_or_else(|| Error::parse("rating"))?;
+
+    let thumbnail_url = figure
+        .select(&THUMBNAIL)
+        .next()
+        .and_then(|img| img.value().attr("src"))
+        .map(|src| format!("https:{}", src))
+        .ok_or_else(|| Error::parse("thumbnail"))?;
+
+    let mut links = figure.select(&CAPTION_LINK);
+
+    let title = links
+        .next()
+        .and_then(|link| link.value().attr("title"))
+        .ok_or_else(|| Error::parse("title"))?
+        .to_string();
+
+    let artist = links
+        .next()
+        .and_then(|link| link.value().attr("title"))
+        .or_else(|| {
+            figure
+                .value()
+                .classes()
+                .find_map(|class| class.strip_prefix("u-"))
+        })
+        .ok_or_else(|| Error::parse("artist"))?
+        .to_string();
+
+    Ok(SubmissionPreview {
+        id,
+        title,
+        artist,
+        thumbnail_url,
+        rating,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_gallery() {
+        let page = r#"
+            <section id="gallery-gallery" class="gallery s-250">
+                <figure id="sid-41234567" class="r-general t-image u-example">
+                    <b><u><a href="/view/41234567/"><img alt="" src="//t.furaffinity.net/41234567@200-1617235200.jpg"></a></u></b>
+                    <figcaption>
+                        <p><a href="/view/41234567/" title="Spring Sketch">Spring Sketch</a></p>
+                        <p><i>by</i> <a href="/user/example/" title="Example">Example</a></p>
+                    </figcaption>
+                </figure>
+                <figure id="sid-41234000" class="r-mature t-image u-example">
+                    <b><u><a href="/view/41234000/"><img alt="" src="//t.furaffinity.net/41234000@200-1617148800.jpg"></a></u></b>
+                    <figcaption>
+                        <p><a href="/view/41234000/" title="Commission &amp; Stuff">Commission &amp; Stuff</a></p>
+                        <p><i>by</i> <a href="/user/example/" title="Example">Example</a></p>
+                    </figcaption>
+                </figure>
+            </section>
+        "#;
+
+        let submissions = parse_gallery(page).unwrap();
+
+        assert_eq!(submissions.len(), 2);
+        assert_eq!(submissions[0].id, SubmissionId(41234567));
+        assert_eq!(submissions[0].title, "Spring Sketch");
+        assert_eq!(submissions[0].artist, "Example");
+        assert_eq!(
+            submissions[0].thumbnail_url,
+            "https://t.furaffinity.net/41234567@200-1617235200.jpg"
+        );
+        assert!(matches!(submissions[0].rating, Rating::General));
+        assert_eq!(
+            submissions[0].uploaded_at().map(|date| date.timestamp()),
+            Some(1617235200)
+        );
+        assert_eq!(submissions[1].title, "Commission & Stuff");
+        assert!(matches!(submissions[1].rating, Rating::Mature));
+    }
+
+    #[test]
+    fn test_parse_listing() {
+        let listing = parse_listing(crate::test_utils::GALLERY).unwrap();
+        assert_eq!(listing.submissions.len(), 2);
+        assert!(listing.guest);
+        assert!(!listing.is_complete());
+
+        let page = r#"<img class="loggedin_user_avatar"><section class="gallery"></section>"#;
+        let listing = parse_listing(page).unwrap();
+        assert!(!listing.guest);
+        assert!(listing.is_empty());
+    }
+
+    #[test]
+    fn test_parse_favorites() {
+        let page = crate::test_utils::GALLERY.replace(
+            "</body>",
+            r#"<form action="/favorites/example/1234567890/next" method="get"><button class="button standard" type="submit">Next</button></form></body>"#,
+        );
+        let favorites = parse_favorites(&page).unwrap();
+        assert_eq!(favorites.listing.submissions.len(), 2);
+        assert_eq!(favorites.next_page.as_deref(), Some("1234567890/next"));
+
+        let favorites = parse_favorites(crate::test_utils::GALLERY).unwrap();
+        assert_eq!(favorites.next_page, None);
+    }
+
+    #[test]
+    fn test_parse_recent() {
+        let recent = parse_recent(crate::test_utils::FRONT_PAGE).unwrap();
+
+        assert_eq!(recent.len(), 2);
+        assert_eq!(recent[0].id, SubmissionId(41234568));
+        assert_eq!(recent[0].title, "Snowfall");
+        assert_eq!(recent[0].artist, "example");
+        assert!(missing_ids(&recent).is_empty());
+
+        let gapped: Vec<_> = [41234570, 41234567]
+            .iter()
+            .map(|id| SubmissionPreview {
+                id: SubmissionId(*id),
+                ..recent[0].clone()
+            })
+            .collect();
+        assert_eq!(
+            missing_ids(&gapped),
+            vec![SubmissionId(41234568), SubmissionId(41234569)]
+        );
+    }
+
+    #[cfg(feature = "client")]
+    #[tokio::test]
+    async fn test_front_page() {
+        let server = crate::test_utils::MockServer::with_fixtures()
+            .await
+            .unwrap();
+        let front_page = server.client().get_front_page().await.unwrap();
+
+        assert_eq!(
+            front_page.artwork,
+            parse_recent(crate::test_utils::FRONT_PAGE).unwrap()
+        );
+        assert_eq!(front_page.writing.len(), 1);
+        assert_eq!(front_page.writing[0].title, "Northern Lights");
+        assert_eq!(front_page.writing[0].rating, Rating::Mature);
+        assert_eq!(front_page.music[0].id, SubmissionId(41234550));
+        assert_eq!(front_page.music[0].artist, "deadrussiansoul");
+        assert!(front_page.crafts.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_preview_stream_ratings() {
+        let preview = |id, rating| SubmissionPreview {
+            id: SubmissionId(id),
+            title: String::new(),
+            artist: "Example".into(),
+            thumbnail_url: String::new(),
+            rating,
+        };
+        let previews = || {
+            futures::stream::iter(vec![
+                Ok(preview(1, Rating::General)),
+                Ok(preview(2, Rating::Adult)),
+                Err(Error::new("failed", true)),
+                Ok(preview(3, Rating::Mature)),
+            ])
+        };
+        let ids = |items: Vec<Result<SubmissionPreview, Error>>| {
+            items
+                .into_iter()
+                .map(|item| item.map(|item| item.id.0).ok())
+                .collect::<Vec<_>>()
+        };
+
+        let sfw = previews().only_sfw().collect().await;
+        assert_eq!(ids(sfw), vec![Some(1), None]);
+
+        let mature = previews().min_rating(Rating::Mature).collect().await;
+        assert_eq!(ids(mature), vec![Some(2), None, Some(3)]);
+    }
+}
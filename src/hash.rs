@@ -0,0 +1,1076 @@
+#[cfg(feature = "hash")]
+use crate::Error;
+
+/// Algorithm used to reduce an image into a perceptual hash.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub enum HashAlgorithm {
+    Mean,
+    Gradient,
+    VertGradient,
+    DoubleGradient,
+    Blockhash,
+}
+
+#[cfg(feature = "hash")]
+impl HashAlgorithm {
+    fn hash_alg(self) -> img_hash::HashAlg {
+        match self {
+            HashAlgorithm::Mean => img_hash::HashAlg::Mean,
+            HashAlgorithm::Gradient => img_hash::HashAlg::Gradient,
+            HashAlgorithm::VertGradient => img_hash::HashAlg::VertGradient,
+            HashAlgorithm::DoubleGradient => img_hash::HashAlg::DoubleGradient,
+            HashAlgorithm::Blockhash => img_hash::HashAlg::Blockhash,
+        }
+    }
+}
+
+/// Parameters for perceptual hashing.
+///
+/// The default is an 8x8 gradient hash with DCT preprocessing, matching
+/// [`hash_image`].
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct HashConfig {
+    pub algorithm: HashAlgorithm,
+    pub width: u32,
+    pub height: u32,
+    /// Run a discrete cosine transform over the image before hashing.
+    pub dct: bool,
+    /// Frame to hash from animated GIFs.
+    pub gif_frame: GifFrame,
+}
+
+/// Which part of an animated GIF is used when hashing.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub enum GifFrame {
+    /// The first frame of the animation.
+    First,
+    /// The frame halfway through the animation.
+    Middle,
+    /// The average of every frame in the animation.
+    Average,
+}
+
+impl Default for HashConfig {
+    fn default() -> Self {
+        Self {
+            algorithm: HashAlgorithm::Gradient,
+            width: 8,
+            height: 8,
+            dct: true,
+            gif_frame: GifFrame::First,
+        }
+    }
+}
+
+impl HashConfig {
+    /// A short name for the configuration, such as `gradient-8x8-dct-first`,
+    /// which changes whenever hashes from it would differ.
+    pub fn fingerprint(&self) -> String {
+        let algorithm = match self.algorithm {
+            HashAlgorithm::Mean => "mean",
+            HashAlgorithm::Gradient => "gradient",
+            HashAlgorithm::VertGradient => "vert_gradient",
+            HashAlgorithm::DoubleGradient => "double_gradient",
+            HashAlgorithm::Blockhash => "blockhash",
+        };
+
+        let gif_frame = match self.gif_frame {
+            GifFrame::First => "first",
+            GifFrame::Middle => "middle",
+            GifFrame::Average => "average",
+        };
+
+        format!(
+            "{}-{}x{}-{}-{}",
+            algorithm,
+            self.width,
+            self.height,
+            if self.dct { "dct" } else { "nodct" },
+            gif_frame
+        )
+    }
+}
+
+#[cfg(feature = "hash")]
+impl HashConfig {
+    /// The same configuration with a different hash size.
+    fn with_size(&self, (width, height): (u32, u32)) -> Self {
+        Self {
+            width,
+            height,
+            ..self.clone()
+        }
+    }
+
+    fn hash(&self, image: &image::DynamicImage) -> ImageHash {
+        self.hasher().hash_image(image).as_bytes().into()
+    }
+
+    fn hasher(&self) -> img_hash::Hasher {
+        let config = img_hash::HasherConfig::new()
+            .hash_alg(self.algorithm.hash_alg())
+            .hash_size(self.width, self.height);
+
+        let config = if self.dct {
+            config.preproc_dct()
+        } else {
+            config
+        };
+
+        config.to_hasher()
+    }
+}
+
+/// Options controlling how files are processed by `calc_image_hash`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct HashOptions {
+    pub config: HashConfig,
+    /// Decode and hash images on the blocking thread pool instead of the
+    /// async executor.
+    pub blocking: bool,
+    /// Keep the downloaded file on the submission. When disabled, only the
+    /// hashes and file size are kept.
+    pub retain_file: bool,
+    /// Also compute an MD5 digest of the file.
+    pub md5: bool,
+    /// Also compute a BLAKE3 digest of the file.
+    pub blake3: bool,
+    /// Largest file, in bytes, that will be decoded for a perceptual hash.
+    /// Larger files still have their digests and size calculated.
+    pub max_hash_bytes: Option<usize>,
+    /// Hash the thumbnail of Flash submissions, as the files themselves
+    /// cannot be decoded.
+    pub flash_thumbnails: bool,
+    /// Hash the thumbnail of every submission instead of downloading full
+    /// files. File digests and sizes are not calculated.
+    pub thumbnail_only: bool,
+    /// Number of horizontal and vertical components to use when generating
+    /// a blurhash, or `None` to skip generating one.
+    pub blurhash: Option<(u32, u32)>,
+    /// Additional hash sizes to calculate from the same decoded image, using
+    /// the algorithm and preprocessing from `config`.
+    pub extra_sizes: Vec<(u32, u32)>,
+    /// Extract EXIF and XMP metadata from downloaded files.
+    pub metadata: bool,
+}
+
+impl Default for HashOptions {
+    fn default() -> Self {
+        Self {
+            config: Default::default(),
+            blocking: true,
+            retain_file: true,
+            md5: false,
+            blake3: false,
+            max_hash_bytes: None,
+            flash_thumbnails: false,
+            thumbnail_only: false,
+            blurhash: None,
+            extra_sizes: Vec::new(),
+            metadata: false,
+        }
+    }
+}
+
+impl HashOptions {
+    #[cfg(feature = "client")]
+    pub(crate) fn within_limit(&self, size: usize) -> bool {
+        self.max_hash_bytes.map(|max| size <= max).unwrap_or(true)
+    }
+}
+
+/// What was downloaded to calculate a perceptual hash.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub enum HashSource {
+    /// The submission's full file.
+    File,
+    /// The submission's thumbnail, which is not directly comparable with
+    /// hashes of full files.
+    Thumbnail,
+}
+
+/// Reason a perceptual hash was not calculated for a file.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub enum HashSkipReason {
+    /// The file was larger than the configured `max_hash_bytes`.
+    TooLarge,
+    /// The file was not in an image format that could be decoded, either
+    /// because it is not an image or support for it was not enabled.
+    UnsupportedFormat,
+}
+
+/// Cryptographic digests of a file.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Digests {
+    pub sha256: Vec<u8>,
+    pub md5: Option<Vec<u8>>,
+    pub blake3: Option<Vec<u8>>,
+}
+
+/// Lowercase hex of a digest.
+pub fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// Computes every enabled digest while walking the data once.
+#[cfg(feature = "hash")]
+pub(crate) struct DigestState {
+    sha256: sha2::Sha256,
+    md5: Option<md5::Md5>,
+    blake3: Option<blake3::Hasher>,
+}
+
+#[cfg(feature = "hash")]
+impl DigestState {
+    pub(crate) fn new(options: &HashOptions) -> Self {
+        use sha2::Digest;
+
+        Self {
+            sha256: sha2::Sha256::new(),
+            md5: if options.md5 {
+                Some(md5::Md5::new())
+            } else {
+                None
+            },
+            blake3: if options.blake3 {
+                Some(blake3::Hasher::new())
+            } else {
+                None
+            },
+        }
+    }
+
+    pub(crate) fn update(&mut self, data: &[u8]) {
+        use sha2::Digest;
+
+        self.sha256.update(data);
+
+        if let Some(md5) = self.md5.as_mut() {
+            md5.update(data);
+        }
+
+        if let Some(blake3) = self.blake3.as_mut() {
+            blake3.update(data);
+        }
+    }
+
+    pub(crate) fn finalize(self) -> Digests {
+        use sha2::Digest;
+
+        Digests {
+            sha256: self.sha256.finalize().to_vec(),
+            md5: self.md5.map(|md5| md5.finalize().to_vec()),
+            blake3: self
+                .blake3
+                .map(|blake3| blake3.finalize().as_bytes().to_vec()),
+        }
+    }
+}
+
+/// Compute the digests enabled in options over a complete file.
+#[cfg(feature = "hash")]
+pub fn digest_file(options: &HashOptions, data: &[u8]) -> Digests {
+    let mut state = DigestState::new(options);
+
+    for chunk in data.chunks(64 * 1024) {
+        state.update(chunk);
+    }
+
+    state.finalize()
+}
+
+/// A perceptual hash of an image.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(
+    feature = "diesel",
+    derive(diesel::expression::AsExpression, diesel::deserialize::FromSqlRow),
+    diesel(sql_type = diesel::sql_types::Binary)
+)]
+pub struct ImageHash(Vec<u8>);
+
+impl From<&[u8]> for ImageHash {
+    fn from(bytes: &[u8]) -> Self {
+        Self::from_bytes(bytes)
+    }
+}
+
+impl ImageHash {
+    pub fn from_bytes(bytes: &[u8]) -> Self {
+        Self(bytes.to_vec())
+    }
+
+    pub fn from_base64(hash: &str) -> Option<Self> {
+        base64::decode(hash).ok().map(Self)
+    }
+
+    pub fn from_i64(num: i64) -> Self {
+        Self::from_bytes(&i64_to_bytes(num))
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+
+    pub fn to_base64(&self) -> String {
+        base64::encode(&self.0)
+    }
+
+    /// Numeric form of the hash, only available for 64 bit hashes.
+    pub fn to_i64(&self) -> Option<i64> {
+        bytes_to_i64(&self.0)
+    }
+
+    /// Number of bits in the hash.
+    pub fn bits(&self) -> usize {
+        self.0.len() * 8
+    }
+}
+
+#[cfg(feature = "hash")]
+/// Hash an image with the default configuration, an 8x8 gradient hash with
+/// DCT preprocessing.
+pub fn hash_image(image: &[u8]) -> Result<ImageHash, Error> {
+    hash_image_with(&HashConfig::default(), image)
+}
+
+#[cfg(feature = "hash")]
+/// Hash an image using the provided configuration.
+pub fn hash_image_with(config: &HashConfig, image: &[u8]) -> Result<ImageHash, Error> {
+    hash_image_info(config, image).map(|(hash, _info)| hash)
+}
+
+/// Details about an image, discovered while decoding it.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct ImageInfo {
+    pub width: u32,
+    pub height: u32,
+    /// Format of the image, as its most common file extension.
+    pub format: Option<String>,
+    /// Number of frames in the image, more than one if it is animated.
+    pub frame_count: u32,
+}
+
+impl ImageInfo {
+    /// If the image has more than one frame, such as an animated GIF or
+    /// APNG.
+    pub fn is_animated(&self) -> bool {
+        self.frame_count > 1
+    }
+}
+
+#[cfg(feature = "hash")]
+/// Read details about an image from its headers, without decoding it.
+/// Returns `None` if the format isn't supported or the headers are invalid.
+pub fn read_image_info(data: &[u8]) -> Option<ImageInfo> {
+    let format = image::guess_format(data).ok()?;
+    let (width, height) = image::io::Reader::with_format(std::io::Cursor::new(data), format)
+        .into_dimensions()
+        .ok()?;
+
+    let frame_count = match format {
+        image::ImageFormat::Gif => gif_frame_count(data)?,
+        image::ImageFormat::Png => apng_frame_count(data).unwrap_or(1),
+        _ => 1,
+    };
+
+    Some(ImageInfo {
+        width,
+        height,
+        format: format.extensions_str().first().map(|ext| ext.to_string()),
+        frame_count,
+    })
+}
+
+#[cfg(feature = "hash")]
+/// Hash an image using the provided configuration, also returning details
+/// about the decoded image.
+pub fn hash_image_info(config: &HashConfig, image: &[u8]) -> Result<(ImageHash, ImageInfo), Error> {
+    let (image, info) = decode_image(image, config.gif_frame)?;
+
+    Ok((config.hash(&image), info))
+}
+
+/// A perceptual hash along with the size it was calculated at.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct SizedHash {
+    pub width: u32,
+    pub height: u32,
+    pub hash: ImageHash,
+}
+
+#[cfg(feature = "hash")]
+/// Hash an image at multiple sizes, only decoding it once.
+pub fn hash_image_sizes(
+    config: &HashConfig,
+    sizes: &[(u32, u32)],
+    image: &[u8],
+) -> Result<Vec<SizedHash>, Error> {
+    let (image, _info) = decode_image(image, config.gif_frame)?;
+
+    Ok(hash_sizes(config, sizes, &image))
+}
+
+#[cfg(feature = "hash")]
+fn hash_sizes(
+    config: &HashConfig,
+    sizes: &[(u32, u32)],
+    image: &image::DynamicImage,
+) -> Vec<SizedHash> {
+    sizes
+        .iter()
+        .map(|&(width, height)| SizedHash {
+            width,
+            height,
+            hash: config.with_size((width, height)).hash(image),
+        })
+        .collect()
+}
+
+#[cfg(feature = "client")]
+/// Everything calculated from decoding an image for hashing.
+pub(crate) struct HashedImage {
+    pub(crate) hash: ImageHash,
+    pub(crate) extra: Vec<SizedHash>,
+    pub(crate) info: ImageInfo,
+    pub(crate) blurhash: Option<String>,
+}
+
+#[cfg(feature = "client")]
+/// Hash an image if it is in a format that can be decoded, otherwise
+/// returning `None`.
+pub(crate) fn hash_supported_image(
+    options: &HashOptions,
+    image: &[u8],
+) -> Result<Option<HashedImage>, Error> {
+    let (image, info) = match decode_image(image, options.config.gif_frame) {
+        Ok(decoded) => decoded,
+        Err(image::ImageError::Unsupported(_)) => return Ok(None),
+        Err(err) => return Err(err.into()),
+    };
+
+    let blurhash = options
+        .blurhash
+        .map(|(x, y)| encode_blurhash(&image, x, y))
+        .transpose()?;
+
+    Ok(Some(HashedImage {
+        hash: options.config.hash(&image),
+        extra: hash_sizes(&options.config, &options.extra_sizes, &image),
+        info,
+        blurhash,
+    }))
+}
+
+#[cfg(feature = "client")]
+fn encode_blurhash(image: &image::DynamicImage, x: u32, y: u32) -> Result<String, Error> {
+    // A blurhash only holds a few components, so encoding a small version of
+    // the image gives nearly the same result in a fraction of the time.
+    let image = image.thumbnail(64, 64).to_rgba8();
+
+    blurhash::encode(x, y, image.width(), image.height(), image.as_raw())
+        .map_err(|err| Error::new(err.to_string(), false))
+}
+
+#[cfg(feature = "hash")]
+fn decode_image(
+    data: &[u8],
+    gif_frame: GifFrame,
+) -> image::ImageResult<(image::DynamicImage, ImageInfo)> {
+    use image::GenericImageView;
+
+    let format = image::guess_format(data)?;
+
+    let (image, frame_count) = match format {
+        image::ImageFormat::Gif => load_gif(data, gif_frame)?,
+        image::ImageFormat::Png => (
+            image::load_from_memory_with_format(data, format)?,
+            apng_frame_count(data).unwrap_or(1),
+        ),
+        _ => (image::load_from_memory_with_format(data, format)?, 1),
+    };
+
+    let (width, height) = image.dimensions();
+    let info = ImageInfo {
+        width,
+        height,
+        format: format.extensions_str().first().map(|ext| ext.to_string()),
+        frame_count,
+    };
+
+    Ok((image, info))
+}
+
+/// Load the requested frame of a GIF, along with the number of frames it
+/// contains.
+#[cfg(feature = "hash")]
+fn load_gif(data: &[u8], frame: GifFrame) -> image::ImageResult<(image::DynamicImage, u32)> {
+    use image::AnimationDecoder;
+
+    let frames = || -> image::ImageResult<_> {
+        let decoder = image::codecs::gif::GifDecoder::new(std::io::Cursor::new(data))?;
+        Ok(decoder.into_frames())
+    };
+
+    let mut frame_count = 0;
+    let mut first = None;
+    let mut sums: Vec<u32> = Vec::new();
+
+    for buffer in frames()? {
+        let buffer = buffer?.into_buffer();
+
+        match frame {
+            GifFrame::First if first.is_none() => first = Some(buffer),
+            GifFrame::Average => {
+                if sums.is_empty() {
+                    first = Some(buffer.clone());
+                    sums = vec![0; buffer.as_raw().len()];
+                }
+
+                // Frames are composited onto the full canvas so they should
+                // always be the same size.
+                for (sum, value) in sums.iter_mut().zip(buffer.as_raw()) {
+                    *sum += *value as u32;
+                }
+            }
+            _ => (),
+        }
+
+        frame_count += 1;
+    }
+
+    let image = match frame {
+        GifFrame::First => first,
+        GifFrame::Middle => frames()?
+            .nth(frame_count as usize / 2)
+            .transpose()?
+            .map(|frame| frame.into_buffer()),
+        GifFrame::Average => first.map(|mut image| {
+            for (value, sum) in image.iter_mut().zip(sums) {
+                *value = (sum / frame_count) as u8;
+            }
+
+            image
+        }),
+    };
+
+    let image = image.ok_or_else(|| {
+        image::ImageError::Decoding(image::error::DecodingError::new(
+            image::ImageFormat::Gif.into(),
+            "gif had no frames",
+        ))
+    })?;
+
+    Ok((image::DynamicImage::ImageRgba8(image), frame_count))
+}
+
+/// Number of frames in an APNG, from its `acTL` chunk, or `None` if it is
+/// a PNG without animation.
+#[cfg(feature = "hash")]
+fn apng_frame_count(data: &[u8]) -> Option<u32> {
+    use std::convert::TryInto;
+
+    let mut chunks = data.get(8..)?;
+
+    // The animation control chunk must come before the image data.
+    while chunks.len() >= 12 {
+        let len = u32::from_be_bytes(chunks[..4].try_into().ok()?) as usize;
+        let (kind, rest) = chunks[4..].split_at(4);
+
+        match kind {
+            b"acTL" => return Some(u32::from_be_bytes(rest.get(..4)?.try_into().ok()?)),
+            b"IDAT" | b"IEND" => return None,
+            _ => chunks = rest.get(len.checked_add(4)?..)?,
+        }
+    }
+
+    None
+}
+
+/// Number of frames in a GIF, from walking its blocks without decoding any
+/// of them. Returns `None` if the GIF ends early.
+#[cfg(feature = "hash")]
+fn gif_frame_count(data: &[u8]) -> Option<u32> {
+    /// Size of a color table from the packed fields of a descriptor.
+    fn color_table(packed: u8) -> usize {
+        if packed & 0x80 == 0 {
+            0
+        } else {
+            3 << ((packed & 0x07) + 1)
+        }
+    }
+
+    /// Skip data sub-blocks, returning the position after the terminator.
+    fn skip_sub_blocks(data: &[u8], mut pos: usize) -> Option<usize> {
+        loop {
+            let len = *data.get(pos)? as usize;
+            pos += 1 + len;
+
+            if len == 0 {
+                return Some(pos);
+            }
+        }
+    }
+
+    let mut pos = 13 + color_table(*data.get(10)?);
+    let mut frames = 0;
+
+    loop {
+        match *data.get(pos)? {
+            // Image descriptor, followed by the LZW code size and image data.
+            0x2c => {
+                frames += 1;
+                pos += 10 + color_table(*data.get(pos + 9)?) + 1;
+                pos = skip_sub_blocks(data, pos)?;
+            }
+            // Extension, with its label then sub-blocks.
+            0x21 => pos = skip_sub_blocks(data, pos + 2)?,
+            0x3b => return Some(frames),
+            _ => return None,
+        }
+    }
+}
+
+/// Fingerprint text with a 64 bit simhash, so that near-duplicate text has
+/// hashes a small distance apart, similar to a perceptual hash of an image.
+///
+/// Text is compared as overlapping runs of three words, ignoring case and
+/// punctuation.
+pub fn hash_text(text: &str) -> i64 {
+    const SHINGLE_SIZE: usize = 3;
+
+    let words: Vec<String> = text
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect();
+
+    let mut weights = [0i32; 64];
+
+    for shingle in words.windows(SHINGLE_SIZE.min(words.len()).max(1)) {
+        let hash = fnv1a(shingle);
+
+        for (bit, weight) in weights.iter_mut().enumerate() {
+            if hash & (1 << bit) != 0 {
+                *weight += 1;
+            } else {
+                *weight -= 1;
+            }
+        }
+    }
+
+    let hash = weights
+        .iter()
+        .enumerate()
+        .filter(|(_bit, weight)| **weight > 0)
+        .fold(0u64, |hash, (bit, _weight)| hash | (1 << bit));
+
+    u64_to_i64(hash)
+}
+
+/// A stable hash of words, as the standard library's hashers may change
+/// between releases.
+fn fnv1a(words: &[String]) -> u64 {
+    words
+        .iter()
+        .flat_map(|word| word.bytes().chain(std::iter::once(b' ')))
+        .fold(0xcbf29ce484222325, |hash, byte| {
+            (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+        })
+}
+
+// Conversions between the ways 64 bit hashes are stored. Bytes are always
+// big endian, matching `Submission::hash_num`.
+
+/// Numeric form of hash bytes, if there are exactly 8 of them.
+pub fn bytes_to_i64(bytes: &[u8]) -> Option<i64> {
+    use std::convert::TryInto;
+
+    bytes.try_into().ok().map(i64::from_be_bytes)
+}
+
+pub fn i64_to_bytes(num: i64) -> [u8; 8] {
+    num.to_be_bytes()
+}
+
+/// Numeric form of a base64 encoded hash, if it contains exactly 8 bytes.
+pub fn base64_to_i64(hash: &str) -> Option<i64> {
+    bytes_to_i64(&base64::decode(hash).ok()?)
+}
+
+pub fn i64_to_base64(num: i64) -> String {
+    base64::encode(i64_to_bytes(num))
+}
+
+/// Reinterpret the bits of a signed hash as unsigned.
+pub fn i64_to_u64(num: i64) -> u64 {
+    num as u64
+}
+
+/// Reinterpret the bits of an unsigned hash as signed.
+pub fn u64_to_i64(num: u64) -> i64 {
+    num as i64
+}
+
+/// Lowercase hex with all 16 digits.
+pub fn i64_to_hex(num: i64) -> String {
+    format!("{:016x}", i64_to_u64(num))
+}
+
+/// Numeric form of a hex encoded hash of up to 16 digits, in either case.
+pub fn hex_to_i64(hash: &str) -> Option<i64> {
+    if hash.is_empty() || hash.len() > 16 || !hash.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+
+    u64::from_str_radix(hash, 16).ok().map(u64_to_i64)
+}
+
+/// Hamming distance between perceptual hashes, regardless of how they are
+/// stored.
+///
+/// Numeric hashes are compared bitwise, so signed and unsigned values with
+/// the same bits are equivalent. Strings are base64 encoded hashes. Hashes
+/// that are invalid or of different lengths have no distance.
+pub trait HashDistance {
+    /// Number of bits that differ between the hashes.
+    fn distance(&self, other: &Self) -> Option<u32>;
+
+    /// If the hashes differ by no more than `threshold` bits.
+    fn matches_within(&self, other: &Self, threshold: u32) -> bool {
+        self.distance(other)
+            .map(|distance| distance <= threshold)
+            .unwrap_or(false)
+    }
+}
+
+impl HashDistance for i64 {
+    fn distance(&self, other: &Self) -> Option<u32> {
+        Some((self ^ other).count_ones())
+    }
+}
+
+impl HashDistance for u64 {
+    fn distance(&self, other: &Self) -> Option<u32> {
+        Some((self ^ other).count_ones())
+    }
+}
+
+impl HashDistance for [u8] {
+    fn distance(&self, other: &Self) -> Option<u32> {
+        if self.len() != other.len() {
+            return None;
+        }
+
+        Some(
+            self.iter()
+                .zip(other)
+                .map(|(a, b)| (a ^ b).count_ones())
+                .sum(),
+        )
+    }
+}
+
+impl HashDistance for ImageHash {
+    fn distance(&self, other: &Self) -> Option<u32> {
+        self.as_bytes().distance(other.as_bytes())
+    }
+}
+
+impl HashDistance for str {
+    fn distance(&self, other: &Self) -> Option<u32> {
+        let a = base64::decode(self).ok()?;
+        let b = base64::decode(other).ok()?;
+
+        a[..].distance(&b[..])
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    #[cfg(feature = "hash")]
+    pub(crate) fn test_image() -> Vec<u8> {
+        let image = image::RgbImage::from_fn(64, 64, |x, y| {
+            image::Rgb([(x * 4) as u8, (y * 4) as u8, ((x + y) * 2) as u8])
+        });
+
+        let mut buf = Vec::new();
+        image::DynamicImage::ImageRgb8(image)
+            .write_to(&mut buf, image::ImageOutputFormat::Png)
+            .unwrap();
+
+        buf
+    }
+
+    #[cfg(feature = "hash")]
+    #[test]
+    fn test_default_config_matches_hasher() {
+        let image = test_image();
+
+        let hasher = img_hash::HasherConfig::with_bytes_type::<[u8; 8]>()
+            .hash_alg(img_hash::HashAlg::Gradient)
+            .hash_size(8, 8)
+            .preproc_dct()
+            .to_hasher();
+        let fixed = hasher.hash_image(&image::load_from_memory(&image).unwrap());
+
+        let configured = hash_image(&image).unwrap();
+
+        assert_eq!(fixed.as_bytes(), configured.as_bytes());
+        assert_eq!(fixed.to_base64(), configured.to_base64());
+    }
+
+    #[test]
+    fn test_image_hash() {
+        let hash = ImageHash::from_i64(-2);
+
+        assert_eq!(hash.bits(), 64);
+        assert_eq!(hash.to_i64(), Some(-2));
+        assert_eq!(
+            ImageHash::from_base64(&hash.to_base64()),
+            Some(hash.clone())
+        );
+        assert_eq!(hash.distance(&ImageHash::from_i64(-1)), Some(1));
+        assert_eq!(ImageHash::from_bytes(&[0; 32]).to_i64(), None);
+    }
+
+    #[cfg(feature = "hash")]
+    #[test]
+    fn test_digest_file() {
+        let options = HashOptions {
+            md5: true,
+            ..Default::default()
+        };
+
+        let digests = digest_file(&options, b"furaffinity");
+
+        assert_eq!(digests.sha256.len(), 32);
+        assert_eq!(
+            digests.md5.as_deref(),
+            Some(&b"\x73\x55\x17\x12\xd8\x65\x19\x38\x0d\xa8\x14\x87\x65\x24\x2e\x6a"[..])
+        );
+        assert!(digests.blake3.is_none());
+    }
+
+    #[cfg(feature = "hash")]
+    #[test]
+    fn test_image_info() {
+        let (_hash, info) = hash_image_info(&HashConfig::default(), &test_image()).unwrap();
+
+        assert_eq!(
+            info,
+            ImageInfo {
+                width: 64,
+                height: 64,
+                format: Some("png".into()),
+                frame_count: 1,
+            }
+        );
+    }
+
+    #[cfg(feature = "hash")]
+    #[test]
+    fn test_read_image_info() {
+        let (_hash, decoded) = hash_image_info(&HashConfig::default(), &test_image()).unwrap();
+        assert_eq!(read_image_info(&test_image()), Some(decoded));
+
+        let info = read_image_info(&test_gif()).unwrap();
+        assert_eq!((info.width, info.height), (4, 4));
+        assert_eq!(info.format.as_deref(), Some("gif"));
+        assert_eq!(info.frame_count, 3);
+        assert!(info.is_animated());
+
+        assert_eq!(read_image_info(b"Once upon a time"), None);
+        assert_eq!(read_image_info(&test_gif()[..40]), None);
+    }
+
+    #[cfg(feature = "hash")]
+    #[test]
+    fn test_apng_frame_count() {
+        let png = test_image();
+        assert_eq!(apng_frame_count(&png), None);
+
+        // An acTL chunk for 5 frames, after the IHDR chunk. Its CRC isn't
+        // checked.
+        let mut apng = png[..33].to_vec();
+        apng.extend_from_slice(&8u32.to_be_bytes());
+        apng.extend_from_slice(b"acTL");
+        apng.extend_from_slice(&5u32.to_be_bytes());
+        apng.extend_from_slice(&0u32.to_be_bytes());
+        apng.extend_from_slice(&[0; 4]);
+        apng.extend_from_slice(&png[33..]);
+        assert_eq!(apng_frame_count(&apng), Some(5));
+    }
+
+    #[cfg(feature = "hash")]
+    fn test_gif() -> Vec<u8> {
+        let frames = (0..3u8).map(|n| {
+            let buffer = image::RgbaImage::from_pixel(4, 4, image::Rgba([n * 60, 0, 0, 255]));
+            image::Frame::new(buffer)
+        });
+
+        let mut buf = Vec::new();
+        image::codecs::gif::GifEncoder::new(&mut buf)
+            .encode_frames(frames)
+            .unwrap();
+
+        buf
+    }
+
+    #[cfg(feature = "hash")]
+    #[test]
+    fn test_gif_frames() {
+        let gif = test_gif();
+
+        let (image, frame_count) = load_gif(&gif, GifFrame::First).unwrap();
+        assert_eq!(frame_count, 3);
+        assert_eq!(image.to_rgba8().get_pixel(0, 0)[0], 0);
+
+        let (image, _) = load_gif(&gif, GifFrame::Middle).unwrap();
+        assert_eq!(image.to_rgba8().get_pixel(0, 0)[0], 60);
+
+        let (image, _) = load_gif(&gif, GifFrame::Average).unwrap();
+        assert_eq!(image.to_rgba8().get_pixel(0, 0)[0], 60);
+    }
+
+    #[cfg(feature = "hash")]
+    #[test]
+    fn test_hash_sizes() {
+        let image = test_image();
+        let config = HashConfig::default();
+
+        let hashes = hash_image_sizes(&config, &[(8, 8), (16, 16)], &image).unwrap();
+
+        assert_eq!(hashes.len(), 2);
+        assert_eq!(hashes[0].hash, hash_image(&image).unwrap());
+        assert_eq!((hashes[1].width, hashes[1].height), (16, 16));
+        assert_eq!(hashes[1].hash.bits(), 256);
+    }
+
+    #[cfg(feature = "client")]
+    #[test]
+    fn test_unsupported_format() {
+        let options = HashOptions::default();
+
+        assert!(hash_supported_image(&options, b"Once upon a time")
+            .unwrap()
+            .is_none());
+        assert!(hash_supported_image(&options, &test_image())
+            .unwrap()
+            .is_some());
+    }
+
+    #[cfg(feature = "client")]
+    #[test]
+    fn test_blurhash() {
+        let options = HashOptions {
+            blurhash: Some((4, 3)),
+            ..Default::default()
+        };
+
+        let hashed = hash_supported_image(&options, &test_image())
+            .unwrap()
+            .unwrap();
+        let blurhash = hashed.blurhash.expect("blurhash should be generated");
+
+        // Size flag, maximum value, DC component, and two characters for
+        // each AC component.
+        assert_eq!(blurhash.len(), 1 + 1 + 4 + 2 * (4 * 3 - 1));
+    }
+
+    #[test]
+    fn test_conversions_round_trip() {
+        for &num in &[0, 1, -1, i64::MIN, i64::MAX, 0x0123_4567_89ab_cdef] {
+            assert_eq!(bytes_to_i64(&i64_to_bytes(num)), Some(num));
+            assert_eq!(base64_to_i64(&i64_to_base64(num)), Some(num));
+            assert_eq!(u64_to_i64(i64_to_u64(num)), num);
+            assert_eq!(hex_to_i64(&i64_to_hex(num)), Some(num));
+        }
+    }
+
+    #[test]
+    fn test_conversions() {
+        assert_eq!(i64_to_bytes(1), [0, 0, 0, 0, 0, 0, 0, 1]);
+        assert_eq!(i64_to_base64(1), "AAAAAAAAAAE=");
+        assert_eq!(i64_to_u64(-1), u64::MAX);
+        assert_eq!(i64_to_hex(-2), "fffffffffffffffe");
+        assert_eq!(hex_to_i64("FF"), Some(255));
+
+        assert_eq!(bytes_to_i64(&[0; 16]), None);
+        assert_eq!(base64_to_i64("AAAA"), None);
+        assert_eq!(hex_to_i64("+1"), None);
+        assert_eq!(hex_to_i64("00000000000000000"), None);
+    }
+
+    #[test]
+    fn test_distance() {
+        assert_eq!(0i64.distance(&-1), Some(64));
+        assert_eq!(0u64.distance(&u64::MAX), Some(64));
+        assert_eq!(5i64.distance(&4), Some(1));
+
+        let a: &[u8] = &[0b1010, 0xff];
+        let b: &[u8] = &[0b0000, 0xfe];
+        assert_eq!(a.distance(b), Some(3));
+        assert_eq!(a.distance(&[0][..]), None);
+
+        assert_eq!("AAAAAAAAAAA=".distance("AAAAAAAAAAE="), Some(1));
+        assert_eq!("AAAAAAAAAAA=".distance("not base64!"), None);
+
+        assert!(5i64.matches_within(&4, 1));
+        assert!(!5i64.matches_within(&2, 1));
+        assert!(!"AAAAAAAAAAA=".matches_within("AAAA", 64));
+    }
+
+    #[cfg(feature = "hash")]
+    #[test]
+    fn test_hash_size() {
+        let image = test_image();
+
+        let config = HashConfig {
+            algorithm: HashAlgorithm::Mean,
+            width: 16,
+            height: 16,
+            dct: false,
+            ..Default::default()
+        };
+        let hash = hash_image_with(&config, &image).unwrap();
+
+        assert_eq!(hash.as_bytes().len(), 32);
+    }
+
+    #[test]
+    fn test_hash_text() {
+        let story = "The fox trotted through the snowy forest, looking for somewhere warm to spend the night before the storm arrived.";
+        let repost = "the fox trotted through the snowy forest looking for somewhere warm to spend the night, before the storm arrived!";
+        let edited = "The fox trotted through the snowy forest, looking for somewhere dry to spend the night before the storm arrived.";
+        let other = "A dragon guarded the mountain pass for a hundred years, until one day a traveller brought it a gift.";
+
+        assert_eq!(hash_text(story), hash_text(repost));
+        assert!(
+            hash_text(story).distance(&hash_text(edited))
+                < hash_text(story).distance(&hash_text(other))
+        );
+        assert_eq!(hash_text(""), 0);
+    }
+}
@@ -0,0 +1,184 @@
+//! Identifiers for things on FurAffinity.
+
+use std::fmt;
+use std::str::FromStr;
+
+/// The ID of a submission, as in `/view/{id}/`.
+///
+/// IDs are stored as 64 bits so they can't run out, and are kept separate
+/// from other IDs so they can't be mixed up with them.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(transparent)
+)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct SubmissionId(pub u64);
+
+impl SubmissionId {
+    /// The ID after this one.
+    pub fn next(self) -> Self {
+        Self(self.0.saturating_add(1))
+    }
+
+    /// The ID from a link to a submission, from `/view/` or `/full/` with or
+    /// without anything after the ID. Returns `None` for other links.
+    pub fn from_url(link: &str) -> Option<Self> {
+        match crate::url::FaUrl::parse(link)? {
+            crate::url::FaUrl::View(id) => Some(id),
+            _ => None,
+        }
+    }
+
+    /// The ID as a signed integer, for databases without unsigned types.
+    pub fn to_i64(self) -> i64 {
+        self.0 as i64
+    }
+}
+
+impl fmt::Display for SubmissionId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl FromStr for SubmissionId {
+    type Err = std::num::ParseIntError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.parse().map(Self)
+    }
+}
+
+impl From<u64> for SubmissionId {
+    fn from(id: u64) -> Self {
+        Self(id)
+    }
+}
+
+impl From<u32> for SubmissionId {
+    fn from(id: u32) -> Self {
+        Self(id.into())
+    }
+}
+
+impl From<SubmissionId> for u64 {
+    fn from(id: SubmissionId) -> Self {
+        id.0
+    }
+}
+
+impl std::ops::Add<u64> for SubmissionId {
+    type Output = Self;
+
+    fn add(self, rhs: u64) -> Self {
+        Self(self.0 + rhs)
+    }
+}
+
+impl std::ops::AddAssign<u64> for SubmissionId {
+    fn add_assign(&mut self, rhs: u64) {
+        self.0 += rhs;
+    }
+}
+
+/// The ID of a journal, as in `/journal/{id}/`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(transparent)
+)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct JournalId(pub u64);
+
+impl JournalId {
+    /// The ID from a link to a journal. Returns `None` for other links.
+    pub fn from_url(link: &str) -> Option<Self> {
+        match crate::url::FaUrl::parse(link)? {
+            crate::url::FaUrl::Journal(id) => Some(id),
+            _ => None,
+        }
+    }
+
+    /// The ID as a signed integer, for databases without unsigned types.
+    pub fn to_i64(self) -> i64 {
+        self.0 as i64
+    }
+}
+
+impl fmt::Display for JournalId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl FromStr for JournalId {
+    type Err = std::num::ParseIntError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.parse().map(Self)
+    }
+}
+
+impl From<u64> for JournalId {
+    fn from(id: u64) -> Self {
+        Self(id)
+    }
+}
+
+impl From<u32> for JournalId {
+    fn from(id: u32) -> Self {
+        Self(id.into())
+    }
+}
+
+impl From<JournalId> for u64 {
+    fn from(id: JournalId) -> Self {
+        id.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_submission_id() {
+        let id: SubmissionId = "31209021".parse().unwrap();
+
+        assert_eq!(id, SubmissionId(31209021));
+        assert_eq!(id.to_string(), "31209021");
+        assert_eq!(id.next(), id + 1);
+        assert!("-1".parse::<SubmissionId>().is_err());
+
+        for link in [
+            "https://www.furaffinity.net/view/31209021/",
+            "https://www.furaffinity.net/full/31209021",
+            "furaffinity.net/view/31209021/extra/?upload-successful",
+        ]
+        .iter()
+        {
+            assert_eq!(SubmissionId::from_url(link), Some(id), "{}", link);
+        }
+        assert_eq!(
+            SubmissionId::from_url("https://www.furaffinity.net/user/example/"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_journal_id() {
+        let id: JournalId = "10123456".parse().unwrap();
+
+        assert_eq!(
+            JournalId::from_url("https://www.furaffinity.net/journal/10123456/"),
+            Some(id)
+        );
+        assert_eq!(
+            JournalId::from_url("https://www.furaffinity.net/view/10123456/"),
+            None
+        );
+    }
+}
@@ -0,0 +1,176 @@
+//! Category, species, gender, and folders shown in a submission's sidebar.
+
+use crate::join_text_nodes;
+use lazy_static::lazy_static;
+use scraper::Selector;
+
+lazy_static! {
+    static ref CATEGORY: Selector = Selector::parse(".info .category-name").unwrap();
+    static ref TYPE: Selector = Selector::parse(".info .type-name").unwrap();
+    static ref ROW: Selector = Selector::parse(".info div").unwrap();
+    static ref LABEL: Selector = Selector::parse("strong.highlight").unwrap();
+    static ref VALUE: Selector = Selector::parse("span").unwrap();
+    static ref FOLDER: Selector = Selector::parse(".folder-list-container a").unwrap();
+    static ref FOLDER_ID: regex::Regex = regex::Regex::new(r"/folder/(\d+)/").unwrap();
+}
+
+/// Gender of the characters in a submission, as chosen by the artist.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub enum Gender {
+    Any,
+    Male,
+    Female,
+    Herm,
+    Intersex,
+    TransMale,
+    TransFemale,
+    NonBinary,
+    Multiple,
+    Other,
+    /// A gender that isn't recognized yet, as it appeared on the page.
+    Unknown(String),
+}
+
+impl Gender {
+    pub fn parse(gender: &str) -> Self {
+        match gender.trim().to_lowercase().as_str() {
+            "any" => Gender::Any,
+            "male" => Gender::Male,
+            "female" => Gender::Female,
+            "herm" => Gender::Herm,
+            "intersex" => Gender::Intersex,
+            "trans (male)" | "trans male" => Gender::TransMale,
+            "trans (female)" | "trans female" => Gender::TransFemale,
+            "non-binary" | "nonbinary" => Gender::NonBinary,
+            "multiple characters" | "multiple" => Gender::Multiple,
+            "other / not specified" | "other" | "not specified" => Gender::Other,
+            _ => Gender::Unknown(gender.trim().to_string()),
+        }
+    }
+}
+
+/// A gallery or scraps folder a submission is listed in.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Folder {
+    pub id: i64,
+    pub name: String,
+    /// Path of the folder's first page, like
+    /// `/gallery/deadrussiansoul/folder/123456/Foxes/`.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub path: String,
+}
+
+impl Folder {
+    /// Parse a folder from its link, like
+    /// `/gallery/deadrussiansoul/folder/123456/Foxes/`.
+    pub(crate) fn parse(href: &str, name: String) -> Option<Self> {
+        let id = FOLDER_ID.captures(href)?[1].parse().ok()?;
+        let path = href.split(['?', '#']).next().unwrap_or_default();
+
+        Some(Self {
+            id,
+            name,
+            path: format!("{}/", path.trim_end_matches('/')),
+        })
+    }
+}
+
+/// Category, species, gender, and folders of a submission. Fields are empty
+/// if they weren't shown.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct SubmissionInfo {
+    /// Category, like `Artwork (Traditional)`.
+    pub category: Option<String>,
+    /// Type within the category, like `All` or `Fanart`. The classic
+    /// template calls this the theme.
+    pub kind: Option<String>,
+    pub species: Option<String>,
+    pub gender: Option<Gender>,
+    pub folders: Vec<Folder>,
+}
+
+impl SubmissionInfo {
+    /// Set a field from a row of the sidebar, by its label. Labels that
+    /// aren't for these fields are ignored.
+    pub(crate) fn set(&mut self, label: &str, value: &str) {
+        let value = value.trim();
+        if value.is_empty() {
+            return;
+        }
+
+        match label.trim().trim_end_matches(':') {
+            "Category" => self.category = Some(value.to_string()),
+            "Type" | "Theme" => self.kind = Some(value.to_string()),
+            "Species" => self.species = Some(value.to_string()),
+            "Gender" => self.gender = Some(Gender::parse(value)),
+            _ => (),
+        }
+    }
+}
+
+/// Parse the sidebar of a submission page in the modern template.
+pub(crate) fn parse_info(document: &scraper::Html) -> SubmissionInfo {
+    let mut info = SubmissionInfo::default();
+
+    if let Some(category) = document.select(&CATEGORY).next() {
+        info.set("Category", &join_text_nodes(category));
+    }
+    if let Some(kind) = document.select(&TYPE).next() {
+        info.set("Type", &join_text_nodes(kind));
+    }
+
+    for row in document.select(&ROW) {
+        let label = match row.select(&LABEL).next() {
+            Some(label) => join_text_nodes(label),
+            None => continue,
+        };
+        // The category row has its own elements for the category and type.
+        if label == "Category" {
+            continue;
+        }
+
+        if let Some(value) = row.select(&VALUE).next() {
+            info.set(&label, &join_text_nodes(value));
+        }
+    }
+
+    info.folders = document
+        .select(&FOLDER)
+        .filter_map(|link| Folder::parse(link.value().attr("href")?, join_text_nodes(link)))
+        .collect();
+
+    info
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::SUBMISSION;
+
+    #[test]
+    fn test_parse_info() {
+        let info = parse_info(&scraper::Html::parse_document(SUBMISSION));
+
+        assert_eq!(info.category.as_deref(), Some("Artwork (Traditional)"));
+        assert_eq!(info.kind.as_deref(), Some("All"));
+        assert_eq!(info.species.as_deref(), Some("Fox"));
+        assert_eq!(info.gender, Some(Gender::Any));
+        assert_eq!(
+            info.folders,
+            vec![Folder {
+                id: 123456,
+                name: "Foxes".to_string(),
+                path: "/gallery/deadrussiansoul/folder/123456/Foxes/".to_string(),
+            }]
+        );
+
+        assert_eq!(Gender::parse("Trans (Female)"), Gender::TransFemale);
+        assert_eq!(Gender::parse("Robot"), Gender::Unknown("Robot".to_string()));
+    }
+}
@@ -0,0 +1,217 @@
+//! Parsing journals posted by users.
+
+use crate::username::Artist;
+use crate::{parse_date, Error, JournalId};
+use lazy_static::lazy_static;
+use scraper::Selector;
+
+lazy_static! {
+    static ref JOURNAL_SECTION: Selector = Selector::parse(r#"section[id^="jid:"]"#).unwrap();
+    static ref JOURNAL_TITLE: Selector = Selector::parse(".section-header h2").unwrap();
+    static ref JOURNAL_POSTED_AT: Selector =
+        Selector::parse(".section-header .popup_date").unwrap();
+    static ref JOURNAL_BODY: Selector = Selector::parse(".section-body").unwrap();
+    static ref JOURNAL_LINK: Selector = Selector::parse(r#"a[href^="/journal/"]"#).unwrap();
+    static ref JOURNAL_PAGE: Selector =
+        Selector::parse(r#".journal-page, #columnpage section[id^="jid:"]"#).unwrap();
+    static ref JOURNAL_CONTENT: Selector = Selector::parse(".journal-content").unwrap();
+    static ref NOTICE: Selector =
+        Selector::parse("section.notice-message .redirect-message, .error-message-box").unwrap();
+}
+
+/// The details of a journal shown in a user's list of journals.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct JournalPreview {
+    pub id: JournalId,
+    pub title: String,
+    pub posted_at: chrono::DateTime<chrono::Utc>,
+    /// Text of the journal as shown in the listing, which may be cut short.
+    pub snippet: String,
+}
+
+impl JournalPreview {
+    pub fn url(&self) -> String {
+        format!("https://www.furaffinity.net/journal/{}/", self.id)
+    }
+}
+
+/// A journal, from its page.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Journal {
+    pub id: JournalId,
+    pub title: String,
+    pub author: Artist,
+    pub posted_at: chrono::DateTime<chrono::Utc>,
+    /// HTML of the journal.
+    pub body: String,
+    /// Number of comments shown on the page, including hidden ones.
+    pub comment_count: usize,
+}
+
+impl Journal {
+    pub fn url(&self) -> String {
+        format!("https://www.furaffinity.net/journal/{}/", self.id)
+    }
+}
+
+/// Parse a journal's page, at `/journal/{id}/`. Returns `None` if the
+/// journal was deleted or can't be viewed.
+pub fn parse_journal(id: JournalId, page: &str) -> Result<Option<Journal>, Error> {
+    let document = scraper::Html::parse_document(page);
+
+    if document.select(&NOTICE).next().is_some() {
+        return Ok(None);
+    }
+
+    let author = crate::profile::parse_owner(&document, None)?;
+
+    let section = document
+        .select(&JOURNAL_PAGE)
+        .next()
+        .ok_or_else(|| Error::parse("journal"))?;
+
+    let title = section
+        .select(&JOURNAL_TITLE)
+        .next()
+        .map(crate::join_text_nodes)
+        .ok_or_else(|| Error::parse("journal title"))?;
+
+    let posted_at = section
+        .select(&JOURNAL_POSTED_AT)
+        .next()
+        .and_then(|posted_at| posted_at.value().attr("title"))
+        .ok_or_else(|| Error::parse("posted at"))?;
+
+    let body = section
+        .select(&JOURNAL_CONTENT)
+        .next()
+        .map(|body| body.inner_html().trim().to_string())
+        .ok_or_else(|| Error::parse("journal content"))?;
+
+    Ok(Some(Journal {
+        id,
+        title,
+        author,
+        posted_at: parse_date(posted_at)?,
+        body,
+        comment_count: crate::comment::parse_comments(page)?.len(),
+    }))
+}
+
+/// Parse the journals shown on a page of a user's journals, newest first.
+pub fn parse_journals(page: &str) -> Result<Vec<JournalPreview>, Error> {
+    let document = scraper::Html::parse_document(page);
+
+    document
+        .select(&JOURNAL_SECTION)
+        .map(parse_section)
+        .collect()
+}
+
+/// Parse a section showing a journal. The ID comes from the section's
+/// `jid:` ID, or its link to the journal for sections on profiles.
+pub(crate) fn parse_section(section: scraper::ElementRef) -> Result<JournalPreview, Error> {
+    let id = section
+        .value()
+        .id()
+        .and_then(|id| id.strip_prefix("jid:"))
+        .or_else(|| {
+            section
+                .select(&JOURNAL_LINK)
+                .find_map(|link| link.value().attr("href"))
+                .and_then(|href| href.split('/').nth(2))
+        })
+        .ok_or_else(|| Error::parse("journal id"))?
+        .parse()?;
+
+    let title = section
+        .select(&JOURNAL_TITLE)
+        .next()
+        .map(crate::join_text_nodes)
+        .ok_or_else(|| Error::parse("journal title"))?;
+
+    let posted_at = section
+        .select(&JOURNAL_POSTED_AT)
+        .next()
+        .and_then(|posted_at| posted_at.value().attr("title"))
+        .ok_or_else(|| Error::parse("posted at"))?;
+
+    let snippet = section
+        .select(&JOURNAL_BODY)
+        .next()
+        .map(|body| crate::fragment_text(&body.inner_html()))
+        .unwrap_or_default();
+
+    Ok(JournalPreview {
+        id,
+        title,
+        posted_at: parse_date(posted_at)?,
+        snippet,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{DELETED, JOURNAL};
+
+    #[test]
+    fn test_parse_journal() {
+        let journal = parse_journal(JournalId(10123456), JOURNAL)
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(journal.title, "Commissions open!");
+        assert_eq!(journal.author.slug, "deadrussiansoul");
+        assert_eq!(journal.author.display_name, "Dead_Russian_Soul");
+        assert_eq!(
+            journal.posted_at,
+            parse_date("Apr 1st, 2021 09:00 AM").unwrap()
+        );
+        assert!(journal.body.contains("<b>first come</b>"));
+        assert_eq!(journal.comment_count, 1);
+
+        assert_eq!(parse_journal(JournalId(10123456), DELETED).unwrap(), None);
+    }
+
+    #[test]
+    fn test_parse_journals() {
+        let page = r#"
+            <div id="columnpage">
+                <section id="jid:10123456" class="aligncenter auto_link roundedb">
+                    <div class="section-header">
+                        <h2>Commissions open!</h2>
+                        <span class="popup_date" title="Apr 1st, 2021 09:00 AM">5 days ago</span>
+                    </div>
+                    <div class="section-body">Slots are open.</div>
+                </section>
+                <section id="jid:10100000" class="aligncenter auto_link roundedb">
+                    <div class="section-header">
+                        <h2>Art trade results</h2>
+                        <span class="popup_date" title="Mar 20th, 2021 11:30 PM">2 weeks ago</span>
+                    </div>
+                    <div class="section-body">Thanks everyone.</div>
+                </section>
+            </div>
+        "#;
+
+        let journals = parse_journals(page).unwrap();
+
+        assert_eq!(journals.len(), 2);
+        assert_eq!(journals[0].id, JournalId(10123456));
+        assert_eq!(journals[0].title, "Commissions open!");
+        assert_eq!(journals[0].snippet, "Slots are open.");
+        assert_eq!(
+            journals[0].url(),
+            "https://www.furaffinity.net/journal/10123456/"
+        );
+        assert_eq!(
+            journals[1].posted_at,
+            parse_date("Mar 20, 2021 11:30 PM").unwrap()
+        );
+    }
+}
@@ -1,6 +1,105 @@
+#[cfg(feature = "client")]
+use futures::{Stream, StreamExt};
 use lazy_static::lazy_static;
 use scraper::Selector;
-use std::collections::HashMap;
+
+pub mod actions;
+pub mod anomaly;
+#[cfg(feature = "client")]
+pub mod backfill;
+pub mod browse;
+pub mod cache;
+pub mod caption;
+pub mod checkpoint;
+pub mod clock;
+pub mod comment;
+pub mod commissions;
+#[cfg(feature = "client")]
+pub mod coordinator;
+pub mod dedup;
+pub mod description;
+pub mod diff;
+#[cfg(feature = "client")]
+pub mod download;
+#[cfg(feature = "elasticsearch")]
+pub mod elasticsearch;
+#[cfg(feature = "client")]
+pub mod events;
+#[cfg(feature = "export")]
+pub mod export;
+#[cfg(feature = "fast-parse")]
+mod fast_parse;
+#[cfg(feature = "feed")]
+pub mod feed;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+#[cfg(feature = "client")]
+pub mod file_store;
+#[cfg(feature = "fuzzysearch")]
+pub mod fuzzysearch;
+pub mod gallery;
+pub mod hash;
+pub mod id;
+pub mod info;
+pub mod journal;
+pub mod login;
+#[cfg(feature = "hash")]
+pub mod metadata;
+#[cfg(feature = "client")]
+pub mod metrics;
+pub mod mime;
+pub mod notes;
+pub mod notifications;
+pub mod pagination;
+pub mod parse;
+#[cfg(feature = "client")]
+pub mod poller;
+#[cfg(feature = "sqlx")]
+pub mod postgres;
+pub mod profile;
+#[cfg(feature = "prost")]
+pub mod proto;
+#[cfg(feature = "python")]
+pub mod python;
+#[cfg(feature = "client")]
+pub mod queue;
+#[cfg(feature = "client")]
+pub mod rate_limit;
+#[cfg(any(feature = "sqlx", feature = "diesel"))]
+pub mod record;
+#[cfg(feature = "client")]
+pub mod retry;
+#[cfg(feature = "client")]
+pub mod sampler;
+#[cfg(feature = "diesel")]
+pub mod schema;
+pub mod search;
+#[cfg(feature = "client")]
+pub mod series;
+#[cfg(feature = "client")]
+pub mod session;
+pub mod settings;
+pub mod site;
+pub mod status;
+#[cfg(feature = "store")]
+pub mod store;
+pub mod tags;
+pub mod template;
+#[cfg(any(test, feature = "test-utils"))]
+pub mod test_utils;
+#[cfg(feature = "client")]
+pub mod transport;
+pub mod url;
+pub mod username;
+#[cfg(feature = "client")]
+pub mod verify;
+#[cfg(feature = "client")]
+pub mod watcher;
+pub mod watchlist;
+
+#[cfg(feature = "hash")]
+pub use hash::hash_image;
+pub use id::{JournalId, SubmissionId};
 
 lazy_static! {
     static ref PAGE_TITLE: Selector = Selector::parse("title").unwrap();
@@ -13,14 +112,23 @@ lazy_static! {
     // use src attribute
     static ref IMAGE_URL: Selector = Selector::parse("#submissionImg").unwrap();
     static ref FLASH_OBJECT: Selector = Selector::parse("#flash_embed").unwrap();
+    // use href attribute
+    static ref DOWNLOAD_LINK: Selector = Selector::parse(".download a").unwrap();
     // use title attribute
     static ref POSTED_AT: Selector = Selector::parse(".submission-id-sub-container strong span.popup_date").unwrap();
     // get all, use inner text
     static ref TAGS: Selector = Selector::parse("section.tags-row a").unwrap();
     // html description, includes unneeded .submission-title div but unsure how best to remove
     static ref DESCRIPTION: Selector = Selector::parse(".submission-content section").unwrap();
+    // submission category and type, use inner text
+    static ref CATEGORY: Selector = Selector::parse(".info .category-name, .info .type-name").unwrap();
+    static ref AI_LABEL: regex::Regex = regex::Regex::new(r"\bAI\b").unwrap();
     // submission rating, use inner text
     static ref RATING: Selector = Selector::parse(".stats-container .rating span.rating-box").unwrap();
+    // submission counts, use inner text
+    static ref VIEWS: Selector = Selector::parse(".stats-container .views .font-large").unwrap();
+    static ref FAVORITES: Selector = Selector::parse(".stats-container .favorites .font-large").unwrap();
+    static ref COMMENTS: Selector = Selector::parse(".stats-container .comments .font-large").unwrap();
 
     static ref LATEST_SUBMISSION: Selector = Selector::parse("#gallery-frontpage-submissions figure:first-child b u a").unwrap();
 
@@ -31,496 +139,4813 @@ lazy_static! {
 
     static ref NAV_LINKS: Selector = Selector::parse(".parsed_nav_links").unwrap();
     static ref LINK: Selector = Selector::parse("a").unwrap();
+    static ref CHAPTER: regex::Regex = regex::Regex::new(r"(?i)\b(page|pg|p|chapter|chap|ch|part|pt)\.?\s*#?(\d+)\b").unwrap();
     static ref LINK_ID: regex::Regex = regex::Regex::new(r"/view/(\d+)").unwrap();
+
+    static ref FILE_TIMESTAMP: regex::Regex = regex::Regex::new(r"/art/[^/]+/(?:\w+/)*?(\d+)/").unwrap();
 }
 
 #[derive(thiserror::Error, Debug)]
-#[error("could not process submission: {message}")]
-pub struct Error {
-    pub message: String,
-    pub retry: bool,
+pub enum Error {
+    /// A request failed before a response was received.
+    #[error("network error: {0}")]
+    NetworkError(String),
+    /// The server responded with an error status code.
+    #[error("got server error: {0}")]
+    ServerError(u16),
+    /// The server asked for fewer requests.
+    #[error("rate limited")]
+    RateLimited,
+    /// The page can only be viewed by a logged in account, or one with
+    /// different settings.
+    #[error("login required: {0}")]
+    LoginRequired(String),
+    /// The submission or page was deleted.
+    #[error("deleted")]
+    Deleted,
+    /// Cloudflare showed a challenge instead of the page.
+    #[error("blocked by cloudflare challenge")]
+    CloudflareChallenge,
+    /// The site is offline for maintenance.
+    #[error("site is offline for maintenance")]
+    SiteMaintenance,
+    /// A page was missing something it should always have.
+    #[error("unable to parse {field}")]
+    ParseError { field: &'static str },
+    /// Any other error, and if trying again may succeed.
+    #[error("{message}")]
+    Other { message: String, retry: bool },
 }
 
 impl Error {
-    fn new<T>(message: T, retry: bool) -> Self
+    pub(crate) fn new<T>(message: T, retry: bool) -> Self
     where
         T: Into<String>,
     {
-        Self {
+        Error::Other {
             message: message.into(),
             retry,
         }
     }
+
+    pub(crate) fn parse(field: &'static str) -> Self {
+        Error::ParseError { field }
+    }
+
+    /// An error for a response's status code.
+    pub fn from_status(status: u16) -> Self {
+        match status {
+            429 => Error::RateLimited,
+            status => Error::ServerError(status),
+        }
+    }
+
+    /// If trying again may succeed, such as after a network or server
+    /// error.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            Error::NetworkError(_)
+            | Error::RateLimited
+            | Error::CloudflareChallenge
+            | Error::SiteMaintenance => true,
+            Error::ServerError(status) => *status >= 500,
+            Error::Other { retry, .. } => *retry,
+            Error::LoginRequired(_) | Error::Deleted | Error::ParseError { .. } => false,
+        }
+    }
 }
 
+#[cfg(feature = "client")]
 impl From<reqwest::Error> for Error {
     fn from(error: reqwest::Error) -> Self {
-        Self::new(error.to_string(), true)
+        match error.status() {
+            Some(status) => Self::from_status(status.as_u16()),
+            None => Error::NetworkError(error.to_string()),
+        }
     }
 }
 
+#[cfg(feature = "hash")]
 impl From<image::ImageError> for Error {
     fn from(error: image::ImageError) -> Self {
         Self::new(error.to_string(), false)
     }
 }
 
+impl From<std::io::Error> for Error {
+    fn from(error: std::io::Error) -> Self {
+        Self::new(error.to_string(), false)
+    }
+}
+
+#[cfg(feature = "redis")]
+impl From<redis::RedisError> for Error {
+    fn from(error: redis::RedisError) -> Self {
+        let retry = error.is_io_error() || error.is_timeout();
+
+        Self::new(error.to_string(), retry)
+    }
+}
+
+#[cfg(feature = "store")]
+impl From<rusqlite::Error> for Error {
+    fn from(error: rusqlite::Error) -> Self {
+        Self::new(error.to_string(), false)
+    }
+}
+
 impl From<std::num::ParseIntError> for Error {
     fn from(_error: std::num::ParseIntError) -> Self {
-        Self::new("value was not number", false)
+        Self::parse("number")
     }
 }
 
-type Cookies = HashMap<String, String>;
+#[cfg(feature = "client")]
+type Cookies = std::collections::HashMap<String, String>;
 
+#[cfg(feature = "client")]
 pub struct FurAffinity {
-    cookies: Cookies,
+    /// Sets of cookies to use, in the order to fail over to them.
+    cookies: std::sync::RwLock<Vec<Cookies>>,
+    active_cookies: std::sync::atomic::AtomicUsize,
 
     user_agent: String,
-    client: reqwest::Client,
+    headers: reqwest::header::HeaderMap,
+    transport: std::sync::Arc<dyn transport::HttpTransport>,
+    page_timeout: Option<std::time::Duration>,
+    download_timeout: Option<std::time::Duration>,
+    /// How many times a cut off download may continue from where it stopped.
+    download_resumes: u32,
+
+    hash_options: hash::HashOptions,
+    page_cache: Option<(std::sync::Arc<dyn cache::CacheBackend>, std::time::Duration)>,
+    hash_cache: Option<std::sync::Arc<dyn cache::CacheBackend>>,
+    validated_pages: Option<std::sync::Arc<dyn cache::CacheBackend>>,
+    file_store: Option<std::sync::Arc<dyn file_store::FileStore>>,
+    base_url: String,
+    tag_normalization: Option<tags::TagNormalization>,
+    retry_policy: Option<retry::RetryPolicy>,
+    rate_limiter: Option<rate_limit::RateLimiter>,
+    session_pool: Option<std::sync::Arc<session::SessionPool>>,
+    anomaly_hook: Option<anomaly::AnomalyHook>,
+    metrics_hook: Option<metrics::MetricsHook>,
+    /// Template to parse pages as, instead of detecting it.
+    template: Option<template::Template>,
+    /// The site's clock, once known.
+    server_time: std::sync::RwLock<Option<clock::ServerTime>>,
 }
 
-impl FurAffinity {
-    pub fn new<T>(cookie_a: T, cookie_b: T, user_agent: T, client: Option<reqwest::Client>) -> Self
-    where
-        T: Into<String>,
-    {
-        let mut cookies = HashMap::new();
-        cookies.insert("a".into(), cookie_a.into());
-        cookies.insert("b".into(), cookie_b.into());
+/// Builds a [`FurAffinity`] client, from [`FurAffinity::builder`].
+///
+/// A proxy or timeout can only be set when the builder creates the reqwest
+/// client, not with a custom one.
+#[cfg(feature = "client")]
+#[derive(Debug)]
+pub struct FurAffinityBuilder {
+    cookies: Cookies,
+    user_agent: String,
+    client: Option<reqwest::Client>,
+    proxy: Option<reqwest::Proxy>,
+    timeout: Option<std::time::Duration>,
+    page_timeout: Option<std::time::Duration>,
+    download_timeout: Option<std::time::Duration>,
+    rate_limit: Option<std::time::Duration>,
+    retry_policy: Option<retry::RetryPolicy>,
+}
 
+#[cfg(feature = "client")]
+impl Default for FurAffinityBuilder {
+    fn default() -> Self {
         Self {
-            cookies,
-            user_agent: user_agent.into(),
-            client: client.unwrap_or_default(),
+            cookies: Cookies::new(),
+            user_agent: concat!("furaffinity-rs/", env!("CARGO_PKG_VERSION")).to_string(),
+            client: None,
+            proxy: None,
+            timeout: None,
+            page_timeout: None,
+            download_timeout: None,
+            rate_limit: None,
+            retry_policy: None,
         }
     }
+}
 
-    async fn get_cookies(&self) -> String {
-        self.cookies
-            .iter()
-            .map(|(name, value)| build_cookie(name, value))
-            .collect::<Vec<_>>()
-            .join(";")
+#[cfg(feature = "client")]
+impl FurAffinityBuilder {
+    /// Use the `a` and `b` cookies of a session.
+    pub fn with_cookies<A: Into<String>, B: Into<String>>(
+        mut self,
+        cookie_a: A,
+        cookie_b: B,
+    ) -> Self {
+        self.cookies = cookie_set(cookie_a.into(), cookie_b.into());
+        self
     }
 
-    pub async fn load_page(&self, url: &str) -> reqwest::Result<reqwest::Response> {
-        use reqwest::header;
-
-        self.client
-            .get(url)
-            .header(header::USER_AGENT, &self.user_agent)
-            .header(header::COOKIE, self.get_cookies().await)
-            .send()
-            .await
+    /// Use every cookie in a `Cookie` header, such as one copied from a
+    /// browser's developer tools.
+    pub fn with_cookie_header(mut self, cookie_header: &str) -> Self {
+        self.cookies = parse_cookie_header(cookie_header);
+        self
     }
 
-    pub async fn latest_id(&self) -> Result<(i32, OnlineCounts), Error> {
-        let page = self.load_page("https://www.furaffinity.net/").await?;
+    /// Defaults to `furaffinity-rs/` and the crate's version.
+    pub fn with_user_agent<T: Into<String>>(mut self, user_agent: T) -> Self {
+        self.user_agent = user_agent.into();
+        self
+    }
 
-        if page.status().is_server_error() {
-            return Err(Error::new(
-                format!("got server error: {}", page.status()),
-                true,
-            ));
-        }
+    /// Send requests with an existing reqwest client.
+    pub fn with_client(mut self, client: reqwest::Client) -> Self {
+        self.client = Some(client);
+        self
+    }
 
-        let document = scraper::Html::parse_document(&page.text().await?);
+    /// Send every request through a proxy, such as
+    /// `reqwest::Proxy::all("socks5://127.0.0.1:1080")`. SOCKS proxies need
+    /// the `socks` feature.
+    pub fn with_proxy(mut self, proxy: reqwest::Proxy) -> Self {
+        self.proxy = Some(proxy);
+        self
+    }
 
-        let online = document
-            .select(&ONLINE_STATS_ELEMENT)
-            .next()
-            .map(|elem| elem.text().collect::<String>());
-        let online = online.unwrap_or_default();
-        let mut numbers = ONLINE_NUMBER
-            .find_iter(&online)
-            .collect::<Vec<_>>()
-            .into_iter()
-            .filter_map(|m| m.as_str().parse::<usize>().ok());
+    /// Fail requests that take longer than `timeout` to complete, unless
+    /// they have their own timeout.
+    pub fn with_timeout(mut self, timeout: std::time::Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
 
-        let online = OnlineCounts {
-            total: numbers.next().unwrap_or_default(),
-            guests: numbers.next().unwrap_or_default(),
-            registered: numbers.next().unwrap_or_default(),
-            other: numbers.next().unwrap_or_default(),
-        };
+    /// Fail loading a page if it takes longer than `timeout`.
+    pub fn with_page_timeout(mut self, timeout: std::time::Duration) -> Self {
+        self.page_timeout = Some(timeout);
+        self
+    }
 
-        let latest = document
-            .select(&LATEST_SUBMISSION)
-            .next()
-            .ok_or_else(|| Error::new("value not found", false))?;
+    /// Fail downloading a file if it takes longer than `timeout`, including
+    /// reading all of it.
+    pub fn with_download_timeout(mut self, timeout: std::time::Duration) -> Self {
+        self.download_timeout = Some(timeout);
+        self
+    }
 
-        let id = latest
-            .value()
-            .attr("href")
-            .ok_or_else(|| Error::new("href not found", false))?
-            .split('/')
-            .filter(|part| !part.is_empty())
-            .last()
-            .ok_or_else(|| Error::new("part not found", false))?;
+    /// Wait at least `interval` between starting requests.
+    pub fn with_rate_limit(mut self, interval: std::time::Duration) -> Self {
+        self.rate_limit = Some(interval);
+        self
+    }
 
-        Ok((id.parse()?, online))
+    /// Retry loading pages and files when they fail with a retryable error.
+    pub fn with_retry_policy(mut self, policy: retry::RetryPolicy) -> Self {
+        self.retry_policy = Some(policy);
+        self
     }
 
-    pub async fn get_submission(&self, id: i32) -> Result<Option<Submission>, Error> {
-        let page = self
-            .load_page(&format!("https://www.furaffinity.net/view/{}", id))
-            .await?;
+    /// Create the client. Fails if a proxy or timeout was set along with a
+    /// custom client, or the reqwest client could not be created.
+    pub fn build(self) -> Result<FurAffinity, Error> {
+        let (proxy, timeout) = (self.proxy, self.timeout);
+        let transport = match self.client {
+            Some(_) if proxy.is_some() || timeout.is_some() => {
+                return Err(Error::new(
+                    "a proxy or timeout can't be set with a custom client",
+                    false,
+                ))
+            }
+            Some(client) => transport::ReqwestTransport::new(client),
+            None => transport::ReqwestTransport::with_options(|mut builder| {
+                if let Some(proxy) = &proxy {
+                    builder = builder.proxy(proxy.clone());
+                }
+                if let Some(timeout) = timeout {
+                    builder = builder.timeout(timeout);
+                }
 
-        if page.status().is_server_error() {
-            return Err(Error::new(
-                format!("got server error: {}", page.status()),
-                true,
-            ));
+                builder
+            })?,
+        };
+
+        let mut fa = FurAffinity::new(String::new(), String::new(), self.user_agent, None)
+            .with_transport(std::sync::Arc::new(transport));
+        *fa.cookies.get_mut().unwrap() = vec![self.cookies];
+        fa.page_timeout = self.page_timeout;
+        fa.download_timeout = self.download_timeout;
+
+        if let Some(interval) = self.rate_limit {
+            fa = fa.with_rate_limit(interval);
+        }
+        if let Some(policy) = self.retry_policy {
+            fa = fa.with_retry_policy(policy);
         }
 
-        parse_submission(id, &page.text().await?)
+        Ok(fa)
     }
+}
 
-    pub async fn calc_image_hash(&self, sub: Submission) -> Result<Submission, Error> {
-        let url = match &sub.content {
-            Content::Flash(_) => return Ok(Submission { hash: None, ..sub }),
-            Content::Image(url) => url.clone(),
-        };
-
-        let image = self.load_page(&url).await?;
+#[cfg(feature = "client")]
+impl FurAffinity {
+    /// Configure a client one option at a time.
+    pub fn builder() -> FurAffinityBuilder {
+        Default::default()
+    }
 
-        if image.status().is_server_error() {
-            return Err(Error::new(
-                format!("got server error: {}", image.status()),
-                true,
-            ));
+    pub fn new<T>(cookie_a: T, cookie_b: T, user_agent: T, client: Option<reqwest::Client>) -> Self
+    where
+        T: Into<String>,
+    {
+        Self {
+            cookies: std::sync::RwLock::new(vec![cookie_set(cookie_a, cookie_b)]),
+            active_cookies: Default::default(),
+            user_agent: user_agent.into(),
+            headers: Default::default(),
+            transport: std::sync::Arc::new(transport::ReqwestTransport::new(
+                client.unwrap_or_default(),
+            )),
+            page_timeout: None,
+            download_timeout: None,
+            download_resumes: 3,
+            hash_options: Default::default(),
+            page_cache: None,
+            hash_cache: None,
+            validated_pages: None,
+            file_store: None,
+            base_url: "https://www.furaffinity.net".to_string(),
+            tag_normalization: None,
+            retry_policy: None,
+            rate_limiter: None,
+            session_pool: None,
+            anomaly_hook: None,
+            metrics_hook: None,
+            template: None,
+            server_time: Default::default(),
         }
+    }
 
-        let buf = image.bytes().await?.to_vec();
+    /// Create a client from a `Cookie` header, such as one copied from a
+    /// browser's developer tools. Every cookie in the header is sent,
+    /// including ones like `cf_clearance`.
+    pub fn with_cookie_header<T>(
+        cookie_header: &str,
+        user_agent: T,
+        client: Option<reqwest::Client>,
+    ) -> Self
+    where
+        T: Into<String>,
+    {
+        Self {
+            cookies: std::sync::RwLock::new(vec![parse_cookie_header(cookie_header)]),
+            ..Self::new(String::new(), String::new(), user_agent.into(), client)
+        }
+    }
 
-        use sha2::Digest;
-        let mut hasher = sha2::Sha256::new();
-        hasher.update(&buf);
-        let result: [u8; 32] = hasher.finalize().into();
-        let result: Vec<u8> = result.to_vec();
+    /// Load pages from another server instead of FurAffinity, such as a
+    /// mock server in tests. Defaults to `https://www.furaffinity.net`.
+    pub fn with_base_url<T: Into<String>>(mut self, base_url: T) -> Self {
+        self.base_url = base_url.into().trim_end_matches('/').to_string();
+        self
+    }
 
-        hash_image(&buf).map(|hash| {
-            let mut bytes: [u8; 8] = [0; 8];
-            bytes.copy_from_slice(hash.as_bytes());
+    /// Send requests through a transport instead of reqwest, such as a mock
+    /// or a client with extra middleware. Replaces the client given to
+    /// `new`.
+    pub fn with_transport(
+        mut self,
+        transport: std::sync::Arc<dyn transport::HttpTransport>,
+    ) -> Self {
+        self.transport = transport;
+        self
+    }
 
-            let num = i64::from_be_bytes(bytes);
+    /// Fail loading a page if it takes longer than `timeout`.
+    pub fn with_page_timeout(mut self, timeout: std::time::Duration) -> Self {
+        self.page_timeout = Some(timeout);
+        self
+    }
 
-            Submission {
-                hash: Some(hash.to_base64()),
-                hash_num: Some(num),
-                file_size: Some(buf.len()),
-                file_sha256: Some(result),
-                file: Some(buf),
-                ..sub
-            }
-        })
+    /// Fail downloading a submission's file or thumbnail if it takes longer
+    /// than `timeout`, including reading all of it.
+    pub fn with_download_timeout(mut self, timeout: std::time::Duration) -> Self {
+        self.download_timeout = Some(timeout);
+        self
     }
-}
 
-fn extract_url(elem: scraper::ElementRef, attr: &'static str) -> Option<(String, String, String)> {
-    let url = "https:".to_owned() + elem.value().attr(attr)?;
+    /// Continue a file download that was cut off up to `resumes` times,
+    /// requesting only the missing part of the file. Defaults to 3.
+    ///
+    /// This only happens when the file was sent with its length and an
+    /// `ETag` or `Last-Modified` to check it didn't change, and the server
+    /// supports range requests.
+    pub fn with_download_resumes(mut self, resumes: u32) -> Self {
+        self.download_resumes = resumes;
+        self
+    }
 
-    let url_ext = url.split('.').last()?.to_string();
-    let filename = url.split('/').last()?.to_string();
+    /// URL of a page, from a path starting with `/`.
+    fn page_url(&self, path: &str) -> String {
+        format!("{}{}", self.base_url, path)
+    }
 
-    Some((url, url_ext, filename))
-}
+    /// Set the perceptual hash configuration used by `calc_image_hash`.
+    pub fn with_hash_config(mut self, config: hash::HashConfig) -> Self {
+        self.hash_options.config = config;
+        self
+    }
 
-pub fn parse_submission(id: i32, page: &str) -> Result<Option<Submission>, Error> {
-    let document = scraper::Html::parse_document(page);
+    /// Set all options used by `calc_image_hash`.
+    pub fn with_hash_options(mut self, options: hash::HashOptions) -> Self {
+        self.hash_options = options;
+        self
+    }
 
-    let title_system_error = document
-        .select(&PAGE_TITLE)
-        .next()
-        .map(|elem| join_text_nodes(elem) == "System Error")
-        .unwrap_or(false);
+    /// Cache the contents of submission, gallery, and journal pages for up
+    /// to `ttl`. The cache may be shared with other instances.
+    pub fn with_page_cache(
+        mut self,
+        cache: std::sync::Arc<dyn cache::CacheBackend>,
+        ttl: std::time::Duration,
+    ) -> Self {
+        self.page_cache = Some((cache, ttl));
+        self
+    }
 
-    if title_system_error {
-        return Ok(None);
+    /// Keep pages sent with an `ETag` or `Last-Modified` header and only
+    /// load them again if they changed, using `If-None-Match` and
+    /// `If-Modified-Since`. Unlike the page cache, the server is always
+    /// asked if the page changed.
+    pub fn with_conditional_requests(
+        mut self,
+        cache: std::sync::Arc<dyn cache::CacheBackend>,
+    ) -> Self {
+        self.validated_pages = Some(cache);
+        self
     }
 
-    if document.select(&ERROR_MESSAGE).next().is_some() {
-        return Ok(None);
+    /// Memoize perceptual hashes by the SHA-256 digest of files, so files
+    /// that were already hashed, such as reposts, are not decoded again.
+    ///
+    /// Only hashes that fit in an `i64` are memoized, and images are still
+    /// decoded when a blurhash or extra hash sizes are needed. Submissions
+    /// with a memoized hash have `image_info` read from the file's headers.
+    /// The cache should only be shared by clients with the same hash
+    /// configuration.
+    pub fn with_hash_cache(mut self, cache: std::sync::Arc<dyn cache::CacheBackend>) -> Self {
+        self.hash_cache = Some(cache);
+        self
     }
 
-    let title = match document.select(&TITLE).next() {
-        Some(title) => join_text_nodes(title),
-        None => return Err(Error::new("unable to select title", false)),
-    };
+    /// Keep the contents of files downloaded for hashing in a store. Files
+    /// over the hash options' size limit are not kept.
+    pub fn with_file_store(mut self, store: std::sync::Arc<dyn file_store::FileStore>) -> Self {
+        self.file_store = Some(store);
+        self
+    }
 
-    let artist = match document.select(&ARTIST).next() {
-        Some(artist) => join_text_nodes(artist),
-        None => return Err(Error::new("unable to select artist", false)),
-    };
+    /// Parse submission pages as a template instead of detecting which one
+    /// they were rendered with, for accounts known to use it.
+    pub fn with_template(mut self, template: template::Template) -> Self {
+        self.template = Some(template);
+        self
+    }
 
-    let (content, url_ext, filename) = {
-        if let Some(url) = document.select(&IMAGE_URL).next() {
-            let (url, url_ext, filename) =
-                extract_url(url, "src").ok_or_else(|| Error::new("missing image url", true))?;
+    /// Normalize the tags of submissions as they are loaded, keeping the
+    /// original tags in `original_tags`.
+    pub fn with_tag_normalization(mut self, normalization: tags::TagNormalization) -> Self {
+        self.tag_normalization = Some(normalization);
+        self
+    }
 
-            (Content::Image(url), url_ext, filename)
-        } else if let Some(url) = document.select(&FLASH_OBJECT).next() {
-            let (url, url_ext, filename) =
-                extract_url(url, "data").ok_or_else(|| Error::new("missing flash url", true))?;
+    /// Send a header with every request, such as `Accept-Language`. The
+    /// `User-Agent` and `Cookie` headers are always set from the client's
+    /// user agent and cookies.
+    pub fn with_header(
+        mut self,
+        name: reqwest::header::HeaderName,
+        value: reqwest::header::HeaderValue,
+    ) -> Self {
+        use reqwest::header;
 
-            (Content::Flash(url), url_ext, filename)
-        } else {
-            panic!("invalid submission type")
+        if name != header::USER_AGENT && name != header::COOKIE {
+            self.headers.insert(name, value);
         }
-    };
-
-    let rating = match document.select(&RATING).next() {
-        Some(rating) => Rating::parse(&join_text_nodes(rating))
-            .ok_or_else(|| Error::new("missing rating", true))?,
-        None => return Err(Error::new("unable to select submission rating", false)),
-    };
-
-    let posted_at = match document.select(&POSTED_AT).next() {
-        Some(posted_at) => posted_at
-            .value()
-            .attr("title")
-            .ok_or_else(|| Error::new("missing title", true))?
-            .to_string(),
-        None => return Err(Error::new("unable to select posted at", false)),
-    };
+        self
+    }
 
-    let tags: Vec<String> = document
-        .select(&TAGS)
-        .into_iter()
-        .map(join_text_nodes)
-        .collect();
+    /// Add another set of cookies to fail over to when pages load as a
+    /// guest or the current session is rate limited. Sets are tried in the
+    /// order they were added, after the cookies given to `new`.
+    pub fn with_fallback_cookies<T: Into<String>>(mut self, cookie_a: T, cookie_b: T) -> Self {
+        self.cookies
+            .get_mut()
+            .unwrap()
+            .push(cookie_set(cookie_a, cookie_b));
+        self
+    }
 
-    let description = match document.select(&DESCRIPTION).next() {
-        Some(description) => description.inner_html(),
-        None => return Err(Error::new("unable to select description", false)),
-    };
+    /// Index of the set of cookies currently in use, where 0 is the cookies
+    /// given to `new` and later sets are from `with_fallback_cookies`.
+    pub fn active_cookies(&self) -> usize {
+        self.active_cookies
+            .load(std::sync::atomic::Ordering::SeqCst)
+    }
 
-    Ok(Some(Submission {
-        id,
-        title,
-        artist,
-        content,
-        ext: url_ext,
-        hash: None,
-        hash_num: None,
-        filename,
-        rating,
-        posted_at: parse_date(&posted_at)?,
-        tags,
-        description,
-        file_size: None,
-        file_sha256: None,
-        file: None,
-    }))
-}
+    /// Move to the next set of cookies, unless another request already
+    /// moved on from `failed`.
+    fn fail_over(&self, failed: usize) {
+        let next = (failed + 1) % self.cookies.read().unwrap().len();
 
-#[derive(Clone, Debug, PartialEq)]
-pub struct NavLinks {
-    pub prev: Option<i32>,
-    pub first: Option<i32>,
-    pub next: Option<i32>,
-}
+        let _ = self.active_cookies.compare_exchange(
+            failed,
+            next,
+            std::sync::atomic::Ordering::SeqCst,
+            std::sync::atomic::Ordering::SeqCst,
+        );
+    }
 
-fn parse_nav_links(description: &str) -> Option<NavLinks> {
-    let mut parts = description.split('|');
+    /// Send each request with a session from a pool instead of the client's
+    /// own cookies.
+    pub fn with_session_pool(mut self, pool: std::sync::Arc<session::SessionPool>) -> Self {
+        self.session_pool = Some(pool);
+        self
+    }
 
-    Some(NavLinks {
-        prev: get_link_if_exists(parts.next()?),
-        first: get_link_if_exists(parts.next()?),
-        next: get_link_if_exists(parts.next()?),
-    })
-}
+    /// Wait at least `interval` between starting requests, including file
+    /// downloads.
+    pub fn with_rate_limit(self, interval: std::time::Duration) -> Self {
+        self.with_rate_limiter(rate_limit::RateLimiter::new(interval))
+    }
 
-fn get_link_if_exists(text: &str) -> Option<i32> {
-    let fragment = scraper::Html::parse_fragment(text);
-    let link = fragment.select(&LINK).next()?;
-    let href = link.value().attr("href")?;
+    /// Pace every request with a limiter, which may be shared with other
+    /// clients or have jitter.
+    pub fn with_rate_limiter(mut self, limiter: rate_limit::RateLimiter) -> Self {
+        self.rate_limiter = Some(limiter);
+        self
+    }
 
-    let id = LINK_ID.captures(href)?;
-    id.get(1).and_then(|id| id.as_str().parse().ok())
-}
+    async fn wait_for_rate_limit(&self) {
+        if let Some(limiter) = &self.rate_limiter {
+            limiter.wait().await;
+        }
+    }
 
-pub fn get_hasher() -> img_hash::Hasher<[u8; 8]> {
-    img_hash::HasherConfig::with_bytes_type::<[u8; 8]>()
-        .hash_alg(img_hash::HashAlg::Gradient)
-        .hash_size(8, 8)
-        .preproc_dct()
-        .to_hasher()
-}
+    /// Use a known offset for times shown on pages instead of US Eastern
+    /// time, such as one saved from an earlier
+    /// [`server_time`](Self::server_time). The offset is taken as current.
+    pub fn with_server_offset(self, offset: chrono::FixedOffset) -> Self {
+        *self.server_time.write().unwrap() = Some(clock::ServerTime {
+            now: chrono::Utc::now(),
+            offset,
+        });
+        self
+    }
 
-pub fn hash_image(image: &[u8]) -> Result<img_hash::ImageHash<[u8; 8]>, Error> {
-    let hasher = get_hasher();
+    /// Retry `get_submission`, `calc_image_hash`, and loading the front page
+    /// for `latest_id` when they fail with a retryable error. By default
+    /// errors are returned immediately.
+    pub fn with_retry_policy(mut self, policy: retry::RetryPolicy) -> Self {
+        self.retry_policy = Some(policy);
+        self
+    }
 
-    let image = image::load_from_memory(image)?;
-    let hash = hasher.hash_image(&image);
+    /// Run an operation, retrying it if a retry policy is set.
+    async fn retrying<T, F, Fut>(&self, operation: F) -> Result<T, Error>
+    where
+        F: FnMut() -> Fut,
+        Fut: std::future::Future<Output = Result<T, Error>>,
+    {
+        self.retrying_with(&Default::default(), operation).await
+    }
 
-    Ok(hash)
-}
+    /// Run an operation within the limits of a call, retrying it with the
+    /// call's retry policy or the client's.
+    async fn retrying_with<T, F, Fut>(
+        &self,
+        call: &retry::CallOptions,
+        operation: F,
+    ) -> Result<T, Error>
+    where
+        F: FnMut() -> Fut,
+        Fut: std::future::Future<Output = Result<T, Error>>,
+    {
+        call.run(self.retry_policy.as_ref(), operation).await
+    }
 
-#[derive(Clone, Debug)]
-pub enum Rating {
-    General,
-    Mature,
-    Adult,
-}
+    /// Call a function whenever part of a submission page did not parse as
+    /// expected, with the page's URL.
+    pub fn with_anomaly_hook<F>(mut self, hook: F) -> Self
+    where
+        F: Fn(&str, &anomaly::Anomaly) + Send + Sync + 'static,
+    {
+        self.anomaly_hook = Some(std::sync::Arc::new(hook));
+        self
+    }
 
-impl Rating {
-    fn parse(name: &str) -> Option<Self> {
-        match name {
-            "General" => Some(Rating::General),
-            "Mature" => Some(Rating::Mature),
-            "Adult" => Some(Rating::Adult),
-            _ => None,
-        }
+    /// Call a function with the measurements of each request, once its body
+    /// was read or dropped.
+    pub fn with_metrics_hook<F>(mut self, hook: F) -> Self
+    where
+        F: Fn(&metrics::RequestMetrics) + Send + Sync + 'static,
+    {
+        self.metrics_hook = Some(std::sync::Arc::new(hook));
+        self
     }
 
-    pub fn serialize(&self) -> String {
-        match self {
-            Rating::General => "g".into(),
-            Rating::Mature => "m".into(),
-            Rating::Adult => "a".into(),
+    fn report_anomalies(&self, url: &str, anomalies: &[anomaly::Anomaly]) {
+        if let Some(hook) = &self.anomaly_hook {
+            for anomaly in anomalies {
+                hook(url, anomaly);
+            }
         }
     }
-}
 
-#[derive(Clone, Debug, PartialEq)]
-pub enum Content {
-    Image(String),
-    Flash(String),
-}
+    /// Apply the configured processing to a newly parsed submission.
+    fn prepare(&self, mut sub: Submission) -> Submission {
+        if let Some(time) = *self.server_time.read().unwrap() {
+            sub.posted_at = time.correct(sub.posted_at);
+        }
 
-impl Content {
-    /// Extract URL from any type of Content.
-    pub fn url(&self) -> String {
-        match self {
-            Content::Image(url) => url.clone(),
-            Content::Flash(url) => url.clone(),
+        match &self.tag_normalization {
+            Some(normalization) => sub.with_normalized_tags(normalization),
+            None => sub,
         }
     }
-}
-
-#[derive(Clone, Debug)]
-pub struct Submission {
-    pub id: i32,
-    pub title: String,
-    pub artist: String,
-    pub content: Content,
-    pub ext: String,
-    pub hash: Option<String>,
-    pub hash_num: Option<i64>,
-    pub filename: String,
-    pub rating: Rating,
-    pub posted_at: chrono::DateTime<chrono::Utc>,
-    pub tags: Vec<String>,
-    pub description: String,
-    pub file: Option<Vec<u8>>,
-    pub file_size: Option<usize>,
-    pub file_sha256: Option<Vec<u8>>,
-}
-
-impl Submission {
-    pub fn nav_links(&self) -> Option<NavLinks> {
-        let description = scraper::Html::parse_fragment(&self.description);
-        let parsed_links_section = description.select(&NAV_LINKS).next()?;
 
-        parse_nav_links(&parsed_links_section.inner_html())
+    async fn get_cookies(&self) -> String {
+        cookie_header(&self.cookies.read().unwrap()[self.active_cookies()])
     }
-}
 
-#[derive(Clone, Debug)]
-pub struct OnlineCounts {
-    pub total: usize,
-    pub guests: usize,
-    pub registered: usize,
-    pub other: usize,
-}
+    /// Log in with a username and password, replacing the cookies currently
+    /// in use with the new session's cookies.
+    ///
+    /// Fails if the login page shows a captcha, or the login was rejected.
+    pub async fn login(&self, username: &str, password: &str) -> Result<(), Error> {
+        use reqwest::header;
 
-fn build_cookie(name: &str, value: &str) -> String {
-    format!("{}={}", name, value)
-}
+        let page = self.load_page(&self.page_url("/login/")).await?;
+        if page.status().is_server_error() {
+            return Err(Error::from_status(page.status().as_u16()));
+        }
 
-fn join_text_nodes(elem: scraper::ElementRef) -> String {
-    elem.text().collect::<Vec<_>>().join("").trim().to_string()
-}
+        let form = login::parse_login_form(&page.text().await?)?;
+        if form.captcha {
+            return Err(Error::LoginRequired(
+                "login page requires solving a captcha".into(),
+            ));
+        }
 
-pub fn parse_date(date: &str) -> Result<chrono::DateTime<chrono::Utc>, Error> {
-    use chrono::offset::TimeZone;
+        let action = ::url::Url::parse(&self.page_url("/login/"))
+            .and_then(|base| base.join(&form.action))
+            .map_err(|_err| Error::parse("login form action"))?;
 
-    let date_str = DATE_CLEANER.replace(date, "$1");
+        // Session cookies are set on the redirect after logging in, so it
+        // can't be followed.
+        let request = transport::Request {
+            follow_redirects: false,
+            ..transport::Request::post(action, form.params(username, password))
+        };
+        let resp = self.send(request).await?;
 
-    let zone = chrono::FixedOffset::west(5 * 3600);
-    let date = zone
-        .datetime_from_str(&date_str, "%b %e, %Y %l:%M %p")
-        .map_err(|_err| Error::new("unable to parse date", false))?;
+        if resp.status().is_server_error() {
+            return Err(Error::from_status(resp.status().as_u16()));
+        }
 
-    Ok(date.with_timezone(&chrono::Utc))
-}
+        let cookies = login::parse_set_cookies(
+            resp.headers()
+                .get_all(header::SET_COOKIE)
+                .iter()
+                .filter_map(|value| value.to_str().ok()),
+        );
+        let has_session = login::SESSION_COOKIES
+            .iter()
+            .all(|name| cookies.iter().any(|(cookie, _value)| cookie == name));
+        if !has_session {
+            let message = login::parse_login_error(&resp.text().await?)
+                .unwrap_or_else(|| "no session cookies were set".to_string());
+
+            return Err(Error::LoginRequired(message));
+        }
+
+        let active = self.active_cookies();
+        let mut sets = self.cookies.write().unwrap();
+        sets[active].extend(cookies);
+
+        Ok(())
+    }
+
+    /// If the cookies currently in use are for a logged in session, from
+    /// loading the front page.
+    pub async fn verify_session(&self) -> Result<bool, Error> {
+        let page = self.load_page(&self.page_url("/")).await?;
+        if page.status().is_server_error() {
+            return Err(Error::from_status(page.status().as_u16()));
+        }
+
+        Ok(is_logged_in(&page.text().await?))
+    }
+
+    /// Favorite a submission. Does nothing if it was already favorited.
+    pub async fn fav_submission(&self, id: SubmissionId) -> Result<(), Error> {
+        self.follow_action_link(&format!("/view/{}", id), "favorite link", |links| {
+            (links.fav.as_deref(), links.unfav.is_some())
+        })
+        .await
+    }
+
+    /// Remove a submission from favorites. Does nothing if it wasn't
+    /// favorited.
+    pub async fn unfav_submission(&self, id: SubmissionId) -> Result<(), Error> {
+        self.follow_action_link(&format!("/view/{}", id), "unfavorite link", |links| {
+            (links.unfav.as_deref(), links.fav.is_some())
+        })
+        .await
+    }
+
+    /// Watch a user. Does nothing if they were already watched.
+    pub async fn watch_user(&self, username: &str) -> Result<(), Error> {
+        let path = format!("/user/{}/", username::slug(username));
+        self.follow_action_link(&path, "watch link", |links| {
+            (links.watch.as_deref(), links.unwatch.is_some())
+        })
+        .await
+    }
+
+    /// Stop watching a user. Does nothing if they weren't watched.
+    pub async fn unwatch_user(&self, username: &str) -> Result<(), Error> {
+        let path = format!("/user/{}/", username::slug(username));
+        self.follow_action_link(&path, "unwatch link", |links| {
+            (links.unwatch.as_deref(), links.watch.is_some())
+        })
+        .await
+    }
+
+    /// Load a page and follow one of its favorite or watch links. The
+    /// function picks the link to follow, and if the page instead shows the
+    /// link for undoing it, meaning there is nothing to do.
+    async fn follow_action_link<F>(
+        &self,
+        path: &str,
+        field: &'static str,
+        link: F,
+    ) -> Result<(), Error>
+    where
+        F: Fn(&actions::ActionLinks) -> (Option<&str>, bool),
+    {
+        let page = self.load_uncached(&self.page_url(path)).await?;
+        if !is_logged_in(&page) {
+            return Err(Error::LoginRequired(format!(
+                "{} is only shown when logged in",
+                field
+            )));
+        }
+
+        let links = actions::parse_action_links(&page);
+        let href = match link(&links) {
+            (Some(href), _) => href,
+            (None, true) => return Ok(()),
+            (None, false) => return Err(Error::parse(field)),
+        };
+
+        let resp = self.load_page(&self.page_url(href)).await?;
+        if !resp.status().is_success() {
+            return Err(Error::from_status(resp.status().as_u16()));
+        }
+
+        Ok(())
+    }
+
+    /// Post a comment on a submission.
+    pub async fn post_comment(&self, id: SubmissionId, text: &str) -> Result<(), Error> {
+        if text.trim().is_empty() {
+            return Err(Error::new("comment is empty", false));
+        }
+
+        let url = self.page_url(&format!("/view/{}", id));
+        let page = self.load_uncached(&url).await?;
+        if !is_logged_in(&page) {
+            return Err(Error::LoginRequired(
+                "comments can only be posted when logged in".into(),
+            ));
+        }
+
+        let form = actions::parse_comment_form(&page)?;
+        let action = ::url::Url::parse(&url)
+            .and_then(|base| base.join(&form.action))
+            .map_err(|_err| Error::parse("comment form action"))?;
+
+        let resp = self
+            .send(transport::Request::post(action, form.params(text)))
+            .await?;
+
+        if !resp.status().is_success() {
+            return Err(Error::from_status(resp.status().as_u16()));
+        }
+
+        Ok(())
+    }
+
+    /// Load the text of a page without using the page cache, for pages with
+    /// keys tied to the current session.
+    async fn load_uncached(&self, url: &str) -> Result<String, Error> {
+        let page = self.load_page(url).await?;
+        let status = page.status().as_u16();
+        let text = page.text().await?;
+
+        match site::SiteStatus::detect(status, &text).error() {
+            Some(err) => Err(err),
+            None => Ok(text),
+        }
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(skip_all, fields(url = %url, status))
+    )]
+    pub async fn load_page(&self, url: &str) -> Result<transport::Response, Error> {
+        let request = transport::Request {
+            timeout: self.page_timeout,
+            ..transport::Request::get(url)
+        };
+
+        let page = self.send(request).await?;
+
+        #[cfg(feature = "tracing")]
+        tracing::Span::current().record("status", page.status().as_u16());
+
+        Ok(page)
+    }
+
+    /// Load a page's status and text, sending a conditional request if
+    /// conditional requests are enabled and the page was seen before.
+    async fn load_page_text(&self, url: &str) -> Result<PageText, Error> {
+        use reqwest::header::{self, HeaderValue};
+        use reqwest::StatusCode;
+
+        let cache = match &self.validated_pages {
+            Some(cache) => cache.as_ref(),
+            None => {
+                let page = self.load_page(url).await?;
+                let status = page.status();
+                let info = ResponseInfo::from_response(&page);
+                let session = page.session;
+
+                return Ok(PageText {
+                    status,
+                    text: page.text().await?,
+                    info,
+                    session,
+                });
+            }
+        };
+
+        let mut request = transport::Request {
+            timeout: self.page_timeout,
+            ..transport::Request::get(url)
+        };
+
+        let seen = cache::get_validated_page(cache, url).await?;
+        if let Some(seen) = &seen {
+            let validators = [
+                (header::IF_NONE_MATCH, &seen.etag),
+                (header::IF_MODIFIED_SINCE, &seen.last_modified),
+            ];
+
+            for (name, value) in validators {
+                if let Some(value) = value
+                    .as_deref()
+                    .and_then(|value| HeaderValue::from_str(value).ok())
+                {
+                    request.headers.insert(name, value);
+                }
+            }
+        }
+
+        let page = self.send(request).await?;
+        let status = page.status();
+        let info = ResponseInfo::from_response(&page);
+        let session = page.session;
+
+        if let (StatusCode::NOT_MODIFIED, Some(seen)) = (status, seen) {
+            return Ok(PageText {
+                status: StatusCode::OK,
+                text: seen.body,
+                info,
+                session,
+            });
+        }
+
+        let header = |name| {
+            page.headers()
+                .get(name)
+                .and_then(|value: &HeaderValue| value.to_str().ok())
+                .map(str::to_string)
+        };
+        let mut validated = cache::ValidatedPage {
+            etag: header(header::ETAG),
+            last_modified: header(header::LAST_MODIFIED),
+            body: String::new(),
+        };
+
+        let text = page.text().await?;
+
+        if status.is_success() && validated.has_validators() {
+            validated.body = text;
+            cache::set_validated_page(cache, url, &validated).await?;
+
+            return Ok(PageText {
+                status,
+                text: validated.body,
+                info,
+                session,
+            });
+        }
+
+        Ok(PageText {
+            status,
+            text,
+            info,
+            session,
+        })
+    }
+
+    /// Start downloading a file, with the download timeout instead of the
+    /// page timeout.
+    pub(crate) async fn load_file(&self, url: &str) -> Result<transport::Response, Error> {
+        let request = transport::Request {
+            timeout: self.download_timeout,
+            ..transport::Request::get(url)
+        };
+
+        self.send(request).await
+    }
+
+    /// Request the rest of a file of `len` bytes, starting at `offset`,
+    /// counting each attempt in `resumes`.
+    ///
+    /// `validator` is the `ETag` or `Last-Modified` of the file, so a file
+    /// that changed is sent again in full with a `200` instead of joining
+    /// parts of different files. Returns `None` once out of attempts or if
+    /// the server sent neither the rest nor the whole file.
+    async fn resume_file(
+        &self,
+        url: &str,
+        validator: &str,
+        offset: u64,
+        len: u64,
+        resumes: &mut u32,
+    ) -> Option<transport::Response> {
+        use reqwest::header::{self, HeaderValue};
+
+        while *resumes < self.download_resumes {
+            *resumes += 1;
+
+            #[cfg(feature = "tracing")]
+            tracing::debug!(url, offset, len, "resuming download");
+
+            let mut request = transport::Request {
+                timeout: self.download_timeout,
+                ..transport::Request::get(url)
+            };
+            request.headers.insert(
+                header::RANGE,
+                HeaderValue::from_str(&format!("bytes={}-", offset)).ok()?,
+            );
+            request
+                .headers
+                .insert(header::IF_RANGE, HeaderValue::from_str(validator).ok()?);
+
+            let resp = match self.send(request).await {
+                Ok(resp) => resp,
+                Err(_err) => continue,
+            };
+
+            match resp.status() {
+                reqwest::StatusCode::OK => return Some(resp),
+                reqwest::StatusCode::PARTIAL_CONTENT => {
+                    let range = resp
+                        .headers()
+                        .get(header::CONTENT_RANGE)
+                        .and_then(|value| value.to_str().ok())
+                        .and_then(parse_content_range);
+
+                    return Some(resp).filter(|_| range == Some((offset, len)));
+                }
+                _ => return None,
+            }
+        }
+
+        None
+    }
+
+    /// Send a request through the transport with the client's headers and
+    /// cookies, waiting for the rate limit.
+    async fn send(&self, mut request: transport::Request) -> Result<transport::Response, Error> {
+        use reqwest::header::{self, HeaderValue};
+
+        let mut headers = self.headers.clone();
+        headers.insert(
+            header::USER_AGENT,
+            HeaderValue::from_str(&self.user_agent)
+                .map_err(|_err| Error::new("invalid user agent", false))?,
+        );
+        let lease = match &self.session_pool {
+            Some(pool) => Some(pool.checkout().ok_or_else(|| {
+                Error::LoginRequired("every session in the pool was removed".to_string())
+            })?),
+            None => None,
+        };
+        let cookies = match &lease {
+            Some(lease) => lease.cookies.clone(),
+            None => self.get_cookies().await,
+        };
+        if let Ok(cookies) = HeaderValue::from_str(&cookies) {
+            headers.insert(header::COOKIE, cookies);
+        }
+        headers.extend(request.headers);
+        request.headers = headers;
+
+        self.wait_for_rate_limit().await;
+        if let Some(limiter) = lease.as_ref().and_then(|lease| lease.rate_limiter.as_ref()) {
+            limiter.wait().await;
+        }
+
+        let url = request.url.clone();
+        let mut recorder =
+            metrics::Recorder::start(request.method, &url, self.metrics_hook.clone());
+        let resp = self.transport.send(request).await?;
+        recorder.headers(resp.status().as_u16());
+
+        let mut resp = match resp.url() {
+            Some(_) => resp,
+            None => resp.with_url(url),
+        };
+        resp.session = lease.map(|lease| lease.id);
+
+        Ok(resp.inspect_body(move |chunk| recorder.chunk(chunk)))
+    }
+
+    /// Load the text of a page, using the page cache if enabled.
+    async fn load_text(&self, url: &str) -> Result<String, Error> {
+        let (text, _info) = self.load_text_with_response(url).await?;
+
+        Ok(text)
+    }
+
+    /// Load the text of a page along with the response it came from, which
+    /// is `None` if it came from the page cache.
+    async fn load_text_with_response(
+        &self,
+        url: &str,
+    ) -> Result<(String, Option<ResponseInfo>), Error> {
+        if let Some((cache, _ttl)) = &self.page_cache {
+            if let Some(text) = cache.get(&cache::page_key(url)).await? {
+                return Ok((String::from_utf8_lossy(&text).into_owned(), None));
+            }
+        }
+
+        let mut attempts = 0;
+        let pooled = self.session_pool.as_ref().map_or(0, |pool| pool.len());
+        let PageText {
+            status, text, info, ..
+        } = loop {
+            let active = self.active_cookies();
+            let page = self.load_page_text(url).await?;
+
+            attempts += 1;
+            let failed = session_failed(page.status, &page.text);
+
+            if let (Some(pool), Some(session)) = (&self.session_pool, page.session) {
+                // Guest pages mean the session expired, but rate limited
+                // sessions may still be used later.
+                if failed && page.status.is_success() {
+                    pool.remove(session);
+                }
+
+                if failed && attempts < pooled && !pool.is_empty() {
+                    continue;
+                }
+            } else if attempts < self.cookies.read().unwrap().len() && failed {
+                self.fail_over(active);
+                continue;
+            }
+
+            break page;
+        };
+
+        if let Some(err) = site::SiteStatus::detect(status.as_u16(), &text).error() {
+            return Err(err);
+        }
+
+        let cacheable = status.is_success();
+
+        if let (Some((cache, ttl)), true) = (&self.page_cache, cacheable) {
+            cache
+                .set(&cache::page_key(url), text.as_bytes(), Some(*ttl))
+                .await?;
+        }
+
+        Ok((text, Some(info)))
+    }
+
+    /// ID of the most recent submission, from the front page.
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
+    pub async fn latest_id(&self) -> Result<SubmissionId, Error> {
+        let page = self.load_front_page().await?;
+
+        #[cfg(feature = "tracing")]
+        let start = std::time::Instant::now();
+        let id = parse_latest_id(&page);
+
+        #[cfg(feature = "tracing")]
+        tracing::debug!(
+            parse_ms = start.elapsed().as_millis() as u64,
+            "parsed front page"
+        );
+
+        id
+    }
+
+    /// Number of users online, from the front page.
+    pub async fn online_stats(&self) -> Result<OnlineCounts, Error> {
+        let page = self.load_front_page().await?;
+
+        Ok(parse_online_counts(&page))
+    }
+
+    /// ID of the most recent submission and the number of users online,
+    /// from a single load of the front page.
+    pub async fn latest_id_and_online(&self) -> Result<(SubmissionId, OnlineCounts), Error> {
+        let page = self.load_front_page().await?;
+
+        parse_front_page(&page)
+    }
+
+    /// The submissions in the grid of recent uploads on the front page,
+    /// newest first.
+    pub async fn recent_submissions(&self) -> Result<Vec<gallery::SubmissionPreview>, Error> {
+        let page = self.load_front_page().await?;
+
+        gallery::parse_recent(&page)
+    }
+
+    /// The recent uploads in each section of the front page, from a single
+    /// load of it.
+    pub async fn get_front_page(&self) -> Result<gallery::FrontPage, Error> {
+        let page = self.load_front_page().await?;
+
+        gallery::parse_front_page_sections(&page)
+    }
+
+    async fn load_front_page(&self) -> Result<String, Error> {
+        self.retrying(|| self.try_load_front_page()).await
+    }
+
+    async fn try_load_front_page(&self) -> Result<String, Error> {
+        let PageText { status, text, .. } = self.load_page_text(&self.page_url("/")).await?;
+
+        match site::SiteStatus::detect(status.as_u16(), &text).error() {
+            Some(err) => Err(err),
+            None => Ok(text),
+        }
+    }
+
+    /// If FurAffinity is up, from loading the front page once.
+    pub async fn site_status(&self) -> Result<site::SiteStatus, Error> {
+        let page = self.load_page(&self.page_url("/")).await?;
+        let status = page.status().as_u16();
+
+        Ok(site::SiteStatus::detect(status, &page.text().await?))
+    }
+
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip(self), fields(id = %id)))]
+    pub async fn get_submission(&self, id: SubmissionId) -> Result<Option<Submission>, Error> {
+        self.retrying(|| self.load_submission(id)).await
+    }
+
+    /// Load a submission, or the reason it can't be viewed, such as it
+    /// being deleted or needing an account to view it.
+    pub async fn get_submission_status(
+        &self,
+        id: SubmissionId,
+    ) -> Result<status::SubmissionStatus, Error> {
+        self.retrying(|| async {
+            let page = self
+                .load_text(&self.page_url(&format!("/view/{}", id)))
+                .await?;
+
+            Ok(match parse_submission_status(id, &page)? {
+                status::SubmissionStatus::Found(sub) => {
+                    status::SubmissionStatus::Found(Box::new(self.prepare(*sub)))
+                }
+                status => status,
+            })
+        })
+        .await
+    }
+
+    /// Load a submission from a link to it, from `/view/` or `/full/`.
+    pub async fn get_submission_by_url(&self, link: &str) -> Result<Option<Submission>, Error> {
+        let id = SubmissionId::from_url(link)
+            .ok_or_else(|| Error::new(format!("not a submission link: {}", link), false))?;
+
+        self.get_submission(id).await
+    }
+
+    /// Load a submission within the limits of a single call.
+    pub async fn get_submission_within(
+        &self,
+        id: SubmissionId,
+        call: &retry::CallOptions,
+    ) -> Result<Option<Submission>, Error> {
+        self.retrying_with(call, || self.load_submission(id)).await
+    }
+
+    async fn load_submission(&self, id: SubmissionId) -> Result<Option<Submission>, Error> {
+        let page = self
+            .load_submission_with(id, &GetSubmissionOptions::default())
+            .await?;
+
+        Ok(page.map(|page| page.submission))
+    }
+
+    /// Load a submission along with its comments, from a single request.
+    pub async fn get_submission_with_comments(
+        &self,
+        id: SubmissionId,
+    ) -> Result<Option<(Submission, Vec<comment::Comment>)>, Error> {
+        let page = self
+            .load_submission_with(id, &GetSubmissionOptions::new().with_comments(true))
+            .await?;
+
+        Ok(page.map(|page| (page.submission, page.comments.unwrap_or_default())))
+    }
+
+    /// Load the comments on a submission, in the order they are shown. Use
+    /// [`comment::threads`] to group replies with the comments they reply to.
+    pub async fn get_submission_comments(
+        &self,
+        id: SubmissionId,
+    ) -> Result<Option<Vec<comment::Comment>>, Error> {
+        let page = self.get_submission_with_comments(id).await?;
+
+        Ok(page.map(|(_, comments)| comments))
+    }
+
+    /// Load a submission, only parsing some of its fields. Fields that were
+    /// not requested are left empty.
+    pub async fn get_submission_fields(
+        &self,
+        id: SubmissionId,
+        fields: ParseFields,
+    ) -> Result<Option<Submission>, Error> {
+        let page = self
+            .load_submission_with(id, &GetSubmissionOptions::new().with_fields(fields))
+            .await?;
+
+        Ok(page.map(|page| page.submission))
+    }
+
+    /// Load a submission along with the HTML of its page and the response it
+    /// came from, to keep for parsing again later.
+    pub async fn get_submission_with_page(
+        &self,
+        id: SubmissionId,
+    ) -> Result<Option<SubmissionPage>, Error> {
+        self.get_submission_with(id, &GetSubmissionOptions::new().with_raw_html(true))
+            .await
+    }
+
+    /// Load a submission, with options for what else to load or keep.
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, fields(id = %id)))]
+    pub async fn get_submission_with(
+        &self,
+        id: SubmissionId,
+        options: &GetSubmissionOptions,
+    ) -> Result<Option<SubmissionPage>, Error> {
+        self.retrying_with(&options.call, || self.load_submission_with(id, options))
+            .await
+    }
+
+    async fn load_submission_with(
+        &self,
+        id: SubmissionId,
+        options: &GetSubmissionOptions,
+    ) -> Result<Option<SubmissionPage>, Error> {
+        let url = self.page_url(&format!("/view/{}", id));
+        let (page, response) = self.load_text_with_response(&url).await?;
+
+        let mut anomalies = Vec::new();
+        #[cfg(feature = "tracing")]
+        let start = std::time::Instant::now();
+        let template = self
+            .template
+            .unwrap_or_else(|| template::Template::detect(&page));
+        let parsed = parse_submission_page(id, &page, template, options, &mut anomalies);
+        self.report_anomalies(&url, &anomalies);
+
+        #[cfg(feature = "tracing")]
+        tracing::debug!(
+            bytes = page.len(),
+            parse_ms = start.elapsed().as_millis() as u64,
+            anomalies = anomalies.len(),
+            "parsed submission"
+        );
+
+        let (submission, comments, template) = match parsed? {
+            Some(parsed) => (
+                self.prepare(parsed.submission),
+                parsed.comments,
+                parsed.template,
+            ),
+            None => return Ok(None),
+        };
+
+        let submission = if options.download {
+            self.load_image_hash(submission).await?
+        } else {
+            submission
+        };
+
+        Ok(Some(SubmissionPage {
+            submission,
+            comments,
+            html: if options.raw_html { Some(page) } else { None },
+            response: response.filter(|_| options.raw_html),
+            template,
+        }))
+    }
+
+    /// Load a submission again, returning the new version and what changed
+    /// since the old one. Returns `None` if the submission is no longer
+    /// available.
+    pub async fn refresh(
+        &self,
+        old: &Submission,
+    ) -> Result<Option<(Submission, diff::SubmissionDiff)>, Error> {
+        let new = match self.get_submission(old.id).await? {
+            Some(new) => new,
+            None => return Ok(None),
+        };
+
+        let diff = diff::diff_submissions(old, &new);
+
+        Ok(Some((new, diff)))
+    }
+
+    /// Load a page of a user's gallery, newest submissions first. Pages
+    /// start at 1.
+    pub async fn get_gallery(
+        &self,
+        username: &str,
+        page: u32,
+    ) -> Result<Vec<gallery::SubmissionPreview>, Error> {
+        Ok(self.get_gallery_listing(username, page).await?.submissions)
+    }
+
+    /// Load a page of a user's gallery, including if it was loaded as a
+    /// guest and may be missing submissions.
+    pub async fn get_gallery_listing(
+        &self,
+        username: &str,
+        page: u32,
+    ) -> Result<gallery::Listing, Error> {
+        self.load_folder("gallery", username, page).await
+    }
+
+    /// Load a page of a user's scraps, newest submissions first. Pages start
+    /// at 1.
+    pub async fn get_scraps(
+        &self,
+        username: &str,
+        page: u32,
+    ) -> Result<Vec<gallery::SubmissionPreview>, Error> {
+        Ok(self.get_scraps_listing(username, page).await?.submissions)
+    }
+
+    /// Load a page of a user's scraps, including if it was loaded as a guest
+    /// and may be missing submissions.
+    pub async fn get_scraps_listing(
+        &self,
+        username: &str,
+        page: u32,
+    ) -> Result<gallery::Listing, Error> {
+        self.load_folder("scraps", username, page).await
+    }
+
+    async fn load_folder(
+        &self,
+        folder: &str,
+        username: &str,
+        page: u32,
+    ) -> Result<gallery::Listing, Error> {
+        let page = self
+            .load_text(&self.page_url(&format!(
+                "/{}/{}/{}/",
+                folder,
+                username::slug(username),
+                page
+            )))
+            .await?;
+
+        gallery::parse_listing(&page)
+    }
+
+    /// Load the folders listed in the sidebar of a user's gallery.
+    pub async fn get_gallery_folders(&self, username: &str) -> Result<Vec<info::Folder>, Error> {
+        let page = self
+            .load_text(&self.page_url(&format!("/gallery/{}/1/", username::slug(username))))
+            .await?;
+
+        Ok(gallery::parse_folders(&page))
+    }
+
+    /// Load a page of the submissions in a gallery folder, newest first.
+    /// Pages start at 1.
+    pub async fn get_folder(
+        &self,
+        folder: &info::Folder,
+        page: u32,
+    ) -> Result<gallery::Listing, Error> {
+        let path = match page {
+            0 | 1 => folder.path.clone(),
+            page => format!("{}{}/", folder.path, page),
+        };
+        let page = self.load_text(&self.page_url(&path)).await?;
+
+        gallery::parse_listing(&page)
+    }
+
+    /// Load every submission in the series a submission is part of, in
+    /// order, from its nav links. See [`series::walk_series`].
+    pub async fn walk_series(&self, start: SubmissionId) -> Result<Vec<Submission>, Error> {
+        series::walk_series(self, start).await
+    }
+
+    /// Load every submission in a gallery folder, oldest first.
+    pub async fn walk_folder(
+        &self,
+        folder: &info::Folder,
+    ) -> Result<Vec<gallery::SubmissionPreview>, Error> {
+        series::walk_folder(self, folder).await
+    }
+
+    /// Load a page of a user's favorites, most recently added first. The
+    /// first page is loaded with `None`, and later pages with the previous
+    /// page's [`next_page`](gallery::Favorites::next_page) token.
+    pub async fn get_favorites(
+        &self,
+        username: &str,
+        page_token: Option<&str>,
+    ) -> Result<gallery::Favorites, Error> {
+        let username = username::slug(username);
+        let path = match page_token {
+            Some(token) => format!("/favorites/{}/{}/", username, token.trim_matches('/')),
+            None => format!("/favorites/{}/", username),
+        };
+        let page = self.load_text(&self.page_url(&path)).await?;
+
+        gallery::parse_favorites(&page)
+    }
+
+    /// Load a page of search results for a query, using FurAffinity's
+    /// search syntax. Pages start at 1.
+    pub async fn search(
+        &self,
+        query: &str,
+        page: u32,
+    ) -> Result<Vec<gallery::SubmissionPreview>, Error> {
+        Ok(self.search_listing(query, page).await?.submissions)
+    }
+
+    /// Load a page of search results, including if it was loaded as a
+    /// guest and may be missing submissions.
+    pub async fn search_listing(&self, query: &str, page: u32) -> Result<gallery::Listing, Error> {
+        let mut url = reqwest::Url::parse(&self.page_url("/search/"))
+            .map_err(|err| Error::new(err.to_string(), false))?;
+        url.query_pairs_mut()
+            .append_pair("q", query)
+            .append_pair("page", &page.to_string());
+
+        let page = self.load_text(url.as_str()).await?;
+
+        gallery::parse_listing(&page)
+    }
+
+    /// Load a page of search results with filters and sorting.
+    pub async fn search_with(
+        &self,
+        options: &search::SearchOptions,
+    ) -> Result<search::SearchResults, Error> {
+        let mut url = reqwest::Url::parse(&self.page_url("/search/"))
+            .map_err(|err| Error::new(err.to_string(), false))?;
+        url.query_pairs_mut().extend_pairs(options.params());
+
+        let page = self.load_text(url.as_str()).await?;
+
+        search::parse_search_results(&page)
+    }
+
+    /// Load a page of the newest submissions matching filters.
+    pub async fn browse(
+        &self,
+        options: &browse::BrowseOptions,
+    ) -> Result<browse::BrowsePage, Error> {
+        let mut url = reqwest::Url::parse(&self.page_url("/browse/"))
+            .map_err(|err| Error::new(err.to_string(), false))?;
+        url.query_pairs_mut().extend_pairs(options.params());
+
+        let page = self.load_text(url.as_str()).await?;
+
+        browse::parse_browse(&page)
+    }
+
+    /// Load a page of search results for submissions by a single user.
+    pub async fn search_user(
+        &self,
+        username: &str,
+        keywords: &str,
+        page: u32,
+    ) -> Result<Vec<gallery::SubmissionPreview>, Error> {
+        self.search(&search::user_query(username, keywords), page)
+            .await
+    }
+
+    /// Stream every submission in a user's gallery, newest first, loading
+    /// pages as needed. Ends after the first page that fails to load.
+    pub fn gallery_stream<'a>(
+        &'a self,
+        username: &'a str,
+    ) -> impl Stream<Item = Result<gallery::SubmissionPreview, Error>> + 'a {
+        self.items(pagination::PageCursor::gallery(username))
+    }
+
+    /// Stream every submission in a user's scraps, newest first, loading
+    /// pages as needed. Ends after the first page that fails to load.
+    pub fn scraps_stream<'a>(
+        &'a self,
+        username: &'a str,
+    ) -> impl Stream<Item = Result<gallery::SubmissionPreview, Error>> + 'a {
+        self.items(pagination::PageCursor::scraps(username))
+    }
+
+    /// Stream every submission in a user's favorites, most recently added
+    /// first, loading pages as needed. Ends after the first page that fails
+    /// to load.
+    pub fn favorites_stream<'a>(
+        &'a self,
+        username: &'a str,
+    ) -> impl Stream<Item = Result<gallery::SubmissionPreview, Error>> + 'a {
+        self.items(pagination::PageCursor::favorites(username))
+    }
+
+    /// Stream the items of a listing, loading pages as needed.
+    fn items(
+        &self,
+        cursor: pagination::PageCursor,
+    ) -> impl Stream<Item = Result<gallery::SubmissionPreview, Error>> + '_ {
+        self.pages(cursor).flat_map(|page| {
+            let items: Vec<_> = match page {
+                Ok(page) => page.items.into_iter().map(Ok).collect(),
+                Err(err) => vec![Err(err)],
+            };
+
+            futures::stream::iter(items)
+        })
+    }
+
+    /// Load a page of a listing.
+    pub async fn fetch_page(
+        &self,
+        cursor: &pagination::PageCursor,
+    ) -> Result<pagination::Page<gallery::SubmissionPreview>, Error> {
+        use pagination::PagedListing;
+
+        let listing = match &cursor.listing {
+            PagedListing::Gallery(username) => {
+                self.get_gallery_listing(username, cursor.page).await?
+            }
+            PagedListing::Scraps(username) => {
+                self.get_scraps_listing(username, cursor.page).await?
+            }
+            PagedListing::Search(query) => self.search_listing(query, cursor.page).await?,
+            PagedListing::Favorites { username, token } => {
+                let favorites = self.get_favorites(username, token.as_deref()).await?;
+
+                return Ok(cursor.favorites_page_of(favorites));
+            }
+        };
+
+        Ok(cursor.page_of(listing))
+    }
+
+    /// Load the page after a page, or `None` if it was the last page.
+    pub async fn fetch_next(
+        &self,
+        page: &pagination::Page<gallery::SubmissionPreview>,
+    ) -> Result<Option<pagination::Page<gallery::SubmissionPreview>>, Error> {
+        match &page.next {
+            Some(next) => self.fetch_page(next).await.map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// Stream the pages of a listing starting at a cursor, until a page is
+    /// empty. Ends after the first page that fails to load.
+    pub fn pages(
+        &self,
+        cursor: pagination::PageCursor,
+    ) -> impl Stream<Item = Result<pagination::Page<gallery::SubmissionPreview>, Error>> + '_ {
+        futures::stream::unfold(Some(cursor), move |cursor| async move {
+            let cursor = cursor?;
+
+            match self.fetch_page(&cursor).await {
+                Ok(page) if page.items.is_empty() => None,
+                Ok(page) => {
+                    let next = page.next.clone();
+                    Some((Ok(page), next))
+                }
+                Err(err) => Some((Err(err), None)),
+            }
+        })
+    }
+
+    /// Load the comment a link points to, along with its replies. Returns
+    /// `None` if the comment is not on the page, such as when it or the page
+    /// was deleted.
+    pub async fn get_comment_thread(
+        &self,
+        link: &comment::CommentLink,
+    ) -> Result<Option<comment::CommentThread>, Error> {
+        let path = match &link.page {
+            url::FaUrl::View(id) => format!("/view/{}", id),
+            url::FaUrl::Journal(id) => format!("/journal/{}/", id),
+            _ => {
+                return Err(Error::new(
+                    "comment link is not to a submission or journal",
+                    false,
+                ))
+            }
+        };
+
+        let page = self.load_text(&self.page_url(&path)).await?;
+        let comments = comment::parse_comments(&page)?;
+
+        Ok(comment::thread(comments, link.id))
+    }
+
+    /// Load the options for filtering submissions from the browse page.
+    pub async fn get_taxonomy(&self) -> Result<search::Taxonomy, Error> {
+        let page = self.load_text(&self.page_url("/browse/")).await?;
+
+        search::parse_taxonomy(&page)
+    }
+
+    /// Load the site's current time and the offset of times shown on pages.
+    /// Submissions loaded afterwards have their posted times corrected for
+    /// the offset.
+    pub async fn server_time(&self) -> Result<clock::ServerTime, Error> {
+        let page = self.load_page(&self.page_url("/")).await?;
+
+        let now = page
+            .headers()
+            .get(reqwest::header::DATE)
+            .and_then(|date| date.to_str().ok())
+            .and_then(|date| chrono::DateTime::parse_from_rfc2822(date).ok())
+            .map(|date| date.with_timezone(&chrono::Utc))
+            .unwrap_or_else(chrono::Utc::now);
+
+        let time = clock::ServerTime::from_page(now, &page.text().await?)?;
+        *self.server_time.write().unwrap() = Some(time);
+
+        Ok(time)
+    }
+
+    /// Load a user's profile. Returns `None` if the user does not exist.
+    pub async fn get_user(&self, username: &str) -> Result<Option<profile::UserProfile>, Error> {
+        let page = self
+            .load_text(&self.page_url(&format!("/user/{}/", username::slug(username))))
+            .await?;
+
+        profile::parse_profile(username, &page)
+    }
+
+    /// Load a user's commission info. Returns `None` if the user does not
+    /// exist or their commission info can't be viewed.
+    pub async fn get_commissions(
+        &self,
+        username: &str,
+    ) -> Result<Option<commissions::CommissionInfo>, Error> {
+        let page = self
+            .load_text(&self.page_url(&format!("/commissions/{}/", username::slug(username))))
+            .await?;
+
+        commissions::parse_commissions(username, &page)
+    }
+
+    /// Check which ratings the active session can view, from the account's
+    /// settings page.
+    pub async fn content_settings(&self) -> Result<settings::ContentSettings, Error> {
+        let page = self
+            .load_text(&self.page_url("/controls/settings/"))
+            .await?;
+
+        settings::parse_content_settings(&page)
+    }
+
+    /// Load a user's profile to find the URL of their current avatar,
+    /// including when it was changed. Returns `None` if the user does not
+    /// exist.
+    ///
+    /// [`url::avatar_url`] builds a URL without loading the profile.
+    pub async fn get_avatar_url(&self, username: &str) -> Result<Option<String>, Error> {
+        let username = username::slug(username);
+        let page = self
+            .load_text(&self.page_url(&format!("/user/{}/", username)))
+            .await?;
+
+        Ok(url::parse_avatar_url(&page, &username))
+    }
+
+    /// Load a user's current avatar. Returns `None` if the user does not
+    /// exist.
+    ///
+    /// If `download` is set, the avatar is downloaded and hashed the same way
+    /// as submission files, using the hash cache if enabled.
+    pub async fn get_user_avatar(
+        &self,
+        username: &str,
+        download: bool,
+    ) -> Result<Option<profile::Avatar>, Error> {
+        let avatar = match self.get_avatar_url(username).await? {
+            Some(url) => profile::Avatar::from_url(url),
+            None => return Ok(None),
+        };
+
+        if !download {
+            return Ok(Some(avatar));
+        }
+
+        let file = self.load_file(&avatar.url).await?;
+
+        if file.status().is_server_error() {
+            return Err(Error::from_status(file.status().as_u16()));
+        }
+
+        let content = Content::Image(avatar.url.clone());
+        let file = self
+            .read_file(file, Some(&content), None::<&mut tokio::io::Sink>)
+            .await?;
+        file.check_complete()?;
+
+        let data = match file.data {
+            Some(data) => data,
+            None => return Ok(Some(avatar)),
+        };
+
+        let hash_num = match self.memoized_hash(&file.digests.sha256).await? {
+            Some(hash_num) => Some(hash_num),
+            None => {
+                let (_data, hashed) = self.hash_image_data(data).await?;
+                let hash_num = hashed.and_then(|hashed| hashed.hash.to_i64());
+
+                if let (Some(cache), Some(hash_num)) = (&self.hash_cache, hash_num) {
+                    cache::set_hash(
+                        cache.as_ref(),
+                        &self.hash_options.config,
+                        &file.digests.sha256,
+                        hash_num,
+                    )
+                    .await?;
+                }
+
+                hash_num
+            }
+        };
+
+        Ok(Some(profile::Avatar {
+            hash_num,
+            sha256: Some(file.digests.sha256),
+            ..avatar
+        }))
+    }
+
+    /// Load a journal. Returns `None` if it was deleted or can't be viewed.
+    pub async fn get_journal(&self, id: JournalId) -> Result<Option<journal::Journal>, Error> {
+        let page = self
+            .load_text(&self.page_url(&format!("/journal/{}/", id)))
+            .await?;
+
+        journal::parse_journal(id, &page)
+    }
+
+    /// Load a page of a user's journals, newest first. Pages start at 1.
+    pub async fn get_user_journals(
+        &self,
+        username: &str,
+        page: u32,
+    ) -> Result<Vec<journal::JournalPreview>, Error> {
+        let page = self
+            .load_text(&self.page_url(&format!("/journals/{}/{}/", username::slug(username), page)))
+            .await?;
+
+        journal::parse_journals(&page)
+    }
+
+    /// Load a page of the users a user is watching. Pages start at 1.
+    pub async fn get_watching(
+        &self,
+        username: &str,
+        page: u32,
+    ) -> Result<watchlist::WatchList, Error> {
+        self.load_watchlist("by", username, page).await
+    }
+
+    /// Load a page of the users watching a user. Pages start at 1.
+    pub async fn get_watchers(
+        &self,
+        username: &str,
+        page: u32,
+    ) -> Result<watchlist::WatchList, Error> {
+        self.load_watchlist("to", username, page).await
+    }
+
+    async fn load_watchlist(
+        &self,
+        direction: &str,
+        username: &str,
+        page: u32,
+    ) -> Result<watchlist::WatchList, Error> {
+        let page = self
+            .load_text(&self.page_url(&format!(
+                "/watchlist/{}/{}/{}/",
+                direction,
+                username::slug(username),
+                page.max(1)
+            )))
+            .await?;
+
+        watchlist::parse_watchlist(&page)
+    }
+
+    /// Load counts of unread notifications for the logged in account.
+    pub async fn get_notifications(&self) -> Result<notifications::Notifications, Error> {
+        let page = self.load_text(&self.page_url("/")).await?;
+
+        notifications::parse_notifications(&page)
+    }
+
+    /// Load a page of notes in a folder for the logged in account, newest
+    /// first. Pages start at 1.
+    pub async fn get_notes(
+        &self,
+        folder: notes::NoteFolder,
+        page: u32,
+    ) -> Result<Vec<notes::NotePreview>, Error> {
+        let page = self
+            .load_text(&self.page_url(&format!(
+                "/controls/switchbox/{}/{}/",
+                folder.as_str(),
+                page.max(1)
+            )))
+            .await?;
+
+        notes::parse_notes(&page)
+    }
+
+    /// Load a note sent to or by the logged in account. Returns `None` if it
+    /// was deleted or can't be viewed.
+    pub async fn get_note(&self, id: i64) -> Result<Option<notes::Note>, Error> {
+        let page = self
+            .load_text(&self.page_url(&format!("/viewmessage/{}/", id)))
+            .await?;
+
+        notes::parse_note(id, &page)
+    }
+
+    /// Load a page of new submissions from users the logged in account
+    /// watches. The first page is loaded with `None`, and later pages with
+    /// the previous page's [`next_page`](notifications::NewSubmissions::next_page).
+    pub async fn get_new_submissions(
+        &self,
+        page: Option<&str>,
+    ) -> Result<notifications::NewSubmissions, Error> {
+        let path = match page {
+            Some(page) => format!("/msg/submissions/{}/", page.trim_matches('/')),
+            None => "/msg/submissions/".to_string(),
+        };
+        let page = self.load_text(&self.page_url(&path)).await?;
+
+        notifications::parse_new_submissions(&page)
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(skip_all, fields(id = %sub.id))
+    )]
+    pub async fn calc_image_hash(&self, sub: Submission) -> Result<Submission, Error> {
+        self.retrying(|| self.load_image_hash(sub.clone())).await
+    }
+
+    /// Download and hash a submission within the limits of a single call.
+    pub async fn calc_image_hash_within(
+        &self,
+        sub: Submission,
+        call: &retry::CallOptions,
+    ) -> Result<Submission, Error> {
+        self.retrying_with(call, || self.load_image_hash(sub.clone()))
+            .await
+    }
+
+    /// Download and hash a submission, writing the file to `writer` as it
+    /// is received.
+    ///
+    /// The file is only kept in memory if something needs all of it, such
+    /// as decoding an image for its perceptual hash, and is never kept in
+    /// the submission's `file`. Failed downloads are not retried, as part of
+    /// the file may already have been written, but downloads that were cut
+    /// off continue from where they stopped.
+    pub async fn calc_image_hash_to<W>(
+        &self,
+        sub: Submission,
+        writer: &mut W,
+    ) -> Result<Submission, Error>
+    where
+        W: tokio::io::AsyncWrite + Unpin,
+    {
+        let file = self.load_file(sub.content.url()).await?;
+
+        if file.status() == reqwest::StatusCode::NOT_FOUND {
+            return Err(Error::Deleted);
+        }
+
+        if !file.status().is_success() {
+            return Err(Error::from_status(file.status().as_u16()));
+        }
+
+        let file = self
+            .read_file(file, Some(&sub.content), Some(writer))
+            .await?;
+        file.check_complete()?;
+
+        let sub = self.hash_file(sub, file).await?;
+
+        Ok(Submission { file: None, ..sub })
+    }
+
+    async fn load_image_hash(&self, sub: Submission) -> Result<Submission, Error> {
+        if self.hash_options.thumbnail_only {
+            return self.hash_thumbnail(sub).await;
+        }
+
+        let url = match &sub.content {
+            Content::Flash(_) if self.hash_options.flash_thumbnails => {
+                return self.hash_thumbnail(sub).await
+            }
+            Content::Flash(_) => return Ok(Submission { hash: None, ..sub }),
+            Content::Image(url) | Content::Text(url) | Content::Audio(url) => url.clone(),
+        };
+
+        let image = self.load_file(&url).await?;
+
+        if image.status() == reqwest::StatusCode::NOT_FOUND {
+            return Err(Error::Deleted);
+        }
+
+        if !image.status().is_success() {
+            return Err(Error::from_status(image.status().as_u16()));
+        }
+
+        let file = self
+            .read_file(image, Some(&sub.content), None::<&mut tokio::io::Sink>)
+            .await?;
+        file.check_complete()?;
+
+        self.hash_file(sub, file).await
+    }
+
+    /// Download and hash many submissions, with up to `concurrency` running
+    /// at once. Results are yielded as they complete, along with the ID of
+    /// the submission they are for.
+    pub fn hash_pipeline<'a, S>(
+        &'a self,
+        submissions: S,
+        concurrency: usize,
+    ) -> impl Stream<Item = (SubmissionId, Result<Submission, Error>)> + 'a
+    where
+        S: Stream<Item = Submission> + 'a,
+    {
+        submissions
+            .map(move |sub| async move { (sub.id, self.calc_image_hash(sub).await) })
+            .buffer_unordered(concurrency)
+    }
+
+    /// Check if a submission's file still matches a known SHA-256 digest.
+    pub async fn verify_content(
+        &self,
+        sub: &Submission,
+        expected_sha256: &[u8],
+    ) -> Result<Verification, Error> {
+        let file = self.load_file(sub.content.url()).await?;
+
+        if file.status() == reqwest::StatusCode::NOT_FOUND {
+            return Ok(Verification::Missing);
+        }
+
+        if file.status().is_server_error() {
+            return Err(Error::from_status(file.status().as_u16()));
+        }
+
+        let file = self
+            .read_file(file, None, None::<&mut tokio::io::Sink>)
+            .await?;
+        file.check_complete()?;
+
+        if file.digests.sha256 == expected_sha256 {
+            Ok(Verification::Matches)
+        } else {
+            Ok(Verification::Changed {
+                sha256: file.digests.sha256,
+            })
+        }
+    }
+
+    /// Read a file from a response, calculating digests as each chunk arrives
+    /// instead of walking the completed file a second time.
+    ///
+    /// The contents are kept if hashing the content needs them, and each
+    /// chunk is written to `writer` if there is one.
+    async fn read_file<W>(
+        &self,
+        mut resp: transport::Response,
+        content: Option<&Content>,
+        mut writer: Option<&mut W>,
+    ) -> Result<DownloadedFile, Error>
+    where
+        W: tokio::io::AsyncWrite + Unpin,
+    {
+        use tokio::io::AsyncWriteExt;
+
+        let mut headers = FileHeaders::from_response(&resp);
+        let mut validator = range_validator(&resp);
+
+        let mut digests = hash::DigestState::new(&self.hash_options);
+        let mut data = Some(Vec::new()).filter(|_| content.is_some());
+        let mut size = 0;
+        let mut mime = None;
+        let mut over_limit = false;
+
+        let url = resp.url().map(str::to_string);
+        let mut resumes = 0;
+
+        loop {
+            let chunk = match resp.chunk().await {
+                Ok(Some(chunk)) => chunk,
+                Ok(None) if headers.content_length.is_none_or(|len| size as u64 >= len) => break,
+                result => {
+                    // The file was cut off, so only ask for the rest of it.
+                    let rest = match (&url, &validator, headers.content_length) {
+                        (Some(url), Some(validator), Some(len)) => {
+                            self.resume_file(url, validator, size as u64, len, &mut resumes)
+                                .await
+                        }
+                        _ => None,
+                    };
+
+                    match (rest, result) {
+                        (Some(rest), _) if rest.status() == reqwest::StatusCode::OK => {
+                            // The file changed, so start over with the new
+                            // one, unless part of the old one was written.
+                            if writer.is_some() {
+                                return Err(Error::new(
+                                    "corrupt download: file changed while downloading",
+                                    false,
+                                ));
+                            }
+
+                            headers = FileHeaders::from_response(&rest);
+                            validator = range_validator(&rest);
+                            digests = hash::DigestState::new(&self.hash_options);
+                            data = Some(Vec::new()).filter(|_| content.is_some());
+                            size = 0;
+                            mime = None;
+                            over_limit = false;
+                            resp = rest;
+                            continue;
+                        }
+                        (Some(rest), _) => {
+                            resp = rest;
+                            continue;
+                        }
+                        (None, Err(err)) => return Err(err),
+                        // Left for `check_complete` to report.
+                        (None, Ok(_)) => break,
+                    }
+                }
+            };
+
+            if size == 0 {
+                mime = mime::sniff(&chunk);
+
+                let retain = writer.is_none();
+                if !content.is_some_and(|content| self.needs_file(content, mime, retain)) {
+                    data = None;
+                }
+            }
+
+            digests.update(&chunk);
+            size += chunk.len();
+
+            if let Some(writer) = writer.as_mut() {
+                writer.write_all(&chunk).await?;
+            }
+
+            // Files over the limit are only digested, there's no reason to
+            // keep collecting them.
+            if !self.hash_options.within_limit(size) {
+                over_limit = true;
+                data = None;
+            }
+
+            if let Some(data) = data.as_mut() {
+                data.extend_from_slice(&chunk);
+            }
+        }
+
+        if let Some(writer) = writer {
+            writer.flush().await?;
+        }
+
+        Ok(DownloadedFile {
+            data,
+            size,
+            over_limit,
+            digests: digests.finalize(),
+            mime,
+            headers: Some(headers),
+        })
+    }
+
+    /// If hashing a file needs its entire contents, instead of only its
+    /// digests.
+    fn needs_file(&self, content: &Content, mime: Option<&str>, retain: bool) -> bool {
+        matches!(content, Content::Image(_))
+            || mime == Some("text/plain")
+            || self.file_store.is_some()
+            || self.hash_options.metadata
+            || (retain && self.hash_options.retain_file)
+    }
+
+    /// Calculate hashes for a submission using file contents that have
+    /// already been downloaded.
+    pub async fn calc_image_hash_from_bytes(
+        &self,
+        sub: Submission,
+        buf: Vec<u8>,
+    ) -> Result<Submission, Error> {
+        let file = DownloadedFile::from_bytes(&self.hash_options, buf, None);
+
+        self.hash_file(sub, file).await
+    }
+
+    /// Calculate hashes for a submission using a file from
+    /// [`download_content`](Self::download_content).
+    pub async fn calc_image_hash_from_file(
+        &self,
+        sub: Submission,
+        file: DownloadedFile,
+    ) -> Result<Submission, Error> {
+        self.hash_file(sub, file).await
+    }
+
+    /// Download a submission's file of any type, including flash, without
+    /// hashing it. The contents are always kept.
+    pub async fn download_content(&self, sub: &Submission) -> Result<DownloadedFile, Error> {
+        self.retrying(|| self.load_content(sub)).await
+    }
+
+    async fn load_content(&self, sub: &Submission) -> Result<DownloadedFile, Error> {
+        let resp = self.load_file(sub.content.url()).await?;
+
+        if resp.status() == reqwest::StatusCode::NOT_FOUND {
+            return Err(Error::Deleted);
+        }
+
+        if !resp.status().is_success() {
+            return Err(Error::from_status(resp.status().as_u16()));
+        }
+
+        let headers = FileHeaders::from_response(&resp);
+        let data = resp.bytes().await?.to_vec();
+
+        let file = DownloadedFile {
+            digests: hash::digest_file(&self.hash_options, &data),
+            size: data.len(),
+            over_limit: false,
+            mime: mime::sniff(&data),
+            data: Some(data),
+            headers: Some(headers),
+        };
+        file.check_complete()?;
+
+        Ok(file)
+    }
+
+    /// Perceptually hash an image with the client's hash configuration, or
+    /// `None` if it is not in a format that can be decoded.
+    pub async fn perceptual_hash(&self, data: &[u8]) -> Result<Option<hash::ImageHash>, Error> {
+        let (_data, hashed) = self.hash_image_data(data.to_vec()).await?;
+
+        Ok(hashed.map(|hashed| hashed.hash))
+    }
+
+    async fn hash_file(&self, sub: Submission, file: DownloadedFile) -> Result<Submission, Error> {
+        let is_image = matches!(sub.content, Content::Image(_));
+        let is_text = file.mime == Some("text/plain");
+
+        let (data, sub) = match file.data {
+            None if !file.over_limit => (None, sub),
+            None => (
+                None,
+                Submission {
+                    hash_skipped: Some(hash::HashSkipReason::TooLarge),
+                    ..sub
+                },
+            ),
+            Some(data) if !is_image => (Some(data), sub),
+            Some(data) => match self.memoized_hash(&file.digests.sha256).await? {
+                Some(hash_num) => {
+                    let image_info = hash::read_image_info(&data);
+                    let sub = Submission {
+                        image_info,
+                        ..sub.with_memoized_hash(hash_num)
+                    };
+
+                    (Some(data), sub)
+                }
+                None => {
+                    #[cfg(feature = "tracing")]
+                    let start = std::time::Instant::now();
+                    #[cfg(feature = "tracing")]
+                    let bytes = data.len();
+
+                    let (data, hashed) = self.hash_image_data(data).await?;
+
+                    #[cfg(feature = "tracing")]
+                    tracing::debug!(
+                        bytes,
+                        hash_ms = start.elapsed().as_millis() as u64,
+                        "hashed image"
+                    );
+
+                    let sub = sub.with_hashed(hashed, hash::HashSource::File);
+
+                    if let (Some(cache), Some(hash_num)) = (&self.hash_cache, sub.hash_num) {
+                        cache::set_hash(
+                            cache.as_ref(),
+                            &self.hash_options.config,
+                            &file.digests.sha256,
+                            hash_num,
+                        )
+                        .await?;
+                    }
+
+                    (Some(data), sub)
+                }
+            },
+        };
+
+        if let (Some(store), Some(data)) = (&self.file_store, data.as_deref()) {
+            file_store::put_if_missing(store.as_ref(), &file.digests.sha256, data).await?;
+        }
+
+        let text_hash = data
+            .as_deref()
+            .filter(|_| is_text)
+            .map(|data| hash::hash_text(&String::from_utf8_lossy(data)));
+
+        let file_metadata = data
+            .as_deref()
+            .filter(|_| self.hash_options.metadata)
+            .map(metadata::extract);
+
+        Ok(Submission {
+            file_size: Some(file.size),
+            file_sha256: Some(file.digests.sha256),
+            file_md5: file.digests.md5,
+            file_blake3: file.digests.blake3,
+            file_mime: file.mime.map(String::from),
+            file_headers: file.headers,
+            text_hash,
+            file_metadata,
+            file: data.filter(|_| self.hash_options.retain_file),
+            ..sub
+        })
+    }
+
+    /// The memoized perceptual hash of a file, if there is one and the
+    /// image doesn't need to be decoded for anything else.
+    async fn memoized_hash(&self, sha256: &[u8]) -> Result<Option<i64>, Error> {
+        let cache = match &self.hash_cache {
+            Some(cache) => cache,
+            None => return Ok(None),
+        };
+
+        if self.hash_options.blurhash.is_some() || !self.hash_options.extra_sizes.is_empty() {
+            return Ok(None);
+        }
+
+        cache::get_hash(cache.as_ref(), &self.hash_options.config, sha256).await
+    }
+
+    /// Perceptually hash the submission's thumbnail instead of its file.
+    async fn hash_thumbnail(&self, sub: Submission) -> Result<Submission, Error> {
+        let url = sub
+            .thumbnail_url(THUMBNAIL_HASH_SIZE)
+            .ok_or_else(|| Error::new("unable to build thumbnail url", false))?;
+
+        let thumbnail = self.load_file(&url).await?;
+
+        if thumbnail.status() == reqwest::StatusCode::NOT_FOUND {
+            return Err(Error::Deleted);
+        }
+
+        if !thumbnail.status().is_success() {
+            return Err(Error::from_status(thumbnail.status().as_u16()));
+        }
+
+        let data = thumbnail.bytes().await?.to_vec();
+        let (_data, hashed) = self.hash_image_data(data).await?;
+
+        Ok(sub.with_hashed(hashed, hash::HashSource::Thumbnail))
+    }
+
+    /// Perceptually hash file contents, handing the data back after hashing.
+    async fn hash_image_data(
+        &self,
+        data: Vec<u8>,
+    ) -> Result<(Vec<u8>, Option<hash::HashedImage>), Error> {
+        let options = self.hash_options.clone();
+
+        if !options.blocking {
+            let hashed = hash::hash_supported_image(&options, &data)?;
+            return Ok((data, hashed));
+        }
+
+        let (data, hashed) = tokio::task::spawn_blocking(move || {
+            let hashed = hash::hash_supported_image(&options, &data);
+            (data, hashed)
+        })
+        .await
+        .map_err(|err| Error::new(err.to_string(), false))?;
+
+        Ok((data, hashed?))
+    }
+}
+
+/// Size of thumbnail to download when hashing thumbnails.
+#[cfg(feature = "client")]
+const THUMBNAIL_HASH_SIZE: u32 = 600;
+
+/// Contents and details of a downloaded file.
+#[cfg(feature = "client")]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DownloadedFile {
+    /// File contents, if they were kept.
+    pub data: Option<Vec<u8>>,
+    pub size: usize,
+    /// If the file was over the size limit, so its contents weren't kept.
+    pub over_limit: bool,
+    pub digests: hash::Digests,
+    /// MIME type detected from the contents of the file.
+    pub mime: Option<&'static str>,
+    /// Response headers from downloading the file.
+    pub headers: Option<FileHeaders>,
+}
+
+#[cfg(feature = "client")]
+impl DownloadedFile {
+    /// Details of file contents, keeping them if they are within the size
+    /// limit of the hash options.
+    fn from_bytes(
+        options: &hash::HashOptions,
+        data: Vec<u8>,
+        headers: Option<FileHeaders>,
+    ) -> Self {
+        let within_limit = options.within_limit(data.len());
+
+        Self {
+            digests: hash::digest_file(options, &data),
+            size: data.len(),
+            over_limit: !within_limit,
+            mime: mime::sniff(&data),
+            data: Some(data).filter(|_| within_limit),
+            headers,
+        }
+    }
+
+    pub fn sha256(&self) -> &[u8] {
+        &self.digests.sha256
+    }
+
+    /// Content type the file was sent with, or the type detected from its
+    /// contents.
+    pub fn content_type(&self) -> Option<&str> {
+        self.headers
+            .as_ref()
+            .and_then(|headers| headers.content_type.as_deref())
+            .or(self.mime)
+    }
+
+    /// Make sure the entire file was received, so a cut off download is
+    /// retried instead of being hashed.
+    fn check_complete(&self) -> Result<(), Error> {
+        let expected = self
+            .headers
+            .as_ref()
+            .and_then(|headers| headers.content_length);
+
+        match (expected, self.mime, &self.data) {
+            (Some(expected), _, _) if expected != self.size as u64 => Err(Error::new(
+                format!(
+                    "corrupt download: expected {} bytes, got {}",
+                    expected, self.size
+                ),
+                true,
+            )),
+            // Without a length, the only sign of a cut off file is a missing
+            // trailer.
+            (None, Some(mime), Some(data)) if mime::is_truncated(mime, data) => {
+                Err(Error::new("corrupt download: file ended early", true))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Result of checking a submission's file against a known digest.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub enum Verification {
+    /// The file has the expected digest.
+    Matches,
+    /// The file no longer has the expected digest.
+    Changed { sha256: Vec<u8> },
+    /// The file no longer exists.
+    Missing,
+}
+
+/// Headers from the response when downloading a submission's file.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct FileHeaders {
+    pub content_type: Option<String>,
+    pub content_length: Option<u64>,
+    pub last_modified: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+#[cfg(feature = "client")]
+impl FileHeaders {
+    fn from_response(resp: &transport::Response) -> Self {
+        use reqwest::header;
+
+        let headers = resp.headers();
+        let get = |name| headers.get(name).and_then(|value| value.to_str().ok());
+
+        Self {
+            content_type: get(header::CONTENT_TYPE).map(String::from),
+            content_length: get(header::CONTENT_LENGTH).and_then(|len| len.parse().ok()),
+            last_modified: get(header::LAST_MODIFIED)
+                .and_then(|date| chrono::DateTime::parse_from_rfc2822(date).ok())
+                .map(|date| date.with_timezone(&chrono::Utc)),
+        }
+    }
+}
+
+/// Validator to send as `If-Range` when resuming a download, from a strong
+/// `ETag` or else `Last-Modified`.
+#[cfg(feature = "client")]
+fn range_validator(resp: &transport::Response) -> Option<String> {
+    use reqwest::header;
+
+    let get = |name| {
+        resp.headers()
+            .get(name)
+            .and_then(|value: &reqwest::header::HeaderValue| value.to_str().ok())
+    };
+
+    get(header::ETAG)
+        .filter(|etag| !etag.starts_with("W/"))
+        .or_else(|| get(header::LAST_MODIFIED))
+        .map(str::to_string)
+}
+
+/// Start and total length from a `Content-Range` header, like
+/// `bytes 100-199/200`.
+#[cfg(feature = "client")]
+fn parse_content_range(value: &str) -> Option<(u64, u64)> {
+    let (range, len) = value.strip_prefix("bytes ")?.split_once('/')?;
+    let (start, _end) = range.split_once('-')?;
+
+    Some((start.trim().parse().ok()?, len.trim().parse().ok()?))
+}
+
+fn extract_url(elem: scraper::ElementRef, attr: &'static str) -> Option<(String, String, String)> {
+    file_url(elem.value().attr(attr)?)
+}
+
+/// The full URL, extension, and filename of a file from a protocol-relative
+/// link.
+fn file_url(src: &str) -> Option<(String, String, String)> {
+    let url = "https:".to_owned() + src;
+
+    let url_ext = url.split('.').next_back()?.to_string();
+    let filename = url.split('/').next_back()?.to_string();
+
+    Some((url, url_ext, filename))
+}
+
+/// What to load or keep when loading a submission with
+/// [`FurAffinity::get_submission_with`].
+#[cfg(feature = "client")]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GetSubmissionOptions {
+    fields: ParseFields,
+    comments: bool,
+    download: bool,
+    raw_html: bool,
+    call: retry::CallOptions,
+}
+
+#[cfg(feature = "client")]
+impl Default for GetSubmissionOptions {
+    fn default() -> Self {
+        Self {
+            fields: ParseFields::ALL,
+            comments: false,
+            download: false,
+            raw_html: false,
+            call: Default::default(),
+        }
+    }
+}
+
+#[cfg(feature = "client")]
+impl GetSubmissionOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Optional fields to parse. Defaults to all fields.
+    pub fn with_fields(mut self, fields: ParseFields) -> Self {
+        self.fields = fields;
+        self
+    }
+
+    /// Parse the comments on the submission.
+    pub fn with_comments(mut self, comments: bool) -> Self {
+        self.comments = comments;
+        self
+    }
+
+    /// Download and hash the file, as with [`FurAffinity::calc_image_hash`].
+    pub fn with_download(mut self, download: bool) -> Self {
+        self.download = download;
+        self
+    }
+
+    /// Keep the HTML of the submission page and details of the response.
+    pub fn with_raw_html(mut self, raw_html: bool) -> Self {
+        self.raw_html = raw_html;
+        self
+    }
+
+    /// Deadline and retry policy for loading the submission and its file.
+    pub fn with_call_options(mut self, call: retry::CallOptions) -> Self {
+        self.call = call;
+        self
+    }
+}
+
+/// A submission loaded with [`FurAffinity::get_submission_with`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct SubmissionPage {
+    pub submission: Submission,
+    /// Comments on the submission, if requested.
+    pub comments: Option<Vec<comment::Comment>>,
+    /// HTML of the submission page, if requested.
+    pub html: Option<String>,
+    /// Response the page came from, if the HTML was requested and the page
+    /// didn't come from the page cache.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub response: Option<ResponseInfo>,
+    /// Template the page was rendered with.
+    pub template: template::Template,
+}
+
+/// A page's text, with the status and response it came from.
+#[cfg(feature = "client")]
+struct PageText {
+    status: reqwest::StatusCode,
+    text: String,
+    info: ResponseInfo,
+    /// Session from the session pool the page was loaded with.
+    session: Option<usize>,
+}
+
+/// Details of the response a page came from.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct ResponseInfo {
+    /// URL of the page, after following redirects.
+    pub url: String,
+    /// Status code, which is 304 if the page hadn't changed since it was
+    /// loaded with conditional requests.
+    pub status: u16,
+    /// Headers in the order they were sent, with lowercase names.
+    pub headers: Vec<(String, String)>,
+}
+
+#[cfg(feature = "client")]
+impl ResponseInfo {
+    fn from_response(resp: &transport::Response) -> Self {
+        Self {
+            url: resp.url().unwrap_or_default().to_string(),
+            status: resp.status().as_u16(),
+            headers: resp
+                .headers()
+                .iter()
+                .map(|(name, value)| {
+                    (
+                        name.to_string(),
+                        String::from_utf8_lossy(value.as_bytes()).into_owned(),
+                    )
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Optional fields to parse from a submission page.
+///
+/// The title, artist, content, rating, and posting date are always parsed.
+/// Skipping the tags and description saves time when only the file is
+/// needed, such as when hashing.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct ParseFields(u8);
+
+impl ParseFields {
+    /// Only the fields that are always parsed.
+    pub const NONE: Self = Self(0);
+    pub const TAGS: Self = Self(1 << 0);
+    pub const DESCRIPTION: Self = Self(1 << 1);
+    pub const ALL: Self = Self(Self::TAGS.0 | Self::DESCRIPTION.0);
+
+    /// If all of the fields in `other` are included.
+    pub fn contains(self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+}
+
+impl Default for ParseFields {
+    fn default() -> Self {
+        Self::ALL
+    }
+}
+
+impl std::ops::BitOr for ParseFields {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self {
+        Self(self.0 | rhs.0)
+    }
+}
+
+pub fn parse_submission(id: SubmissionId, page: &str) -> Result<Option<Submission>, Error> {
+    parse_submission_fields(id, page, ParseFields::ALL)
+}
+
+/// Parse a submission page, or the reason it didn't show the submission.
+pub fn parse_submission_status(
+    id: SubmissionId,
+    page: &str,
+) -> Result<status::SubmissionStatus, Error> {
+    Ok(match parse_submission(id, page)? {
+        Some(sub) => status::SubmissionStatus::Found(Box::new(sub)),
+        None => status::parse_unavailable(page),
+    })
+}
+
+/// Parse a submission, only parsing some of its fields. Fields that were not
+/// requested are left empty.
+pub fn parse_submission_fields(
+    id: SubmissionId,
+    page: &str,
+    fields: ParseFields,
+) -> Result<Option<Submission>, Error> {
+    let template = template::Template::detect(page);
+
+    #[cfg(feature = "fast-parse")]
+    if template == template::Template::Modern {
+        return fast_parse::parse_submission(id, page, fields, &mut Vec::new());
+    }
+
+    let document = scraper::Html::parse_document(page);
+
+    parse_submission_document(id, &document, template, fields, &mut Vec::new())
+}
+
+/// Parse a submission and the comments on it from the same page.
+pub fn parse_submission_with_comments(
+    id: SubmissionId,
+    page: &str,
+) -> Result<Option<(Submission, Vec<comment::Comment>)>, Error> {
+    let document = scraper::Html::parse_document(page);
+    let template = template::Template::detect(page);
+
+    let mut anomalies = Vec::new();
+    let sub =
+        match parse_submission_document(id, &document, template, ParseFields::ALL, &mut anomalies)?
+        {
+            Some(sub) => sub,
+            None => return Ok(None),
+        };
+    let comments = comment::parse_comments_document(&document, &mut anomalies)?;
+
+    Ok(Some((sub, comments)))
+}
+
+/// Parse a submission page rendered with a template, with the fields and
+/// comments requested in the options, without keeping its HTML.
+#[cfg(feature = "client")]
+fn parse_submission_page(
+    id: SubmissionId,
+    page: &str,
+    template: template::Template,
+    options: &GetSubmissionOptions,
+    anomalies: &mut Vec<anomaly::Anomaly>,
+) -> Result<Option<SubmissionPage>, Error> {
+    #[cfg(feature = "fast-parse")]
+    if !options.comments && template == template::Template::Modern {
+        let submission = fast_parse::parse_submission(id, page, options.fields, anomalies)?;
+
+        return Ok(submission.map(|submission| SubmissionPage {
+            submission,
+            comments: None,
+            html: None,
+            response: None,
+            template,
+        }));
+    }
+
+    let document = scraper::Html::parse_document(page);
+
+    let submission =
+        match parse_submission_document(id, &document, template, options.fields, anomalies)? {
+            Some(submission) => submission,
+            None => return Ok(None),
+        };
+    let comments = if options.comments {
+        Some(comment::parse_comments_document(&document, anomalies)?)
+    } else {
+        None
+    };
+
+    Ok(Some(SubmissionPage {
+        submission,
+        comments,
+        html: None,
+        response: None,
+        template,
+    }))
+}
+
+fn parse_submission_document(
+    id: SubmissionId,
+    document: &scraper::Html,
+    template: template::Template,
+    fields: ParseFields,
+    anomalies: &mut Vec<anomaly::Anomaly>,
+) -> Result<Option<Submission>, Error> {
+    let title_system_error = document
+        .select(&PAGE_TITLE)
+        .next()
+        .map(|elem| join_text_nodes(elem) == "System Error")
+        .unwrap_or(false);
+
+    if title_system_error {
+        return Ok(None);
+    }
+
+    if document.select(&ERROR_MESSAGE).next().is_some() {
+        return Ok(None);
+    }
+
+    if template == template::Template::Classic {
+        anomalies.push(anomaly::Anomaly::new(
+            "template",
+            anomaly::AnomalyKind::Fallback,
+        ));
+
+        return template::parse_classic_submission(id, document, fields, anomalies).map(Some);
+    }
+
+    let title = match document.select(&TITLE).next() {
+        Some(title) => join_text_nodes(title),
+        None => return Err(Error::parse("title")),
+    };
+
+    let (artist, artist_info) = match document.select(&ARTIST).next() {
+        Some(artist) => {
+            let name = join_text_nodes(artist);
+            let info = username::Artist::parse(&name, artist.value().attr("href"));
+
+            (name, info)
+        }
+        None => return Err(Error::parse("artist")),
+    };
+
+    let (content, url_ext, filename) = parse_content(document, anomalies)?;
+
+    let image = document.select(&IMAGE_URL).next();
+    let image_url = |attr| {
+        image
+            .and_then(|image| extract_url(image, attr))
+            .map(|(url, _, _)| url)
+    };
+    let preview_url = image_url("data-preview-src");
+    let fullview_url = image_url("data-fullview-src");
+    let avatar_url = url::parse_avatar_url_document(document, &artist_info.slug);
+
+    let rating = match document.select(&RATING).next() {
+        Some(rating) => {
+            Rating::parse(&join_text_nodes(rating)).ok_or_else(|| Error::parse("rating"))?
+        }
+        None => return Err(Error::parse("submission rating")),
+    };
+
+    let ai_generated = document
+        .select(&CATEGORY)
+        .any(|category| AI_LABEL.is_match(&join_text_nodes(category)));
+
+    let count = |selector: &Selector| {
+        document
+            .select(selector)
+            .next()
+            .map(|count| SubmissionStats::parse_count(&join_text_nodes(count)))
+            .transpose()
+            .map(Option::unwrap_or_default)
+    };
+    let stats = SubmissionStats {
+        views: count(&VIEWS)?,
+        favorites: count(&FAVORITES)?,
+        comments: count(&COMMENTS)?,
+    };
+
+    let posted_at = match document.select(&POSTED_AT).next() {
+        Some(posted_at) => posted_at
+            .value()
+            .attr("title")
+            .ok_or_else(|| Error::parse("posted at"))?
+            .to_string(),
+        None => return Err(Error::parse("posted at")),
+    };
+
+    let tags: Vec<String> = if fields.contains(ParseFields::TAGS) {
+        document.select(&TAGS).map(join_text_nodes).collect()
+    } else {
+        Vec::new()
+    };
+
+    let description = if fields.contains(ParseFields::DESCRIPTION) {
+        match document.select(&DESCRIPTION).next() {
+            Some(description) => description.inner_html(),
+            None => return Err(Error::parse("description")),
+        }
+    } else {
+        String::new()
+    };
+
+    let mut builder = SubmissionBuilder::new(id, content);
+    if let Some(preview_url) = preview_url {
+        builder = builder.with_preview_url(preview_url);
+    }
+    if let Some(fullview_url) = fullview_url {
+        builder = builder.with_fullview_url(fullview_url);
+    }
+    if let Some(avatar_url) = avatar_url {
+        builder = builder.with_avatar_url(avatar_url);
+    }
+
+    let sub = builder
+        .with_title(title)
+        .with_artist(artist)
+        .with_artist_info(artist_info)
+        .with_rating(rating)
+        .with_ai_generated(ai_generated)
+        .with_stats(stats)
+        .with_info(info::parse_info(document))
+        .with_posted_at(parse_date(&posted_at)?)
+        .with_tags(tags)
+        .with_description(description)
+        .build();
+
+    Ok(Some(Submission {
+        ext: url_ext,
+        filename,
+        ..sub
+    }))
+}
+
+/// The submission's file, with its extension and filename. Both templates
+/// show the file the same way.
+fn parse_content(
+    document: &scraper::Html,
+    anomalies: &mut Vec<anomaly::Anomaly>,
+) -> Result<(Content, String, String), Error> {
+    let download = document
+        .select(&DOWNLOAD_LINK)
+        .next()
+        .and_then(|link| extract_url(link, "href"))
+        .and_then(|(url, url_ext, filename)| {
+            Content::from_download(url).map(|content| (content, url_ext, filename))
+        });
+
+    let (content, url_ext, filename) = if let Some(download) = download {
+        download
+    } else if let Some(url) = document.select(&IMAGE_URL).next() {
+        let (url, url_ext, filename) =
+            extract_url(url, "src").ok_or_else(|| Error::parse("image url"))?;
+
+        (Content::Image(url), url_ext, filename)
+    } else if let Some(url) = document.select(&FLASH_OBJECT).next() {
+        let (url, url_ext, filename) =
+            extract_url(url, "data").ok_or_else(|| Error::parse("flash url"))?;
+
+        (Content::Flash(url), url_ext, filename)
+    } else {
+        return Err(Error::parse("content type"));
+    };
+
+    if !filename.contains('.') {
+        anomalies.push(anomaly::Anomaly::new("ext", anomaly::AnomalyKind::Missing));
+    }
+
+    Ok((content, url_ext, filename))
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct NavLinks {
+    pub prev: Option<SubmissionId>,
+    pub first: Option<SubmissionId>,
+    pub next: Option<SubmissionId>,
+    /// Text of the link to the previous submission, such as `<<< PREV`.
+    pub prev_label: Option<String>,
+    /// Text of the link to the first submission.
+    pub first_label: Option<String>,
+    /// Text of the link to the next submission.
+    pub next_label: Option<String>,
+    /// Where this submission is in the series, from its title or
+    /// description.
+    pub chapter: Option<Chapter>,
+}
+
+/// A position in a series, such as `Page 3` or `Ch. 2`.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Chapter {
+    pub unit: ChapterUnit,
+    pub number: u32,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub enum ChapterUnit {
+    Page,
+    Chapter,
+    Part,
+}
+
+impl Chapter {
+    /// Find the first position mentioned in some text.
+    pub fn parse(text: &str) -> Option<Self> {
+        let captures = CHAPTER.captures(text)?;
+
+        let unit = match captures[1].to_lowercase().as_str() {
+            "page" | "pg" | "p" => ChapterUnit::Page,
+            "chapter" | "ch" | "chap" => ChapterUnit::Chapter,
+            _ => ChapterUnit::Part,
+        };
+
+        Some(Self {
+            unit,
+            number: captures[2].parse().ok()?,
+        })
+    }
+}
+
+/// Which navigation link a label is for.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum NavLabel {
+    Prev,
+    First,
+    Next,
+}
+
+impl NavLabel {
+    fn parse(label: &str) -> Option<Self> {
+        let label = label.to_lowercase();
+        let words: Vec<_> = label
+            .split(|c: char| !c.is_alphabetic())
+            .filter(|word| !word.is_empty())
+            .collect();
+
+        if words
+            .iter()
+            .any(|word| matches!(*word, "first" | "start" | "beginning"))
+        {
+            Some(NavLabel::First)
+        } else if words
+            .iter()
+            .any(|word| matches!(*word, "prev" | "previous" | "back"))
+        {
+            Some(NavLabel::Prev)
+        } else if words.contains(&"next") {
+            Some(NavLabel::Next)
+        } else if words.is_empty() {
+            // Links that are only arrows, such as `<<<` or `>>>`.
+            let arrows = label.trim();
+            if !arrows.is_empty() && arrows.chars().all(|c| matches!(c, '<' | '«' | '←')) {
+                Some(NavLabel::Prev)
+            } else if !arrows.is_empty() && arrows.chars().all(|c| matches!(c, '>' | '»' | '→'))
+            {
+                Some(NavLabel::Next)
+            } else {
+                None
+            }
+        } else {
+            None
+        }
+    }
+}
+
+/// Find links to other submissions labeled as the previous, first, or next
+/// submission, in any order and with any separators.
+fn parse_nav_links(description: &str) -> Option<NavLinks> {
+    let fragment = scraper::Html::parse_fragment(description);
+
+    let mut links = NavLinks::default();
+    let mut found = false;
+
+    for link in fragment.select(&LINK) {
+        let id = match link
+            .value()
+            .attr("href")
+            .and_then(|href| LINK_ID.captures(href))
+            .and_then(|id| id.get(1)?.as_str().parse().ok())
+        {
+            Some(id) => id,
+            None => continue,
+        };
+
+        let label = join_text_nodes(link)
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ");
+
+        let (slot, slot_label) = match NavLabel::parse(&label) {
+            Some(NavLabel::Prev) => (&mut links.prev, &mut links.prev_label),
+            Some(NavLabel::First) => (&mut links.first, &mut links.first_label),
+            Some(NavLabel::Next) => (&mut links.next, &mut links.next_label),
+            None => continue,
+        };
+
+        if slot.is_none() {
+            *slot = Some(id);
+            *slot_label = Some(label);
+            found = true;
+        }
+    }
+
+    if found {
+        Some(links)
+    } else {
+        None
+    }
+}
+
+/// Submission ratings, ordered from least to most explicit.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(
+    feature = "diesel",
+    derive(diesel::expression::AsExpression, diesel::deserialize::FromSqlRow),
+    diesel(sql_type = diesel::sql_types::Text)
+)]
+pub enum Rating {
+    #[cfg_attr(feature = "serde", serde(rename = "g", alias = "General"))]
+    General,
+    #[cfg_attr(feature = "serde", serde(rename = "m", alias = "Mature"))]
+    Mature,
+    #[cfg_attr(feature = "serde", serde(rename = "a", alias = "Adult"))]
+    Adult,
+}
+
+impl Rating {
+    fn parse(name: &str) -> Option<Self> {
+        match name {
+            "General" => Some(Rating::General),
+            "Mature" => Some(Rating::Mature),
+            "Adult" => Some(Rating::Adult),
+            _ => None,
+        }
+    }
+
+    pub fn serialize(&self) -> String {
+        self.code().into()
+    }
+
+    /// Single letter code for the rating, as used by [`Rating::serialize`].
+    pub fn code(&self) -> &'static str {
+        match self {
+            Rating::General => "g",
+            Rating::Mature => "m",
+            Rating::Adult => "a",
+        }
+    }
+
+    /// If the rating is safe for work.
+    pub fn is_sfw(&self) -> bool {
+        *self == Rating::General
+    }
+
+    /// Parse a rating from its single letter code.
+    pub fn from_code(code: &str) -> Option<Self> {
+        match code {
+            "g" => Some(Rating::General),
+            "m" => Some(Rating::Mature),
+            "a" => Some(Rating::Adult),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub enum Content {
+    Image(String),
+    Flash(String),
+    /// A story or poem, such as a text, PDF, or Word file.
+    Text(String),
+    /// Music or other audio.
+    Audio(String),
+}
+
+impl Content {
+    /// Extract URL from any type of Content.
+    pub fn url(&self) -> &str {
+        match self {
+            Content::Image(url) => url,
+            Content::Flash(url) => url,
+            Content::Text(url) => url,
+            Content::Audio(url) => url,
+        }
+    }
+
+    /// Name of the kind of content, such as `image`.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Content::Image(_) => "image",
+            Content::Flash(_) => "flash",
+            Content::Text(_) => "text",
+            Content::Audio(_) => "audio",
+        }
+    }
+
+    /// Content for a story, poem, or music file, from the URL of a
+    /// submission's download link. Returns `None` for other files.
+    pub(crate) fn from_download(url: String) -> Option<Self> {
+        const TEXT: &[&str] = &["txt", "rtf", "doc", "docx", "odt", "pdf"];
+        const AUDIO: &[&str] = &["mp3", "wav", "ogg", "mid", "midi", "flac", "m4a"];
+
+        let path = url.to_lowercase();
+        let ext = path
+            .rsplit_once('.')
+            .map(|(_path, ext)| ext)
+            .unwrap_or_default();
+
+        if path.contains("/stories/") || path.contains("/poetry/") || TEXT.contains(&ext) {
+            Some(Content::Text(url))
+        } else if path.contains("/music/") || AUDIO.contains(&ext) {
+            Some(Content::Audio(url))
+        } else {
+            None
+        }
+    }
+
+    /// Name of the file, the last part of the URL.
+    pub fn filename(&self) -> &str {
+        self.url().rsplit('/').next().unwrap_or_default()
+    }
+
+    /// Extension of the file, without a leading period. Empty if the file
+    /// has no extension.
+    pub fn ext(&self) -> &str {
+        self.filename()
+            .rsplit_once('.')
+            .map(|(_name, ext)| ext)
+            .unwrap_or_default()
+    }
+}
+
+impl std::fmt::Display for Content {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.url())
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Submission {
+    pub id: SubmissionId,
+    pub title: String,
+    pub artist: String,
+    /// The artist's username and account status, when parsed from a page.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub artist_info: Option<username::Artist>,
+    /// URL of the artist's avatar shown on the submission page.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub avatar_url: Option<String>,
+    pub content: Content,
+    pub ext: String,
+    pub hash: Option<String>,
+    /// Numeric representation of the hash, only set for 8x8 hashes.
+    pub hash_num: Option<i64>,
+    /// Hashes at the additional sizes requested in the hash options.
+    pub extra_hashes: Vec<hash::SizedHash>,
+    /// Why a perceptual hash was not calculated for the file, if it was
+    /// skipped.
+    pub hash_skipped: Option<hash::HashSkipReason>,
+    /// What was downloaded to calculate the perceptual hash.
+    pub hash_source: Option<hash::HashSource>,
+    /// Details about the image, available after it was decoded for hashing.
+    pub image_info: Option<hash::ImageInfo>,
+    /// Blurhash placeholder for the image, if enabled when hashing.
+    pub blurhash: Option<String>,
+    /// Simhash fingerprint of plain text files, comparable with
+    /// [`hash::HashDistance`] to find near-duplicate writing.
+    pub text_hash: Option<i64>,
+    pub filename: String,
+    pub rating: Rating,
+    /// If FurAffinity labels the submission as generated by AI.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub ai_generated: bool,
+    /// Views, favorites, and comments when the page was loaded.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub stats: SubmissionStats,
+    /// Category, species, gender, and folders from the page's sidebar.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub info: info::SubmissionInfo,
+    /// URL of the preview image shown on the submission page, which is a
+    /// thumbnail for stories, music, and most images.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub preview_url: Option<String>,
+    /// URL of the full size image shown on the submission page.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub fullview_url: Option<String>,
+    pub posted_at: chrono::DateTime<chrono::Utc>,
+    pub tags: Vec<String>,
+    /// Tags as they appeared on the page, if `tags` were normalized.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub original_tags: Option<Vec<String>>,
+    pub description: String,
+    #[cfg_attr(feature = "serde", serde(with = "base64_file", default))]
+    #[cfg_attr(feature = "schemars", schemars(with = "Option<String>"))]
+    pub file: Option<Vec<u8>>,
+    pub file_size: Option<usize>,
+    pub file_sha256: Option<Vec<u8>>,
+    pub file_md5: Option<Vec<u8>>,
+    pub file_blake3: Option<Vec<u8>>,
+    /// MIME type detected from the contents of the file.
+    pub file_mime: Option<String>,
+    /// Response headers from downloading the file.
+    pub file_headers: Option<FileHeaders>,
+    /// EXIF and XMP metadata embedded in the file, if enabled when hashing.
+    pub file_metadata: Option<std::collections::BTreeMap<String, String>>,
+}
+
+/// How many times a submission was viewed, favorited, and commented on.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct SubmissionStats {
+    pub views: u32,
+    pub favorites: u32,
+    pub comments: u32,
+}
+
+impl SubmissionStats {
+    /// Parse a count as shown on a page, like `1,024`.
+    pub(crate) fn parse_count(count: &str) -> Result<u32, Error> {
+        Ok(count.trim().replace(',', "").parse()?)
+    }
+}
+
+/// Builds a [`Submission`] without having to set every field, such as for
+/// tests.
+///
+/// The filename and extension come from the content's URL. Fields without a
+/// setter start empty.
+#[derive(Clone, Debug)]
+pub struct SubmissionBuilder {
+    sub: Submission,
+}
+
+impl SubmissionBuilder {
+    pub fn new(id: SubmissionId, content: Content) -> Self {
+        Self {
+            sub: Submission {
+                id,
+                title: String::new(),
+                artist: String::new(),
+                artist_info: None,
+                avatar_url: None,
+                ext: content.ext().to_string(),
+                filename: content.filename().to_string(),
+                content,
+                hash: None,
+                hash_num: None,
+                extra_hashes: Vec::new(),
+                hash_skipped: None,
+                hash_source: None,
+                image_info: None,
+                blurhash: None,
+                text_hash: None,
+                rating: Rating::General,
+                ai_generated: false,
+                stats: SubmissionStats::default(),
+                info: info::SubmissionInfo::default(),
+                preview_url: None,
+                fullview_url: None,
+                posted_at: chrono::DateTime::UNIX_EPOCH,
+                tags: Vec::new(),
+                original_tags: None,
+                description: String::new(),
+                file: None,
+                file_size: None,
+                file_sha256: None,
+                file_md5: None,
+                file_blake3: None,
+                file_mime: None,
+                file_headers: None,
+                file_metadata: None,
+            },
+        }
+    }
+
+    pub fn with_title<T: Into<String>>(mut self, title: T) -> Self {
+        self.sub.title = title.into();
+        self
+    }
+
+    pub fn with_artist<T: Into<String>>(mut self, artist: T) -> Self {
+        self.sub.artist = artist.into();
+        self
+    }
+
+    pub fn with_artist_info(mut self, artist_info: username::Artist) -> Self {
+        self.sub.artist_info = Some(artist_info);
+        self
+    }
+
+    pub fn with_avatar_url<T: Into<String>>(mut self, avatar_url: T) -> Self {
+        self.sub.avatar_url = Some(avatar_url.into());
+        self
+    }
+
+    /// Defaults to [`Rating::General`].
+    pub fn with_rating(mut self, rating: Rating) -> Self {
+        self.sub.rating = rating;
+        self
+    }
+
+    pub fn with_ai_generated(mut self, ai_generated: bool) -> Self {
+        self.sub.ai_generated = ai_generated;
+        self
+    }
+
+    pub fn with_stats(mut self, stats: SubmissionStats) -> Self {
+        self.sub.stats = stats;
+        self
+    }
+
+    pub fn with_info(mut self, info: info::SubmissionInfo) -> Self {
+        self.sub.info = info;
+        self
+    }
+
+    pub fn with_preview_url<T: Into<String>>(mut self, preview_url: T) -> Self {
+        self.sub.preview_url = Some(preview_url.into());
+        self
+    }
+
+    pub fn with_fullview_url<T: Into<String>>(mut self, fullview_url: T) -> Self {
+        self.sub.fullview_url = Some(fullview_url.into());
+        self
+    }
+
+    /// Defaults to the Unix epoch.
+    pub fn with_posted_at(mut self, posted_at: chrono::DateTime<chrono::Utc>) -> Self {
+        self.sub.posted_at = posted_at;
+        self
+    }
+
+    pub fn with_tags<I, T>(mut self, tags: I) -> Self
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<String>,
+    {
+        self.sub.tags = tags.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Description HTML.
+    pub fn with_description<T: Into<String>>(mut self, description: T) -> Self {
+        self.sub.description = description.into();
+        self
+    }
+
+    /// Contents of the file, setting its size and digests.
+    #[cfg(feature = "hash")]
+    pub fn with_file(mut self, file: Vec<u8>) -> Self {
+        let digests = hash::digest_file(&Default::default(), &file);
+
+        self.sub.file_size = Some(file.len());
+        self.sub.file_sha256 = Some(digests.sha256);
+        self.sub.file_md5 = digests.md5;
+        self.sub.file_blake3 = digests.blake3;
+        self.sub.file = Some(file);
+        self
+    }
+
+    pub fn build(self) -> Submission {
+        self.sub
+    }
+}
+
+impl Submission {
+    /// Normalize the tags, keeping the original tags in `original_tags`.
+    pub fn with_normalized_tags(mut self, normalization: &tags::TagNormalization) -> Self {
+        let tags = normalization.apply(&self.tags);
+        let original = std::mem::replace(&mut self.tags, tags);
+
+        self.original_tags.get_or_insert(original);
+        self
+    }
+
+    /// URL of the submission's page.
+    pub fn url(&self) -> String {
+        url::FaUrl::View(self.id).canonical()
+    }
+
+    /// URL of the submission's page showing only the full size file.
+    pub fn full_view_url(&self) -> String {
+        url::FaUrl::Full(self.id).canonical()
+    }
+
+    /// URL of the artist's profile.
+    pub fn artist_url(&self) -> String {
+        match &self.artist_info {
+            Some(artist) => artist.profile_url(),
+            None => url::FaUrl::User(username::slug(&self.artist)).canonical(),
+        }
+    }
+
+    /// Links to the previous, first, and next submissions in a series.
+    ///
+    /// Uses the links FurAffinity generates from `[prev,first,next]` in the
+    /// description if present, otherwise links labeled as navigation
+    /// anywhere in the description.
+    pub fn nav_links(&self) -> Option<NavLinks> {
+        let description = scraper::Html::parse_fragment(&self.description);
+
+        let links = match description.select(&NAV_LINKS).next() {
+            // FurAffinity's links are navigation even if they are all missing.
+            Some(parsed_links_section) => {
+                Some(parse_nav_links(&parsed_links_section.inner_html()).unwrap_or_default())
+            }
+            None => parse_nav_links(&self.description),
+        }?;
+
+        Some(NavLinks {
+            chapter: Chapter::parse(&self.title)
+                .or_else(|| Chapter::parse(&fragment_text(&self.description))),
+            ..links
+        })
+    }
+
+    /// Fill in the results of perceptually hashing the submission, where
+    /// `None` means the file was not in a supported format.
+    #[cfg(feature = "client")]
+    fn with_hashed(self, hashed: Option<hash::HashedImage>, source: hash::HashSource) -> Self {
+        match hashed {
+            Some(hashed) => Submission {
+                hash: Some(hashed.hash.to_base64()),
+                hash_num: hashed.hash.to_i64(),
+                extra_hashes: hashed.extra,
+                hash_skipped: None,
+                hash_source: Some(source),
+                image_info: Some(hashed.info),
+                blurhash: hashed.blurhash,
+                ..self
+            },
+            None => Submission {
+                hash: None,
+                hash_num: None,
+                extra_hashes: Vec::new(),
+                hash_skipped: Some(hash::HashSkipReason::UnsupportedFormat),
+                hash_source: None,
+                image_info: None,
+                blurhash: None,
+                ..self
+            },
+        }
+    }
+
+    #[cfg(feature = "client")]
+    fn with_memoized_hash(self, hash_num: i64) -> Self {
+        Submission {
+            hash: Some(hash::i64_to_base64(hash_num)),
+            hash_num: Some(hash_num),
+            extra_hashes: Vec::new(),
+            hash_skipped: None,
+            hash_source: Some(hash::HashSource::File),
+            image_info: None,
+            blurhash: None,
+            ..self
+        }
+    }
+
+    /// Build the URL of the submission's thumbnail at a given size.
+    ///
+    /// FA thumbnails are named after the submission ID and a timestamp. It
+    /// is taken from the preview URL when the page had one, otherwise from
+    /// the file's URL, which doesn't always match.
+    pub fn thumbnail_url(&self, size: u32) -> Option<String> {
+        let preview_timestamp = self
+            .preview_url
+            .as_deref()
+            .and_then(url::FaUrl::parse)
+            .and_then(|url| match url {
+                url::FaUrl::Thumbnail { timestamp, .. } => Some(timestamp),
+                _ => None,
+            });
+
+        let timestamp = match preview_timestamp {
+            Some(timestamp) => timestamp,
+            None => file_timestamp(self.content.url())?.parse().ok()?,
+        };
+
+        Some(url::thumbnail_url(self.id, size, timestamp))
+    }
+
+    /// The description as plain text, without the title block and with
+    /// links to users replaced by their names.
+    pub fn description_text(&self) -> String {
+        description::to_text(&self.description)
+    }
+
+    /// The description converted to Markdown, with links to users replaced
+    /// by links to their profiles.
+    pub fn description_markdown(&self) -> String {
+        description::to_markdown(&self.description)
+    }
+
+    /// Users, submissions, and journals linked from the description.
+    pub fn description_references(&self) -> description::References {
+        description::references(&self.description)
+    }
+
+    /// If fewer bytes were downloaded than the server said it would send.
+    ///
+    /// Returns `None` if the file has not been downloaded or the server did
+    /// not include a content length.
+    pub fn file_truncated(&self) -> Option<bool> {
+        let expected = self.file_headers.as_ref()?.content_length?;
+        let size = self.file_size?;
+
+        Some((size as u64) < expected)
+    }
+
+    /// If the detected type of the downloaded file does not match the
+    /// extension from its URL.
+    ///
+    /// Returns `None` if the file has not been downloaded or its type could
+    /// not be detected.
+    pub fn extension_mismatch(&self) -> Option<bool> {
+        let mime = self.file_mime.as_deref()?;
+
+        Some(!mime::matches_extension(mime, &self.ext))
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct OnlineCounts {
+    pub total: usize,
+    pub guests: usize,
+    pub registered: usize,
+    pub other: usize,
+}
+
+/// Serializes file contents as base64 instead of a list of numbers.
+#[cfg(feature = "serde")]
+mod base64_file {
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(
+        file: &Option<Vec<u8>>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        match file {
+            Some(file) => serializer.serialize_some(&base64::encode(file)),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<Vec<u8>>, D::Error> {
+        Option::<String>::deserialize(deserializer)?
+            .map(|file| base64::decode(file).map_err(serde::de::Error::custom))
+            .transpose()
+    }
+}
+
+/// Get the upload timestamp from a submission file's URL.
+fn file_timestamp(url: &str) -> Option<&str> {
+    FILE_TIMESTAMP
+        .captures(url)
+        .and_then(|captures| captures.get(1))
+        .map(|timestamp| timestamp.as_str())
+}
+
+#[cfg(feature = "client")]
+fn cookie_set<T: Into<String>>(cookie_a: T, cookie_b: T) -> Cookies {
+    let mut cookies = Cookies::new();
+    cookies.insert("a".into(), cookie_a.into());
+    cookies.insert("b".into(), cookie_b.into());
+
+    cookies
+}
+
+/// Cookies from a `Cookie` header, ignoring pairs without a name.
+#[cfg(feature = "client")]
+fn parse_cookie_header(header: &str) -> Cookies {
+    let header = header.trim();
+    let header = header
+        .get(..7)
+        .filter(|name| name.eq_ignore_ascii_case("cookie:"))
+        .map(|_| &header[7..])
+        .unwrap_or(header);
+
+    header
+        .split(';')
+        .filter_map(|pair| pair.split_once('='))
+        .map(|(name, value)| (name.trim(), value.trim()))
+        .filter(|(name, _value)| !name.is_empty())
+        .map(|(name, value)| (name.to_string(), value.to_string()))
+        .collect()
+}
+
+/// If a page shows the session can't be used, because it was rate limited
+/// or the page was loaded as a guest.
+#[cfg(feature = "client")]
+fn session_failed(status: reqwest::StatusCode, page: &str) -> bool {
+    use reqwest::StatusCode;
+
+    match status {
+        StatusCode::TOO_MANY_REQUESTS | StatusCode::SERVICE_UNAVAILABLE => true,
+        status if status.is_success() => !is_logged_in(page),
+        _ => false,
+    }
+}
+
+/// If a page was loaded by a logged in user, from the user menu shown in
+/// either theme.
+pub(crate) fn is_logged_in(page: &str) -> bool {
+    page.contains("loggedin_user_avatar") || page.contains(r#"id="my-username""#)
+}
+
+/// A `Cookie` header for a set of cookies.
+#[cfg(feature = "client")]
+fn cookie_header(cookies: &Cookies) -> String {
+    cookies
+        .iter()
+        .map(|(name, value)| build_cookie(name, value))
+        .collect::<Vec<_>>()
+        .join(";")
+}
+
+#[cfg(feature = "client")]
+fn build_cookie(name: &str, value: &str) -> String {
+    format!("{}={}", name, value)
+}
+
+fn join_text_nodes(elem: scraper::ElementRef) -> String {
+    elem.text().collect::<Vec<_>>().join("").trim().to_string()
+}
+
+/// Text of an HTML fragment, with runs of whitespace collapsed to a space.
+pub(crate) fn fragment_text(html: &str) -> String {
+    let fragment = scraper::Html::parse_fragment(html);
+
+    fragment
+        .root_element()
+        .text()
+        .flat_map(str::split_whitespace)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Parse the number of users online, shown at the bottom of every page.
+pub fn parse_online_counts(page: &str) -> OnlineCounts {
+    parse_online_counts_document(&scraper::Html::parse_document(page))
+}
+
+fn parse_online_counts_document(document: &scraper::Html) -> OnlineCounts {
+    let online = document
+        .select(&ONLINE_STATS_ELEMENT)
+        .next()
+        .map(|elem| elem.text().collect::<String>());
+    let online = online.unwrap_or_default();
+    let mut numbers = ONLINE_NUMBER
+        .find_iter(&online)
+        .collect::<Vec<_>>()
+        .into_iter()
+        .filter_map(|m| m.as_str().parse::<usize>().ok());
+
+    OnlineCounts {
+        total: numbers.next().unwrap_or_default(),
+        guests: numbers.next().unwrap_or_default(),
+        registered: numbers.next().unwrap_or_default(),
+        other: numbers.next().unwrap_or_default(),
+    }
+}
+
+/// Parse the ID of the most recent submission and the number of users
+/// online from the front page.
+pub fn parse_front_page(page: &str) -> Result<(SubmissionId, OnlineCounts), Error> {
+    let document = scraper::Html::parse_document(page);
+
+    Ok((
+        parse_latest_id_document(&document)?,
+        parse_online_counts_document(&document),
+    ))
+}
+
+/// Parse the ID of the most recent submission from the front page.
+pub fn parse_latest_id(page: &str) -> Result<SubmissionId, Error> {
+    parse_latest_id_document(&scraper::Html::parse_document(page))
+}
+
+fn parse_latest_id_document(document: &scraper::Html) -> Result<SubmissionId, Error> {
+    let latest = document
+        .select(&LATEST_SUBMISSION)
+        .next()
+        .ok_or_else(|| Error::parse("latest submission"))?;
+
+    let id = latest
+        .value()
+        .attr("href")
+        .ok_or_else(|| Error::parse("latest submission"))?
+        .split('/')
+        .rfind(|part| !part.is_empty())
+        .ok_or_else(|| Error::parse("latest submission"))?;
+
+    Ok(id.parse()?)
+}
+
+/// Parse a date shown on a page, like `Apr 1st, 2021 09:00 AM` in US Eastern
+/// time. Relative dates like `5 days ago`, shown instead of the full date
+/// with some account settings, are from the current time.
+pub fn parse_date(date: &str) -> Result<chrono::DateTime<chrono::Utc>, Error> {
+    let date_str = DATE_CLEANER.replace(date, "$1");
+
+    if let Ok(date) = chrono::NaiveDateTime::parse_from_str(&date_str, "%b %e, %Y %l:%M %p") {
+        return Ok(clock::from_site_local(date));
+    }
+
+    let now = chrono::Utc::now();
+    let time = clock::ServerTime {
+        now,
+        offset: clock::site_offset(now),
+    };
+
+    time.parse_relative(date)
+        .ok_or_else(|| Error::parse("date"))
+}
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    /// Parse a minimal submission page, for testing anything that needs a
+    /// submission without loading one.
+    pub(crate) fn test_submission(title: &str, timestamp: u32, tags: &[&str]) -> Submission {
+        let tags: String = tags
+            .iter()
+            .map(|tag| format!(r#"<a href="/search/@keywords {0}">{0}</a>"#, tag))
+            .collect();
+
+        let page = format!(
+            r#"
+            <div class="submission-id-sub-container">
+                <div class="submission-title"><h2><p>{title}</p></h2></div>
+                <a href="/user/example/"><strong>Example</strong></a>
+                <strong><span class="popup_date" title="Apr 1st, 2021 09:00 AM">5 days ago</span></strong>
+            </div>
+            <img id="submissionImg" src="//d.furaffinity.net/art/example/{timestamp}/{timestamp}.example_image.png">
+            <div class="submission-content"><section>A description.</section></div>
+            <div class="stats-container"><div class="rating"><span class="rating-box">General</span></div></div>
+            <section class="tags-row">{tags}</section>
+            "#,
+            title = title,
+            timestamp = timestamp,
+            tags = tags,
+        );
+
+        parse_submission(SubmissionId(41234567), &page)
+            .unwrap()
+            .unwrap()
+    }
+
+    #[cfg(feature = "client")]
+    #[tokio::test]
+    async fn test_latest_id() {
+        let server = test_utils::MockServer::with_fixtures().await.unwrap();
+        let fa = server.client();
+
+        let (latest_id, online) = fa.latest_id_and_online().await.unwrap();
+        assert_eq!(latest_id, SubmissionId(41234568));
+        assert_eq!(online.other, 6888);
+    }
+
+    #[cfg(feature = "client")]
+    #[tokio::test]
+    async fn test_load_submission() {
+        let server = test_utils::MockServer::with_fixtures().await.unwrap();
+        let fa = server.client();
+
+        let sub = fa
+            .get_submission(SubmissionId(31209021))
+            .await
+            .expect("unable to load test submission")
+            .expect("submission did not exist");
+
+        assert_eq!(sub.title, "Bilberry fox");
+        assert_eq!(sub.artist, "deadrussiansoul");
+        assert_eq!(sub.content, Content::Image("https://d.furaffinity.net/art/deadrussiansoul/1555431774/1555431774.deadrussiansoul_Скан_20190411__7_.png".into()));
+        assert_eq!(sub.tags, vec!["fox", "bilberry"]);
+
+        let sub = fa
+            .get_submission(SubmissionId(34426892))
+            .await
+            .expect("unable to load submission");
+
+        assert!(sub.is_none());
+
+        let sub = fa
+            .get_submission(SubmissionId(34999322))
+            .await
+            .expect("unable to load submission");
+
+        assert!(sub.is_none());
+    }
+
+    #[cfg(feature = "client")]
+    #[tokio::test]
+    async fn test_hashing() {
+        let server = test_utils::MockServer::with_fixtures().await.unwrap();
+        server.route(
+            "/art/deadrussiansoul/1555431774/1555431774.deadrussiansoul_file.png",
+            200,
+            "not an image",
+        );
+        let fa = server.client();
+
+        let sub = fa
+            .get_submission(SubmissionId(31209021))
+            .await
+            .expect("unable to load test submission")
+            .expect("submission did not exist");
+        let sub = Submission {
+            content: Content::Image(format!(
+                "{}/art/deadrussiansoul/1555431774/1555431774.deadrussiansoul_file.png",
+                server.url()
+            )),
+            ..sub
+        };
+
+        assert!(sub.file.is_none(), "file was downloaded before expected");
+        let hashed = fa
+            .calc_image_hash(sub.clone())
+            .await
+            .expect("unable to calculate image hash");
+        assert!(hashed.file.is_some(), "file was not downloaded");
+        assert!(
+            !hashed.file.unwrap().is_empty(),
+            "file data was not populated"
+        );
+        assert_eq!(
+            hashed.hash_skipped,
+            Some(hash::HashSkipReason::UnsupportedFormat)
+        );
+
+        let fa = fa.with_hash_options(hash::HashOptions {
+            retain_file: false,
+            ..Default::default()
+        });
+        let hashed = fa
+            .calc_image_hash(sub)
+            .await
+            .expect("unable to calculate image hash");
+        assert!(hashed.file.is_none(), "file was retained");
+        assert_eq!(hashed.file_size, Some(12));
+        assert_eq!(
+            hashed.file_sha256,
+            Some(hash::digest_file(&Default::default(), b"not an image").sha256)
+        );
+    }
+
+    #[cfg(feature = "client")]
+    #[tokio::test]
+    async fn test_hash_too_large() {
+        let server = test_utils::MockServer::start().await.unwrap();
+        server.route("/art/example/1617285600/large.png", 200, "not an image");
+        let fa = server.client().with_hash_options(hash::HashOptions {
+            max_hash_bytes: Some(4),
+            ..Default::default()
+        });
+
+        let sub = SubmissionBuilder::new(
+            SubmissionId(1),
+            Content::Image(format!("{}/art/example/1617285600/large.png", server.url())),
+        )
+        .build();
+
+        let hashed = fa.calc_image_hash(sub).await.unwrap();
+
+        assert_eq!(hashed.hash_skipped, Some(hash::HashSkipReason::TooLarge));
+        assert_eq!(hashed.hash, None);
+        assert_eq!(hashed.file, None);
+        assert_eq!(hashed.file_size, Some(12));
+        assert_eq!(
+            hashed.file_sha256,
+            Some(hash::digest_file(&Default::default(), b"not an image").sha256)
+        );
+    }
+
+    #[cfg(feature = "client")]
+    #[tokio::test]
+    async fn test_default_headers() {
+        use reqwest::header;
+
+        let server = test_utils::MockServer::start().await.unwrap();
+        let fa = server
+            .client()
+            .with_header(
+                header::ACCEPT_LANGUAGE,
+                header::HeaderValue::from_static("en-US"),
+            )
+            .with_header(
+                header::USER_AGENT,
+                header::HeaderValue::from_static("other"),
+            );
+
+        fa.get_gallery("example", 1).await.unwrap();
+
+        let headers = &server.request_headers()[0];
+        assert_eq!(headers["accept-language"], "en-US");
+        assert_eq!(headers["user-agent"], "furaffinity-rs test");
+    }
+
+    #[cfg(feature = "client")]
+    #[tokio::test]
+    async fn test_builder() {
+        let server = test_utils::MockServer::with_fixtures().await.unwrap();
+        let fa = FurAffinity::builder()
+            .with_cookie_header("a=1; b=2; cf_clearance=3")
+            .with_user_agent("furaffinity-rs test")
+            .with_timeout(std::time::Duration::from_secs(10))
+            .with_retry_policy(retry::RetryPolicy::new(2, std::time::Duration::ZERO))
+            .build()
+            .unwrap()
+            .with_base_url(server.url());
+
+        fa.latest_id().await.unwrap();
+
+        let headers = &server.request_headers()[0];
+        assert_eq!(headers["user-agent"], "furaffinity-rs test");
+        let mut cookies: Vec<_> = headers["cookie"].split(';').collect();
+        cookies.sort_unstable();
+        assert_eq!(cookies, vec!["a=1", "b=2", "cf_clearance=3"]);
+
+        assert!(FurAffinity::builder()
+            .with_client(Default::default())
+            .with_timeout(std::time::Duration::from_secs(10))
+            .build()
+            .is_err());
+    }
+
+    #[cfg(feature = "client")]
+    #[tokio::test]
+    async fn test_builder_proxy() {
+        let server = test_utils::MockServer::start().await.unwrap();
+        server.route("http://fa.test/", 200, test_utils::FRONT_PAGE);
+
+        let fa = FurAffinity::builder()
+            .with_proxy(reqwest::Proxy::http(server.url()).unwrap())
+            .build()
+            .unwrap()
+            .with_base_url("http://fa.test");
+
+        assert_eq!(fa.latest_id().await.unwrap(), SubmissionId(41234568));
+        assert_eq!(server.requests(), vec!["http://fa.test/"]);
+    }
+
+    #[cfg(feature = "client")]
+    #[tokio::test]
+    async fn test_conditional_requests() {
+        let server = test_utils::MockServer::with_fixtures().await.unwrap();
+        server.route_with_headers(
+            "/view/31209021",
+            200,
+            &[("ETag", "\"abc\"")],
+            test_utils::SUBMISSION,
+        );
+        let fa = server
+            .client()
+            .with_conditional_requests(std::sync::Arc::new(cache::MemoryCache::new()));
+
+        let sub = fa.get_submission(SubmissionId(31209021)).await.unwrap();
+        assert!(!server.request_headers()[0].contains_key("if-none-match"));
+
+        server.route("/view/31209021", 304, "");
+        let unchanged = fa.get_submission(SubmissionId(31209021)).await.unwrap();
+        assert_eq!(server.request_headers()[1]["if-none-match"], "\"abc\"");
+        assert_eq!(unchanged, sub);
+    }
+
+    #[cfg(feature = "client")]
+    #[tokio::test]
+    async fn test_submission_with_page() {
+        let server = test_utils::MockServer::with_fixtures().await.unwrap();
+        server.route_with_headers(
+            "/view/31209021",
+            200,
+            &[("X-Request-Id", "abc")],
+            test_utils::SUBMISSION,
+        );
+        let fa = server.client();
+
+        let page = fa
+            .get_submission_with_page(SubmissionId(31209021))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(page.html.as_deref(), Some(test_utils::SUBMISSION));
+
+        let response = page.response.unwrap();
+        assert_eq!(response.url, format!("{}/view/31209021", server.url()));
+        assert_eq!(response.status, 200);
+        assert!(response
+            .headers
+            .contains(&("x-request-id".to_string(), "abc".to_string())));
+
+        let page = fa
+            .get_submission_with(SubmissionId(31209021), &Default::default())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(page.html, None);
+        assert_eq!(page.response, None);
+    }
+
+    #[cfg(feature = "client")]
+    #[tokio::test]
+    async fn test_hash_cache() {
+        let cache = std::sync::Arc::new(cache::MemoryCache::new());
+        let fa =
+            FurAffinity::new("", "", "furaffinity-rs test", None).with_hash_cache(cache.clone());
+
+        let sub = test_submission("Snow", 1617285600, &[]);
+        let file = b"not an image".to_vec();
+
+        let hashed = fa
+            .calc_image_hash_from_bytes(sub.clone(), file.clone())
+            .await
+            .unwrap();
+        assert_eq!(
+            hashed.hash_skipped,
+            Some(hash::HashSkipReason::UnsupportedFormat)
+        );
+
+        let sha256 = hashed.file_sha256.unwrap();
+        cache::set_hash(cache.as_ref(), &Default::default(), &sha256, 42)
+            .await
+            .unwrap();
+
+        // Hashes memoized with another configuration aren't used.
+        let other = FurAffinity::new("", "", "furaffinity-rs test", None)
+            .with_hash_cache(cache.clone())
+            .with_hash_options(hash::HashOptions {
+                config: hash::HashConfig {
+                    algorithm: hash::HashAlgorithm::Mean,
+                    ..Default::default()
+                },
+                ..Default::default()
+            });
+        let hashed = other
+            .calc_image_hash_from_bytes(sub.clone(), file.clone())
+            .await
+            .unwrap();
+        assert_eq!(hashed.hash_num, None);
+
+        let hashed = fa.calc_image_hash_from_bytes(sub, file).await.unwrap();
+        assert_eq!(hashed.hash_num, Some(42));
+        assert_eq!(
+            hashed.hash.as_deref(),
+            Some(hash::i64_to_base64(42).as_str())
+        );
+        assert_eq!(hashed.hash_skipped, None);
+    }
+
+    #[cfg(feature = "client")]
+    #[tokio::test]
+    async fn test_download_content() {
+        let server = test_utils::MockServer::start().await.unwrap();
+        server.route("/art/example/1617285600/game.swf", 200, "FWS flash");
+        let fa = server.client();
+
+        let sub = SubmissionBuilder::new(
+            SubmissionId(41234569),
+            Content::Flash(format!("{}/art/example/1617285600/game.swf", server.url())),
+        )
+        .build();
+
+        let file = fa.download_content(&sub).await.unwrap();
+        assert_eq!(file.data.as_deref(), Some(&b"FWS flash"[..]));
+        assert_eq!(file.size, 9);
+        assert_eq!(
+            file.sha256(),
+            hash::digest_file(&Default::default(), b"FWS flash").sha256
+        );
+        assert_eq!(file.content_type(), Some("text/html; charset=UTF-8"));
+
+        let missing = SubmissionBuilder::new(
+            SubmissionId(41234569),
+            Content::Flash(format!("{}/art/example/1/missing.swf", server.url())),
+        )
+        .build();
+        assert!(matches!(
+            fa.download_content(&missing).await,
+            Err(Error::Deleted)
+        ));
+
+        let image = hash::tests::test_image();
+        let hash = fa.perceptual_hash(&image).await.unwrap().unwrap();
+        assert_eq!(
+            hash.to_base64(),
+            hash::hash_image(&image).unwrap().to_base64()
+        );
+        assert!(fa
+            .perceptual_hash(&file.data.unwrap())
+            .await
+            .unwrap()
+            .is_none());
+    }
+
+    /// Serves an image for every request, remembering the URLs.
+    #[cfg(feature = "client")]
+    #[derive(Default)]
+    struct ImageTransport {
+        urls: std::sync::Mutex<Vec<String>>,
+    }
+
+    #[cfg(feature = "client")]
+    impl transport::HttpTransport for ImageTransport {
+        fn send(
+            &self,
+            request: transport::Request,
+        ) -> futures::future::BoxFuture<'_, Result<transport::Response, Error>> {
+            self.urls.lock().unwrap().push(request.url);
+
+            Box::pin(async {
+                Ok(transport::Response::new(
+                    reqwest::StatusCode::OK,
+                    Default::default(),
+                    hash::tests::test_image(),
+                ))
+            })
+        }
+    }
+
+    #[cfg(feature = "client")]
+    #[tokio::test]
+    async fn test_hash_thumbnails() {
+        let transport = std::sync::Arc::new(ImageTransport::default());
+        let fa = FurAffinity::new("", "", "furaffinity-rs test", None)
+            .with_transport(transport.clone())
+            .with_hash_options(hash::HashOptions {
+                thumbnail_only: true,
+                ..Default::default()
+            });
+
+        let sub = test_submission("Snow", 1617285600, &[]);
+        let hashed = fa.calc_image_hash(sub.clone()).await.unwrap();
+        assert_eq!(hashed.hash_source, Some(hash::HashSource::Thumbnail));
+        assert_eq!(
+            *transport.urls.lock().unwrap(),
+            vec![sub.thumbnail_url(THUMBNAIL_HASH_SIZE).unwrap()]
+        );
+
+        let transport = std::sync::Arc::new(ImageTransport::default());
+        let fa = fa
+            .with_transport(transport.clone())
+            .with_hash_options(hash::HashOptions {
+                flash_thumbnails: true,
+                ..Default::default()
+            });
+
+        let flash = Submission {
+            content: Content::Flash(
+                "https://d.furaffinity.net/art/example/1617285600/1617285600.example_game.swf"
+                    .into(),
+            ),
+            ..sub
+        };
+        let hashed = fa.calc_image_hash(flash.clone()).await.unwrap();
+        assert_eq!(hashed.hash_source, Some(hash::HashSource::Thumbnail));
+        assert_eq!(
+            *transport.urls.lock().unwrap(),
+            vec![flash.thumbnail_url(THUMBNAIL_HASH_SIZE).unwrap()]
+        );
+    }
+
+    #[cfg(feature = "client")]
+    #[tokio::test]
+    async fn test_hash_config() {
+        let sub = test_submission("Snow", 1617285600, &[]);
+        let config = hash::HashConfig {
+            algorithm: hash::HashAlgorithm::Mean,
+            width: 16,
+            height: 16,
+            dct: false,
+            ..Default::default()
+        };
+
+        let fa = FurAffinity::new("", "", "furaffinity-rs test", None)
+            .with_transport(std::sync::Arc::new(ImageTransport::default()));
+        let default = fa.calc_image_hash(sub.clone()).await.unwrap();
+
+        let fa = fa.with_hash_config(config.clone());
+        let configured = fa.calc_image_hash(sub).await.unwrap();
+
+        let image = hash::tests::test_image();
+        assert_eq!(
+            default.hash,
+            Some(hash::hash_image(&image).unwrap().to_base64())
+        );
+        assert_eq!(
+            configured.hash,
+            Some(hash::hash_image_with(&config, &image).unwrap().to_base64())
+        );
+        assert_ne!(default.hash, configured.hash);
+    }
+
+    #[cfg(feature = "client")]
+    #[tokio::test]
+    async fn test_calc_image_hash_to() {
+        let server = test_utils::MockServer::start().await.unwrap();
+        server.route("/art/example/music/1617285600/song.mp3", 200, "ID3 song");
+        let fa = server.client();
+
+        let sub = SubmissionBuilder::new(
+            SubmissionId(41234568),
+            Content::Audio(format!(
+                "{}/art/example/music/1617285600/song.mp3",
+                server.url()
+            )),
+        )
+        .build();
+
+        let mut written = Vec::new();
+        let hashed = fa.calc_image_hash_to(sub, &mut written).await.unwrap();
+
+        assert_eq!(written, b"ID3 song");
+        assert_eq!(hashed.file_size, Some(8));
+        assert_eq!(
+            hashed.file_sha256,
+            Some(hash::digest_file(&Default::default(), b"ID3 song").sha256)
+        );
+        assert_eq!(hashed.hash_skipped, None);
+        assert_eq!(hashed.file, None);
+    }
+
+    #[cfg(feature = "client")]
+    #[tokio::test]
+    async fn test_hash_pipeline() {
+        let server = test_utils::MockServer::start().await.unwrap();
+        server.route("/art/example/music/1/slow.mp3", 200, "ID3 slow");
+        server.route("/art/example/music/2/fast.mp3", 200, "ID3 fast");
+        server.route("/art/example/music/3/error.mp3", 500, "");
+        server.delay(
+            "/art/example/music/1/slow.mp3",
+            std::time::Duration::from_millis(300),
+        );
+        server.delay(
+            "/art/example/music/2/fast.mp3",
+            std::time::Duration::from_millis(100),
+        );
+        let fa = server.client();
+
+        let subs = ["1/slow", "2/fast", "3/error"]
+            .iter()
+            .enumerate()
+            .map(|(i, path)| {
+                SubmissionBuilder::new(
+                    SubmissionId(i as u64 + 1),
+                    Content::Audio(format!("{}/art/example/music/{}.mp3", server.url(), path)),
+                )
+                .build()
+            })
+            .collect::<Vec<_>>();
+
+        let results: Vec<_> = fa
+            .hash_pipeline(futures::stream::iter(subs), 2)
+            .collect()
+            .await;
+
+        // The slow file was started first, but finished last.
+        let ids: Vec<_> = results.iter().map(|(id, _result)| *id).collect();
+        assert_eq!(ids, vec![SubmissionId(2), SubmissionId(3), SubmissionId(1)]);
+
+        let (_id, fast) = &results[0];
+        assert_eq!(fast.as_ref().unwrap().file_size, Some(8));
+        assert!(matches!(results[1].1, Err(Error::ServerError(500))));
+        assert_eq!(results[2].1.as_ref().unwrap().id, SubmissionId(1));
+
+        assert_eq!(server.max_in_flight(), 2);
+    }
+
+    #[cfg(feature = "client")]
+    #[tokio::test]
+    async fn test_hash_missing_file() {
+        let server = test_utils::MockServer::start().await.unwrap();
+        server.route("/art/example/1/missing.png", 404, "<html>Not Found</html>");
+        server.route(
+            "/art/example/1/forbidden.png",
+            403,
+            "<html>Forbidden</html>",
+        );
+        let fa = server.client();
+
+        let sub = |name: &str| {
+            SubmissionBuilder::new(
+                SubmissionId(1),
+                Content::Image(format!("{}/art/example/1/{}.png", server.url(), name)),
+            )
+            .build()
+        };
+
+        let missing = fa.calc_image_hash(sub("missing")).await;
+        assert!(matches!(missing, Err(Error::Deleted)));
+
+        let forbidden = fa.calc_image_hash(sub("forbidden")).await;
+        assert!(matches!(forbidden, Err(Error::ServerError(403))));
+
+        let mut writer = Vec::new();
+        let missing = fa.calc_image_hash_to(sub("missing"), &mut writer).await;
+        assert!(matches!(missing, Err(Error::Deleted)));
+        assert!(writer.is_empty());
+    }
+
+    #[cfg(feature = "client")]
+    #[tokio::test]
+    async fn test_verify_content() {
+        let server = test_utils::MockServer::start().await.unwrap();
+        server.route("/art/example/music/1/song.mp3", 200, "ID3 song");
+        server.route("/art/example/music/2/missing.mp3", 404, "");
+        server.route("/art/example/music/3/error.mp3", 500, "");
+        let fa = server.client();
+
+        let sub = |path: &str| {
+            SubmissionBuilder::new(
+                SubmissionId(1),
+                Content::Audio(format!("{}/art/example/music/{}.mp3", server.url(), path)),
+            )
+            .build()
+        };
+
+        let sha256 = hash::digest_file(&Default::default(), b"ID3 song").sha256;
+        let other = hash::digest_file(&Default::default(), b"ID3 other").sha256;
+
+        assert_eq!(
+            fa.verify_content(&sub("1/song"), &sha256).await.unwrap(),
+            Verification::Matches
+        );
+        assert_eq!(
+            fa.verify_content(&sub("1/song"), &other).await.unwrap(),
+            Verification::Changed {
+                sha256: sha256.clone()
+            }
+        );
+        assert_eq!(
+            fa.verify_content(&sub("2/missing"), &sha256).await.unwrap(),
+            Verification::Missing
+        );
+        assert!(matches!(
+            fa.verify_content(&sub("3/error"), &sha256).await,
+            Err(Error::ServerError(500))
+        ));
+    }
+
+    /// Serves a file that is cut off after every 400 bytes, supporting
+    /// range requests for the rest of it. If `replaced`, a different file
+    /// of the same size is served after the first request.
+    #[cfg(feature = "client")]
+    #[derive(Default)]
+    struct CutOffTransport {
+        replaced: bool,
+        ranges: std::sync::Mutex<Vec<Option<String>>>,
+    }
+
+    #[cfg(feature = "client")]
+    fn cut_off_file(version: u8) -> Vec<u8> {
+        (0..1000).map(|i| (i as u8) ^ version).collect()
+    }
+
+    #[cfg(feature = "client")]
+    impl transport::HttpTransport for CutOffTransport {
+        fn send(
+            &self,
+            request: transport::Request,
+        ) -> futures::future::BoxFuture<'_, Result<transport::Response, Error>> {
+            use reqwest::header::{
+                HeaderValue, CONTENT_LENGTH, CONTENT_RANGE, ETAG, IF_RANGE, RANGE,
+            };
+
+            let header = |name| {
+                request
+                    .headers
+                    .get(name)
+                    .map(|value: &HeaderValue| value.to_str().unwrap().to_string())
+            };
+            let range = header(RANGE);
+
+            let mut ranges = self.ranges.lock().unwrap();
+            let version = if self.replaced && !ranges.is_empty() {
+                2
+            } else {
+                1
+            };
+            ranges.push(range.clone());
+
+            let file = cut_off_file(version);
+            let etag = format!("\"{}\"", version);
+
+            let mut headers = reqwest::header::HeaderMap::new();
+            headers.insert(ETAG, HeaderValue::from_str(&etag).unwrap());
+            let (status, offset) = match range {
+                Some(range) if header(IF_RANGE) == Some(etag) => {
+                    let offset: usize = range
+                        .trim_start_matches("bytes=")
+                        .trim_end_matches('-')
+                        .parse()
+                        .unwrap();
+                    let content_range = format!("bytes {}-999/1000", offset);
+                    headers.insert(
+                        CONTENT_RANGE,
+                        HeaderValue::from_str(&content_range).unwrap(),
+                    );
+
+                    (reqwest::StatusCode::PARTIAL_CONTENT, offset)
+                }
+                _ => (reqwest::StatusCode::OK, 0),
+            };
+            headers.insert(CONTENT_LENGTH, HeaderValue::from(1000 - offset));
+
+            let end = (offset + 400).min(file.len());
+            let mut body = vec![Ok(file[offset..end].to_vec())];
+            if end < file.len() {
+                body.push(Err(Error::NetworkError("connection reset".into())));
+            }
+
+            Box::pin(async move {
+                Ok(
+                    transport::Response::from_stream(status, headers, futures::stream::iter(body))
+                        .with_url(request.url),
+                )
+            })
+        }
+    }
+
+    #[cfg(feature = "client")]
     #[tokio::test]
-    async fn test_latest_id() {
-        let fa = FurAffinity::new("", "", "furaffinity-rs test", None);
-        let latest_id = fa.latest_id().await;
+    async fn test_resume_download() {
+        let transport = std::sync::Arc::new(CutOffTransport::default());
+        let fa =
+            FurAffinity::new("", "", "furaffinity-rs test", None).with_transport(transport.clone());
 
-        assert!(latest_id.is_ok(), "unable to get latest id");
-        let latest_id = latest_id.unwrap();
-        assert!(latest_id.0 > 4_000_000, "latest ID seemed to be incorrect");
-        assert!(
-            latest_id.1.other > 1_000,
-            "other online seemed to be incorrect"
+        let sub = SubmissionBuilder::new(
+            SubmissionId(41234568),
+            Content::Audio("https://d.furaffinity.net/art/example/music/1/song.mp3".into()),
+        )
+        .build();
+
+        let hashed = fa.calc_image_hash(sub.clone()).await.unwrap();
+        assert_eq!(hashed.file_size, Some(1000));
+        assert_eq!(
+            hashed.file_sha256,
+            Some(hash::digest_file(&Default::default(), &cut_off_file(1)).sha256)
+        );
+        assert_eq!(
+            *transport.ranges.lock().unwrap(),
+            vec![None, Some("bytes=400-".into()), Some("bytes=800-".into())]
+        );
+
+        // A file replaced while downloading is started over, instead of
+        // joining parts of both.
+        let transport = std::sync::Arc::new(CutOffTransport {
+            replaced: true,
+            ..Default::default()
+        });
+        let fa = fa.with_transport(transport.clone());
+
+        let hashed = fa.calc_image_hash(sub.clone()).await.unwrap();
+        assert_eq!(
+            hashed.file_sha256,
+            Some(hash::digest_file(&Default::default(), &cut_off_file(2)).sha256)
         );
+        assert_eq!(
+            *transport.ranges.lock().unwrap(),
+            vec![
+                None,
+                Some("bytes=400-".into()),
+                Some("bytes=400-".into()),
+                Some("bytes=800-".into())
+            ]
+        );
+
+        // Part of the old file was already written, so it can't start over.
+        let fa = fa.with_transport(std::sync::Arc::new(CutOffTransport {
+            replaced: true,
+            ..Default::default()
+        }));
+        assert!(matches!(
+            fa.calc_image_hash_to(sub.clone(), &mut Vec::new())
+                .await
+                .unwrap_err(),
+            Error::Other { retry: false, .. }
+        ));
+
+        let fa = fa
+            .with_transport(std::sync::Arc::new(CutOffTransport::default()))
+            .with_download_resumes(1);
+        assert!(matches!(
+            fa.calc_image_hash_to(sub, &mut Vec::new())
+                .await
+                .unwrap_err(),
+            Error::NetworkError(_)
+        ));
     }
 
+    #[cfg(feature = "client")]
     #[tokio::test]
-    async fn test_load_submission() {
-        let fa = FurAffinity::new("", "", "furaffinity-rs test", None);
+    async fn test_file_store() {
+        let store = std::sync::Arc::new(file_store::MemoryFileStore::new());
+        let fa =
+            FurAffinity::new("", "", "furaffinity-rs test", None).with_file_store(store.clone());
 
-        let sub = fa
-            .get_submission(31209021)
+        let sub = test_submission("Snow", 1617285600, &[]);
+        let hashed = fa
+            .calc_image_hash_from_bytes(sub, b"not an image".to_vec())
             .await
-            .expect("unable to load test submission")
-            .expect("submission did not exist");
+            .unwrap();
 
-        assert_eq!(sub.title, "Bilberry fox");
-        assert_eq!(sub.artist, "deadrussiansoul");
-        assert_eq!(sub.content, Content::Image("https://d.furaffinity.net/art/deadrussiansoul/1555431774/1555431774.deadrussiansoul_Скан_20190411__7_.png".into()));
-        assert_eq!(sub.tags, vec!["fox", "bilberry"]);
-
-        let sub = fa
-            .get_submission(34426892)
+        let stored = file_store::FileStore::get(store.as_ref(), &hashed.file_sha256.unwrap())
             .await
-            .expect("unable to load submission");
+            .unwrap();
+        assert_eq!(stored.as_deref(), Some(&b"not an image"[..]));
+    }
 
-        assert!(sub.is_none());
+    #[cfg(feature = "client")]
+    #[tokio::test]
+    async fn test_get_submission_by_url() {
+        let server = test_utils::MockServer::with_fixtures().await.unwrap();
+        let fa = server.client();
 
         let sub = fa
-            .get_submission(34999322)
+            .get_submission_by_url("https://www.furaffinity.net/full/31209021/")
             .await
-            .expect("unable to load submission");
+            .unwrap()
+            .unwrap();
+        assert_eq!(sub.id, SubmissionId(31209021));
+        assert_eq!(
+            server.requests().last().map(String::as_str),
+            Some("/view/31209021")
+        );
 
-        assert!(sub.is_none());
+        assert!(fa
+            .get_submission_by_url("https://www.furaffinity.net/user/example/")
+            .await
+            .is_err());
     }
 
+    #[cfg(feature = "client")]
     #[tokio::test]
-    async fn test_hashing() {
-        let fa = FurAffinity::new("", "", "furaffinity-rs test", None);
-        let sub = fa
-            .get_submission(31209021)
-            .await
-            .expect("unable to load test submission")
-            .expect("submission did not exist");
+    async fn test_latest_id_retries() {
+        let server = test_utils::MockServer::start().await.unwrap();
+        server.route("/", 503, "");
+        let fa = server.client().with_retry_policy(
+            retry::RetryPolicy::new(3, std::time::Duration::from_millis(1)).with_multiplier(3),
+        );
 
-        assert!(sub.file.is_none(), "file was downloaded before expected");
-        let sub = fa
-            .calc_image_hash(sub)
+        let err = fa.latest_id().await.unwrap_err();
+        assert!(matches!(err, Error::ServerError(503)));
+        assert_eq!(server.requests().len(), 3);
+    }
+
+    #[cfg(feature = "client")]
+    #[tokio::test]
+    async fn test_rate_limit() {
+        let server = test_utils::MockServer::with_fixtures().await.unwrap();
+        let fa = server
+            .client()
+            .with_rate_limit(std::time::Duration::from_millis(200));
+
+        let start = std::time::Instant::now();
+        for _ in 0..3 {
+            fa.latest_id().await.unwrap();
+        }
+        assert!(start.elapsed() >= std::time::Duration::from_millis(400));
+    }
+
+    #[cfg(feature = "client")]
+    #[tokio::test]
+    async fn test_get_submission_comments() {
+        let server = test_utils::MockServer::with_fixtures().await.unwrap();
+        let fa = server.client();
+
+        let comments = fa
+            .get_submission_comments(SubmissionId(31209021))
             .await
-            .expect("unable to calculate image hash");
-        assert!(sub.file.is_some(), "file was not downloaded");
-        assert!(sub.file.unwrap().len() > 0, "file data was not populated");
+            .unwrap()
+            .unwrap();
+        assert_eq!(comments.len(), 3);
+        assert_eq!(comments[1].parent_id, Some(comments[0].id));
+        assert_eq!(comment::threads(comments).len(), 2);
+    }
+
+    #[cfg(feature = "client")]
+    #[tokio::test]
+    async fn test_server_time() {
+        let server = test_utils::MockServer::with_fixtures().await.unwrap();
+        let fa = server.client();
+
+        let before = fa.get_submission(SubmissionId(31209021)).await.unwrap();
+        let time = fa.server_time().await.unwrap();
+        assert_eq!(time.offset.local_minus_utc(), -4 * 3600);
+        assert_eq!(
+            time.now,
+            chrono::DateTime::parse_from_rfc2822("Thu, 01 Apr 2021 14:00:30 GMT").unwrap()
+        );
+
+        // The site's clock matches US Eastern time, so nothing changes.
+        let after = fa.get_submission(SubmissionId(31209021)).await.unwrap();
+        assert_eq!(before.unwrap().posted_at, after.unwrap().posted_at);
+    }
+
+    #[cfg(feature = "client")]
+    #[tokio::test]
+    async fn test_cookie_header() {
+        let server = test_utils::MockServer::start().await.unwrap();
+        let fa = FurAffinity::with_cookie_header(
+            "Cookie: a=1234; b=5678; cf_clearance=abc=def; ; invalid",
+            "furaffinity-rs test",
+            None,
+        )
+        .with_base_url(server.url());
+
+        fa.get_gallery("example", 1).await.unwrap();
+
+        let mut cookies: Vec<_> = server.request_headers()[0]["cookie"]
+            .split(';')
+            .map(String::from)
+            .collect();
+        cookies.sort();
+        assert_eq!(cookies, vec!["a=1234", "b=5678", "cf_clearance=abc=def"]);
+    }
+
+    #[cfg(feature = "client")]
+    #[tokio::test]
+    async fn test_cookie_failover() {
+        let server = test_utils::MockServer::start().await.unwrap();
+        server.route("/gallery/example/1/", 429, "");
+        server.route(
+            "/gallery/example/2/",
+            200,
+            r#"<img class="loggedin_user_avatar">"#,
+        );
+
+        let fa = server
+            .client()
+            .with_fallback_cookies("a2", "b2")
+            .with_fallback_cookies("a3", "b3");
+        assert_eq!(fa.active_cookies(), 0);
+
+        fa.get_gallery("example", 2).await.unwrap();
+        assert_eq!(fa.active_cookies(), 0);
+
+        fa.get_gallery("example", 1).await.unwrap();
+        assert_eq!(fa.active_cookies(), 2);
+        assert_eq!(server.requests().len(), 4);
+    }
+
+    #[test]
+    fn test_error_retryable() {
+        assert!(Error::from_status(503).is_retryable());
+        assert!(!Error::from_status(404).is_retryable());
+        assert!(matches!(Error::from_status(429), Error::RateLimited));
+        assert!(!Error::Deleted.is_retryable());
+
+        match parse_submission(SubmissionId(1), "<html>") {
+            Err(Error::ParseError { field }) => assert_eq!(field, "title"),
+            other => panic!("unexpected result: {:?}", other.map(|_| ())),
+        }
+    }
+
+    #[test]
+    fn test_parse_truncated_pages() {
+        use test_utils::*;
+
+        // Every prefix of a page is a page that could be received, and none
+        // of them should cause a panic.
+        for page in [
+            SUBMISSION, DELETED, AGE_GATED, CLASSIC, STORY, MUSIC, FRONT_PAGE, GALLERY, PROFILE,
+            JOURNAL,
+        ] {
+            for end in (0..=page.len())
+                .filter(|end| page.is_char_boundary(*end))
+                .step_by(29)
+            {
+                let page = &page[..end];
+
+                if let Ok(Some(sub)) = parse_submission(SubmissionId(1), page) {
+                    sub.nav_links();
+                }
+                let _ = comment::parse_comments(page);
+                let _ = gallery::parse_listing(page);
+                let _ = journal::parse_journals(page);
+                let _ = parse_front_page(page);
+                let _ = gallery::parse_recent(page);
+                let _ = url::parse_avatar_url(page, "deadrussiansoul");
+                let _ = profile::parse_profile("deadrussiansoul", page);
+                let _ = profile::parse_shouts(page);
+                let _ = commissions::parse_commissions("deadrussiansoul", page);
+                let _ = journal::parse_journal(JournalId(1), page);
+            }
+        }
+
+        assert!(parse_submission(SubmissionId(1), "<html>").is_err());
+    }
+
+    #[test]
+    fn test_parse_pages() {
+        use test_utils::*;
+
+        assert_eq!(parse_latest_id(FRONT_PAGE).unwrap(), SubmissionId(41234568));
+        assert!(parse_latest_id(SUBMISSION).is_err());
+        assert_eq!(parse_online_counts(FRONT_PAGE).registered, 890);
+
+        assert_eq!(
+            url::parse_avatar_url(SUBMISSION, "DeadRussianSoul").as_deref(),
+            Some("https://a.furaffinity.net/1555431700/deadrussiansoul.gif")
+        );
+        assert_eq!(url::parse_avatar_url(SUBMISSION, "example"), None);
     }
 
     #[test]
@@ -528,7 +4953,192 @@ mod tests {
         use chrono::offset::TimeZone;
 
         let parsed = parse_date("Mar 23rd, 2019 12:46 AM").unwrap();
-        assert_eq!(parsed, chrono::Utc.ymd(2019, 3, 23).and_hms(5, 46, 0));
+        assert_eq!(
+            parsed,
+            chrono::Utc.with_ymd_and_hms(2019, 3, 23, 4, 46, 0).unwrap()
+        );
+
+        let parsed = parse_date("Jan 2nd, 2019 12:46 AM").unwrap();
+        assert_eq!(
+            parsed,
+            chrono::Utc.with_ymd_and_hms(2019, 1, 2, 5, 46, 0).unwrap()
+        );
+
+        let parsed = parse_date("5 minutes ago").unwrap();
+        let expected = chrono::Utc::now() - chrono::Duration::minutes(5);
+        assert!((parsed - expected).num_seconds().abs() < 60);
+        assert!(parse_date("sometime").is_err());
+    }
+
+    #[cfg(feature = "client")]
+    #[test]
+    fn test_check_complete() {
+        // A motion photo, with a video after the end of the JPEG.
+        let mut data = b"\xff\xd8\xff\xe0\xff\xd9".to_vec();
+        data.extend(std::iter::repeat_n(0, 4096));
+        let headers = FileHeaders {
+            content_type: None,
+            content_length: Some(data.len() as u64),
+            last_modified: None,
+        };
+        let file = DownloadedFile::from_bytes(&Default::default(), data, Some(headers));
+        assert!(file.check_complete().is_ok());
+
+        let file = DownloadedFile::from_bytes(
+            &Default::default(),
+            b"\xff\xd8\xff\xe0".to_vec(),
+            Some(FileHeaders {
+                content_type: None,
+                content_length: Some(4),
+                last_modified: None,
+            }),
+        );
+        assert!(file.check_complete().is_ok());
+
+        let file =
+            DownloadedFile::from_bytes(&Default::default(), b"\xff\xd8\xff\xe0".to_vec(), None);
+        assert!(file.check_complete().is_err());
+    }
+
+    #[test]
+    fn test_file_timestamp() {
+        assert_eq!(
+            file_timestamp("https://d.furaffinity.net/art/deadrussiansoul/1555431774/1555431774.deadrussiansoul_file.png"),
+            Some("1555431774")
+        );
+        assert_eq!(
+            file_timestamp(
+                "https://d.furaffinity.net/art/1234/stories/1600000000/1600000000.1234_story.txt"
+            ),
+            Some("1600000000")
+        );
+        assert_eq!(file_timestamp("https://www.furaffinity.net/"), None);
+    }
+
+    #[test]
+    fn test_thumbnail_url() {
+        let sub = parse_submission(SubmissionId(31209021), test_utils::SUBMISSION)
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            sub.preview_url.as_deref(),
+            Some("https://t.furaffinity.net/31209021@600-1555431774.jpg")
+        );
+        assert_eq!(sub.fullview_url.as_deref(), Some(sub.content.url()));
+        assert_eq!(
+            sub.avatar_url.as_deref(),
+            Some("https://a.furaffinity.net/1555431700/deadrussiansoul.gif")
+        );
+        assert_eq!(
+            sub.thumbnail_url(300).as_deref(),
+            Some("https://t.furaffinity.net/31209021@300-1555431774.jpg")
+        );
+
+        // The preview's timestamp is used over the file's when they differ.
+        let sub = Submission {
+            preview_url: Some("https://t.furaffinity.net/31209021@600-1600000000.jpg".into()),
+            ..sub
+        };
+        assert_eq!(
+            sub.thumbnail_url(800).as_deref(),
+            Some("https://t.furaffinity.net/31209021@800-1600000000.jpg")
+        );
+
+        let sub = Submission {
+            preview_url: None,
+            ..sub
+        };
+        assert_eq!(
+            sub.thumbnail_url(800).as_deref(),
+            Some("https://t.furaffinity.net/31209021@800-1555431774.jpg")
+        );
+    }
+
+    #[test]
+    fn test_parse_submission_fields() {
+        let page = r#"<html><head><title>Snow by Example</title></head><body>
+            <div class="submission-id-sub-container">
+                <div class="submission-title"><h2><p>Snow</p></h2></div>
+                <a href="/user/example/">Example</a>
+                <strong><span class="popup_date" title="Apr 1st, 2021 09:00 AM">5 years ago</span></strong>
+            </div>
+            <img id="submissionImg" src="//d.furaffinity.net/art/example/1617285600/1617285600.example_snow.png">
+            <div class="stats-container"><div class="rating"><span class="rating-box">General</span></div></div>
+            <div class="submission-content"><section>A fox in the snow</section></div>
+            <section class="tags-row"><a>fox</a><a>winter</a></section>
+        </body></html>"#;
+
+        let full = parse_submission(SubmissionId(41234567), page)
+            .unwrap()
+            .unwrap();
+        assert_eq!(full.title, "Snow");
+        assert_eq!(full.tags, vec!["fox", "winter"]);
+        assert_eq!(full.description, "A fox in the snow");
+
+        let partial = parse_submission_fields(SubmissionId(41234567), page, ParseFields::TAGS)
+            .unwrap()
+            .unwrap();
+        assert_eq!(partial.tags, vec!["fox", "winter"]);
+        assert_eq!(partial.description, "");
+        assert_eq!(partial.content, full.content);
+        assert_eq!(partial.posted_at, full.posted_at);
+
+        assert!(ParseFields::ALL.contains(ParseFields::TAGS | ParseFields::DESCRIPTION));
+        assert!(!ParseFields::NONE.contains(ParseFields::TAGS));
+    }
+
+    #[test]
+    fn test_parse_ai_generated() {
+        let sub = parse_submission(SubmissionId(31209021), test_utils::SUBMISSION)
+            .unwrap()
+            .unwrap();
+        assert!(!sub.ai_generated);
+
+        let page = test_utils::SUBMISSION.replace(
+            r#"<span class="type-name">All</span>"#,
+            r#"<span class="type-name">AI Generated</span>"#,
+        );
+        let sub = parse_submission(SubmissionId(31209021), &page)
+            .unwrap()
+            .unwrap();
+        assert!(sub.ai_generated);
+    }
+
+    #[test]
+    fn test_parse_stats() {
+        let sub = parse_submission(SubmissionId(31209021), test_utils::SUBMISSION)
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            sub.stats,
+            SubmissionStats {
+                views: 412,
+                favorites: 58,
+                comments: 6,
+            }
+        );
+
+        let page = test_utils::SUBMISSION.replace(">412<", ">1,412<");
+        let sub = parse_submission(SubmissionId(31209021), &page)
+            .unwrap()
+            .unwrap();
+        assert_eq!(sub.stats.views, 1412);
+    }
+
+    #[test]
+    fn test_parse_artist_info() {
+        let page = test_utils::SUBMISSION.replace(
+            "<strong>deadrussiansoul</strong>",
+            "<strong>!Dead_Russian_Soul</strong>",
+        );
+        let sub = parse_submission(SubmissionId(31209021), &page)
+            .unwrap()
+            .unwrap();
+
+        let artist = sub.artist_info.unwrap();
+        assert_eq!(artist.slug, "deadrussiansoul");
+        assert_eq!(artist.display_name, "Dead_Russian_Soul");
+        assert_eq!(artist.status_symbol, Some('!'));
     }
 
     #[test]
@@ -543,10 +5153,13 @@ mod tests {
         assert_eq!(
             Some(NavLinks {
                 prev: None,
-                first: Some(37545307),
-                next: Some(37545317),
+                first: Some(SubmissionId(37545307)),
+                next: Some(SubmissionId(37545317)),
+                first_label: Some("FIRST".into()),
+                next_label: Some("NEXT >>>".into()),
+                ..Default::default()
             }),
-            parse_nav_links(&no_prev)
+            parse_nav_links(no_prev)
         );
 
         let all_links = r#"<span class="parsed_nav_links">
@@ -559,11 +5172,15 @@ mod tests {
 
         assert_eq!(
             Some(NavLinks {
-                prev: Some(37545317),
-                first: Some(37545307),
-                next: Some(37676046),
+                prev: Some(SubmissionId(37545317)),
+                first: Some(SubmissionId(37545307)),
+                next: Some(SubmissionId(37676046)),
+                prev_label: Some("<<< PREV".into()),
+                first_label: Some("FIRST".into()),
+                next_label: Some("NEXT >>>".into()),
+                ..Default::default()
             }),
-            parse_nav_links(&all_links)
+            parse_nav_links(all_links)
         );
 
         let no_next = r#"<span class="parsed_nav_links">
@@ -575,32 +5192,247 @@ mod tests {
 
         assert_eq!(
             Some(NavLinks {
-                prev: Some(38195654),
-                first: Some(37545307),
+                prev: Some(SubmissionId(38195654)),
+                first: Some(SubmissionId(37545307)),
                 next: None,
+                prev_label: Some("<<< PREV".into()),
+                first_label: Some("FIRST".into()),
+                ..Default::default()
+            }),
+            parse_nav_links(no_next)
+        );
+    }
+
+    #[test]
+    fn test_parse_nav_links_labels() {
+        let reordered = r#"<a href="/view/38195685/">Next &raquo;</a> -
+            <a href="/view/38102162/">&laquo; Previous page</a> ~
+            <a href="https://www.furaffinity.net/view/37545307/">Beginning</a>"#;
+
+        assert_eq!(
+            Some(NavLinks {
+                prev: Some(SubmissionId(38102162)),
+                first: Some(SubmissionId(37545307)),
+                next: Some(SubmissionId(38195685)),
+                prev_label: Some("« Previous page".into()),
+                first_label: Some("Beginning".into()),
+                next_label: Some("Next »".into()),
+                ..Default::default()
+            }),
+            parse_nav_links(reordered)
+        );
+
+        let arrows = r#"Part two of my story!<br>
+            <a href="/view/38102162/">&lt;&lt;&lt;</a> <a href="/view/38195685/">&gt;&gt;&gt;</a><br>
+            <a href="/user/example/">My gallery</a>"#;
+
+        assert_eq!(
+            Some(NavLinks {
+                prev: Some(SubmissionId(38102162)),
+                first: None,
+                next: Some(SubmissionId(38195685)),
+                prev_label: Some("<<<".into()),
+                next_label: Some(">>>".into()),
+                ..Default::default()
             }),
-            parse_nav_links(&no_next)
+            parse_nav_links(arrows)
+        );
+
+        let unrelated = r#"See you next time! <a href="/user/example/">Next</a>
+            <a href="/view/38102162/">Another picture</a>"#;
+        assert_eq!(None, parse_nav_links(unrelated));
+
+        let sub = Submission {
+            description:
+                r#"Page 2 <a href="/view/38102162/">prev</a> | <a href="/view/38195685/">next</a>"#
+                    .into(),
+            ..test_submission("Comic", 1617285600, &[])
+        };
+        assert_eq!(
+            sub.nav_links().and_then(|links| links.next),
+            Some(SubmissionId(38195685))
+        );
+    }
+
+    #[test]
+    fn test_nav_links_chapter() {
+        assert_eq!(
+            Chapter::parse("The Long Winter - Ch. 2"),
+            Some(Chapter {
+                unit: ChapterUnit::Chapter,
+                number: 2,
+            })
+        );
+        assert_eq!(
+            Chapter::parse("page #12 of the comic"),
+            Some(Chapter {
+                unit: ChapterUnit::Page,
+                number: 12,
+            })
+        );
+        assert_eq!(Chapter::parse("Happy 2021!"), None);
+
+        let sub = Submission {
+            description: r#"<a href="/view/38102162/">prev</a> Part 3 of the story"#.into(),
+            ..test_submission("The Long Winter", 1617285600, &[])
+        };
+        assert_eq!(
+            sub.nav_links().and_then(|links| links.chapter),
+            Some(Chapter {
+                unit: ChapterUnit::Part,
+                number: 3,
+            })
         );
     }
 
+    #[cfg(feature = "client")]
     #[tokio::test]
     async fn test_submission_nav_links() {
-        let fa = FurAffinity::new("", "", "furaffinity-rs test", None);
+        let server = test_utils::MockServer::with_fixtures().await.unwrap();
+        let fa = server.client();
 
         let sub = fa
-            .get_submission(38195654)
+            .get_submission(SubmissionId(31209021))
             .await
             .expect("unable to load test submission")
             .expect("submission did not exist");
 
         let nav_links = sub.nav_links().expect("submission should have nav links");
-        assert_eq!(
-            nav_links,
-            NavLinks {
-                prev: Some(38102162),
-                first: Some(37545307),
-                next: Some(38195685),
-            }
+        assert_eq!(nav_links.prev, None);
+        assert_eq!(nav_links.first, Some(SubmissionId(31200000)));
+        assert_eq!(nav_links.next, Some(SubmissionId(31300000)));
+    }
+
+    #[cfg(feature = "hash")]
+    #[test]
+    fn test_submission_builder() {
+        let sub = SubmissionBuilder::new(
+            SubmissionId(41234567),
+            Content::Image("https://d.furaffinity.net/art/example/1/1.example_snow.png".into()),
         )
+        .with_title("Snow")
+        .with_tags(["fox", "winter"])
+        .with_file(b"file".to_vec())
+        .build();
+
+        assert_eq!(sub.title, "Snow");
+        assert_eq!(sub.filename, "1.example_snow.png");
+        assert_eq!(sub.ext, "png");
+        assert_eq!(sub.tags, vec!["fox", "winter"]);
+        assert_eq!(sub.file_size, Some(4));
+        assert!(sub.file_sha256.is_some());
+    }
+
+    #[test]
+    fn test_submission_normalized_tags() {
+        let sub = test_submission("Snow", 1617285600, &["Fox", "fox", "Winter"])
+            .with_normalized_tags(&tags::TagNormalization::default());
+
+        assert_eq!(sub.tags, vec!["fox", "winter"]);
+        assert_eq!(
+            sub.original_tags,
+            Some(vec!["Fox".into(), "fox".into(), "Winter".into()])
+        );
+    }
+
+    #[test]
+    fn test_submission_eq() {
+        let sub = test_submission("Snow", 1617285600, &["fox"]);
+        let other = test_submission("Rain", 1617285600, &["fox"]);
+
+        let subs: std::collections::HashSet<_> = vec![sub.clone(), other.clone(), sub.clone()]
+            .into_iter()
+            .collect();
+        assert_eq!(subs.len(), 2);
+        assert_ne!(sub, other);
+    }
+
+    #[test]
+    fn test_content_accessors() {
+        let content = Content::Image(
+            "https://d.furaffinity.net/art/example/1617285600/1617285600.example_snow.png".into(),
+        );
+
+        assert_eq!(content.filename(), "1617285600.example_snow.png");
+        assert_eq!(content.ext(), "png");
+        assert_eq!(content.to_string(), content.url());
+
+        let content = Content::Flash("https://d.furaffinity.net/art/example/1/noext".into());
+        assert_eq!(content.filename(), "noext");
+        assert_eq!(content.ext(), "");
+    }
+
+    #[test]
+    fn test_parse_story_and_music() {
+        let story = parse_submission(SubmissionId(41234567), test_utils::STORY)
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            story.content,
+            Content::Text(
+                "https://d.furaffinity.net/art/example/stories/1617285600/1617285600.example_the_long_winter.txt"
+                    .into()
+            )
+        );
+        assert_eq!(story.ext, "txt");
+
+        let music = parse_submission(SubmissionId(41234568), test_utils::MUSIC)
+            .unwrap()
+            .unwrap();
+        assert_eq!(music.content.kind(), "audio");
+        assert_eq!(music.content.ext(), "mp3");
+    }
+
+    #[test]
+    fn test_submission_urls() {
+        let sub = test_submission("Snow", 1617285600, &[]);
+
+        assert_eq!(sub.url(), "https://www.furaffinity.net/view/41234567/");
+        assert_eq!(
+            sub.full_view_url(),
+            "https://www.furaffinity.net/full/41234567/"
+        );
+        assert_eq!(
+            sub.artist_url(),
+            "https://www.furaffinity.net/user/example/"
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_submission() {
+        let sub = Submission {
+            file: Some(b"file".to_vec()),
+            ..test_submission("Sketch", 1617235200, &["fox"])
+        };
+
+        let value = serde_json::to_value(&sub).unwrap();
+        assert_eq!(value["file"], "ZmlsZQ==");
+        assert_eq!(value["rating"], "g");
+        assert_eq!(value["posted_at"], "2021-04-01T13:00:00Z");
+
+        let parsed: Submission = serde_json::from_value(value).unwrap();
+        assert_eq!(parsed.rating, sub.rating);
+        assert_eq!(
+            serde_json::from_str::<Rating>(r#""Mature""#).unwrap(),
+            Rating::Mature
+        );
+        assert_eq!(parsed.file, sub.file);
+        assert_eq!(parsed.tags, sub.tags);
+        assert_eq!(parsed.content, sub.content);
+    }
+
+    #[cfg(feature = "schemars")]
+    #[test]
+    fn test_submission_schema() {
+        let schema = serde_json::to_value(schemars::schema_for!(Submission)).unwrap();
+        let properties = &schema["properties"];
+
+        assert_eq!(
+            properties["file"]["type"],
+            serde_json::json!(["string", "null"])
+        );
+        assert_eq!(properties["posted_at"]["format"], "date-time");
+        assert!(schema["definitions"]["Rating"].is_object());
     }
 }
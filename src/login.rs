@@ -0,0 +1,199 @@
+//! Logging in with a username and password to get session cookies.
+//!
+//! FurAffinity may show a captcha on the login page, which can't be solved
+//! here. Cookies can still be copied from a browser when that happens.
+
+use crate::Error;
+use lazy_static::lazy_static;
+use scraper::Selector;
+
+lazy_static! {
+    static ref LOGIN_FORM: Selector =
+        Selector::parse(r#"form[action*="/login"], form#login-form"#).unwrap();
+    static ref HIDDEN_INPUT: Selector = Selector::parse(r#"input[type="hidden"][name]"#).unwrap();
+    static ref CAPTCHA: Selector =
+        Selector::parse(".g-recaptcha, .h-captcha, .cf-turnstile, #captcha_img").unwrap();
+    static ref LOGIN_ERROR: Selector =
+        Selector::parse(".notice-message .redirect-message, .error-message-box, .alert").unwrap();
+}
+
+/// Cookies that make up a session.
+pub const SESSION_COOKIES: &[&str] = &["a", "b"];
+
+/// The login form, with the hidden fields it must be sent with, such as its
+/// CSRF token.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct LoginForm {
+    /// Where the form is sent, as it appears in the page.
+    pub action: String,
+    pub fields: Vec<(String, String)>,
+    /// If the form has a captcha, so it can't be sent without a browser.
+    pub captcha: bool,
+}
+
+impl LoginForm {
+    /// Fields to send to log in as a user.
+    pub fn params(&self, username: &str, password: &str) -> Vec<(String, String)> {
+        let mut params = self.fields.clone();
+        params.push(("name".to_string(), username.to_string()));
+        params.push(("pass".to_string(), password.to_string()));
+
+        params
+    }
+}
+
+/// Parse the form on the login page.
+pub fn parse_login_form(page: &str) -> Result<LoginForm, Error> {
+    let document = scraper::Html::parse_document(page);
+
+    let form = document
+        .select(&LOGIN_FORM)
+        .next()
+        .ok_or_else(|| Error::parse("login form"))?;
+
+    let action = form
+        .value()
+        .attr("action")
+        .filter(|action| !action.is_empty())
+        .unwrap_or("/login/")
+        .to_string();
+
+    let fields = form
+        .select(&HIDDEN_INPUT)
+        .filter_map(|input| {
+            let value = input.value();
+            Some((
+                value.attr("name")?.to_string(),
+                value.attr("value").unwrap_or_default().to_string(),
+            ))
+        })
+        .collect();
+
+    let captcha = form.select(&CAPTCHA).next().is_some();
+
+    Ok(LoginForm {
+        action,
+        fields,
+        captcha,
+    })
+}
+
+/// The message shown after a login failed, such as for a wrong password.
+pub fn parse_login_error(page: &str) -> Option<String> {
+    let document = scraper::Html::parse_document(page);
+
+    document
+        .select(&LOGIN_ERROR)
+        .map(crate::join_text_nodes)
+        .find(|message| !message.is_empty())
+}
+
+/// Cookies set by `Set-Cookie` header values, skipping ones that were
+/// removed by setting them to `deleted` or an empty value.
+pub fn parse_set_cookies<'a, I>(headers: I) -> Vec<(String, String)>
+where
+    I: IntoIterator<Item = &'a str>,
+{
+    headers
+        .into_iter()
+        .filter_map(|header| header.split(';').next()?.split_once('='))
+        .map(|(name, value)| (name.trim(), value.trim()))
+        .filter(|(name, value)| !name.is_empty() && !value.is_empty() && *value != "deleted")
+        .map(|(name, value)| (name.to_string(), value.to_string()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(feature = "client")]
+    use crate::test_utils::{MockServer, FRONT_PAGE};
+
+    const LOGIN: &str = r#"<html><body><div id="standardpage">
+        <form method="post" action="/login/?ref=/">
+            <input type="hidden" name="csrf_token" value="abc123">
+            <input type="text" name="name">
+            <input type="password" name="pass">
+            <button type="submit">Log In</button>
+        </form>
+    </div></body></html>"#;
+
+    #[test]
+    fn test_parse_login_form() {
+        let form = parse_login_form(LOGIN).unwrap();
+        assert_eq!(form.action, "/login/?ref=/");
+        assert!(!form.captcha);
+        assert_eq!(
+            form.params("user", "hunter2"),
+            vec![
+                ("csrf_token".to_string(), "abc123".to_string()),
+                ("name".to_string(), "user".to_string()),
+                ("pass".to_string(), "hunter2".to_string()),
+            ]
+        );
+
+        let page = LOGIN.replace(
+            "<button",
+            r#"<div class="g-recaptcha" data-sitekey="key"></div><button"#,
+        );
+        assert!(parse_login_form(&page).unwrap().captcha);
+
+        assert_eq!(
+            parse_set_cookies(vec![
+                "a=1234; expires=Fri, 01-Apr-2022 14:00:30 GMT; path=/",
+                "b=5678; path=/; HttpOnly",
+                "old=deleted; path=/",
+            ]),
+            vec![
+                ("a".to_string(), "1234".to_string()),
+                ("b".to_string(), "5678".to_string()),
+            ]
+        );
+    }
+
+    #[cfg(feature = "client")]
+    #[tokio::test]
+    async fn test_login() {
+        let server = MockServer::start().await.unwrap();
+        server.route("/login/", 200, LOGIN);
+        server.route_with_headers(
+            "/login/?ref=/",
+            302,
+            &[
+                ("Set-Cookie", "a=1234; path=/; HttpOnly"),
+                ("Set-Cookie", "b=5678; path=/; HttpOnly"),
+                ("Location", "/"),
+            ],
+            "",
+        );
+        server.route(
+            "/",
+            200,
+            FRONT_PAGE.replace(
+                "</body>",
+                r#"<a id="my-username" href="/user/user/">~user</a></body>"#,
+            ),
+        );
+
+        let fa = server.client();
+        fa.login("user", "hunter2").await.unwrap();
+        assert!(fa.verify_session().await.unwrap());
+
+        let body = server.request_bodies()[1].clone();
+        assert_eq!(body, "csrf_token=abc123&name=user&pass=hunter2");
+        let cookies = server.request_headers().last().unwrap()["cookie"].clone();
+        assert!(cookies.contains("a=1234") && cookies.contains("b=5678"));
+
+        server.route("/login/?ref=/", 200, LOGIN.replace(
+            "<form",
+            r#"<section class="notice-message"><div class="redirect-message">The username and password combination do not match.</div></section><form"#,
+        ));
+        let err = server.client().login("user", "wrong").await.unwrap_err();
+        assert!(err.to_string().contains("do not match"));
+
+        server.route("/", 200, FRONT_PAGE);
+        assert!(!server.client().verify_session().await.unwrap());
+    }
+}
@@ -0,0 +1,122 @@
+//! Extracting embedded EXIF and XMP metadata from files.
+
+use lazy_static::lazy_static;
+use std::collections::BTreeMap;
+
+lazy_static! {
+    static ref XMP_PROPERTY: regex::Regex =
+        regex::Regex::new(r#"\b([A-Za-z]+:[A-Za-z]+)="([^"]*)""#).unwrap();
+}
+
+/// Extract metadata embedded in a file.
+///
+/// EXIF fields from the primary image are keyed by their tag name, such as
+/// `Orientation` or `Software`. Simple XMP properties are keyed by their
+/// qualified name, such as `xmp:CreatorTool`.
+pub fn extract(data: &[u8]) -> BTreeMap<String, String> {
+    let mut metadata = BTreeMap::new();
+
+    if let Ok(exif) = exif::Reader::new().read_from_container(&mut std::io::Cursor::new(data)) {
+        for field in exif.fields() {
+            if field.ifd_num != exif::In::PRIMARY {
+                continue;
+            }
+
+            metadata.insert(field.tag.to_string(), exif_value(field, &exif));
+        }
+    }
+
+    if let Some(xmp) = find_xmp(data) {
+        for captures in XMP_PROPERTY.captures_iter(&xmp) {
+            // Namespace declarations aren't metadata.
+            if captures[1].starts_with("xmlns:") {
+                continue;
+            }
+
+            metadata.insert(captures[1].to_string(), captures[2].to_string());
+        }
+    }
+
+    metadata
+}
+
+fn exif_value(field: &exif::Field, exif: &exif::Exif) -> String {
+    match &field.value {
+        // Displaying text values wraps them in quotes.
+        exif::Value::Ascii(values) => values
+            .iter()
+            .map(|value| String::from_utf8_lossy(value).into_owned())
+            .collect::<Vec<_>>()
+            .join(", "),
+        _ => field.display_value().with_unit(exif).to_string(),
+    }
+}
+
+/// Find the XMP packet in a file, which is stored as plain XML regardless of
+/// the container format.
+fn find_xmp(data: &[u8]) -> Option<String> {
+    const START: &[u8] = b"<x:xmpmeta";
+    const END: &[u8] = b"</x:xmpmeta>";
+
+    let start = find(data, START)?;
+    let end = start + find(&data[start..], END)? + END.len();
+
+    Some(String::from_utf8_lossy(&data[start..end]).into_owned())
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extract_exif() {
+        let software = exif::Field {
+            tag: exif::Tag::Software,
+            ifd_num: exif::In::PRIMARY,
+            value: exif::Value::Ascii(vec![b"Krita".to_vec()]),
+        };
+        let orientation = exif::Field {
+            tag: exif::Tag::Orientation,
+            ifd_num: exif::In::PRIMARY,
+            value: exif::Value::Short(vec![1]),
+        };
+
+        let mut writer = exif::experimental::Writer::new();
+        writer.push_field(&software);
+        writer.push_field(&orientation);
+
+        let mut buf = std::io::Cursor::new(Vec::new());
+        writer.write(&mut buf, false).unwrap();
+
+        let metadata = extract(buf.get_ref());
+
+        assert_eq!(metadata.get("Software").map(String::as_str), Some("Krita"));
+        assert_eq!(
+            metadata.get("Orientation").map(String::as_str),
+            Some("row 0 at top and column 0 at left")
+        );
+    }
+
+    #[test]
+    fn test_extract_xmp() {
+        let data = br#"junk<x:xmpmeta xmlns:x="adobe:ns:meta/"><rdf:Description xmlns:xmp="http://ns.adobe.com/xap/1.0/" xmp:CreatorTool="Clip Studio Paint" xmp:CreateDate="2020-01-02T03:04:05"/></x:xmpmeta>junk"#;
+
+        let metadata = extract(data);
+
+        assert_eq!(
+            metadata.get("xmp:CreatorTool").map(String::as_str),
+            Some("Clip Studio Paint")
+        );
+        assert_eq!(
+            metadata.get("xmp:CreateDate").map(String::as_str),
+            Some("2020-01-02T03:04:05")
+        );
+        assert!(!metadata.contains_key("xmlns:xmp"));
+    }
+}
@@ -0,0 +1,106 @@
+//! Measuring requests, for watching how a client that makes many of them is
+//! doing.
+
+use crate::transport::Method;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Measurements of a single request, taken once its body was read or
+/// dropped.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RequestMetrics {
+    pub method: Method,
+    pub url: String,
+    /// Status code, or `None` if the request failed before a response.
+    pub status: Option<u16>,
+    /// Bytes of the body that were read.
+    pub bytes: u64,
+    /// How long until the response's headers arrived, if they did.
+    pub time_to_headers: Option<Duration>,
+    /// How long from sending the request until the body was finished with.
+    pub duration: Duration,
+}
+
+/// Called with the measurements of each request.
+pub type MetricsHook = Arc<dyn Fn(&RequestMetrics) + Send + Sync>;
+
+/// Takes measurements of a request, reporting them when dropped.
+pub(crate) struct Recorder {
+    metrics: RequestMetrics,
+    start: Instant,
+    hook: Option<MetricsHook>,
+}
+
+impl Recorder {
+    pub(crate) fn start(method: Method, url: &str, hook: Option<MetricsHook>) -> Self {
+        Self {
+            metrics: RequestMetrics {
+                method,
+                url: url.to_string(),
+                status: None,
+                bytes: 0,
+                time_to_headers: None,
+                duration: Duration::ZERO,
+            },
+            start: Instant::now(),
+            hook,
+        }
+    }
+
+    pub(crate) fn headers(&mut self, status: u16) {
+        self.metrics.status = Some(status);
+        self.metrics.time_to_headers = Some(self.start.elapsed());
+    }
+
+    pub(crate) fn chunk(&mut self, chunk: &[u8]) {
+        self.metrics.bytes += chunk.len() as u64;
+    }
+}
+
+impl Drop for Recorder {
+    fn drop(&mut self) {
+        self.metrics.duration = self.start.elapsed();
+
+        #[cfg(feature = "tracing")]
+        tracing::debug!(
+            method = ?self.metrics.method,
+            url = %self.metrics.url,
+            status = ?self.metrics.status,
+            bytes = self.metrics.bytes,
+            duration_ms = self.metrics.duration.as_millis() as u64,
+            "finished request"
+        );
+
+        if let Some(hook) = &self.hook {
+            hook(&self.metrics);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{MockServer, SUBMISSION};
+    use crate::SubmissionId;
+    use std::sync::Mutex;
+
+    #[tokio::test]
+    async fn test_metrics_hook() {
+        let server = MockServer::with_fixtures().await.unwrap();
+        let recorded: Arc<Mutex<Vec<RequestMetrics>>> = Default::default();
+        let fa = server.client().with_metrics_hook({
+            let recorded = recorded.clone();
+            move |metrics| recorded.lock().unwrap().push(metrics.clone())
+        });
+
+        fa.get_submission(SubmissionId(31209021)).await.unwrap();
+
+        let recorded = recorded.lock().unwrap();
+        assert_eq!(recorded.len(), 1);
+        assert_eq!(recorded[0].method, Method::Get);
+        assert_eq!(recorded[0].url, format!("{}/view/31209021", server.url()));
+        assert_eq!(recorded[0].status, Some(200));
+        assert_eq!(recorded[0].bytes, SUBMISSION.len() as u64);
+        assert!(recorded[0].time_to_headers.unwrap() <= recorded[0].duration);
+    }
+}
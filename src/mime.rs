@@ -0,0 +1,176 @@
+//! Detecting file types from their contents.
+
+struct Signature {
+    offset: usize,
+    magic: &'static [u8],
+    mime: &'static str,
+}
+
+const fn sig(offset: usize, magic: &'static [u8], mime: &'static str) -> Signature {
+    Signature {
+        offset,
+        magic,
+        mime,
+    }
+}
+
+const SIGNATURES: &[Signature] = &[
+    sig(0, b"\x89PNG\r\n\x1a\n", "image/png"),
+    sig(0, b"\xff\xd8\xff", "image/jpeg"),
+    sig(0, b"GIF87a", "image/gif"),
+    sig(0, b"GIF89a", "image/gif"),
+    sig(8, b"WEBP", "image/webp"),
+    sig(8, b"WAVE", "audio/wav"),
+    sig(4, b"ftypavif", "image/avif"),
+    sig(4, b"ftypavis", "image/avif"),
+    sig(0, b"II*\x00", "image/tiff"),
+    sig(0, b"MM\x00*", "image/tiff"),
+    sig(0, b"BM", "image/bmp"),
+    sig(0, b"FWS", "application/x-shockwave-flash"),
+    sig(0, b"CWS", "application/x-shockwave-flash"),
+    sig(0, b"ZWS", "application/x-shockwave-flash"),
+    sig(0, b"%PDF-", "application/pdf"),
+    sig(0, b"{\\rtf", "application/rtf"),
+    sig(0, b"\xd0\xcf\x11\xe0\xa1\xb1\x1a\xe1", "application/msword"),
+    sig(0, b"PK\x03\x04", "application/zip"),
+    sig(0, b"ID3", "audio/mpeg"),
+    sig(0, b"\xff\xfb", "audio/mpeg"),
+    sig(0, b"\xff\xf3", "audio/mpeg"),
+    sig(0, b"\xff\xf2", "audio/mpeg"),
+    sig(0, b"OggS", "audio/ogg"),
+    sig(0, b"fLaC", "audio/flac"),
+    sig(0, b"MThd", "audio/midi"),
+];
+
+/// Detect the MIME type of a file from the first bytes of its contents.
+///
+/// Contents without a known signature that look like text are reported as
+/// `text/plain`.
+pub fn sniff(data: &[u8]) -> Option<&'static str> {
+    let signature = SIGNATURES.iter().find(|signature| {
+        data.get(signature.offset..signature.offset + signature.magic.len())
+            == Some(signature.magic)
+    });
+
+    if let Some(signature) = signature {
+        return Some(signature.mime);
+    }
+
+    if looks_like_text(data) {
+        return Some("text/plain");
+    }
+
+    None
+}
+
+fn looks_like_text(data: &[u8]) -> bool {
+    let head = &data[..data.len().min(512)];
+
+    if head.is_empty() {
+        return false;
+    }
+
+    // The sample may end in the middle of a multibyte character.
+    let text = match std::str::from_utf8(head) {
+        Ok(text) => text,
+        Err(err) if err.error_len().is_none() => {
+            std::str::from_utf8(&head[..err.valid_up_to()]).unwrap_or_default()
+        }
+        Err(_) => return false,
+    };
+
+    !text.is_empty()
+        && !text
+            .chars()
+            .any(|c| c.is_control() && !c.is_ascii_whitespace())
+}
+
+/// Check if a file is missing the trailer its format always ends with,
+/// which happens when a download is cut short.
+///
+/// Only formats with a reliable trailer are checked, everything else is
+/// assumed to be complete.
+pub fn is_truncated(mime: &str, data: &[u8]) -> bool {
+    let (trailer, tail): (&[u8], _) = match mime {
+        // Some encoders add padding after the trailer, so look near the end
+        // instead of requiring it to be the last bytes.
+        "image/png" => (b"IEND", &data[data.len().saturating_sub(1024)..]),
+        // Markers can't appear inside JPEG entropy coded data, but anything
+        // can follow the end, like the video of a motion photo.
+        "image/jpeg" => (b"\xff\xd9", data),
+        _ => return false,
+    };
+
+    !tail.windows(trailer.len()).any(|window| window == trailer)
+}
+
+/// File extensions that are expected for a MIME type.
+pub fn extensions(mime: &str) -> &'static [&'static str] {
+    match mime {
+        "image/png" => &["png"],
+        "image/jpeg" => &["jpg", "jpeg", "jpe"],
+        "image/gif" => &["gif"],
+        "image/webp" => &["webp"],
+        "image/avif" => &["avif"],
+        "image/tiff" => &["tif", "tiff"],
+        "image/bmp" => &["bmp"],
+        "application/x-shockwave-flash" => &["swf"],
+        "application/pdf" => &["pdf"],
+        "application/rtf" => &["rtf"],
+        "application/msword" => &["doc"],
+        // Office and OpenDocument files are zip archives.
+        "application/zip" => &["zip", "docx", "odt"],
+        "audio/mpeg" => &["mp3"],
+        "audio/wav" => &["wav"],
+        "audio/ogg" => &["ogg", "oga"],
+        "audio/flac" => &["flac"],
+        "audio/midi" => &["mid", "midi"],
+        "text/plain" => &["txt"],
+        _ => &[],
+    }
+}
+
+/// Check if a file extension is expected for a MIME type.
+pub fn matches_extension(mime: &str, ext: &str) -> bool {
+    extensions(mime)
+        .iter()
+        .any(|known| known.eq_ignore_ascii_case(ext))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sniff() {
+        assert_eq!(sniff(b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR"), Some("image/png"));
+        assert_eq!(sniff(b"\xff\xd8\xff\xe0\0\x10JFIF"), Some("image/jpeg"));
+        assert_eq!(sniff(b"RIFF\0\0\0\0WEBPVP8 "), Some("image/webp"));
+        assert_eq!(sniff(b"CWS\x0a"), Some("application/x-shockwave-flash"));
+        assert_eq!(sniff("Once upon a time…\n".as_bytes()), Some("text/plain"));
+        assert_eq!(sniff(b"\x00\x01\x02\x03"), None);
+        assert_eq!(sniff(b""), None);
+    }
+
+    #[test]
+    fn test_is_truncated() {
+        let png = b"\x89PNG\r\n\x1a\n\0\0\0\0IEND\xaeB`\x82";
+        assert!(!is_truncated("image/png", png));
+        assert!(is_truncated("image/png", &png[..12]));
+
+        assert!(!is_truncated("image/jpeg", b"\xff\xd8\xff\xe0\xff\xd9"));
+        assert!(is_truncated("image/jpeg", b"\xff\xd8\xff\xe0"));
+        let mut motion_photo = b"\xff\xd8\xff\xe0\xff\xd9".to_vec();
+        motion_photo.extend(std::iter::repeat_n(0, 4096));
+        assert!(!is_truncated("image/jpeg", &motion_photo));
+
+        assert!(!is_truncated("text/plain", b"The end"));
+    }
+
+    #[test]
+    fn test_matches_extension() {
+        assert!(matches_extension("image/jpeg", "JPG"));
+        assert!(matches_extension("application/zip", "docx"));
+        assert!(!matches_extension("image/png", "jpg"));
+    }
+}
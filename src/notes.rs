@@ -0,0 +1,270 @@
+//! Reading notes, FurAffinity's private messages, for the logged in account.
+
+use crate::username::Artist;
+use crate::{parse_date, Error};
+use lazy_static::lazy_static;
+use scraper::Selector;
+
+lazy_static! {
+    static ref NOTE_ROW: Selector =
+        Selector::parse("#notes-list tr.note, .note-list-container").unwrap();
+    static ref NOTE_LINK: Selector =
+        Selector::parse(r#"a[href*="/viewmessage/"], a[href^="/msg/pms/"]"#).unwrap();
+    static ref NOTE_ID: regex::Regex =
+        regex::Regex::new(r"(?:/viewmessage/|/msg/pms/\d+/)(\d+)").unwrap();
+    static ref LIST_SUBJECT: Selector = Selector::parse(".note-list-subject").unwrap();
+    static ref USER_LINK: Selector = Selector::parse(r#"a[href^="/user/"]"#).unwrap();
+    static ref POSTED_AT: Selector = Selector::parse(".popup_date").unwrap();
+    static ref MESSAGE: Selector = Selector::parse("#message, #pms-form .note-view").unwrap();
+    static ref SUBJECT: Selector =
+        Selector::parse(".section-header h2, .note-subject h2, .note-subject").unwrap();
+    static ref ADDRESSES: Selector = Selector::parse(
+        r#".addresses a[href^="/user/"], .note-from a[href^="/user/"], .note-to a[href^="/user/"]"#
+    )
+    .unwrap();
+    static ref BODY: Selector = Selector::parse(".section-body, .note-content").unwrap();
+    static ref NOTICE: Selector =
+        Selector::parse("section.notice-message .redirect-message, .error-message-box").unwrap();
+}
+
+/// A folder of notes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub enum NoteFolder {
+    Inbox,
+    Unread,
+    Outbox,
+    Archive,
+    Trash,
+    HighPriority,
+    MediumPriority,
+    LowPriority,
+}
+
+impl NoteFolder {
+    /// Name of the folder in URLs.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            NoteFolder::Inbox => "inbox",
+            NoteFolder::Unread => "unread",
+            NoteFolder::Outbox => "outbox",
+            NoteFolder::Archive => "archive",
+            NoteFolder::Trash => "trash",
+            NoteFolder::HighPriority => "high_prio",
+            NoteFolder::MediumPriority => "medium_prio",
+            NoteFolder::LowPriority => "low_prio",
+        }
+    }
+}
+
+/// A note shown in a folder's list.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct NotePreview {
+    pub id: i64,
+    pub subject: String,
+    /// Who sent the note, or who it was sent to for notes in the outbox.
+    pub sender: Artist,
+    pub posted_at: chrono::DateTime<chrono::Utc>,
+    pub unread: bool,
+}
+
+/// A note, from its page.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Note {
+    pub id: i64,
+    pub subject: String,
+    pub sender: Artist,
+    pub recipient: Option<Artist>,
+    pub posted_at: chrono::DateTime<chrono::Utc>,
+    /// HTML of the note.
+    pub body: String,
+}
+
+/// Parse the notes shown on a page of a folder, newest first.
+pub fn parse_notes(page: &str) -> Result<Vec<NotePreview>, Error> {
+    require_login(page)?;
+
+    let document = scraper::Html::parse_document(page);
+
+    document
+        .select(&NOTE_ROW)
+        .map(|row| {
+            let link = row
+                .select(&NOTE_LINK)
+                .find(|link| {
+                    link.value()
+                        .attr("href")
+                        .is_some_and(|href| NOTE_ID.is_match(href))
+                })
+                .ok_or_else(|| Error::parse("note link"))?;
+
+            let id = NOTE_ID
+                .captures(link.value().attr("href").unwrap_or_default())
+                .ok_or_else(|| Error::parse("note id"))?[1]
+                .parse()?;
+
+            let subject = row
+                .select(&LIST_SUBJECT)
+                .next()
+                .map(crate::join_text_nodes)
+                .unwrap_or_else(|| crate::join_text_nodes(link));
+
+            let sender = row
+                .select(&USER_LINK)
+                .next()
+                .map(|user| Artist::parse(&crate::join_text_nodes(user), user.value().attr("href")))
+                .ok_or_else(|| Error::parse("note sender"))?;
+
+            let posted_at = row
+                .select(&POSTED_AT)
+                .next()
+                .and_then(|posted_at| posted_at.value().attr("title"))
+                .ok_or_else(|| Error::parse("posted at"))?;
+
+            let unread = [row, link]
+                .iter()
+                .any(|elem| elem.value().classes().any(|class| class.contains("unread")));
+
+            Ok(NotePreview {
+                id,
+                subject,
+                sender,
+                posted_at: parse_date(posted_at)?,
+                unread,
+            })
+        })
+        .collect()
+}
+
+/// Parse a note's page, at `/viewmessage/{id}/`. Returns `None` if the note
+/// was deleted or belongs to another account.
+pub fn parse_note(id: i64, page: &str) -> Result<Option<Note>, Error> {
+    require_login(page)?;
+
+    let document = scraper::Html::parse_document(page);
+
+    if document.select(&NOTICE).next().is_some() {
+        return Ok(None);
+    }
+
+    let message = document
+        .select(&MESSAGE)
+        .next()
+        .ok_or_else(|| Error::parse("note"))?;
+
+    let subject = message
+        .select(&SUBJECT)
+        .next()
+        .map(crate::join_text_nodes)
+        .ok_or_else(|| Error::parse("note subject"))?;
+
+    let mut users = message
+        .select(&ADDRESSES)
+        .map(|user| Artist::parse(&crate::join_text_nodes(user), user.value().attr("href")));
+    let sender = users.next().ok_or_else(|| Error::parse("note sender"))?;
+    let recipient = users.next();
+
+    let posted_at = message
+        .select(&POSTED_AT)
+        .next()
+        .and_then(|posted_at| posted_at.value().attr("title"))
+        .ok_or_else(|| Error::parse("posted at"))?;
+
+    let body = message
+        .select(&BODY)
+        .next()
+        .map(|body| body.inner_html().trim().to_string())
+        .ok_or_else(|| Error::parse("note body"))?;
+
+    Ok(Some(Note {
+        id,
+        subject,
+        sender,
+        recipient,
+        posted_at: parse_date(posted_at)?,
+        body,
+    }))
+}
+
+fn require_login(page: &str) -> Result<(), Error> {
+    if crate::is_logged_in(page) {
+        Ok(())
+    } else {
+        Err(Error::LoginRequired(
+            "notes can only be loaded when logged in".to_string(),
+        ))
+    }
+}
+
+#[cfg(all(test, feature = "client"))]
+mod tests {
+    use super::*;
+    use crate::test_utils::MockServer;
+
+    const NOTES: &str = r#"<html><body><a id="my-username" href="/user/user/">~user</a>
+        <div id="notes-list">
+            <div class="note-list-container unread">
+                <a href="/msg/pms/1/123456/#message"><div class="note-list-subject">Commission</div></a>
+                <div class="note-list-sender"><a href="/user/deadrussiansoul/">Dead_Russian_Soul</a></div>
+                <span class="popup_date" title="Apr 16th, 2019 12:22 PM">7 years ago</span>
+            </div>
+            <div class="note-list-container">
+                <a href="/msg/pms/1/123400/#message"><div class="note-list-subject">Hello</div></a>
+                <div class="note-list-sender"><a href="/user/example/">!Example</a></div>
+                <span class="popup_date" title="Apr 1st, 2019 09:00 AM">7 years ago</span>
+            </div>
+        </div>
+    </body></html>"#;
+
+    const NOTE: &str = r#"<html><body><a id="my-username" href="/user/user/">~user</a>
+        <section id="message">
+            <div class="section-header">
+                <h2>Commission</h2>
+                <div class="addresses">
+                    <a href="/user/deadrussiansoul/">Dead_Russian_Soul</a> sent this message to
+                    <a href="/user/user/">User</a>
+                    <span class="popup_date" title="Apr 16th, 2019 12:22 PM">7 years ago</span>
+                </div>
+            </div>
+            <div class="section-body"><p>Are commissions open?</p></div>
+        </section>
+    </body></html>"#;
+
+    #[tokio::test]
+    async fn test_notes() {
+        let server = MockServer::start().await.unwrap();
+        server.route("/controls/switchbox/inbox/1/", 200, NOTES);
+        server.route("/viewmessage/123456/", 200, NOTE);
+        let fa = server.client();
+
+        let notes = fa.get_notes(NoteFolder::Inbox, 1).await.unwrap();
+        assert_eq!(notes.len(), 2);
+        assert_eq!(notes[0].id, 123456);
+        assert_eq!(notes[0].subject, "Commission");
+        assert_eq!(notes[0].sender.slug, "deadrussiansoul");
+        assert!(notes[0].unread);
+        assert!(!notes[1].unread);
+        assert_eq!(notes[1].sender.status_symbol, Some('!'));
+
+        let note = fa.get_note(123456).await.unwrap().unwrap();
+        assert_eq!(note.subject, "Commission");
+        assert_eq!(note.sender.display_name, "Dead_Russian_Soul");
+        assert_eq!(note.recipient.unwrap().slug, "user");
+        assert_eq!(note.body, "<p>Are commissions open?</p>");
+        assert_eq!(note.posted_at, notes[0].posted_at);
+
+        server.route("/viewmessage/1/", 200, r#"<html><body><a id="my-username" href="/user/user/">~user</a><section class="notice-message"><div class="redirect-message">This message does not exist.</div></section></body></html>"#);
+        assert_eq!(fa.get_note(1).await.unwrap(), None);
+
+        server.route("/viewmessage/123456/", 200, "<html><body></body></html>");
+        assert!(matches!(
+            fa.get_note(123456).await.unwrap_err(),
+            Error::LoginRequired(_)
+        ));
+    }
+}
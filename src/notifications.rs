@@ -0,0 +1,188 @@
+//! Notifications for the logged in account, from the message bar in the
+//! header of every page and `/msg/submissions/`.
+
+use crate::gallery::SubmissionPreview;
+use crate::Error;
+use lazy_static::lazy_static;
+use scraper::Selector;
+
+lazy_static! {
+    static ref MESSAGE_LINK: Selector = Selector::parse(r#"a[href^="/msg/"]"#).unwrap();
+    static ref COUNT: regex::Regex = regex::Regex::new(r"^([\d,]+)\s*([A-Z]{1,2})$").unwrap();
+    static ref NEXT_PAGE: Selector = Selector::parse(r#"a[href*="/msg/submissions/"]"#).unwrap();
+    static ref CURSOR: regex::Regex = regex::Regex::new(r"/msg/submissions/([^/?#]+)").unwrap();
+}
+
+/// Counts of unread notifications, from the message bar.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Notifications {
+    pub submissions: u32,
+    pub comments: u32,
+    pub journals: u32,
+    pub favorites: u32,
+    pub watches: u32,
+    pub notes: u32,
+    pub trouble_tickets: u32,
+}
+
+impl Notifications {
+    /// Total number of notifications of every kind.
+    pub fn total(&self) -> u32 {
+        self.submissions
+            + self.comments
+            + self.journals
+            + self.favorites
+            + self.watches
+            + self.notes
+            + self.trouble_tickets
+    }
+}
+
+/// A page of new submissions from watched users.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct NewSubmissions {
+    pub submissions: Vec<SubmissionPreview>,
+    /// Where the next page starts, like `new~41234567@72`, if there is one.
+    pub next_page: Option<String>,
+}
+
+/// Parse notification counts from the message bar of any page loaded while
+/// logged in. Kinds that aren't shown have no notifications.
+pub fn parse_notifications(page: &str) -> Result<Notifications, Error> {
+    if !crate::is_logged_in(page) {
+        return Err(Error::LoginRequired(
+            "notifications can only be loaded when logged in".to_string(),
+        ));
+    }
+
+    let document = scraper::Html::parse_document(page);
+    let mut notifications = Notifications::default();
+
+    for link in document.select(&MESSAGE_LINK) {
+        let text = crate::join_text_nodes(link);
+        let captures = match COUNT.captures(&text) {
+            Some(captures) => captures,
+            None => continue,
+        };
+
+        let count = match &captures[2] {
+            "S" => &mut notifications.submissions,
+            "C" => &mut notifications.comments,
+            "J" => &mut notifications.journals,
+            "F" => &mut notifications.favorites,
+            "W" => &mut notifications.watches,
+            "N" => &mut notifications.notes,
+            "TT" => &mut notifications.trouble_tickets,
+            _ => continue,
+        };
+
+        *count = crate::SubmissionStats::parse_count(&captures[1])?;
+    }
+
+    Ok(notifications)
+}
+
+/// Parse a page of `/msg/submissions/`, newest first.
+pub fn parse_new_submissions(page: &str) -> Result<NewSubmissions, Error> {
+    if !crate::is_logged_in(page) {
+        return Err(Error::LoginRequired(
+            "new submissions can only be loaded when logged in".to_string(),
+        ));
+    }
+
+    let submissions = crate::gallery::parse_gallery(page)?;
+
+    let document = scraper::Html::parse_document(page);
+    let next_page = document
+        .select(&NEXT_PAGE)
+        .filter(|link| {
+            crate::join_text_nodes(*link)
+                .to_lowercase()
+                .contains("next")
+        })
+        .filter_map(|link| CURSOR.captures(link.value().attr("href")?))
+        .map(|captures| captures[1].to_string())
+        .next();
+
+    Ok(NewSubmissions {
+        submissions,
+        next_page,
+    })
+}
+
+#[cfg(all(test, feature = "client"))]
+mod tests {
+    use super::*;
+    use crate::test_utils::{MockServer, FRONT_PAGE, GALLERY};
+    use crate::SubmissionId;
+
+    const MESSAGE_BAR: &str = r#"<a id="my-username" href="/user/user/">~user</a>
+        <div class="message-bar-desktop">
+            <a class="notification-container inline" href="/msg/submissions/" title="1,234 Submission Notifications">1,234S</a>
+            <a class="notification-container inline" href="/msg/others/#comments" title="12 Comment Notifications">12C</a>
+            <a class="notification-container inline" href="/msg/others/#watches" title="2 Watch Notifications">2W</a>
+            <a class="notification-container inline" href="/msg/pms/" title="1 Note Notification">1N</a>
+        </div>"#;
+
+    #[tokio::test]
+    async fn test_notifications() {
+        let server = MockServer::start().await.unwrap();
+        server.route(
+            "/",
+            200,
+            FRONT_PAGE.replace("</body>", &format!("{}</body>", MESSAGE_BAR)),
+        );
+        server.route(
+            "/msg/submissions/",
+            200,
+            GALLERY.replace(
+                "</body>",
+                &format!(
+                    r#"{}<a class="button standard more" href="/msg/submissions/new~41234500@72/">Next 72</a></body>"#,
+                    MESSAGE_BAR
+                ),
+            ),
+        );
+        server.route(
+            "/msg/submissions/new~41234500@72/",
+            200,
+            GALLERY.replace("</body>", &format!("{}</body>", MESSAGE_BAR)),
+        );
+
+        let fa = server.client();
+
+        let notifications = fa.get_notifications().await.unwrap();
+        assert_eq!(
+            notifications,
+            Notifications {
+                submissions: 1234,
+                comments: 12,
+                watches: 2,
+                notes: 1,
+                ..Default::default()
+            }
+        );
+        assert_eq!(notifications.total(), 1249);
+
+        let new = fa.get_new_submissions(None).await.unwrap();
+        assert!(!new.submissions.is_empty());
+        assert_eq!(new.next_page.as_deref(), Some("new~41234500@72"));
+
+        let new = fa
+            .get_new_submissions(new.next_page.as_deref())
+            .await
+            .unwrap();
+        assert_eq!(new.submissions[0].id, SubmissionId(41234568));
+        assert_eq!(new.next_page, None);
+
+        server.route("/", 200, FRONT_PAGE);
+        assert!(matches!(
+            fa.get_notifications().await.unwrap_err(),
+            Error::LoginRequired(_)
+        ));
+    }
+}
@@ -0,0 +1,226 @@
+//! Paging through listings of submissions, such as galleries and search
+//! results.
+//!
+//! Start from a cursor for the first page, like [`PageCursor::gallery`], and
+//! load it with [`FurAffinity::fetch_page`](crate::FurAffinity::fetch_page).
+//! Each [`Page`] has a cursor for the page after it until the listing ends.
+//! Favorites link to the next page instead of numbering them, so their
+//! cursors hold the token from the previous page.
+
+/// One page of a listing.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    /// The next page, or `None` if this was the last page.
+    pub next: Option<PageCursor>,
+    /// If the page was loaded as a guest, so items may be missing.
+    pub guest: bool,
+}
+
+impl<T> Page<T> {
+    /// If this was the last page.
+    pub fn is_last(&self) -> bool {
+        self.next.is_none()
+    }
+}
+
+/// Where a page of a listing is.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct PageCursor {
+    pub(crate) listing: PagedListing,
+    pub(crate) page: u32,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub(crate) enum PagedListing {
+    Gallery(String),
+    Scraps(String),
+    Search(String),
+    Favorites {
+        username: String,
+        token: Option<String>,
+    },
+}
+
+impl PageCursor {
+    /// The first page of a user's gallery.
+    pub fn gallery(username: &str) -> Self {
+        Self {
+            listing: PagedListing::Gallery(crate::username::slug(username)),
+            page: 1,
+        }
+    }
+
+    /// The first page of a user's scraps.
+    pub fn scraps(username: &str) -> Self {
+        Self {
+            listing: PagedListing::Scraps(crate::username::slug(username)),
+            page: 1,
+        }
+    }
+
+    /// The first page of results for a search query.
+    pub fn search(query: &str) -> Self {
+        Self {
+            listing: PagedListing::Search(query.to_string()),
+            page: 1,
+        }
+    }
+
+    /// The first page of a user's favorites.
+    pub fn favorites(username: &str) -> Self {
+        Self {
+            listing: PagedListing::Favorites {
+                username: crate::username::slug(username),
+                token: None,
+            },
+            page: 1,
+        }
+    }
+
+    /// The same listing at another page, starting at 1. Favorites can't be
+    /// loaded by page number, so this only changes the number reported by
+    /// [`PageCursor::page`] for them.
+    pub fn with_page(mut self, page: u32) -> Self {
+        self.page = page.max(1);
+        self
+    }
+
+    /// Token for where the page starts, for listings that aren't numbered.
+    pub fn token(&self) -> Option<&str> {
+        match &self.listing {
+            PagedListing::Favorites { token, .. } => token.as_deref(),
+            _ => None,
+        }
+    }
+
+    /// Number of the page, starting at 1.
+    pub fn page(&self) -> u32 {
+        self.page
+    }
+
+    /// The page of items loaded from this cursor. Listings end at the first
+    /// empty page.
+    #[cfg(feature = "client")]
+    pub(crate) fn page_of(
+        &self,
+        listing: crate::gallery::Listing,
+    ) -> Page<crate::gallery::SubmissionPreview> {
+        let next =
+            Some(self.clone().with_page(self.page + 1)).filter(|_| !listing.submissions.is_empty());
+
+        Self::page_with_next(listing, next)
+    }
+
+    /// The page of favorites loaded from this cursor, which ends when there
+    /// is no link to the next page.
+    #[cfg(feature = "client")]
+    pub(crate) fn favorites_page_of(
+        &self,
+        favorites: crate::gallery::Favorites,
+    ) -> Page<crate::gallery::SubmissionPreview> {
+        let next = match (&self.listing, favorites.next_page) {
+            (PagedListing::Favorites { username, .. }, Some(token)) => Some(Self {
+                listing: PagedListing::Favorites {
+                    username: username.clone(),
+                    token: Some(token),
+                },
+                page: self.page + 1,
+            }),
+            _ => None,
+        };
+
+        Self::page_with_next(favorites.listing, next)
+    }
+
+    #[cfg(feature = "client")]
+    fn page_with_next(
+        listing: crate::gallery::Listing,
+        next: Option<Self>,
+    ) -> Page<crate::gallery::SubmissionPreview> {
+        Page {
+            items: listing.submissions,
+            next,
+            guest: listing.guest,
+        }
+    }
+}
+
+#[cfg(all(test, feature = "client"))]
+mod tests {
+    use super::*;
+    use crate::test_utils::MockServer;
+    use futures::StreamExt;
+
+    #[tokio::test]
+    async fn test_pages() {
+        let server = MockServer::with_fixtures().await.unwrap();
+        let fa = server.client();
+
+        let first = fa
+            .fetch_page(&PageCursor::gallery("Example"))
+            .await
+            .unwrap();
+        assert_eq!(first.items.len(), 2);
+        assert!(first.guest);
+        assert_eq!(first.next.as_ref().map(PageCursor::page), Some(2));
+
+        let second = fa.fetch_next(&first).await.unwrap().unwrap();
+        assert!(second.items.is_empty());
+        assert!(second.is_last());
+        assert_eq!(fa.fetch_next(&second).await.unwrap(), None);
+
+        let pages: Vec<_> = fa.pages(PageCursor::gallery("example")).collect().await;
+        assert_eq!(pages.len(), 1);
+
+        server.route("/scraps/example/1/", 200, crate::test_utils::GALLERY);
+        let scraps: Vec<_> = fa.scraps_stream("Example").collect().await;
+        assert_eq!(scraps.len(), 2);
+        assert_eq!(
+            server.requests().last().map(String::as_str),
+            Some("/scraps/example/2/")
+        );
+
+        server.route(
+            "/favorites/example/",
+            200,
+            crate::test_utils::GALLERY.replace(
+                "</body>",
+                r#"<a class="button standard right" href="/favorites/example/1234567890/next">Next</a></body>"#,
+            ),
+        );
+        server.route(
+            "/favorites/example/1234567890/next/",
+            200,
+            crate::test_utils::GALLERY,
+        );
+        let favorites: Vec<_> = fa.favorites_stream("Example").collect().await;
+        assert_eq!(favorites.len(), 4);
+        assert_eq!(
+            server.requests().last().map(String::as_str),
+            Some("/favorites/example/1234567890/next/")
+        );
+
+        let first = fa
+            .fetch_page(&PageCursor::favorites("example"))
+            .await
+            .unwrap();
+        let next = first.next.as_ref().unwrap();
+        assert_eq!(next.token(), Some("1234567890/next"));
+        assert_eq!(next.page(), 2);
+
+        fa.fetch_page(&PageCursor::search("fox").with_page(3))
+            .await
+            .unwrap();
+        assert_eq!(
+            server.requests().last().map(String::as_str),
+            Some("/search/?q=fox&page=3")
+        );
+    }
+}
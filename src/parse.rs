@@ -0,0 +1,55 @@
+//! Every parser for FurAffinity's pages in one place.
+//!
+//! These only need the HTML of a page, so they work on saved pages and
+//! without the `client` feature.
+
+pub use crate::actions::{parse_action_links, parse_comment_form};
+pub use crate::browse::parse_browse;
+pub use crate::comment::parse_comments;
+pub use crate::commissions::parse_commissions;
+pub use crate::gallery::{
+    parse_favorites, parse_folders, parse_front_page_sections, parse_gallery,
+    parse_gallery as parse_gallery_page, parse_listing, parse_recent,
+};
+pub use crate::journal::{parse_journal, parse_journals};
+pub use crate::login::{parse_login_error, parse_login_form, parse_set_cookies};
+pub use crate::notes::{parse_note, parse_notes};
+pub use crate::notifications::{parse_new_submissions, parse_notifications};
+pub use crate::profile::{parse_profile, parse_profile as parse_user_profile, parse_shouts};
+pub use crate::search::{parse_search_results, parse_taxonomy};
+pub use crate::settings::parse_content_settings;
+pub use crate::status::parse_unavailable;
+pub use crate::url::parse_avatar_url;
+pub use crate::watchlist::parse_watchlist;
+pub use crate::{
+    parse_front_page, parse_front_page as parse_latest_page, parse_latest_id, parse_online_counts,
+    parse_submission, parse_submission_fields, parse_submission_status,
+    parse_submission_with_comments,
+};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{FRONT_PAGE, GALLERY, PROFILE, SUBMISSION};
+    use crate::SubmissionId;
+
+    #[test]
+    fn test_fixtures() {
+        let (latest, online) = parse_latest_page(FRONT_PAGE).unwrap();
+        assert_eq!(latest, SubmissionId(41234568));
+        assert_eq!(online.other, 6888);
+
+        let sub = parse_submission(SubmissionId(31209021), SUBMISSION)
+            .unwrap()
+            .unwrap();
+        assert_eq!(sub.title, "Bilberry fox");
+
+        let gallery = parse_gallery_page(GALLERY).unwrap();
+        assert_eq!(gallery[0].id, SubmissionId(41234568));
+
+        let profile = parse_user_profile("deadrussiansoul", PROFILE)
+            .unwrap()
+            .unwrap();
+        assert_eq!(profile.slug, "deadrussiansoul");
+    }
+}
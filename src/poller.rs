@@ -0,0 +1,478 @@
+//! Watching for new submissions as they are uploaded.
+
+use crate::checkpoint::{Checkpoint, CheckpointState};
+use crate::retry::backoff;
+use crate::{Error, FurAffinity, Submission, SubmissionId};
+use futures::Stream;
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::time::Duration;
+use tokio::time::Instant;
+
+/// Result of loading a submission found by a [`Poller`].
+#[derive(Debug)]
+pub enum Polled {
+    /// The submission was loaded.
+    Submission(Box<Submission>),
+    /// There was no submission that could be viewed with this ID, after
+    /// retrying.
+    Missing(SubmissionId),
+    /// Loading the submission failed, after retrying if the error was
+    /// retryable.
+    Failed(SubmissionId, Error),
+}
+
+impl Polled {
+    pub(crate) async fn load(fa: &FurAffinity, id: SubmissionId) -> Self {
+        match fa.get_submission(id).await {
+            Ok(Some(sub)) => Polled::Submission(Box::new(sub)),
+            Ok(None) => Polled::Missing(id),
+            Err(err) => Polled::Failed(id, err),
+        }
+    }
+}
+
+/// Yields every submission ID from a starting point onwards, checking for
+/// new uploads on an interval once it has caught up.
+///
+/// Requests are made one at a time, so a poller never makes more than one
+/// request to FurAffinity at once.
+pub struct Poller<'a> {
+    fa: &'a FurAffinity,
+    next_id: SubmissionId,
+    interval: Duration,
+    max_attempts: u32,
+    retry_delay: Duration,
+    adaptive: Option<(Duration, Duration)>,
+    checkpoint: Option<Box<dyn Checkpoint + Send + 'a>>,
+    require_full_visibility: bool,
+
+    resumed: bool,
+    visibility_checked: bool,
+    stopped: bool,
+    latest: Option<SubmissionId>,
+    last_poll: Option<Instant>,
+    next_poll: Option<Instant>,
+    /// IDs waiting to be loaded again, by when they should be retried and
+    /// how many attempts have already been made.
+    retries: BinaryHeap<Reverse<(Instant, SubmissionId, u32)>>,
+}
+
+/// A [`Poller`] used for its stream of loaded submissions, from
+/// [`Poller::loaded_submissions`].
+pub type SubmissionPoller<'a> = Poller<'a>;
+
+impl<'a> Poller<'a> {
+    /// Create a new poller, starting with the given submission ID.
+    pub fn new(fa: &'a FurAffinity, start_id: SubmissionId) -> Self {
+        Self {
+            fa,
+            next_id: start_id,
+            interval: Duration::from_secs(60),
+            max_attempts: 5,
+            retry_delay: Duration::from_secs(60),
+            adaptive: None,
+            checkpoint: None,
+            require_full_visibility: false,
+            resumed: false,
+            visibility_checked: false,
+            stopped: false,
+            latest: None,
+            last_poll: None,
+            next_poll: None,
+            retries: BinaryHeap::new(),
+        }
+    }
+
+    /// Create a new poller that only yields submissions uploaded after the
+    /// current latest submission.
+    pub async fn from_latest(fa: &'a FurAffinity) -> Result<Self, Error> {
+        let latest = fa.latest_id().await?;
+
+        Ok(Self::new(fa, SubmissionId(latest.0 + 1)))
+    }
+
+    /// How long to wait between checking for new submissions, once all
+    /// known submissions have been yielded. Defaults to one minute.
+    pub fn with_interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// Adjust the interval between checks based on how quickly new
+    /// submissions are appearing, staying between the given bounds.
+    ///
+    /// The interval starts from the one set with [`Poller::with_interval`]
+    /// and moves towards the time it takes for one submission to be
+    /// uploaded, backing off when nothing new has appeared.
+    pub fn with_adaptive_interval(mut self, min: Duration, max: Duration) -> Self {
+        self.adaptive = Some((min, max));
+        self
+    }
+
+    /// How many times to try loading submissions that are missing or had a
+    /// retryable error, and how long to wait before the first retry. The
+    /// wait doubles after each attempt. Defaults to 5 attempts, starting
+    /// with one minute.
+    ///
+    /// Newly uploaded submissions are sometimes briefly unavailable, so
+    /// missing submissions are retried along with errors.
+    pub fn with_retries(mut self, max_attempts: u32, retry_delay: Duration) -> Self {
+        self.max_attempts = max_attempts;
+        self.retry_delay = retry_delay;
+        self
+    }
+
+    /// Save progress after each ID, including submissions waiting to be
+    /// retried. If the checkpoint has a saved state, the poller resumes from
+    /// it and retries the pending submissions straight away.
+    pub fn with_checkpoint<C: Checkpoint + Send + 'a>(mut self, checkpoint: C) -> Self {
+        self.checkpoint = Some(Box::new(checkpoint));
+        self
+    }
+
+    /// Make sure the account can view every rating before polling, ending
+    /// with an error if it can't. Otherwise hidden submissions look the same
+    /// as deleted ones.
+    pub fn with_full_visibility_required(mut self, required: bool) -> Self {
+        self.require_full_visibility = required;
+        self
+    }
+
+    /// Stream of every submission ID. Errors are from checking the latest
+    /// ID or using the checkpoint, and polling continues after them.
+    pub fn ids(self) -> impl Stream<Item = Result<SubmissionId, Error>> + 'a {
+        futures::stream::unfold(self, |mut poller| async move {
+            if poller.stopped {
+                return None;
+            }
+
+            let id = match poller.next_id().await {
+                Ok((id, _attempts)) => poller.save_checkpoint().map(|_| id),
+                Err(err) => Err(err),
+            };
+
+            Some((id, poller))
+        })
+    }
+
+    /// Stream of every submission, loaded as it is found. Errors are from
+    /// checking the latest ID or using the checkpoint, and polling continues
+    /// after them.
+    ///
+    /// Submissions that need to be retried are yielded once they load or
+    /// run out of attempts, so they may be out of order.
+    pub fn submissions(self) -> impl Stream<Item = Result<Polled, Error>> + 'a {
+        futures::stream::unfold(self, |mut poller| async move {
+            if poller.stopped {
+                return None;
+            }
+
+            let polled = loop {
+                match poller.next_id().await {
+                    Ok((id, attempts)) => {
+                        let polled = poller.load(id, attempts).await;
+                        if let Err(err) = poller.save_checkpoint() {
+                            break Err(err);
+                        }
+
+                        match polled {
+                            Some(polled) => break Ok(polled),
+                            None => continue,
+                        }
+                    }
+                    Err(err) => break Err(err),
+                }
+            };
+
+            Some((polled, poller))
+        })
+    }
+
+    /// Stream of submissions that could be loaded. IDs without a submission,
+    /// such as deleted ones, are skipped once they run out of attempts, and
+    /// submissions that failed to load are yielded as errors.
+    pub fn loaded_submissions(self) -> impl Stream<Item = Result<Submission, Error>> + 'a {
+        use futures::StreamExt;
+
+        self.submissions().filter_map(|polled| async move {
+            match polled {
+                Ok(Polled::Submission(sub)) => Some(Ok(*sub)),
+                Ok(Polled::Missing(_)) => None,
+                Ok(Polled::Failed(_, err)) | Err(err) => Some(Err(err)),
+            }
+        })
+    }
+
+    /// Wait for the next ID to load, either a retry that is due or a new
+    /// submission, along with the number of attempts already made.
+    async fn next_id(&mut self) -> Result<(SubmissionId, u32), Error> {
+        self.resume()?;
+        self.check_visibility().await?;
+
+        loop {
+            let now = Instant::now();
+
+            if let Some(Reverse((due, id, attempts))) = self.retries.peek().copied() {
+                if due <= now {
+                    self.retries.pop();
+                    return Ok((id, attempts));
+                }
+            }
+
+            match self.latest {
+                Some(latest) if self.next_id <= latest => {
+                    let id = self.next_id;
+                    self.next_id += 1;
+                    return Ok((id, 0));
+                }
+                _ => (),
+            }
+
+            match self.next_poll {
+                Some(next_poll) if next_poll > now => {
+                    let retry = self.retries.peek().map(|Reverse((due, _, _))| *due);
+                    let wake = retry.map_or(next_poll, |due| due.min(next_poll));
+
+                    tokio::time::sleep_until(wake).await;
+                    continue;
+                }
+                _ => (),
+            }
+
+            let latest = match self.fa.latest_id().await {
+                Ok(latest) => latest,
+                Err(err) => {
+                    self.next_poll = Some(now + self.interval);
+                    return Err(err);
+                }
+            };
+
+            if let (Some((min, max)), Some(last_poll), Some(previous)) =
+                (self.adaptive, self.last_poll, self.latest)
+            {
+                let found = latest.0.saturating_sub(previous.0) as u32;
+                self.interval = adapt_interval(self.interval, now - last_poll, found, min, max);
+            }
+
+            self.latest = Some(latest);
+            self.last_poll = Some(now);
+            self.next_poll = Some(now + self.interval);
+        }
+    }
+
+    /// Load the checkpoint's saved state, the first time it is called.
+    fn resume(&mut self) -> Result<(), Error> {
+        if self.resumed {
+            return Ok(());
+        }
+        self.resumed = true;
+
+        let state = match &mut self.checkpoint {
+            Some(checkpoint) => checkpoint.load()?,
+            None => None,
+        };
+
+        if let Some(state) = state {
+            let now = Instant::now();
+
+            self.next_id = state.next_id;
+            self.retries.extend(
+                state
+                    .pending
+                    .into_iter()
+                    .map(|(id, attempts)| Reverse((now, id, attempts))),
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Check the account can view every rating, the first time it is called
+    /// if required.
+    async fn check_visibility(&mut self) -> Result<(), Error> {
+        if !self.require_full_visibility || self.visibility_checked {
+            return Ok(());
+        }
+
+        let settings = self.fa.content_settings().await?;
+        self.visibility_checked = true;
+
+        if !settings.full_visibility() {
+            self.stopped = true;
+            return Err(Error::LoginRequired(format!(
+                "account can't view every submission: {:?}",
+                settings
+            )));
+        }
+
+        Ok(())
+    }
+
+    fn save_checkpoint(&mut self) -> Result<(), Error> {
+        let checkpoint = match &mut self.checkpoint {
+            Some(checkpoint) => checkpoint,
+            None => return Ok(()),
+        };
+
+        let state = CheckpointState {
+            next_id: self.next_id,
+            pending: self
+                .retries
+                .iter()
+                .map(|Reverse((_due, id, attempts))| (*id, *attempts))
+                .collect(),
+        };
+
+        checkpoint.save(&state)
+    }
+
+    /// Load a submission, returning `None` if it was queued to be retried.
+    async fn load(&mut self, id: SubmissionId, attempts: u32) -> Option<Polled> {
+        let polled = Polled::load(self.fa, id).await;
+
+        let retryable = match &polled {
+            Polled::Submission(_) => false,
+            Polled::Missing(_) => true,
+            Polled::Failed(_, err) => err.is_retryable(),
+        };
+
+        let attempts = attempts + 1;
+        if retryable && attempts < self.max_attempts {
+            let due = Instant::now() + backoff(self.retry_delay, attempts);
+            self.retries.push(Reverse((due, id, attempts)));
+
+            return None;
+        }
+
+        Some(polled)
+    }
+}
+
+/// Move the polling interval towards the time between uploads, seen over the
+/// time since the last poll.
+fn adapt_interval(
+    current: Duration,
+    elapsed: Duration,
+    found: u32,
+    min: Duration,
+    max: Duration,
+) -> Duration {
+    let interval = match found {
+        0 => current.saturating_mul(2),
+        found => (current + elapsed / found) / 2,
+    };
+
+    interval.max(min).min(max)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backoff() {
+        let delay = Duration::from_secs(60);
+
+        assert_eq!(backoff(delay, 1), Duration::from_secs(60));
+        assert_eq!(backoff(delay, 2), Duration::from_secs(120));
+        assert_eq!(backoff(delay, 4), Duration::from_secs(480));
+        assert_eq!(backoff(delay, 100), backoff(delay, 17));
+    }
+
+    #[tokio::test]
+    async fn test_checkpoint() {
+        use crate::checkpoint::MemoryCheckpoint;
+
+        let fa = FurAffinity::new("", "", "furaffinity-rs test", None);
+
+        let mut checkpoint = MemoryCheckpoint::new();
+        let state = CheckpointState {
+            next_id: SubmissionId(100),
+            pending: vec![(SubmissionId(95), 2), (SubmissionId(98), 1)]
+                .into_iter()
+                .collect(),
+        };
+        checkpoint.save(&state).unwrap();
+
+        let mut poller = Poller::new(&fa, SubmissionId(1)).with_checkpoint(checkpoint.clone());
+        poller.resume().unwrap();
+        assert_eq!(poller.next_id, SubmissionId(100));
+        assert_eq!(poller.retries.len(), 2);
+
+        // Pending retries are due immediately.
+        assert_eq!(poller.next_id().await.unwrap(), (SubmissionId(95), 2));
+        poller.save_checkpoint().unwrap();
+        assert_eq!(
+            checkpoint.state().unwrap().pending,
+            vec![(SubmissionId(98), 1)].into_iter().collect()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_full_visibility_required() {
+        use futures::StreamExt;
+
+        let server = crate::test_utils::MockServer::start().await.unwrap();
+        server.route("/controls/settings/", 200, "<html></html>");
+        let fa = server.client();
+
+        let ids: Vec<_> = Poller::new(&fa, SubmissionId(1))
+            .with_full_visibility_required(true)
+            .ids()
+            .collect()
+            .await;
+        assert_eq!(ids.len(), 1);
+        assert!(!ids[0].as_ref().unwrap_err().is_retryable());
+    }
+
+    #[tokio::test]
+    async fn test_loaded_submissions() {
+        use futures::StreamExt;
+
+        let server = crate::test_utils::MockServer::with_fixtures()
+            .await
+            .unwrap();
+        server.route("/view/41234566", 200, crate::test_utils::DELETED);
+        let fa = server.client();
+
+        let poller = Poller::from_latest(&fa).await.unwrap();
+        assert_eq!(poller.next_id, SubmissionId(41234569));
+
+        let subs: Vec<_> = Poller::new(&fa, SubmissionId(41234566))
+            .with_retries(1, Duration::from_millis(1))
+            .loaded_submissions()
+            .take(2)
+            .collect()
+            .await;
+        let ids: Vec<_> = subs.into_iter().map(|sub| sub.unwrap().id).collect();
+        assert_eq!(ids, vec![SubmissionId(41234567), SubmissionId(41234568)]);
+    }
+
+    #[test]
+    fn test_adapt_interval() {
+        let min = Duration::from_secs(10);
+        let max = Duration::from_secs(600);
+        let minute = Duration::from_secs(60);
+
+        // Busy, a submission every 6 seconds.
+        assert_eq!(
+            adapt_interval(minute, minute, 10, min, max),
+            Duration::from_secs(33)
+        );
+        assert_eq!(adapt_interval(min, min, 10, min, max), min);
+
+        // Quiet, a submission every 2 minutes.
+        assert_eq!(
+            adapt_interval(minute, Duration::from_secs(240), 2, min, max),
+            Duration::from_secs(90)
+        );
+
+        // Nothing new.
+        assert_eq!(
+            adapt_interval(minute, minute, 0, min, max),
+            Duration::from_secs(120)
+        );
+        assert_eq!(adapt_interval(max, max, 0, min, max), max);
+    }
+}
@@ -0,0 +1,62 @@
+//! Storing submissions in Postgres with sqlx.
+//!
+//! [`Rating`] is stored as text, one of `g`, `m`, or `a`, and
+//! [`ImageHash`] as bytea. Submissions can be read and written with
+//! [`SubmissionRecord`].
+
+use crate::hash::ImageHash;
+use crate::Rating;
+use sqlx::encode::IsNull;
+use sqlx::error::BoxDynError;
+use sqlx::postgres::{PgArgumentBuffer, PgTypeInfo, PgValueRef, Postgres};
+use sqlx::{Decode, Encode, Type};
+
+pub use crate::record::SubmissionRecord;
+
+impl Type<Postgres> for Rating {
+    fn type_info() -> PgTypeInfo {
+        <String as Type<Postgres>>::type_info()
+    }
+
+    fn compatible(ty: &PgTypeInfo) -> bool {
+        <String as Type<Postgres>>::compatible(ty)
+    }
+}
+
+impl Encode<'_, Postgres> for Rating {
+    fn encode_by_ref(&self, buf: &mut PgArgumentBuffer) -> Result<IsNull, BoxDynError> {
+        <&str as Encode<Postgres>>::encode(self.code(), buf)
+    }
+}
+
+impl<'r> Decode<'r, Postgres> for Rating {
+    fn decode(value: PgValueRef<'r>) -> Result<Self, BoxDynError> {
+        let code = <&str as Decode<Postgres>>::decode(value)?;
+
+        Rating::from_code(code).ok_or_else(|| format!("unknown rating: {}", code).into())
+    }
+}
+
+impl Type<Postgres> for ImageHash {
+    fn type_info() -> PgTypeInfo {
+        <Vec<u8> as Type<Postgres>>::type_info()
+    }
+
+    fn compatible(ty: &PgTypeInfo) -> bool {
+        <Vec<u8> as Type<Postgres>>::compatible(ty)
+    }
+}
+
+impl Encode<'_, Postgres> for ImageHash {
+    fn encode_by_ref(&self, buf: &mut PgArgumentBuffer) -> Result<IsNull, BoxDynError> {
+        <&[u8] as Encode<Postgres>>::encode(self.as_bytes(), buf)
+    }
+}
+
+impl<'r> Decode<'r, Postgres> for ImageHash {
+    fn decode(value: PgValueRef<'r>) -> Result<Self, BoxDynError> {
+        let bytes = <&[u8] as Decode<Postgres>>::decode(value)?;
+
+        Ok(ImageHash::from_bytes(bytes))
+    }
+}
@@ -0,0 +1,752 @@
+//! Parsing user profiles.
+
+use crate::username::Artist;
+use crate::Error;
+use lazy_static::lazy_static;
+use scraper::Selector;
+
+lazy_static! {
+    static ref NOTICE: Selector =
+        Selector::parse("section.notice-message .redirect-message, .error-message-box").unwrap();
+    static ref USER_TITLE: Selector =
+        Selector::parse("userpage-nav-user-details username").unwrap();
+    static ref USER_LINK: Selector = Selector::parse("userpage-nav-avatar a[href]").unwrap();
+    static ref USER_ID: Selector =
+        Selector::parse(r#"input[name="user_id"], [data-user-id]"#).unwrap();
+    static ref BADGE: Selector = Selector::parse(".userpage-badges .badge").unwrap();
+    static ref BADGE_IMAGE: Selector = Selector::parse("img").unwrap();
+    static ref BADGE_NAME: Selector = Selector::parse(".badge-name").unwrap();
+    static ref POSTED_AT: Selector = Selector::parse(".popup_date").unwrap();
+    static ref PROFILE_ROW: Selector = Selector::parse(".table-row").unwrap();
+    static ref PROFILE_LABEL: Selector = Selector::parse("strong.highlight").unwrap();
+    static ref LATEST_JOURNAL: Selector = Selector::parse("section.userpage-journal").unwrap();
+    static ref SECTION: Selector = Selector::parse("#page-userpage section").unwrap();
+    static ref SECTION_HEADER: Selector = Selector::parse(".section-header h2").unwrap();
+    static ref SECTION_USER: Selector =
+        Selector::parse(r#".section-body a[href^="/user/"]"#).unwrap();
+    static ref SECTION_COUNT: Selector = Selector::parse(".section-header a").unwrap();
+    static ref SECTION_BODY: Selector = Selector::parse(".section-body").unwrap();
+    static ref DESCRIPTION: Selector = Selector::parse(".userpage-profile").unwrap();
+    static ref CONTACT: Selector = Selector::parse("#userpage-contact .user-contact-item").unwrap();
+    static ref CONTACT_SERVICE: Selector = Selector::parse("strong.highlight").unwrap();
+    static ref CONTACT_LINK: Selector = Selector::parse("a[href]").unwrap();
+    static ref STAT: regex::Regex = regex::Regex::new(r"([A-Za-z ]+):\s*([\d,]+)").unwrap();
+    static ref COUNT: regex::Regex = regex::Regex::new(r"\(([\d,]+)\)").unwrap();
+    static ref SHOUT: Selector = Selector::parse(".userpage-shouts .comment_container").unwrap();
+    static ref SHOUT_ANCHOR: Selector = Selector::parse(r#"a[id^="shout-"]"#).unwrap();
+    static ref SHOUT_USER_LINK: Selector =
+        Selector::parse(r#".comment_username a[href^="/user/"], a[href^="/user/"]"#).unwrap();
+    static ref SHOUT_TEXT: Selector = Selector::parse(".comment_text").unwrap();
+}
+
+/// Status of an account, from the notice shown in place of a profile or the
+/// symbol before the user's name.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub enum AccountStatus {
+    Active,
+    /// Temporarily suspended by staff, shown with `!`.
+    Suspended,
+    /// Banned by staff.
+    Banned,
+    /// Disabled by its owner.
+    Disabled,
+    /// Waiting to be deleted at its owner's request.
+    PendingDeletion,
+    /// Kept in memory of a user who passed away, shown with `∞`.
+    Deceased,
+}
+
+impl AccountStatus {
+    /// Status of an account that can be viewed, from the symbol before its
+    /// name.
+    pub fn from_symbol(symbol: Option<char>) -> Self {
+        match symbol {
+            Some('!') => AccountStatus::Suspended,
+            Some('∞') => AccountStatus::Deceased,
+            _ => AccountStatus::Active,
+        }
+    }
+
+    /// If the account's profile and submissions can be viewed.
+    pub fn is_viewable(&self) -> bool {
+        !matches!(
+            self,
+            AccountStatus::Banned | AccountStatus::Disabled | AccountStatus::PendingDeletion
+        )
+    }
+}
+
+/// A badge or trophy shown on a profile.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Badge {
+    pub name: String,
+    pub image_url: String,
+    /// When the badge was awarded, if the profile shows it.
+    pub awarded_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+/// Counts shown on a profile.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct ProfileStats {
+    pub views: u64,
+    pub submissions: u64,
+    pub favorites: u64,
+    pub comments_earned: u64,
+    pub comments_made: u64,
+    pub journals: u64,
+    /// Number of users watching this user.
+    pub watchers: u64,
+    /// Number of users this user is watching.
+    pub watching: u64,
+}
+
+/// A way to contact a user listed on their profile.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Contact {
+    /// Name of the site or service, such as `Twitter`.
+    pub service: String,
+    /// The user's name or handle on the service.
+    pub name: String,
+    /// Link to the user on the service, if there is one.
+    pub url: Option<String>,
+}
+
+/// A user's profile page.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct UserProfile {
+    /// Username as it appears in URLs.
+    pub slug: String,
+    /// Name as the user typed it, or the slug if the profile can't be
+    /// viewed.
+    pub display_name: String,
+    pub status_symbol: Option<char>,
+    pub status: AccountStatus,
+    /// FurAffinity's numeric ID for the user, which stays the same if they
+    /// change their name. Only included in profiles loaded while logged in.
+    pub user_id: Option<u64>,
+    pub registered_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub avatar_url: Option<String>,
+    /// HTML of the profile's description.
+    pub description: String,
+    pub stats: ProfileStats,
+    pub contacts: Vec<Contact>,
+    pub badges: Vec<Badge>,
+    /// If the user is open to art trades, when their profile says.
+    pub accepting_trades: Option<bool>,
+    /// If the user is taking commissions, when their profile says.
+    pub accepting_commissions: Option<bool>,
+    /// The user's most recent journal.
+    pub latest_journal: Option<crate::journal::JournalPreview>,
+    /// A few of the users who most recently watched this user.
+    pub recent_watchers: Vec<Artist>,
+    /// A few of the users this user most recently watched.
+    pub recently_watched: Vec<Artist>,
+    /// The most recent shouts left on the profile, newest first.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub shouts: Vec<Shout>,
+}
+
+/// A message left on a user's profile.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Shout {
+    pub id: Option<u64>,
+    pub author: Artist,
+    pub posted_at: chrono::DateTime<chrono::Utc>,
+    /// HTML of the shout.
+    pub body: String,
+}
+
+/// A user's avatar, from
+/// [`FurAffinity::get_user_avatar`](crate::FurAffinity::get_user_avatar).
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Avatar {
+    pub url: String,
+    /// When the avatar was changed, if included in its URL.
+    pub changed_at: Option<chrono::DateTime<chrono::Utc>>,
+    /// Perceptual hash, if the avatar was downloaded and could be decoded.
+    pub hash_num: Option<i64>,
+    /// SHA-256 of the file, if the avatar was downloaded.
+    pub sha256: Option<Vec<u8>>,
+}
+
+impl Avatar {
+    /// An avatar that hasn't been downloaded, from its URL.
+    pub fn from_url<T: Into<String>>(url: T) -> Self {
+        use chrono::TimeZone;
+
+        let url = url.into();
+        let changed_at = match crate::url::FaUrl::parse(&url) {
+            Some(crate::url::FaUrl::Avatar {
+                timestamp: Some(timestamp),
+                ..
+            }) => chrono::Utc.timestamp_opt(timestamp, 0).single(),
+            _ => None,
+        };
+
+        Self {
+            url,
+            changed_at,
+            hash_num: None,
+            sha256: None,
+        }
+    }
+}
+
+impl UserProfile {
+    /// A profile that can't be viewed, with only what is known from the
+    /// username.
+    fn unavailable(slug: &str, status: AccountStatus) -> Self {
+        Self {
+            slug: slug.to_string(),
+            display_name: slug.to_string(),
+            status_symbol: None,
+            status,
+            user_id: None,
+            registered_at: None,
+            avatar_url: None,
+            description: String::new(),
+            stats: Default::default(),
+            contacts: Vec::new(),
+            badges: Vec::new(),
+            accepting_trades: None,
+            accepting_commissions: None,
+            latest_journal: None,
+            recent_watchers: Vec::new(),
+            recently_watched: Vec::new(),
+            shouts: Vec::new(),
+        }
+    }
+}
+
+/// Parse a user's profile page, loaded from `/user/{slug}/`. Returns `None`
+/// if the user does not exist.
+///
+/// Banned and disabled accounts are returned with their status, but without
+/// any details from the profile.
+pub fn parse_profile(slug: &str, page: &str) -> Result<Option<UserProfile>, Error> {
+    let slug = crate::username::slug(slug);
+    let document = scraper::Html::parse_document(page);
+
+    if let Some(notice) = document.select(&NOTICE).next() {
+        let notice = crate::join_text_nodes(notice).to_lowercase();
+
+        let status = if notice.contains("voluntarily disabled") {
+            AccountStatus::Disabled
+        } else if notice.contains("pending deletion") {
+            AccountStatus::PendingDeletion
+        } else if notice.contains("banned") {
+            AccountStatus::Banned
+        } else if notice.contains("suspended") {
+            AccountStatus::Suspended
+        } else if notice.contains("not be found") || notice.contains("not in our database") {
+            return Ok(None);
+        } else {
+            return Err(Error::new(
+                format!("unknown profile notice: {}", notice),
+                false,
+            ));
+        };
+
+        return Ok(Some(UserProfile::unavailable(&slug, status)));
+    }
+
+    let artist = parse_owner(&document, Some(&slug))?;
+
+    let registered_at = document
+        .select(&USER_TITLE)
+        .next()
+        .and_then(|title| title.select(&POSTED_AT).next())
+        .and_then(|date| date.value().attr("title"))
+        .map(crate::parse_date)
+        .transpose()?;
+
+    let user_id = document
+        .select(&USER_ID)
+        .find_map(|elem| {
+            let value = elem.value();
+            value.attr("data-user-id").or_else(|| value.attr("value"))
+        })
+        .map(|id| id.parse())
+        .transpose()?;
+
+    let avatar_url = crate::url::parse_avatar_url(page, &slug);
+
+    let description = document
+        .select(&DESCRIPTION)
+        .next()
+        .map(|description| description.inner_html().trim().to_string())
+        .unwrap_or_default();
+
+    let contacts = document
+        .select(&CONTACT)
+        .filter_map(parse_contact)
+        .collect();
+
+    let badges = document
+        .select(&BADGE)
+        .map(parse_badge)
+        .collect::<Result<Option<Vec<_>>, _>>()?
+        .ok_or_else(|| Error::parse("badge"))?;
+
+    let mut accepting_trades = None;
+    let mut accepting_commissions = None;
+    for row in document.select(&PROFILE_ROW) {
+        let label = match row.select(&PROFILE_LABEL).next() {
+            Some(label) => crate::join_text_nodes(label).to_lowercase(),
+            None => continue,
+        };
+
+        let flag = match label.as_str() {
+            "accepting trades" => &mut accepting_trades,
+            "accepting commissions" => &mut accepting_commissions,
+            _ => continue,
+        };
+
+        let value = row
+            .children()
+            .filter_map(|node| node.value().as_text())
+            .map(|text| text.trim().to_lowercase())
+            .find(|text| !text.is_empty());
+
+        *flag = match value.as_deref() {
+            Some("yes") => Some(true),
+            Some("no") => Some(false),
+            _ => None,
+        };
+    }
+
+    let latest_journal = document
+        .select(&LATEST_JOURNAL)
+        .next()
+        .map(crate::journal::parse_section)
+        .transpose()?;
+
+    let mut stats = ProfileStats::default();
+    let mut recent_watchers = Vec::new();
+    let mut recently_watched = Vec::new();
+    for section in document.select(&SECTION) {
+        let header = match section.select(&SECTION_HEADER).next() {
+            Some(header) => crate::join_text_nodes(header).to_lowercase(),
+            None => continue,
+        };
+
+        if header == "stats" {
+            let body = section
+                .select(&SECTION_BODY)
+                .next()
+                .map(crate::join_text_nodes)
+                .unwrap_or_default();
+
+            for captures in STAT.captures_iter(&body) {
+                let stat = match captures[1].trim().to_lowercase().as_str() {
+                    "views" => &mut stats.views,
+                    "submissions" => &mut stats.submissions,
+                    "favs" | "favorites" => &mut stats.favorites,
+                    "comments earned" => &mut stats.comments_earned,
+                    "comments made" => &mut stats.comments_made,
+                    "journals" => &mut stats.journals,
+                    _ => continue,
+                };
+                *stat = parse_count(&captures[2])?;
+            }
+
+            continue;
+        }
+
+        let (users, count) = if header.starts_with("recent watchers") {
+            (&mut recent_watchers, &mut stats.watchers)
+        } else if header.starts_with("recently watched") {
+            (&mut recently_watched, &mut stats.watching)
+        } else {
+            continue;
+        };
+
+        // The link to the full list includes how many users are on it.
+        let shown = section.select(&SECTION_COUNT).find_map(|link| {
+            COUNT
+                .captures(&crate::join_text_nodes(link))
+                .map(|captures| captures[1].to_string())
+        });
+        if let Some(shown) = shown {
+            *count = parse_count(&shown)?;
+        }
+
+        users.extend(
+            section.select(&SECTION_USER).map(|link| {
+                Artist::parse(&crate::join_text_nodes(link), link.value().attr("href"))
+            }),
+        );
+    }
+
+    let shouts = parse_shouts_document(&document)?;
+
+    Ok(Some(UserProfile {
+        slug: artist.slug,
+        display_name: artist.display_name,
+        status_symbol: artist.status_symbol,
+        status: AccountStatus::from_symbol(artist.status_symbol),
+        user_id,
+        registered_at,
+        avatar_url,
+        description,
+        stats,
+        contacts,
+        badges,
+        accepting_trades,
+        accepting_commissions,
+        latest_journal,
+        recent_watchers,
+        recently_watched,
+        shouts,
+    }))
+}
+
+/// Parse the shouts shown on a user's profile, newest first.
+pub fn parse_shouts(page: &str) -> Result<Vec<Shout>, Error> {
+    let document = scraper::Html::parse_document(page);
+
+    parse_shouts_document(&document)
+}
+
+fn parse_shouts_document(document: &scraper::Html) -> Result<Vec<Shout>, Error> {
+    document
+        .select(&SHOUT)
+        .map(|shout| {
+            let id = shout
+                .value()
+                .id()
+                .filter(|id| id.starts_with("shout-"))
+                .or_else(|| {
+                    shout
+                        .select(&SHOUT_ANCHOR)
+                        .next()
+                        .and_then(|anchor| anchor.value().id())
+                })
+                .and_then(|id| id.strip_prefix("shout-")?.parse().ok());
+
+            let author = shout
+                .select(&SHOUT_USER_LINK)
+                .next()
+                .map(|link| Artist::parse(&crate::join_text_nodes(link), link.value().attr("href")))
+                .ok_or_else(|| Error::parse("shout author"))?;
+
+            let posted_at = shout
+                .select(&POSTED_AT)
+                .next()
+                .and_then(|date| date.value().attr("title"))
+                .ok_or_else(|| Error::parse("shout posted at"))?;
+
+            let body = shout
+                .select(&SHOUT_TEXT)
+                .next()
+                .map(|text| text.inner_html().trim().to_string())
+                .ok_or_else(|| Error::parse("shout text"))?;
+
+            Ok(Shout {
+                id,
+                author,
+                posted_at: crate::parse_date(posted_at)?,
+                body,
+            })
+        })
+        .collect()
+}
+
+/// The user in the header of a profile or journal page. The slug comes from
+/// the avatar's link, or `slug` if there is no link.
+pub(crate) fn parse_owner(document: &scraper::Html, slug: Option<&str>) -> Result<Artist, Error> {
+    let title = document
+        .select(&USER_TITLE)
+        .next()
+        .ok_or_else(|| Error::parse("username"))?;
+    // The title also holds the registration date, so only its own text is
+    // the name.
+    let name = title
+        .children()
+        .filter_map(|node| node.value().as_text())
+        .map(|text| text.trim())
+        .find(|text| !text.is_empty())
+        .ok_or_else(|| Error::parse("username"))?;
+
+    let link = document
+        .select(&USER_LINK)
+        .next()
+        .and_then(|link| link.value().attr("href"));
+    let fallback = slug.map(|slug| format!("/user/{}/", slug));
+
+    Ok(Artist::parse(name, link.or(fallback.as_deref())))
+}
+
+fn parse_count(count: &str) -> Result<u64, Error> {
+    Ok(count.replace(',', "").parse()?)
+}
+
+/// Parse a contact, returning `None` if it has no service or name.
+fn parse_contact(contact: scraper::ElementRef) -> Option<Contact> {
+    let service = crate::join_text_nodes(contact.select(&CONTACT_SERVICE).next()?);
+
+    let link = contact.select(&CONTACT_LINK).next();
+    let name = match link {
+        Some(link) => crate::join_text_nodes(link),
+        None => contact
+            .text()
+            .map(str::trim)
+            .filter(|text| !text.is_empty())
+            .last()?
+            .to_string(),
+    };
+
+    if service.is_empty() || name.is_empty() || name == service {
+        return None;
+    }
+
+    Some(Contact {
+        service,
+        name,
+        url: link
+            .and_then(|link| link.value().attr("href"))
+            .map(absolute_url),
+    })
+}
+
+/// Parse a badge, returning `None` if it has no image or name.
+fn parse_badge(badge: scraper::ElementRef) -> Result<Option<Badge>, Error> {
+    let image = match badge.select(&BADGE_IMAGE).next() {
+        Some(image) => image.value(),
+        None => return Ok(None),
+    };
+
+    let name = match badge.select(&BADGE_NAME).next() {
+        Some(name) => crate::join_text_nodes(name),
+        None => match image.attr("alt") {
+            Some(alt) => alt.trim().to_string(),
+            None => return Ok(None),
+        },
+    };
+
+    let image_url = match image.attr("src") {
+        Some(src) => absolute_url(src),
+        None => return Ok(None),
+    };
+
+    let awarded_at = badge
+        .select(&POSTED_AT)
+        .next()
+        .and_then(|date| date.value().attr("title"))
+        .map(crate::parse_date)
+        .transpose()?;
+
+    Ok(Some(Badge {
+        name,
+        image_url,
+        awarded_at,
+    }))
+}
+
+/// Make a protocol or site relative link absolute.
+fn absolute_url(src: &str) -> String {
+    if src.starts_with("//") {
+        format!("https:{}", src)
+    } else if src.starts_with('/') {
+        format!("https://www.furaffinity.net{}", src)
+    } else {
+        src.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::PROFILE;
+
+    fn notice(message: &str) -> String {
+        format!(
+            r#"<section class="aligncenter notice-message"><div class="redirect-message"><h2>System Message</h2><p>{}</p></div></section>"#,
+            message
+        )
+    }
+
+    #[test]
+    fn test_parse_profile() {
+        let profile = parse_profile("deadrussiansoul", PROFILE).unwrap().unwrap();
+        assert_eq!(profile.slug, "deadrussiansoul");
+        assert_eq!(profile.display_name, "Dead_Russian_Soul");
+        assert_eq!(profile.status_symbol, Some('~'));
+        assert_eq!(profile.status, AccountStatus::Active);
+        assert_eq!(profile.user_id, Some(3456789));
+        assert_eq!(
+            profile.registered_at,
+            Some(crate::parse_date("Mar 3rd, 2015 10:12 AM").unwrap())
+        );
+        assert_eq!(
+            profile.avatar_url.as_deref(),
+            Some("https://a.furaffinity.net/1555431700/deadrussiansoul.gif")
+        );
+        assert_eq!(profile.description, "Painting foxes and <b>forests</b>.");
+
+        assert_eq!(
+            profile.stats,
+            ProfileStats {
+                views: 12345,
+                submissions: 56,
+                favorites: 789,
+                comments_earned: 321,
+                comments_made: 123,
+                journals: 7,
+                watchers: 1024,
+                watching: 88,
+            }
+        );
+
+        assert_eq!(profile.contacts.len(), 2);
+        assert_eq!(
+            profile.contacts[0],
+            Contact {
+                service: "Twitter".into(),
+                name: "@deadrussiansoul".into(),
+                url: Some("https://twitter.com/deadrussiansoul".into()),
+            }
+        );
+        assert_eq!(profile.contacts[1].name, "deadrussiansoul#1234");
+        assert_eq!(profile.contacts[1].url, None);
+
+        assert_eq!(profile.badges.len(), 2);
+        assert_eq!(profile.badges[0].name, "Supporter");
+        assert_eq!(
+            profile.badges[0].image_url,
+            "https://t.furaffinity.net/badges/supporter.png"
+        );
+        assert!(profile.badges[0].awarded_at.is_some());
+        assert_eq!(profile.badges[1].name, "YCH Winner");
+        assert_eq!(
+            profile.badges[1].image_url,
+            "https://www.furaffinity.net/themes/beta/img/badges/ych.png"
+        );
+        assert_eq!(profile.badges[1].awarded_at, None);
+
+        assert_eq!(profile.accepting_trades, Some(false));
+        assert_eq!(profile.accepting_commissions, Some(true));
+
+        let journal = profile.latest_journal.as_ref().unwrap();
+        assert_eq!(journal.id, crate::JournalId(10123456));
+        assert_eq!(journal.title, "Commissions open!");
+        assert_eq!(
+            journal.snippet,
+            "Three slots are open, first come first served."
+        );
+
+        let watchers: Vec<_> = profile
+            .recent_watchers
+            .iter()
+            .map(|artist| artist.slug.as_str())
+            .collect();
+        assert_eq!(watchers, vec!["fluffyfox", "example"]);
+        assert_eq!(profile.recent_watchers[1].display_name, "Ex_ample");
+        assert_eq!(profile.recently_watched.len(), 1);
+        assert_eq!(profile.recently_watched[0].slug, "someartist");
+
+        assert_eq!(profile.shouts.len(), 2);
+        assert_eq!(
+            profile.shouts[0],
+            Shout {
+                id: Some(51234567),
+                author: Artist::parse("FluffyFox", Some("/user/fluffyfox/")),
+                posted_at: crate::parse_date("Apr 20th, 2019 03:15 PM").unwrap(),
+                body: "Love the <i>new</i> foxes!".into(),
+            }
+        );
+        assert_eq!(profile.shouts[1].author.status_symbol, Some('~'));
+        assert_eq!(parse_shouts(PROFILE).unwrap(), profile.shouts);
+
+        let page = PROFILE.replace("~Dead_Russian_Soul", "!Dead_Russian_Soul");
+        let profile = parse_profile("deadrussiansoul", &page).unwrap().unwrap();
+        assert_eq!(profile.status, AccountStatus::Suspended);
+    }
+
+    #[test]
+    fn test_parse_unavailable_profile() {
+        let page = notice(
+            r#"User "example" has voluntarily disabled access to their account and all of its contents."#,
+        );
+        let profile = parse_profile("Example", &page).unwrap().unwrap();
+        assert_eq!(profile.slug, "example");
+        assert_eq!(profile.status, AccountStatus::Disabled);
+        assert!(!profile.status.is_viewable());
+
+        let page = notice("This user has been banned.");
+        let profile = parse_profile("example", &page).unwrap().unwrap();
+        assert_eq!(profile.status, AccountStatus::Banned);
+
+        let page = notice(r#"The username "example" could not be found."#);
+        assert_eq!(parse_profile("example", &page).unwrap(), None);
+
+        assert!(parse_profile("example", &notice("Something new.")).is_err());
+    }
+
+    /// Serves the profile fixture, and an image for every avatar.
+    #[cfg(feature = "client")]
+    struct AvatarTransport;
+
+    #[cfg(feature = "client")]
+    impl crate::transport::HttpTransport for AvatarTransport {
+        fn send(
+            &self,
+            request: crate::transport::Request,
+        ) -> futures::future::BoxFuture<'_, Result<crate::transport::Response, Error>> {
+            let body = if request.url.ends_with(".gif") {
+                crate::hash::tests::test_image()
+            } else {
+                PROFILE.as_bytes().to_vec()
+            };
+
+            Box::pin(async move {
+                Ok(crate::transport::Response::new(
+                    reqwest::StatusCode::OK,
+                    Default::default(),
+                    body,
+                ))
+            })
+        }
+    }
+
+    #[cfg(feature = "client")]
+    #[tokio::test]
+    async fn test_get_user_avatar() {
+        let fa = crate::FurAffinity::new("", "", "furaffinity-rs test", None)
+            .with_transport(std::sync::Arc::new(AvatarTransport));
+
+        let avatar = fa
+            .get_user_avatar("Dead_Russian_Soul", false)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            avatar.url,
+            "https://a.furaffinity.net/1555431700/deadrussiansoul.gif"
+        );
+        assert_eq!(avatar.changed_at.unwrap().timestamp(), 1555431700);
+        assert_eq!(avatar.hash_num, None);
+
+        let avatar = fa
+            .get_user_avatar("Dead_Russian_Soul", true)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(avatar.hash_num.is_some());
+        assert_eq!(avatar.sha256.unwrap().len(), 32);
+    }
+}
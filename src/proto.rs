@@ -0,0 +1,262 @@
+//! Protobuf messages for submissions, matching `proto/furaffinity.proto`.
+//!
+//! Details only available after hashing a file locally, such as extra
+//! hashes and image info, are not included.
+
+use crate::Error;
+use std::convert::TryFrom;
+
+/// Protobuf form of [`crate::Rating`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, prost::Enumeration)]
+#[repr(i32)]
+pub enum Rating {
+    General = 0,
+    Mature = 1,
+    Adult = 2,
+}
+
+impl From<&crate::Rating> for Rating {
+    fn from(rating: &crate::Rating) -> Self {
+        match rating {
+            crate::Rating::General => Rating::General,
+            crate::Rating::Mature => Rating::Mature,
+            crate::Rating::Adult => Rating::Adult,
+        }
+    }
+}
+
+impl From<Rating> for crate::Rating {
+    fn from(rating: Rating) -> Self {
+        match rating {
+            Rating::General => crate::Rating::General,
+            Rating::Mature => crate::Rating::Mature,
+            Rating::Adult => crate::Rating::Adult,
+        }
+    }
+}
+
+/// Protobuf form of [`crate::Content`].
+#[derive(Clone, PartialEq, prost::Oneof)]
+pub enum Content {
+    #[prost(string, tag = "4")]
+    Image(String),
+    #[prost(string, tag = "5")]
+    Flash(String),
+    #[prost(string, tag = "21")]
+    Text(String),
+    #[prost(string, tag = "22")]
+    Audio(String),
+}
+
+impl From<&crate::Content> for Content {
+    fn from(content: &crate::Content) -> Self {
+        match content {
+            crate::Content::Image(url) => Content::Image(url.clone()),
+            crate::Content::Flash(url) => Content::Flash(url.clone()),
+            crate::Content::Text(url) => Content::Text(url.clone()),
+            crate::Content::Audio(url) => Content::Audio(url.clone()),
+        }
+    }
+}
+
+impl From<Content> for crate::Content {
+    fn from(content: Content) -> Self {
+        match content {
+            Content::Image(url) => crate::Content::Image(url),
+            Content::Flash(url) => crate::Content::Flash(url),
+            Content::Text(url) => crate::Content::Text(url),
+            Content::Audio(url) => crate::Content::Audio(url),
+        }
+    }
+}
+
+/// Protobuf form of [`crate::Submission`].
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct Submission {
+    #[prost(uint64, tag = "1")]
+    pub id: u64,
+    #[prost(string, tag = "2")]
+    pub title: String,
+    #[prost(string, tag = "3")]
+    pub artist: String,
+    #[prost(oneof = "Content", tags = "4, 5, 21, 22")]
+    pub content: Option<Content>,
+    #[prost(string, tag = "6")]
+    pub ext: String,
+    #[prost(string, tag = "7")]
+    pub filename: String,
+    #[prost(enumeration = "Rating", tag = "8")]
+    pub rating: i32,
+    /// Seconds since the Unix epoch.
+    #[prost(int64, tag = "9")]
+    pub posted_at: i64,
+    #[prost(string, repeated, tag = "10")]
+    pub tags: Vec<String>,
+    #[prost(string, tag = "11")]
+    pub description: String,
+    #[prost(string, optional, tag = "12")]
+    pub hash: Option<String>,
+    #[prost(int64, optional, tag = "13")]
+    pub hash_num: Option<i64>,
+    #[prost(bytes = "vec", optional, tag = "14")]
+    pub file: Option<Vec<u8>>,
+    #[prost(uint64, optional, tag = "15")]
+    pub file_size: Option<u64>,
+    #[prost(bytes = "vec", optional, tag = "16")]
+    pub file_sha256: Option<Vec<u8>>,
+    #[prost(bytes = "vec", optional, tag = "17")]
+    pub file_md5: Option<Vec<u8>>,
+    #[prost(bytes = "vec", optional, tag = "18")]
+    pub file_blake3: Option<Vec<u8>>,
+    #[prost(string, optional, tag = "19")]
+    pub file_mime: Option<String>,
+    #[prost(bool, tag = "20")]
+    pub ai_generated: bool,
+    #[prost(uint32, tag = "23")]
+    pub views: u32,
+    #[prost(uint32, tag = "24")]
+    pub favorites: u32,
+    #[prost(uint32, tag = "25")]
+    pub comments: u32,
+    #[prost(string, optional, tag = "26")]
+    pub preview_url: Option<String>,
+    #[prost(string, optional, tag = "27")]
+    pub fullview_url: Option<String>,
+    #[prost(string, optional, tag = "28")]
+    pub avatar_url: Option<String>,
+}
+
+impl From<&crate::Submission> for Submission {
+    fn from(sub: &crate::Submission) -> Self {
+        Self {
+            id: sub.id.0,
+            title: sub.title.clone(),
+            artist: sub.artist.clone(),
+            content: Some(Content::from(&sub.content)),
+            ext: sub.ext.clone(),
+            filename: sub.filename.clone(),
+            rating: Rating::from(&sub.rating) as i32,
+            posted_at: sub.posted_at.timestamp(),
+            tags: sub.tags.clone(),
+            description: sub.description.clone(),
+            hash: sub.hash.clone(),
+            hash_num: sub.hash_num,
+            file: sub.file.clone(),
+            file_size: sub.file_size.map(|size| size as u64),
+            file_sha256: sub.file_sha256.clone(),
+            file_md5: sub.file_md5.clone(),
+            file_blake3: sub.file_blake3.clone(),
+            file_mime: sub.file_mime.clone(),
+            ai_generated: sub.ai_generated,
+            views: sub.stats.views,
+            favorites: sub.stats.favorites,
+            comments: sub.stats.comments,
+            preview_url: sub.preview_url.clone(),
+            fullview_url: sub.fullview_url.clone(),
+            avatar_url: sub.avatar_url.clone(),
+        }
+    }
+}
+
+impl From<crate::Submission> for Submission {
+    fn from(sub: crate::Submission) -> Self {
+        Self::from(&sub)
+    }
+}
+
+impl TryFrom<Submission> for crate::Submission {
+    type Error = Error;
+
+    fn try_from(sub: Submission) -> Result<Self, Self::Error> {
+        use chrono::TimeZone;
+
+        let rating = Rating::try_from(sub.rating)
+            .map_err(|_| Error::new(format!("unknown rating: {}", sub.rating), false))?;
+
+        let content = sub.content.ok_or_else(|| Error::parse("content"))?;
+
+        let posted_at = chrono::Utc
+            .timestamp_opt(sub.posted_at, 0)
+            .single()
+            .ok_or_else(|| Error::new("invalid posted_at", false))?;
+
+        Ok(crate::Submission {
+            id: crate::SubmissionId(sub.id),
+            title: sub.title,
+            artist: sub.artist,
+            content: content.into(),
+            ext: sub.ext,
+            hash: sub.hash,
+            hash_num: sub.hash_num,
+            extra_hashes: Vec::new(),
+            hash_skipped: None,
+            hash_source: None,
+            image_info: None,
+            artist_info: None,
+            avatar_url: sub.avatar_url,
+            blurhash: None,
+            text_hash: None,
+            filename: sub.filename,
+            rating: rating.into(),
+            posted_at,
+            tags: sub.tags,
+            original_tags: None,
+            description: sub.description,
+            file: sub.file,
+            file_size: sub.file_size.map(|size| size as usize),
+            file_sha256: sub.file_sha256,
+            file_md5: sub.file_md5,
+            file_blake3: sub.file_blake3,
+            file_mime: sub.file_mime,
+            ai_generated: sub.ai_generated,
+            stats: crate::SubmissionStats {
+                views: sub.views,
+                favorites: sub.favorites,
+                comments: sub.comments,
+            },
+            info: Default::default(),
+            preview_url: sub.preview_url,
+            fullview_url: sub.fullview_url,
+            file_headers: None,
+            file_metadata: None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::test_submission;
+    use prost::Message;
+
+    #[test]
+    fn test_round_trip() {
+        let sub = crate::Submission {
+            rating: crate::Rating::Mature,
+            hash_num: Some(-2),
+            file_size: Some(1024),
+            file_sha256: Some(vec![0xab; 32]),
+            ..test_submission("Sketch", 1617235200, &["fox", "sketch"])
+        };
+
+        let encoded = Submission::from(&sub).encode_to_vec();
+        let decoded = Submission::decode(encoded.as_slice()).unwrap();
+        assert_eq!(decoded.rating, Rating::Mature as i32);
+
+        let parsed = crate::Submission::try_from(decoded).unwrap();
+        assert_eq!(parsed.id, sub.id);
+        assert_eq!(parsed.content, sub.content);
+        assert_eq!(parsed.rating, sub.rating);
+        assert_eq!(parsed.posted_at, sub.posted_at);
+        assert_eq!(parsed.tags, sub.tags);
+        assert_eq!(parsed.hash_num, sub.hash_num);
+        assert_eq!(parsed.file_size, sub.file_size);
+        assert_eq!(parsed.file_sha256, sub.file_sha256);
+
+        let missing_content = Submission {
+            content: None,
+            ..Submission::from(&sub)
+        };
+        assert!(crate::Submission::try_from(missing_content).is_err());
+    }
+}
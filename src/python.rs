@@ -0,0 +1,136 @@
+//! A Python extension module, `furaffinity`, wrapping the client, parser,
+//! and hashing.
+//!
+//! Build it with [maturin](https://www.maturin.rs) using `pyproject.toml`,
+//! which enables the `python-extension` feature. Requests are made on a
+//! runtime owned by the client and block the calling thread, releasing the
+//! GIL while waiting. Submissions are returned as dictionaries in the same
+//! format as the `serde` feature.
+
+use crate::hash::HashDistance;
+use crate::{Submission, SubmissionId};
+use pyo3::exceptions::PyRuntimeError;
+use pyo3::prelude::*;
+
+fn to_py_err(err: crate::Error) -> PyErr {
+    PyRuntimeError::new_err(err.to_string())
+}
+
+fn submission_to_py(py: Python<'_>, sub: &Submission) -> PyResult<PyObject> {
+    let json = serde_json::to_string(sub).expect("submissions always serialize");
+
+    Ok(py.import("json")?.call_method1("loads", (json,))?.unbind())
+}
+
+/// A blocking FurAffinity client.
+#[pyclass(name = "FurAffinity")]
+struct PyFurAffinity {
+    fa: crate::FurAffinity,
+    runtime: tokio::runtime::Runtime,
+}
+
+#[pymethods]
+impl PyFurAffinity {
+    #[new]
+    fn new(cookie_a: String, cookie_b: String, user_agent: String) -> PyResult<Self> {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()?;
+
+        Ok(Self {
+            fa: crate::FurAffinity::new(cookie_a, cookie_b, user_agent, None),
+            runtime,
+        })
+    }
+
+    /// ID of the most recent submission.
+    fn latest_id(&self, py: Python<'_>) -> PyResult<u64> {
+        py.allow_threads(|| self.runtime.block_on(self.fa.latest_id()))
+            .map(|id| id.0)
+            .map_err(to_py_err)
+    }
+
+    /// Load a submission, returning None if it does not exist. If `hash` is
+    /// set, the file is downloaded and hashed.
+    #[pyo3(signature = (id, hash = false))]
+    fn get_submission(&self, py: Python<'_>, id: u64, hash: bool) -> PyResult<Option<PyObject>> {
+        let sub = py
+            .allow_threads(|| {
+                self.runtime.block_on(async {
+                    match self.fa.get_submission(SubmissionId(id)).await? {
+                        Some(sub) if hash => self.fa.calc_image_hash(sub).await.map(Some),
+                        sub => Ok(sub),
+                    }
+                })
+            })
+            .map_err(to_py_err)?;
+
+        sub.map(|sub| submission_to_py(py, &sub)).transpose()
+    }
+}
+
+/// Parse the HTML of a submission page, returning None if it does not
+/// exist.
+#[pyfunction]
+fn parse_submission(py: Python<'_>, id: u64, page: &str) -> PyResult<Option<PyObject>> {
+    let sub = crate::parse_submission(SubmissionId(id), page).map_err(to_py_err)?;
+
+    sub.map(|sub| submission_to_py(py, &sub)).transpose()
+}
+
+/// Perceptual hash of an image.
+#[pyfunction]
+fn hash_image(py: Python<'_>, data: &[u8]) -> PyResult<i64> {
+    let hash = py
+        .allow_threads(|| crate::hash::hash_image(data))
+        .map_err(to_py_err)?;
+
+    hash.to_i64()
+        .ok_or_else(|| PyRuntimeError::new_err("hash was not 8 bytes"))
+}
+
+/// Number of bits that differ between two perceptual hashes.
+#[pyfunction]
+fn hash_distance(a: i64, b: i64) -> u32 {
+    a.distance(&b).unwrap_or(u32::MAX)
+}
+
+#[pymodule]
+fn furaffinity(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<PyFurAffinity>()?;
+    m.add_function(wrap_pyfunction!(parse_submission, m)?)?;
+    m.add_function(wrap_pyfunction!(hash_image, m)?)?;
+    m.add_function(wrap_pyfunction!(hash_distance, m)?)?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_module() {
+        pyo3::append_to_inittab!(furaffinity);
+        pyo3::prepare_freethreaded_python();
+
+        Python::with_gil(|py| {
+            let fa = py.import("furaffinity").unwrap();
+
+            let distance: u32 = fa
+                .call_method1("hash_distance", (0b1011, 0b0001))
+                .unwrap()
+                .extract()
+                .unwrap();
+            assert_eq!(distance, 2);
+
+            let page = "<html><head><title>System Error</title></head></html>";
+            let sub = fa.call_method1("parse_submission", (1, page)).unwrap();
+            assert!(sub.is_none());
+
+            assert!(fa
+                .call_method1("hash_image", (b"not an image".as_slice(),))
+                .is_err());
+        });
+    }
+}
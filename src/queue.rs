@@ -0,0 +1,229 @@
+//! Sharing one client between urgent and background requests.
+
+use crate::gallery::SubmissionPreview;
+use crate::journal::JournalPreview;
+use crate::rate_limit::RateLimiter;
+use crate::{Error, FurAffinity, Submission, SubmissionId};
+use std::cmp::{Ordering, Reverse};
+use std::collections::BinaryHeap;
+use std::sync::{Arc, Mutex};
+use tokio::sync::{oneshot, Notify};
+
+/// How soon a job should be run. Higher priorities always run first.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub enum Priority {
+    /// Work that can wait, such as backfilling.
+    Background,
+    Normal,
+    /// Work someone is waiting on, such as a bot command.
+    Interactive,
+}
+
+/// Something to load from FurAffinity.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Job {
+    /// Load a submission page.
+    Submission(SubmissionId),
+    /// Download and hash a submission's file.
+    File(Box<Submission>),
+    /// Load a page of a user's gallery.
+    Gallery { username: String, page: u32 },
+    /// Load a page of a user's journals.
+    Journals { username: String, page: u32 },
+}
+
+/// The result of a [`Job`], with a variant for each kind of job.
+#[derive(Debug)]
+pub enum JobOutput {
+    Submission(Option<Box<Submission>>),
+    File(Box<Submission>),
+    Gallery(Vec<SubmissionPreview>),
+    Journals(Vec<JournalPreview>),
+}
+
+impl Job {
+    async fn run(self, fa: &FurAffinity) -> Result<JobOutput, Error> {
+        let output = match self {
+            Job::Submission(id) => {
+                JobOutput::Submission(fa.get_submission(id).await?.map(Box::new))
+            }
+            Job::File(sub) => JobOutput::File(Box::new(fa.calc_image_hash(*sub).await?)),
+            Job::Gallery { username, page } => {
+                JobOutput::Gallery(fa.get_gallery(&username, page).await?)
+            }
+            Job::Journals { username, page } => {
+                JobOutput::Journals(fa.get_user_journals(&username, page).await?)
+            }
+        };
+
+        Ok(output)
+    }
+}
+
+struct Queued {
+    priority: Priority,
+    seq: u64,
+    job: Job,
+    sender: oneshot::Sender<Result<JobOutput, Error>>,
+}
+
+impl Queued {
+    /// Higher priorities first, then the order jobs were added.
+    fn key(&self) -> (Priority, Reverse<u64>) {
+        (self.priority, Reverse(self.seq))
+    }
+}
+
+impl PartialEq for Queued {
+    fn eq(&self, other: &Self) -> bool {
+        self.key() == other.key()
+    }
+}
+
+impl Eq for Queued {}
+
+impl PartialOrd for Queued {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Queued {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.key().cmp(&other.key())
+    }
+}
+
+#[derive(Default)]
+struct Jobs {
+    heap: BinaryHeap<Queued>,
+    next_seq: u64,
+}
+
+/// A queue of jobs run in order of priority, within a rate limit.
+///
+/// Clones share the same queue. Jobs are only run while something is
+/// awaiting [`FetchQueue::run`].
+#[derive(Clone)]
+pub struct FetchQueue {
+    limiter: RateLimiter,
+    jobs: Arc<Mutex<Jobs>>,
+    notify: Arc<Notify>,
+}
+
+impl FetchQueue {
+    /// Create a new queue, running jobs no faster than the limiter allows.
+    /// The limiter may be shared with other work.
+    pub fn new(limiter: RateLimiter) -> Self {
+        Self {
+            limiter,
+            jobs: Default::default(),
+            notify: Arc::new(Notify::new()),
+        }
+    }
+
+    /// Add a job to the queue and wait for it to run.
+    ///
+    /// If this future is dropped before the job starts, the job is skipped.
+    pub async fn fetch(&self, priority: Priority, job: Job) -> Result<JobOutput, Error> {
+        let (sender, receiver) = oneshot::channel();
+
+        {
+            let mut jobs = self.jobs.lock().unwrap();
+            let seq = jobs.next_seq;
+            jobs.next_seq += 1;
+
+            jobs.heap.push(Queued {
+                priority,
+                seq,
+                job,
+                sender,
+            });
+        }
+        self.notify.notify_one();
+
+        receiver
+            .await
+            .unwrap_or_else(|_| Err(Error::new("queue stopped before job ran", true)))
+    }
+
+    /// Number of jobs waiting to run.
+    pub fn len(&self) -> usize {
+        self.jobs.lock().unwrap().heap.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Run jobs as they are added, forever. Running this more than once at
+    /// a time runs jobs concurrently, still within the rate limit.
+    pub async fn run(&self, fa: &FurAffinity) {
+        loop {
+            let queued = self.next().await;
+
+            if queued.sender.is_closed() {
+                continue;
+            }
+
+            self.limiter.wait().await;
+            let output = queued.job.run(fa).await;
+            let _ = queued.sender.send(output);
+        }
+    }
+
+    async fn next(&self) -> Queued {
+        loop {
+            if let Some(queued) = self.jobs.lock().unwrap().heap.pop() {
+                return queued;
+            }
+
+            self.notify.notified().await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::FutureExt;
+
+    #[tokio::test]
+    async fn test_priority_order() {
+        let queue = FetchQueue::new(RateLimiter::per_minute(60));
+
+        let jobs = vec![
+            (Priority::Background, 1),
+            (Priority::Normal, 2),
+            (Priority::Interactive, 3),
+            (Priority::Background, 4),
+            (Priority::Interactive, 5),
+        ];
+
+        // Start each fetch far enough to queue its job.
+        let mut pending: Vec<_> = jobs
+            .into_iter()
+            .map(|(priority, id)| {
+                queue
+                    .fetch(priority, Job::Submission(SubmissionId(id)))
+                    .boxed()
+            })
+            .collect();
+        for fetch in &mut pending {
+            assert!(fetch.as_mut().now_or_never().is_none());
+        }
+        assert_eq!(queue.len(), 5);
+
+        let mut order = Vec::new();
+        while !queue.is_empty() {
+            match queue.next().await.job {
+                Job::Submission(id) => order.push(id.0),
+                _ => unreachable!(),
+            }
+        }
+
+        assert_eq!(order, vec![3, 5, 2, 1, 4]);
+    }
+}
@@ -0,0 +1,96 @@
+//! Keeping requests to FurAffinity at a polite rate.
+
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Mutex;
+use tokio::time::Instant;
+
+/// Spaces out requests so that no more than one is started per interval.
+///
+/// Clones share the same budget, so a single limiter can be given to
+/// everything making requests.
+#[derive(Clone, Debug)]
+pub struct RateLimiter {
+    interval: Duration,
+    jitter: Duration,
+    next: Arc<Mutex<Option<Instant>>>,
+}
+
+impl RateLimiter {
+    /// Create a limiter allowing one request per interval.
+    pub fn new(interval: Duration) -> Self {
+        Self {
+            interval,
+            jitter: Duration::ZERO,
+            next: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// Create a limiter allowing a number of requests per minute.
+    pub fn per_minute(requests: u32) -> Self {
+        Self::new(Duration::from_secs(60) / requests.max(1))
+    }
+
+    /// Wait up to this much longer than the interval, chosen at random for
+    /// each request, so requests aren't made at exact intervals.
+    pub fn with_jitter(mut self, jitter: Duration) -> Self {
+        self.jitter = jitter;
+        self
+    }
+
+    /// Wait until another request can be made.
+    pub async fn wait(&self) {
+        let slot = {
+            let mut next = self.next.lock().await;
+
+            let now = Instant::now();
+            let slot = next.map_or(now, |next| next.max(now) + self.random_jitter());
+            *next = Some(slot + self.interval);
+
+            slot
+        };
+
+        tokio::time::sleep_until(slot).await;
+    }
+
+    fn random_jitter(&self) -> Duration {
+        if self.jitter.is_zero() {
+            return Duration::ZERO;
+        }
+
+        self.jitter.mul_f64(fastrand::f64())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test(start_paused = true)]
+    async fn test_rate_limiter() {
+        let limiter = RateLimiter::per_minute(60);
+        let shared = limiter.clone();
+
+        let start = Instant::now();
+
+        limiter.wait().await;
+        assert_eq!(start.elapsed(), Duration::from_secs(0));
+
+        shared.wait().await;
+        limiter.wait().await;
+        assert_eq!(start.elapsed(), Duration::from_secs(2));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_rate_limiter_jitter() {
+        let limiter = RateLimiter::new(Duration::from_secs(1)).with_jitter(Duration::from_secs(1));
+
+        let start = Instant::now();
+        for _ in 0..3 {
+            limiter.wait().await;
+        }
+
+        let elapsed = start.elapsed();
+        assert!(elapsed >= Duration::from_secs(2) && elapsed <= Duration::from_secs(4));
+    }
+}
@@ -0,0 +1,91 @@
+//! Submissions flattened into database rows.
+
+use crate::hash::ImageHash;
+use crate::{Rating, Submission};
+
+/// A submission flattened into columns, matching the `submission` table
+/// in [`crate::schema`] when using Diesel, or read with `query_as` when
+/// using sqlx.
+///
+/// Tags are stored as a text array and the file size as a bigint.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "sqlx", derive(sqlx::FromRow))]
+#[cfg_attr(
+    feature = "diesel",
+    derive(diesel::Queryable, diesel::Selectable, diesel::Insertable),
+    diesel(table_name = crate::schema::submission, check_for_backend(diesel::pg::Pg))
+)]
+pub struct SubmissionRecord {
+    pub id: i64,
+    pub artist: String,
+    pub title: String,
+    pub url: String,
+    pub filename: String,
+    pub ext: String,
+    pub rating: Rating,
+    pub posted_at: chrono::DateTime<chrono::Utc>,
+    pub tags: Vec<String>,
+    pub description: String,
+    pub hash: Option<ImageHash>,
+    pub hash_num: Option<i64>,
+    pub file_size: Option<i64>,
+    pub file_sha256: Option<Vec<u8>>,
+    pub file_md5: Option<Vec<u8>>,
+    pub file_blake3: Option<Vec<u8>>,
+    pub file_mime: Option<String>,
+}
+
+impl From<&Submission> for SubmissionRecord {
+    fn from(sub: &Submission) -> Self {
+        use std::convert::TryInto;
+
+        Self {
+            id: sub.id.to_i64(),
+            artist: sub.artist.clone(),
+            title: sub.title.clone(),
+            url: sub.content.url().to_string(),
+            filename: sub.filename.clone(),
+            ext: sub.ext.clone(),
+            rating: sub.rating,
+            posted_at: sub.posted_at,
+            tags: sub.tags.clone(),
+            description: sub.description.clone(),
+            hash: sub.hash.as_deref().and_then(ImageHash::from_base64),
+            hash_num: sub.hash_num,
+            file_size: sub.file_size.and_then(|size| size.try_into().ok()),
+            file_sha256: sub.file_sha256.clone(),
+            file_md5: sub.file_md5.clone(),
+            file_blake3: sub.file_blake3.clone(),
+            file_mime: sub.file_mime.clone(),
+        }
+    }
+}
+
+impl From<Submission> for SubmissionRecord {
+    fn from(sub: Submission) -> Self {
+        Self::from(&sub)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::test_submission;
+
+    #[test]
+    fn test_submission_record() {
+        let hash = ImageHash::from_i64(-2);
+        let sub = Submission {
+            hash: Some(hash.to_base64()),
+            hash_num: Some(-2),
+            file_size: Some(1024),
+            ..test_submission("Sketch", 1617235200, &["fox"])
+        };
+
+        let record = SubmissionRecord::from(&sub);
+        assert_eq!(record.hash, Some(hash));
+        assert_eq!(record.file_size, Some(1024));
+        assert_eq!(record.url, sub.content.url());
+        assert_eq!(record.tags, vec!["fox"]);
+    }
+}
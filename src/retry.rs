@@ -0,0 +1,213 @@
+//! Retrying requests that failed with retryable errors.
+
+use crate::Error;
+use std::future::Future;
+use std::time::Duration;
+
+/// How many times to try a request and how long to wait between attempts.
+///
+/// Only retryable errors are retried. The delay is multiplied after each
+/// attempt, doubling by default.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct RetryPolicy {
+    /// Total attempts, including the first.
+    pub max_attempts: u32,
+    /// How long to wait before the first retry.
+    pub delay: Duration,
+    /// How much longer to wait before each retry than the one before it.
+    pub multiplier: u32,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            delay: Duration::from_secs(1),
+            multiplier: 2,
+        }
+    }
+}
+
+impl RetryPolicy {
+    pub fn new(max_attempts: u32, delay: Duration) -> Self {
+        Self {
+            max_attempts,
+            delay,
+            multiplier: 2,
+        }
+    }
+
+    /// Multiply the delay by this after each retry. A multiplier of 1 waits
+    /// the same amount of time before every retry.
+    pub fn with_multiplier(mut self, multiplier: u32) -> Self {
+        self.multiplier = multiplier.max(1);
+        self
+    }
+
+    /// How long to wait before retrying, after some number of attempts.
+    pub fn delay_for(&self, attempts: u32) -> Duration {
+        scaled_backoff(self.delay, self.multiplier, attempts)
+    }
+
+    /// Run an operation until it succeeds, fails with an error that should
+    /// not be retried, or runs out of attempts.
+    pub async fn run<T, F, Fut>(&self, mut operation: F) -> Result<T, Error>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T, Error>>,
+    {
+        let mut attempts = 0;
+
+        loop {
+            attempts += 1;
+
+            match operation().await {
+                Err(err) if err.is_retryable() && attempts < self.max_attempts => {
+                    tokio::time::sleep(self.delay_for(attempts)).await;
+                }
+                result => return result,
+            }
+        }
+    }
+}
+
+/// Limits for a single call, overriding the client's defaults.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct CallOptions {
+    deadline: Option<Duration>,
+    retry_policy: Option<RetryPolicy>,
+}
+
+impl CallOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Fail with a retryable error if the call, including any retries,
+    /// takes longer than this. Defaults to no deadline.
+    pub fn with_deadline(mut self, deadline: Duration) -> Self {
+        self.deadline = Some(deadline);
+        self
+    }
+
+    /// Retry policy to use instead of the client's.
+    pub fn with_retry_policy(mut self, policy: RetryPolicy) -> Self {
+        self.retry_policy = Some(policy);
+        self
+    }
+
+    /// Retry at most this many times, with the default delay.
+    pub fn with_max_retries(self, retries: u32) -> Self {
+        self.with_retry_policy(RetryPolicy {
+            max_attempts: retries.saturating_add(1),
+            ..Default::default()
+        })
+    }
+
+    /// Run an operation with these limits, using `default_policy` if no
+    /// retry policy was set.
+    pub(crate) async fn run<T, F, Fut>(
+        &self,
+        default_policy: Option<&RetryPolicy>,
+        mut operation: F,
+    ) -> Result<T, Error>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T, Error>>,
+    {
+        let attempts = async {
+            match self.retry_policy.as_ref().or(default_policy) {
+                Some(policy) => policy.run(operation).await,
+                None => operation().await,
+            }
+        };
+
+        match self.deadline {
+            Some(deadline) => tokio::time::timeout(deadline, attempts)
+                .await
+                .unwrap_or_else(|_| Err(Error::new("deadline exceeded", true))),
+            None => attempts.await,
+        }
+    }
+}
+
+/// How long to wait before retrying, after some number of attempts.
+pub(crate) fn backoff(delay: Duration, attempts: u32) -> Duration {
+    scaled_backoff(delay, 2, attempts)
+}
+
+fn scaled_backoff(delay: Duration, multiplier: u32, attempts: u32) -> Duration {
+    // Stop growing eventually so a large number of attempts can't overflow.
+    let exponent = attempts.saturating_sub(1).min(16);
+
+    delay.saturating_mul(multiplier.saturating_pow(exponent))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    #[tokio::test(start_paused = true)]
+    async fn test_run() {
+        let policy = RetryPolicy::new(3, Duration::from_secs(1));
+        let attempts = AtomicU32::new(0);
+
+        let result: Result<(), Error> = policy
+            .run(|| async {
+                attempts.fetch_add(1, Ordering::SeqCst);
+                Err(Error::new("timed out", true))
+            })
+            .await;
+        assert!(result.is_err());
+        assert_eq!(attempts.swap(0, Ordering::SeqCst), 3);
+
+        let result: Result<(), Error> = policy
+            .run(|| async {
+                attempts.fetch_add(1, Ordering::SeqCst);
+                Err(Error::new("not found", false))
+            })
+            .await;
+        assert!(result.is_err());
+        assert_eq!(attempts.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn test_delay_for() {
+        let policy = RetryPolicy::new(5, Duration::from_secs(1)).with_multiplier(3);
+        assert_eq!(policy.delay_for(1), Duration::from_secs(1));
+        assert_eq!(policy.delay_for(2), Duration::from_secs(3));
+        assert_eq!(policy.delay_for(3), Duration::from_secs(9));
+
+        let policy = policy.with_multiplier(1);
+        assert_eq!(policy.delay_for(4), Duration::from_secs(1));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_call_options() {
+        let default_policy = RetryPolicy::new(5, Duration::from_secs(1));
+        let attempts = AtomicU32::new(0);
+        let failing = || async {
+            attempts.fetch_add(1, Ordering::SeqCst);
+            Err::<(), _>(Error::new("timed out", true))
+        };
+
+        let options = CallOptions::new().with_max_retries(1);
+        assert!(options.run(Some(&default_policy), failing).await.is_err());
+        assert_eq!(attempts.swap(0, Ordering::SeqCst), 2);
+
+        let start = tokio::time::Instant::now();
+        let options = CallOptions::new().with_deadline(Duration::from_secs(5));
+        let err = options
+            .run(Some(&default_policy), failing)
+            .await
+            .unwrap_err();
+        assert_eq!(err.to_string(), "deadline exceeded");
+        assert_eq!(start.elapsed(), Duration::from_secs(5));
+        assert_eq!(attempts.load(Ordering::SeqCst), 3);
+    }
+}
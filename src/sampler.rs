@@ -0,0 +1,130 @@
+//! Recording how many users are online over time.
+
+use crate::{Error, FurAffinity, OnlineCounts, SubmissionId};
+use futures::Stream;
+use std::collections::VecDeque;
+use std::time::Duration;
+
+/// Online counts at a point in time.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct OnlineSample {
+    pub sampled_at: chrono::DateTime<chrono::Utc>,
+    pub counts: OnlineCounts,
+    /// The latest submission ID when the sample was taken.
+    pub latest_id: SubmissionId,
+    /// Aggregates over recent samples, including this one, if enabled.
+    pub rolling: Option<RollingCounts>,
+}
+
+/// Aggregates of the total number of users online over recent samples.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct RollingCounts {
+    pub samples: usize,
+    pub min_total: usize,
+    pub max_total: usize,
+    pub mean_total: f64,
+}
+
+impl RollingCounts {
+    fn from_totals(totals: &VecDeque<usize>) -> Option<Self> {
+        Some(Self {
+            samples: totals.len(),
+            min_total: *totals.iter().min()?,
+            max_total: *totals.iter().max()?,
+            mean_total: totals.iter().sum::<usize>() as f64 / totals.len() as f64,
+        })
+    }
+}
+
+/// Takes a sample of the online counts on an interval.
+pub struct OnlineSampler<'a> {
+    fa: &'a FurAffinity,
+    interval: Duration,
+    window: usize,
+}
+
+impl<'a> OnlineSampler<'a> {
+    /// Create a new sampler.
+    pub fn new(fa: &'a FurAffinity) -> Self {
+        Self {
+            fa,
+            interval: Duration::from_secs(300),
+            window: 0,
+        }
+    }
+
+    /// How long to wait between samples. Defaults to five minutes.
+    pub fn with_interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// Include aggregates over this many of the most recent samples.
+    pub fn with_window(mut self, window: usize) -> Self {
+        self.window = window;
+        self
+    }
+
+    /// Stream of samples. Errors are from loading the counts, and sampling
+    /// continues after them.
+    pub fn samples(self) -> impl Stream<Item = Result<OnlineSample, Error>> + 'a {
+        let state = (self, None, VecDeque::new());
+
+        futures::stream::unfold(state, |(sampler, next_sample, mut totals)| async move {
+            if let Some(next_sample) = next_sample {
+                tokio::time::sleep_until(next_sample).await;
+            }
+            let next_sample = Some(tokio::time::Instant::now() + sampler.interval);
+
+            let sample = match sampler.fa.latest_id_and_online().await {
+                Ok((latest_id, counts)) => {
+                    let rolling = if sampler.window > 0 {
+                        totals.push_back(counts.total);
+                        if totals.len() > sampler.window {
+                            totals.pop_front();
+                        }
+
+                        RollingCounts::from_totals(&totals)
+                    } else {
+                        None
+                    };
+
+                    Ok(OnlineSample {
+                        sampled_at: chrono::Utc::now(),
+                        counts,
+                        latest_id,
+                        rolling,
+                    })
+                }
+                Err(err) => Err(err),
+            };
+
+            Some((sample, (sampler, next_sample, totals)))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rolling_counts() {
+        let totals: VecDeque<usize> = vec![10, 30, 20].into();
+
+        assert_eq!(
+            RollingCounts::from_totals(&totals),
+            Some(RollingCounts {
+                samples: 3,
+                min_total: 10,
+                max_total: 30,
+                mean_total: 20.0,
+            })
+        );
+        assert_eq!(RollingCounts::from_totals(&VecDeque::new()), None);
+    }
+}
@@ -0,0 +1,95 @@
+//! Diesel schema and type mappings for storing submissions.
+//!
+//! [`Rating`] is stored as text, one of `g`, `m`, or `a`, and
+//! [`ImageHash`] as binary. Submissions can be read and written with
+//! [`SubmissionRecord`](crate::record::SubmissionRecord).
+
+use crate::hash::ImageHash;
+use crate::Rating;
+use diesel::backend::Backend;
+use diesel::deserialize::{self, FromSql};
+use diesel::serialize::{self, Output, ToSql};
+use diesel::sql_types::{Binary, Text};
+
+diesel::table! {
+    submission (id) {
+        id -> Int8,
+        artist -> Text,
+        title -> Text,
+        url -> Text,
+        filename -> Text,
+        ext -> Text,
+        rating -> Text,
+        posted_at -> Timestamptz,
+        tags -> Array<Text>,
+        description -> Text,
+        hash -> Nullable<Bytea>,
+        hash_num -> Nullable<Int8>,
+        file_size -> Nullable<Int8>,
+        file_sha256 -> Nullable<Bytea>,
+        file_md5 -> Nullable<Bytea>,
+        file_blake3 -> Nullable<Bytea>,
+        file_mime -> Nullable<Text>,
+    }
+}
+
+impl<DB> ToSql<Text, DB> for Rating
+where
+    DB: Backend,
+    str: ToSql<Text, DB>,
+{
+    fn to_sql<'b>(&'b self, out: &mut Output<'b, '_, DB>) -> serialize::Result {
+        self.code().to_sql(out)
+    }
+}
+
+impl<DB> FromSql<Text, DB> for Rating
+where
+    DB: Backend,
+    String: FromSql<Text, DB>,
+{
+    fn from_sql(bytes: DB::RawValue<'_>) -> deserialize::Result<Self> {
+        let code = String::from_sql(bytes)?;
+
+        Rating::from_code(&code).ok_or_else(|| format!("unknown rating: {}", code).into())
+    }
+}
+
+impl<DB> ToSql<Binary, DB> for ImageHash
+where
+    DB: Backend,
+    [u8]: ToSql<Binary, DB>,
+{
+    fn to_sql<'b>(&'b self, out: &mut Output<'b, '_, DB>) -> serialize::Result {
+        self.as_bytes().to_sql(out)
+    }
+}
+
+impl<DB> FromSql<Binary, DB> for ImageHash
+where
+    DB: Backend,
+    Vec<u8>: FromSql<Binary, DB>,
+{
+    fn from_sql(bytes: DB::RawValue<'_>) -> deserialize::Result<Self> {
+        Ok(ImageHash::from_bytes(&Vec::<u8>::from_sql(bytes)?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::record::SubmissionRecord;
+    use crate::tests::test_submission;
+
+    #[test]
+    fn test_insert_submission() {
+        let record = SubmissionRecord::from(test_submission("Sketch", 1617235200, &["fox"]));
+
+        let query = diesel::insert_into(submission::table).values(&record);
+        let sql = diesel::debug_query::<diesel::pg::Pg, _>(&query).to_string();
+
+        assert!(sql.starts_with(r#"INSERT INTO "submission" ("id", "artist""#));
+        assert!(sql.contains(r#"binds: [41234567, "Example", "Sketch""#));
+        assert!(sql.contains(r#"General"#));
+    }
+}
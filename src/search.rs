@@ -0,0 +1,352 @@
+//! Building queries for FurAffinity's search.
+
+use crate::{Error, Rating};
+use lazy_static::lazy_static;
+use scraper::Selector;
+
+lazy_static! {
+    static ref CATEGORY: Selector = Selector::parse(r#"select[name="cat"] option"#).unwrap();
+    static ref TYPE: Selector = Selector::parse(r#"select[name="atype"] option"#).unwrap();
+    static ref SPECIES: Selector = Selector::parse(r#"select[name="species"] option"#).unwrap();
+    static ref GENDER: Selector = Selector::parse(r#"select[name="gender"] option"#).unwrap();
+    static ref QUERY_STATS: Selector = Selector::parse("#query-stats").unwrap();
+    static ref TOTAL: regex::Regex = regex::Regex::new(r"of\s+([\d,]+)").unwrap();
+}
+
+/// How search results are sorted.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub enum SearchOrder {
+    Relevancy,
+    Date,
+    Popularity,
+}
+
+impl SearchOrder {
+    fn code(self) -> &'static str {
+        match self {
+            SearchOrder::Relevancy => "relevancy",
+            SearchOrder::Date => "date",
+            SearchOrder::Popularity => "popularity",
+        }
+    }
+}
+
+/// A type of submission that search results can be limited to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub enum SubmissionType {
+    Art,
+    Music,
+    Flash,
+    Story,
+    Photo,
+    Poetry,
+}
+
+impl SubmissionType {
+    fn code(self) -> &'static str {
+        match self {
+            SubmissionType::Art => "art",
+            SubmissionType::Music => "music",
+            SubmissionType::Flash => "flash",
+            SubmissionType::Story => "story",
+            SubmissionType::Photo => "photo",
+            SubmissionType::Poetry => "poetry",
+        }
+    }
+}
+
+/// A search, with its filters and which page of results to load.
+///
+/// Ratings and types are not filtered unless some are set.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct SearchOptions {
+    query: String,
+    page: u32,
+    order: SearchOrder,
+    ascending: bool,
+    ratings: Vec<Rating>,
+    types: Vec<SubmissionType>,
+}
+
+impl SearchOptions {
+    /// Search for a query, using FurAffinity's search syntax.
+    pub fn new<T: Into<String>>(query: T) -> Self {
+        Self {
+            query: query.into(),
+            page: 1,
+            order: SearchOrder::Relevancy,
+            ascending: false,
+            ratings: Vec::new(),
+            types: Vec::new(),
+        }
+    }
+
+    /// Load another page of results, starting at 1.
+    pub fn with_page(mut self, page: u32) -> Self {
+        self.page = page.max(1);
+        self
+    }
+
+    /// Sort results, descending unless `ascending` is set.
+    pub fn with_order(mut self, order: SearchOrder, ascending: bool) -> Self {
+        self.order = order;
+        self.ascending = ascending;
+        self
+    }
+
+    /// Only include submissions with these ratings.
+    pub fn with_ratings(mut self, ratings: &[Rating]) -> Self {
+        self.ratings = ratings.to_vec();
+        self
+    }
+
+    /// Only include these types of submissions.
+    pub fn with_types(mut self, types: &[SubmissionType]) -> Self {
+        self.types = types.to_vec();
+        self
+    }
+
+    pub fn query(&self) -> &str {
+        &self.query
+    }
+
+    pub fn page(&self) -> u32 {
+        self.page
+    }
+
+    /// The same search at the next page.
+    pub fn next_page(&self) -> Self {
+        self.clone().with_page(self.page + 1)
+    }
+
+    /// Query string parameters for the search page.
+    pub fn params(&self) -> Vec<(String, String)> {
+        let mut params = vec![
+            ("q".to_string(), self.query.clone()),
+            ("page".to_string(), self.page.to_string()),
+            ("order-by".to_string(), self.order.code().to_string()),
+            (
+                "order-direction".to_string(),
+                if self.ascending { "asc" } else { "desc" }.to_string(),
+            ),
+        ];
+
+        let ratings: &[Rating] = if self.ratings.is_empty() {
+            &[Rating::General, Rating::Mature, Rating::Adult]
+        } else {
+            &self.ratings
+        };
+        for rating in ratings {
+            let name = match rating {
+                Rating::General => "rating-general",
+                Rating::Mature => "rating-mature",
+                Rating::Adult => "rating-adult",
+            };
+            params.push((name.to_string(), "1".to_string()));
+        }
+
+        for kind in &self.types {
+            params.push((format!("type-{}", kind.code()), "1".to_string()));
+        }
+
+        params
+    }
+}
+
+/// A page of search results.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct SearchResults {
+    pub submissions: Vec<crate::gallery::SubmissionPreview>,
+    /// Number of results across every page, if shown.
+    pub total: Option<u64>,
+    /// If the page was loaded as a guest, so results may be missing.
+    pub guest: bool,
+}
+
+/// Parse a page of search results, at `/search/`.
+pub fn parse_search_results(page: &str) -> Result<SearchResults, Error> {
+    let listing = crate::gallery::parse_listing(page)?;
+
+    let document = scraper::Html::parse_document(page);
+    let total = document
+        .select(&QUERY_STATS)
+        .next()
+        .map(crate::join_text_nodes)
+        .and_then(|stats| {
+            TOTAL
+                .captures(&stats)
+                .map(|captures| captures[1].replace(',', ""))
+        })
+        .map(|total| total.parse())
+        .transpose()?;
+
+    Ok(SearchResults {
+        submissions: listing.submissions,
+        total,
+        guest: listing.guest,
+    })
+}
+
+/// An option for filtering submissions, as sent to FurAffinity and as shown.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct FilterOption {
+    pub value: String,
+    pub label: String,
+}
+
+/// The options for filtering submissions by category, type, species, and
+/// gender, from the browse page's sidebar.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Taxonomy {
+    pub categories: Vec<FilterOption>,
+    pub types: Vec<FilterOption>,
+    pub species: Vec<FilterOption>,
+    pub genders: Vec<FilterOption>,
+}
+
+/// Parse the filter options from the browse page, at `/browse/`.
+///
+/// Options are in the order shown, and group headings in the lists are
+/// skipped.
+pub fn parse_taxonomy(page: &str) -> Result<Taxonomy, Error> {
+    let document = scraper::Html::parse_document(page);
+
+    let options = |selector: &Selector| {
+        document
+            .select(selector)
+            .filter_map(|option| {
+                Some(FilterOption {
+                    value: option.value().attr("value")?.to_string(),
+                    label: crate::join_text_nodes(option),
+                })
+            })
+            .collect::<Vec<_>>()
+    };
+
+    let taxonomy = Taxonomy {
+        categories: options(&CATEGORY),
+        types: options(&TYPE),
+        species: options(&SPECIES),
+        genders: options(&GENDER),
+    };
+
+    if taxonomy.categories.is_empty() {
+        return Err(Error::parse("categories"));
+    }
+
+    Ok(taxonomy)
+}
+
+/// Build a query that only matches submissions by `username` that also
+/// match `keywords`, using the `@lower` field for the artist's username
+/// slug.
+pub fn user_query(username: &str, keywords: &str) -> String {
+    let username = crate::username::slug(username);
+
+    let keywords = keywords.trim();
+    if keywords.is_empty() {
+        format!("@lower {}", username)
+    } else {
+        format!("@lower {} {}", username, keywords)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_user_query() {
+        assert_eq!(
+            user_query("Dead_Russian_Soul", " bilberry fox "),
+            "@lower deadrussiansoul bilberry fox"
+        );
+        assert_eq!(user_query("Example", ""), "@lower example");
+    }
+
+    #[test]
+    fn test_parse_taxonomy() {
+        let page = r#"<form id="browse-search">
+            <select name="cat">
+                <option value="1" selected="selected">All</option>
+                <optgroup label="Visual Art"><option value="2">Artwork (Digital)</option></optgroup>
+            </select>
+            <select name="atype"><option value="1">All</option><option value="4">Fursuit</option></select>
+            <select name="species"><option value="1">Unspecified / Any</option><option value="6008">Fox</option></select>
+            <select name="gender"><option value="0">Any</option><option value="4">Female</option></select>
+        </form>"#;
+
+        let taxonomy = parse_taxonomy(page).unwrap();
+        assert_eq!(taxonomy.categories.len(), 2);
+        assert_eq!(
+            taxonomy.categories[1],
+            FilterOption {
+                value: "2".into(),
+                label: "Artwork (Digital)".into(),
+            }
+        );
+        assert_eq!(taxonomy.types[1].label, "Fursuit");
+        assert_eq!(taxonomy.species[1].value, "6008");
+        assert_eq!(taxonomy.genders.len(), 2);
+
+        assert!(parse_taxonomy("<html></html>").is_err());
+    }
+
+    #[cfg(feature = "client")]
+    #[tokio::test]
+    async fn test_search_with() {
+        let server = crate::test_utils::MockServer::start().await.unwrap();
+        let page = crate::test_utils::GALLERY.replace(
+            "</body>",
+            r#"<div id="query-stats">Search Results: 1 - 2 of 1,234</div></body>"#,
+        );
+        server.route(
+            "/search/?q=fox&page=2&order-by=date&order-direction=desc&rating-general=1&type-story=1&type-music=1",
+            200,
+            page,
+        );
+
+        let options = SearchOptions::new("fox")
+            .with_order(SearchOrder::Date, false)
+            .with_ratings(&[Rating::General])
+            .with_types(&[SubmissionType::Story, SubmissionType::Music]);
+        let results = server
+            .client()
+            .search_with(&options.next_page())
+            .await
+            .unwrap();
+
+        assert_eq!(results.submissions.len(), 2);
+        assert_eq!(results.total, Some(1234));
+    }
+
+    #[cfg(feature = "client")]
+    #[tokio::test]
+    async fn test_search_user() {
+        let server = crate::test_utils::MockServer::start().await.unwrap();
+        server.route(
+            "/search/?q=%40lower+example+spring&page=1",
+            200,
+            crate::test_utils::GALLERY,
+        );
+
+        let results = server
+            .client()
+            .search_user("Example", "spring", 1)
+            .await
+            .unwrap();
+        assert_eq!(results.len(), 2);
+    }
+}
@@ -0,0 +1,241 @@
+//! Following the links between submissions in a series, such as a comic.
+
+use crate::gallery::SubmissionPreview;
+use crate::info::Folder;
+use crate::rate_limit::RateLimiter;
+use crate::{Error, FurAffinity, Submission, SubmissionId};
+use std::collections::HashSet;
+
+/// The submissions before and after a submission in its series.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Adjacent {
+    /// The previous submission, if linked and it could be loaded.
+    pub prev: Option<Submission>,
+    /// The next submission, if linked and it could be loaded.
+    pub next: Option<Submission>,
+}
+
+/// Load the previous and next submissions linked from a submission's nav
+/// links at the same time, each waiting for the rate limit.
+pub async fn prefetch_adjacent(
+    fa: &FurAffinity,
+    sub: &Submission,
+    limiter: &RateLimiter,
+) -> Result<Adjacent, Error> {
+    let links = sub.nav_links().unwrap_or_default();
+
+    let load = |id: Option<SubmissionId>| async move {
+        match id {
+            Some(id) => {
+                limiter.wait().await;
+                fa.get_submission(id).await
+            }
+            None => Ok(None),
+        }
+    };
+
+    let (prev, next) = futures::try_join!(load(links.prev), load(links.next))?;
+
+    Ok(Adjacent { prev, next })
+}
+
+/// Load every submission in a series, in order, by following the nav links
+/// from a submission back to the start of the series and then forward to
+/// its end.
+///
+/// Each submission is only loaded once, so links that loop back stop the
+/// walk. Returns [`Error::Deleted`] if the first submission can't be loaded.
+pub async fn walk_series(fa: &FurAffinity, start: SubmissionId) -> Result<Vec<Submission>, Error> {
+    let start = fa.get_submission(start).await?.ok_or(Error::Deleted)?;
+    let links = start.nav_links().unwrap_or_default();
+
+    let mut seen = HashSet::new();
+    seen.insert(start.id);
+
+    let mut series = walk(fa, links.prev, &mut seen, |links| links.prev).await?;
+    series.reverse();
+
+    // Without prev links all the way back, fill in from the first link.
+    let first = series
+        .first()
+        .and_then(|sub| sub.nav_links())
+        .and_then(|links| links.first)
+        .or(links.first);
+    if first.is_some_and(|first| !seen.contains(&first)) {
+        let mut head = walk(fa, first, &mut seen, |links| links.next).await?;
+        head.append(&mut series);
+        series = head;
+    }
+
+    let next = links.next;
+    series.push(start);
+    series.extend(walk(fa, next, &mut seen, |links| links.next).await?);
+
+    Ok(series)
+}
+
+/// Follow one kind of nav link until it's missing, leads to a submission
+/// that can't be loaded, or leads to one that was already seen.
+async fn walk<F>(
+    fa: &FurAffinity,
+    mut id: Option<SubmissionId>,
+    seen: &mut HashSet<SubmissionId>,
+    link: F,
+) -> Result<Vec<Submission>, Error>
+where
+    F: Fn(&crate::NavLinks) -> Option<SubmissionId>,
+{
+    let mut subs = Vec::new();
+
+    while let Some(current) = id.filter(|id| seen.insert(*id)) {
+        let sub = match fa.get_submission(current).await? {
+            Some(sub) => sub,
+            None => break,
+        };
+
+        id = sub.nav_links().as_ref().and_then(&link);
+        subs.push(sub);
+    }
+
+    Ok(subs)
+}
+
+/// Load every submission in a folder, oldest first, for series organized
+/// with folders instead of nav links.
+pub async fn walk_folder(
+    fa: &FurAffinity,
+    folder: &Folder,
+) -> Result<Vec<SubmissionPreview>, Error> {
+    let mut seen = HashSet::new();
+    let mut submissions = Vec::new();
+
+    for page in 1.. {
+        let listing = fa.get_folder(folder, page).await?;
+        let before = submissions.len();
+
+        submissions.extend(
+            listing
+                .submissions
+                .into_iter()
+                .filter(|sub| seen.insert(sub.id)),
+        );
+
+        if submissions.len() == before {
+            break;
+        }
+    }
+
+    submissions.reverse();
+
+    Ok(submissions)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{MockServer, DELETED, GALLERY, STORY, SUBMISSION};
+
+    /// A submission page with nav links to other submissions.
+    fn series_page(prev: Option<i32>, first: Option<i32>, next: Option<i32>) -> String {
+        let link = |id: Option<i32>, label: &str| match id {
+            Some(id) => format!(r#"<a href="/view/{}">{}</a>"#, id, label),
+            None => label.to_string(),
+        };
+        let links = format!(
+            "{}&nbsp;|&nbsp;{}&nbsp;|&nbsp;{}",
+            link(prev, "&lt;&lt;&lt;&nbsp;PREV"),
+            link(first, "FIRST"),
+            link(next, "NEXT&nbsp;&gt;&gt;&gt;"),
+        );
+
+        let start = SUBMISSION
+            .find(r#"<span class="parsed_nav_links">"#)
+            .unwrap();
+        let end = start + SUBMISSION[start..].find("</span>").unwrap();
+
+        format!(
+            r#"{}<span class="parsed_nav_links">{}{}"#,
+            &SUBMISSION[..start],
+            links,
+            &SUBMISSION[end..]
+        )
+    }
+
+    #[tokio::test]
+    async fn test_prefetch_adjacent() {
+        let server = MockServer::start().await.unwrap();
+        server.route("/view/31300000", 200, STORY);
+        let fa = server.client();
+
+        let sub = crate::parse_submission(SubmissionId(31209021), SUBMISSION)
+            .unwrap()
+            .unwrap();
+        let adjacent = prefetch_adjacent(&fa, &sub, &RateLimiter::per_minute(600))
+            .await
+            .unwrap();
+
+        assert_eq!(adjacent.prev, None);
+        assert_eq!(
+            adjacent.next.map(|next| next.id),
+            Some(SubmissionId(31300000))
+        );
+        assert_eq!(server.requests(), vec!["/view/31300000"]);
+    }
+
+    #[tokio::test]
+    async fn test_walk_series() {
+        let server = MockServer::start().await.unwrap();
+        server.route("/view/1", 200, series_page(None, None, Some(2)));
+        server.route("/view/2", 200, series_page(Some(1), Some(1), Some(3)));
+        // The last page links back to the start.
+        server.route("/view/3", 200, series_page(None, Some(1), Some(1)));
+        let fa = server.client();
+
+        let ids = |series: Vec<Submission>| -> Vec<u64> {
+            series.into_iter().map(|sub| sub.id.0).collect()
+        };
+
+        assert_eq!(
+            ids(fa.walk_series(SubmissionId(2)).await.unwrap()),
+            [1, 2, 3]
+        );
+        assert_eq!(server.requests().len(), 3);
+
+        // Without a prev link, the series is filled in from the first link.
+        assert_eq!(
+            ids(fa.walk_series(SubmissionId(3)).await.unwrap()),
+            [1, 2, 3]
+        );
+
+        server.route("/view/4", 200, DELETED);
+        assert!(matches!(
+            fa.walk_series(SubmissionId(4)).await.unwrap_err(),
+            Error::Deleted
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_walk_folder() {
+        let server = MockServer::start().await.unwrap();
+        server.route(
+            "/gallery/example/1/",
+            200,
+            GALLERY.replace(
+                "</body>",
+                r#"<div class="user-folders"><a href="/gallery/example/folder/123456/Comic/">Comic</a></div></body>"#,
+            ),
+        );
+        server.route("/gallery/example/folder/123456/Comic/", 200, GALLERY);
+        server.route("/gallery/example/folder/123456/Comic/2/", 200, GALLERY);
+        let fa = server.client();
+
+        let folders = fa.get_gallery_folders("example").await.unwrap();
+        assert_eq!(folders[0].name, "Comic");
+
+        let submissions = fa.walk_folder(&folders[0]).await.unwrap();
+        let ids: Vec<_> = submissions.iter().map(|sub| sub.id.0).collect();
+        assert_eq!(ids, [41234567, 41234568]);
+    }
+}
@@ -0,0 +1,230 @@
+//! Spreading requests across several logged in accounts.
+
+use crate::rate_limit::RateLimiter;
+use crate::Cookies;
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio::time::Instant;
+
+/// How a [`SessionPool`] picks the session for each request.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Selection {
+    /// Each session in turn.
+    #[default]
+    RoundRobin,
+    /// The session that went the longest without a request.
+    LeastRecentlyUsed,
+}
+
+/// Sets of cookies for different accounts, with a session picked for each
+/// request.
+///
+/// Sessions that load pages as a guest have expired or were logged out, so
+/// they are removed from the pool and the page is loaded again with another
+/// session. Share a pool between clients by giving each the same `Arc`.
+#[derive(Debug, Default)]
+pub struct SessionPool {
+    selection: Selection,
+    rate_limit: Option<Duration>,
+    state: Mutex<PoolState>,
+}
+
+#[derive(Debug, Default)]
+struct PoolState {
+    sessions: Vec<Session>,
+    next_id: usize,
+    turn: usize,
+}
+
+#[derive(Debug)]
+struct Session {
+    id: usize,
+    cookies: Cookies,
+    rate_limiter: Option<RateLimiter>,
+    last_used: Option<Instant>,
+}
+
+/// The session picked for a request.
+pub(crate) struct Lease {
+    pub(crate) id: usize,
+    pub(crate) cookies: String,
+    pub(crate) rate_limiter: Option<RateLimiter>,
+}
+
+impl SessionPool {
+    pub fn new(selection: Selection) -> Self {
+        Self {
+            selection,
+            ..Default::default()
+        }
+    }
+
+    /// Wait at least `interval` between starting requests with each
+    /// session, on top of any rate limit for the whole client.
+    pub fn with_rate_limit(mut self, interval: Duration) -> Self {
+        self.rate_limit = Some(interval);
+        for session in &mut self.state.get_mut().unwrap().sessions {
+            session.rate_limiter = Some(RateLimiter::new(interval));
+        }
+        self
+    }
+
+    /// Add a session from its `a` and `b` cookies.
+    pub fn with_session<T: Into<String>>(self, cookie_a: T, cookie_b: T) -> Self {
+        self.add_session(cookie_a, cookie_b);
+        self
+    }
+
+    /// Add a session from a `Cookie` header, such as one copied from a
+    /// browser's developer tools.
+    pub fn with_cookie_header(self, cookie_header: &str) -> Self {
+        self.add_cookies(crate::parse_cookie_header(cookie_header));
+        self
+    }
+
+    /// Add a session to a pool that may already be in use, returning its ID.
+    pub fn add_session<T: Into<String>>(&self, cookie_a: T, cookie_b: T) -> usize {
+        self.add_cookies(crate::cookie_set(cookie_a, cookie_b))
+    }
+
+    fn add_cookies(&self, cookies: Cookies) -> usize {
+        let mut state = self.state.lock().unwrap();
+
+        let id = state.next_id;
+        state.next_id += 1;
+        state.sessions.push(Session {
+            id,
+            cookies,
+            rate_limiter: self.rate_limit.map(RateLimiter::new),
+            last_used: None,
+        });
+
+        id
+    }
+
+    /// Remove a session, returning if it was still in the pool.
+    pub fn remove(&self, id: usize) -> bool {
+        let mut state = self.state.lock().unwrap();
+
+        let len = state.sessions.len();
+        state.sessions.retain(|session| session.id != id);
+
+        state.sessions.len() != len
+    }
+
+    /// IDs of the sessions still in the pool, in the order they were added.
+    pub fn sessions(&self) -> Vec<usize> {
+        let state = self.state.lock().unwrap();
+
+        state.sessions.iter().map(|session| session.id).collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.state.lock().unwrap().sessions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Pick the session for a request, or `None` if every session was
+    /// removed.
+    pub(crate) fn checkout(&self) -> Option<Lease> {
+        let mut state = self.state.lock().unwrap();
+        if state.sessions.is_empty() {
+            return None;
+        }
+
+        let index = match self.selection {
+            Selection::RoundRobin => {
+                let index = state.turn % state.sessions.len();
+                state.turn = index + 1;
+
+                index
+            }
+            Selection::LeastRecentlyUsed => state
+                .sessions
+                .iter()
+                .enumerate()
+                .min_by_key(|(_index, session)| session.last_used)
+                .map(|(index, _session)| index)?,
+        };
+
+        let session = &mut state.sessions[index];
+        session.last_used = Some(Instant::now());
+
+        Some(Lease {
+            id: session.id,
+            cookies: crate::cookie_header(&session.cookies),
+            rate_limiter: session.rate_limiter.clone(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::MockServer;
+    use crate::Error;
+    use std::sync::Arc;
+
+    const LOGGED_IN: &str = r#"<img class="loggedin_user_avatar">"#;
+
+    fn cookie_a(headers: &std::collections::HashMap<String, String>) -> String {
+        headers["cookie"]
+            .split(';')
+            .find_map(|cookie| cookie.strip_prefix("a="))
+            .unwrap()
+            .to_string()
+    }
+
+    #[test]
+    fn test_selection() {
+        let pool = SessionPool::new(Selection::RoundRobin)
+            .with_session("a1", "b1")
+            .with_session("a2", "b2");
+        let ids: Vec<_> = (0..3).map(|_| pool.checkout().unwrap().id).collect();
+        assert_eq!(ids, vec![0, 1, 0]);
+
+        let pool = SessionPool::new(Selection::LeastRecentlyUsed)
+            .with_session("a1", "b1")
+            .with_session("a2", "b2");
+        assert_eq!(pool.checkout().unwrap().id, 0);
+        assert_eq!(pool.checkout().unwrap().id, 1);
+        pool.add_session("a3", "b3");
+        assert_eq!(pool.checkout().unwrap().id, 2);
+        assert_eq!(pool.checkout().unwrap().id, 0);
+
+        assert!(pool.remove(0));
+        assert!(!pool.remove(0));
+        assert_eq!(pool.sessions(), vec![1, 2]);
+    }
+
+    #[tokio::test]
+    async fn test_session_pool() {
+        let server = MockServer::start().await.unwrap();
+        server.route("/gallery/example/1/", 200, LOGGED_IN);
+        let pool = Arc::new(
+            SessionPool::new(Selection::RoundRobin)
+                .with_session("a1", "b1")
+                .with_session("a2", "b2"),
+        );
+        let fa = server.client().with_session_pool(pool.clone());
+
+        fa.get_gallery("example", 1).await.unwrap();
+        fa.get_gallery("example", 1).await.unwrap();
+        let used: Vec<_> = server.request_headers().iter().map(cookie_a).collect();
+        assert_eq!(used, vec!["a1", "a2"]);
+
+        // A guest page means the session expired, so it is removed and the
+        // page is loaded with the next session.
+        server.route("/gallery/example/1/", 200, "<html></html>");
+        assert!(fa.get_gallery("example", 1).await.is_ok());
+        assert_eq!(pool.len(), 0);
+
+        assert!(matches!(
+            fa.get_gallery("example", 1).await.unwrap_err(),
+            Error::LoginRequired(_)
+        ));
+    }
+}
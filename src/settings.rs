@@ -0,0 +1,124 @@
+//! Account settings that change which submissions can be viewed.
+//!
+//! Accounts that haven't enabled mature or adult content, or have SFW mode
+//! turned on, get the same error page for those submissions as for deleted
+//! ones.
+
+use crate::{Error, Rating};
+use lazy_static::lazy_static;
+use scraper::Selector;
+
+lazy_static! {
+    static ref VIEW_MATURE: Selector = Selector::parse(
+        r#"select[name="viewmature"] option[selected], input[name="viewmature"][checked]"#
+    )
+    .unwrap();
+    static ref SFW_TOGGLE: Selector =
+        Selector::parse("#sfw-toggle[checked], .sfw-toggle.active").unwrap();
+}
+
+/// Which ratings an account can view.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct ContentSettings {
+    /// Most explicit rating enabled in the account's settings.
+    pub max_rating: Rating,
+    /// If the site's SFW toggle is on, hiding everything but general
+    /// submissions regardless of the account's settings.
+    pub sfw_mode: bool,
+}
+
+impl ContentSettings {
+    /// What guests can view, which is only general submissions.
+    pub const GUEST: Self = Self {
+        max_rating: Rating::General,
+        sfw_mode: false,
+    };
+
+    /// If submissions with a rating can be viewed.
+    pub fn allows(&self, rating: Rating) -> bool {
+        if self.sfw_mode {
+            rating.is_sfw()
+        } else {
+            rating <= self.max_rating
+        }
+    }
+
+    /// If every submission can be viewed, so a missing submission was
+    /// deleted instead of being hidden.
+    pub fn full_visibility(&self) -> bool {
+        self.allows(Rating::Adult)
+    }
+}
+
+/// Parse the ratings an account can view from its settings page, at
+/// `/controls/settings/`.
+///
+/// The `viewmature` setting is 0 for general, 1 for mature, and 2 for adult
+/// content. Pages loaded as a guest are the same as [`ContentSettings::GUEST`].
+pub fn parse_content_settings(page: &str) -> Result<ContentSettings, Error> {
+    if !crate::is_logged_in(page) {
+        return Ok(ContentSettings::GUEST);
+    }
+
+    let document = scraper::Html::parse_document(page);
+
+    let max_rating = match document
+        .select(&VIEW_MATURE)
+        .next()
+        .and_then(|elem| elem.value().attr("value"))
+    {
+        Some("0") => Rating::General,
+        Some("1") => Rating::Mature,
+        Some("2") => Rating::Adult,
+        Some(_) => return Err(Error::parse("content setting")),
+        None => return Err(Error::parse("content setting")),
+    };
+
+    Ok(ContentSettings {
+        max_rating,
+        sfw_mode: document.select(&SFW_TOGGLE).next().is_some(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SETTINGS: &str = r#"<html><body>
+        <img class="loggedin_user_avatar" src="//a.furaffinity.net/example.gif">
+        <input type="checkbox" id="sfw-toggle">
+        <select name="viewmature">
+            <option value="0">General</option>
+            <option value="1">Mature</option>
+            <option value="2" selected="selected">Adult</option>
+        </select>
+    </body></html>"#;
+
+    #[test]
+    fn test_parse_content_settings() {
+        let settings = parse_content_settings(SETTINGS).unwrap();
+        assert_eq!(settings.max_rating, Rating::Adult);
+        assert!(!settings.sfw_mode);
+        assert!(settings.full_visibility());
+
+        let page = SETTINGS.replace(r#"id="sfw-toggle""#, r#"id="sfw-toggle" checked"#);
+        let settings = parse_content_settings(&page).unwrap();
+        assert!(settings.sfw_mode);
+        assert!(!settings.allows(Rating::Mature));
+
+        let page = SETTINGS
+            .replace(r#" selected="selected""#, "")
+            .replace(r#"value="1""#, r#"value="1" selected"#);
+        let settings = parse_content_settings(&page).unwrap();
+        assert!(settings.allows(Rating::Mature));
+        assert!(!settings.full_visibility());
+
+        assert_eq!(
+            parse_content_settings("<html></html>").unwrap(),
+            ContentSettings::GUEST
+        );
+        assert!(parse_content_settings(&SETTINGS.replace("viewmature", "other")).is_err());
+    }
+}
@@ -0,0 +1,149 @@
+//! Telling whether FurAffinity is up, from pages that replace the site when
+//! it isn't, such as Cloudflare's challenge or the maintenance notice.
+
+use crate::Error;
+use lazy_static::lazy_static;
+use scraper::Selector;
+
+lazy_static! {
+    static ref PAGE_TITLE: Selector = Selector::parse("title").unwrap();
+    static ref CHALLENGE: Selector = Selector::parse(
+        "#challenge-form, #challenge-running, #cf-challenge-running, .cf-browser-verification, script[src*=\"/cdn-cgi/challenge-platform/\"]"
+    )
+    .unwrap();
+}
+
+/// If FurAffinity can be used, or what is shown instead.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub enum SiteStatus {
+    Online,
+    /// Cloudflare is asking the browser to solve a challenge.
+    CloudflareChallenge,
+    /// The site is offline for maintenance.
+    Maintenance,
+    /// The server responded with an error status code.
+    ServerError(u16),
+}
+
+impl SiteStatus {
+    /// Work out the site's status from a response's status code and page.
+    pub fn detect(status: u16, page: &str) -> Self {
+        if is_challenge(page) {
+            SiteStatus::CloudflareChallenge
+        } else if is_maintenance(page) {
+            SiteStatus::Maintenance
+        } else if status >= 500 {
+            SiteStatus::ServerError(status)
+        } else {
+            SiteStatus::Online
+        }
+    }
+
+    pub fn is_online(&self) -> bool {
+        matches!(self, SiteStatus::Online)
+    }
+
+    /// The error for loading a page while the site has this status, if it
+    /// isn't online.
+    pub fn error(&self) -> Option<Error> {
+        match self {
+            SiteStatus::Online => None,
+            SiteStatus::CloudflareChallenge => Some(Error::CloudflareChallenge),
+            SiteStatus::Maintenance => Some(Error::SiteMaintenance),
+            SiteStatus::ServerError(status) => Some(Error::from_status(*status)),
+        }
+    }
+}
+
+/// If a page is Cloudflare's challenge instead of the page that was
+/// requested.
+pub fn is_challenge(page: &str) -> bool {
+    // Checking for Cloudflare's paths first skips parsing most pages.
+    if !page.contains("cdn-cgi") && !page.contains("challenge") {
+        return false;
+    }
+
+    let document = scraper::Html::parse_document(page);
+
+    let title = document
+        .select(&PAGE_TITLE)
+        .next()
+        .map(crate::join_text_nodes)
+        .unwrap_or_default();
+
+    title == "Just a moment..." || document.select(&CHALLENGE).next().is_some()
+}
+
+/// If a page is FurAffinity's notice that the site is offline for
+/// maintenance.
+pub fn is_maintenance(page: &str) -> bool {
+    if !page.contains("aintenance") && !page.contains("ffline") {
+        return false;
+    }
+
+    let document = scraper::Html::parse_document(page);
+
+    let title = document
+        .select(&PAGE_TITLE)
+        .next()
+        .map(|title| crate::join_text_nodes(title).to_lowercase())
+        .unwrap_or_default();
+
+    title.contains("maintenance") || (title.contains("offline") && title.contains("fur affinity"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(feature = "client")]
+    use crate::test_utils::MockServer;
+    use crate::test_utils::{FRONT_PAGE, SUBMISSION};
+
+    const CHALLENGE_PAGE: &str = r#"<!DOCTYPE html><html><head><title>Just a moment...</title></head>
+        <body><div id="challenge-running">Checking your browser</div>
+        <script src="/cdn-cgi/challenge-platform/h/b/orchestrate/chl_page/v1"></script></body></html>"#;
+
+    const MAINTENANCE_PAGE: &str = r#"<html><head><title>Fur Affinity | Site Maintenance</title></head>
+        <body><p>Fur Affinity is down for scheduled maintenance.</p></body></html>"#;
+
+    #[test]
+    fn test_detect() {
+        assert_eq!(SiteStatus::detect(200, FRONT_PAGE), SiteStatus::Online);
+        assert_eq!(SiteStatus::detect(200, SUBMISSION), SiteStatus::Online);
+        assert_eq!(
+            SiteStatus::detect(403, CHALLENGE_PAGE),
+            SiteStatus::CloudflareChallenge
+        );
+        assert_eq!(
+            SiteStatus::detect(503, MAINTENANCE_PAGE),
+            SiteStatus::Maintenance
+        );
+        assert_eq!(SiteStatus::detect(502, ""), SiteStatus::ServerError(502));
+    }
+
+    #[cfg(feature = "client")]
+    #[tokio::test]
+    async fn test_site_status() {
+        let server = MockServer::start().await.unwrap();
+        server.route("/", 403, CHALLENGE_PAGE);
+        server.route("/view/31209021", 503, MAINTENANCE_PAGE);
+
+        let fa = server.client();
+        assert_eq!(
+            fa.site_status().await.unwrap(),
+            SiteStatus::CloudflareChallenge
+        );
+
+        let err = fa.latest_id().await.unwrap_err();
+        assert!(matches!(err, Error::CloudflareChallenge));
+        assert!(err.is_retryable());
+
+        let err = fa
+            .get_submission(crate::SubmissionId(31209021))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, Error::SiteMaintenance));
+    }
+}
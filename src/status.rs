@@ -0,0 +1,109 @@
+//! Why a submission could not be loaded.
+
+use crate::Submission;
+use lazy_static::lazy_static;
+use scraper::Selector;
+
+lazy_static! {
+    static ref NOTICE: Selector = Selector::parse(
+        ".error-message-box, section.notice-message .redirect-message, section.notice-message p.link-override"
+    )
+    .unwrap();
+}
+
+/// A submission, or the reason it can't be viewed.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub enum SubmissionStatus {
+    Found(Box<Submission>),
+    /// The submission was deleted or never existed.
+    Deleted,
+    /// The artist blocked the account, or restricted who can view their
+    /// submissions.
+    AccessRestricted,
+    /// The artist made the submission visible to registered users only.
+    RequiresLogin,
+    /// The submission is mature or adult, and the account can't view that
+    /// rating.
+    AdultFilterBlocked,
+    /// A message that isn't recognized yet.
+    Unknown(String),
+}
+
+impl SubmissionStatus {
+    /// The submission, if it was found.
+    pub fn submission(self) -> Option<Submission> {
+        match self {
+            SubmissionStatus::Found(sub) => Some(*sub),
+            _ => None,
+        }
+    }
+
+    /// If loading the submission again with an account that is logged in,
+    /// or has mature and adult content enabled, may find it.
+    pub fn needs_account(&self) -> bool {
+        matches!(
+            self,
+            SubmissionStatus::RequiresLogin | SubmissionStatus::AdultFilterBlocked
+        )
+    }
+}
+
+/// The reason a submission page didn't show the submission, from the
+/// message shown in its place.
+pub fn parse_unavailable(page: &str) -> SubmissionStatus {
+    let document = scraper::Html::parse_document(page);
+
+    let message = document
+        .select(&NOTICE)
+        .map(crate::join_text_nodes)
+        .max_by_key(|message| message.len())
+        .unwrap_or_default();
+    let lower = message.to_lowercase();
+
+    if lower.contains("not in our database") || lower.contains("has been deleted") {
+        SubmissionStatus::Deleted
+    } else if lower.contains("mature or adult") || lower.contains("content filter") {
+        SubmissionStatus::AdultFilterBlocked
+    } else if lower.contains("registered users only") || lower.contains("must be logged in") {
+        SubmissionStatus::RequiresLogin
+    } else if lower.contains("blocked")
+        || lower.contains("not allowed")
+        || lower.contains("disabled")
+    {
+        SubmissionStatus::AccessRestricted
+    } else {
+        SubmissionStatus::Unknown(message)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{AGE_GATED, DELETED};
+
+    #[test]
+    fn test_parse_unavailable() {
+        assert_eq!(parse_unavailable(DELETED), SubmissionStatus::Deleted);
+        assert_eq!(
+            parse_unavailable(AGE_GATED),
+            SubmissionStatus::AdultFilterBlocked
+        );
+        assert!(parse_unavailable(AGE_GATED).needs_account());
+
+        let page = DELETED.replace(
+            "The submission you are trying to find is not in our database.",
+            "The owner of this page has elected to make it available to registered users only.",
+        );
+        assert_eq!(parse_unavailable(&page), SubmissionStatus::RequiresLogin);
+
+        let page = DELETED.replace(
+            "The submission you are trying to find is not in our database.",
+            "Something new.",
+        );
+        assert!(
+            matches!(parse_unavailable(&page), SubmissionStatus::Unknown(message) if message.contains("Something new."))
+        );
+    }
+}
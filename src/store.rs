@@ -0,0 +1,498 @@
+//! Archiving submissions to a SQLite database.
+
+use crate::hash::HashDistance;
+use crate::poller::Polled;
+use crate::{Error, Rating, Submission, SubmissionId};
+use rusqlite::types::{FromSql, FromSqlError, FromSqlResult, ToSqlOutput, ValueRef};
+use rusqlite::{params, Connection, OptionalExtension, ToSql};
+use std::path::Path;
+
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS submission (
+        id INTEGER PRIMARY KEY,
+        artist TEXT NOT NULL,
+        title TEXT NOT NULL,
+        url TEXT NOT NULL,
+        filename TEXT NOT NULL,
+        ext TEXT NOT NULL,
+        rating TEXT NOT NULL,
+        posted_at TEXT NOT NULL,
+        tags TEXT NOT NULL,
+        description TEXT NOT NULL,
+        hash_num INTEGER,
+        file_size INTEGER,
+        file_sha256 BLOB
+    );
+    CREATE INDEX IF NOT EXISTS submission_artist_idx ON submission (lower(artist));
+    CREATE INDEX IF NOT EXISTS submission_hash_num_idx ON submission (hash_num);
+    CREATE INDEX IF NOT EXISTS submission_file_sha256_idx ON submission (file_sha256);
+
+    CREATE TABLE IF NOT EXISTS fetch (
+        id INTEGER NOT NULL,
+        fetched_at TEXT NOT NULL,
+        outcome TEXT NOT NULL,
+        error TEXT
+    );
+    CREATE INDEX IF NOT EXISTS fetch_id_idx ON fetch (id);
+
+    CREATE TABLE IF NOT EXISTS hash_tree (
+        hash INTEGER PRIMARY KEY,
+        parent INTEGER,
+        distance INTEGER
+    );
+    CREATE INDEX IF NOT EXISTS hash_tree_parent_idx ON hash_tree (parent, distance);
+";
+
+const SUBMISSION_COLUMNS: &str = "id, artist, title, url, filename, ext, rating, posted_at, tags, description, hash_num, file_size, file_sha256";
+
+/// A submission as saved in a [`Store`].
+///
+/// Downloaded files and hashes other than the 8x8 perceptual hash and
+/// SHA-256 digest are not saved.
+#[derive(Clone, Debug, PartialEq)]
+pub struct StoredSubmission {
+    pub id: SubmissionId,
+    pub artist: String,
+    pub title: String,
+    pub url: String,
+    pub filename: String,
+    pub ext: String,
+    pub rating: Rating,
+    pub posted_at: chrono::DateTime<chrono::Utc>,
+    pub tags: Vec<String>,
+    pub description: String,
+    pub hash_num: Option<i64>,
+    pub file_size: Option<i64>,
+    pub file_sha256: Option<Vec<u8>>,
+}
+
+impl StoredSubmission {
+    fn from_row(row: &rusqlite::Row) -> rusqlite::Result<Self> {
+        let rating: String = row.get(6)?;
+        let tags: String = row.get(8)?;
+
+        Ok(Self {
+            id: row.get(0)?,
+            artist: row.get(1)?,
+            title: row.get(2)?,
+            url: row.get(3)?,
+            filename: row.get(4)?,
+            ext: row.get(5)?,
+            rating: Rating::from_code(&rating).unwrap_or(Rating::General),
+            posted_at: row.get(7)?,
+            tags: tags.split_whitespace().map(String::from).collect(),
+            description: row.get(9)?,
+            hash_num: row.get(10)?,
+            file_size: row.get(11)?,
+            file_sha256: row.get(12)?,
+        })
+    }
+}
+
+impl From<&StoredSubmission> for crate::verify::ArchivedSubmission {
+    fn from(sub: &StoredSubmission) -> Self {
+        Self {
+            id: sub.id,
+            sha256: sub.file_sha256.clone(),
+            tags: sub.tags.clone(),
+        }
+    }
+}
+
+/// What happened when loading a submission.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FetchOutcome {
+    Loaded,
+    Missing,
+    Failed,
+}
+
+impl FetchOutcome {
+    fn as_str(self) -> &'static str {
+        match self {
+            FetchOutcome::Loaded => "loaded",
+            FetchOutcome::Missing => "missing",
+            FetchOutcome::Failed => "failed",
+        }
+    }
+
+    fn parse(outcome: &str) -> Self {
+        match outcome {
+            "loaded" => FetchOutcome::Loaded,
+            "missing" => FetchOutcome::Missing,
+            _ => FetchOutcome::Failed,
+        }
+    }
+}
+
+/// A recorded attempt to load a submission.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Fetch {
+    pub id: SubmissionId,
+    pub fetched_at: chrono::DateTime<chrono::Utc>,
+    pub outcome: FetchOutcome,
+    pub error: Option<String>,
+}
+
+/// A submission with a perceptual hash close to the one searched for.
+#[derive(Clone, Debug, PartialEq)]
+pub struct HashMatch {
+    pub submission: StoredSubmission,
+    pub distance: u32,
+}
+
+/// A SQLite database of submissions and attempts to load them.
+///
+/// Saving a submission that is already stored replaces it, keeping the
+/// earlier hashes if the new version wasn't hashed. Perceptual
+/// hashes are also kept in a bk-tree so similar images can be found
+/// without comparing against every submission.
+pub struct Store {
+    conn: Connection,
+}
+
+impl Store {
+    /// Open or create a database at the given path.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        Self::with_connection(Connection::open(path)?)
+    }
+
+    /// Create a database that only exists in memory.
+    pub fn open_in_memory() -> Result<Self, Error> {
+        Self::with_connection(Connection::open_in_memory()?)
+    }
+
+    fn with_connection(conn: Connection) -> Result<Self, Error> {
+        conn.execute_batch(SCHEMA)?;
+
+        Ok(Self { conn })
+    }
+
+    /// Save a submission, replacing any earlier version of it.
+    pub fn save_submission(&self, sub: &Submission) -> Result<(), Error> {
+        use std::convert::TryInto;
+
+        let file_size: Option<i64> = sub.file_size.and_then(|size| size.try_into().ok());
+
+        self.conn.execute(
+            "INSERT INTO submission (id, artist, title, url, filename, ext, rating, posted_at, tags, description, hash_num, file_size, file_sha256)
+                VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)
+                ON CONFLICT (id) DO UPDATE SET
+                    artist = excluded.artist,
+                    title = excluded.title,
+                    url = excluded.url,
+                    filename = excluded.filename,
+                    ext = excluded.ext,
+                    rating = excluded.rating,
+                    posted_at = excluded.posted_at,
+                    tags = excluded.tags,
+                    description = excluded.description,
+                    hash_num = coalesce(excluded.hash_num, submission.hash_num),
+                    file_size = coalesce(excluded.file_size, submission.file_size),
+                    file_sha256 = coalesce(excluded.file_sha256, submission.file_sha256)",
+            params![
+                sub.id,
+                sub.artist,
+                sub.title,
+                sub.content.url(),
+                sub.filename,
+                sub.ext,
+                sub.rating.serialize(),
+                sub.posted_at,
+                sub.tags.join(" "),
+                sub.description,
+                sub.hash_num,
+                file_size,
+                sub.file_sha256,
+            ],
+        )?;
+
+        if let Some(hash) = sub.hash_num {
+            self.insert_hash(hash)?;
+        }
+
+        Ok(())
+    }
+
+    /// Record the result of loading a submission, saving it if it was
+    /// loaded.
+    pub fn record_fetch(&self, polled: &Polled) -> Result<(), Error> {
+        let (id, outcome, error) = match polled {
+            Polled::Submission(sub) => {
+                self.save_submission(sub)?;
+                (sub.id, FetchOutcome::Loaded, None)
+            }
+            Polled::Missing(id) => (*id, FetchOutcome::Missing, None),
+            Polled::Failed(id, err) => (*id, FetchOutcome::Failed, Some(err.to_string())),
+        };
+
+        self.conn.execute(
+            "INSERT INTO fetch (id, fetched_at, outcome, error) VALUES (?1, ?2, ?3, ?4)",
+            params![id, chrono::Utc::now(), outcome.as_str(), error],
+        )?;
+
+        Ok(())
+    }
+
+    /// Get a saved submission.
+    pub fn submission(&self, id: SubmissionId) -> Result<Option<StoredSubmission>, Error> {
+        let sub = self
+            .conn
+            .query_row(
+                &format!(
+                    "SELECT {} FROM submission WHERE id = ?1",
+                    SUBMISSION_COLUMNS
+                ),
+                params![id],
+                StoredSubmission::from_row,
+            )
+            .optional()?;
+
+        Ok(sub)
+    }
+
+    /// Get all saved submissions by an artist, newest first. Artist names
+    /// are matched case insensitively.
+    pub fn submissions_by_artist(&self, artist: &str) -> Result<Vec<StoredSubmission>, Error> {
+        self.query_submissions(
+            &format!(
+                "SELECT {} FROM submission WHERE lower(artist) = lower(?1) ORDER BY id DESC",
+                SUBMISSION_COLUMNS
+            ),
+            params![artist],
+        )
+    }
+
+    /// Get all saved submissions with a file matching a SHA-256 digest.
+    pub fn submissions_by_sha256(&self, sha256: &[u8]) -> Result<Vec<StoredSubmission>, Error> {
+        self.query_submissions(
+            &format!(
+                "SELECT {} FROM submission WHERE file_sha256 = ?1 ORDER BY id",
+                SUBMISSION_COLUMNS
+            ),
+            params![sha256],
+        )
+    }
+
+    /// Find submissions with a perceptual hash within `max_distance` bits of
+    /// the given hash, closest first.
+    pub fn similar_submissions(
+        &self,
+        hash: i64,
+        max_distance: u32,
+    ) -> Result<Vec<HashMatch>, Error> {
+        let mut matches = Vec::new();
+
+        for (found, distance) in self.search_hashes(hash, max_distance)? {
+            let subs = self.query_submissions(
+                &format!(
+                    "SELECT {} FROM submission WHERE hash_num = ?1 ORDER BY id",
+                    SUBMISSION_COLUMNS
+                ),
+                params![found],
+            )?;
+
+            matches.extend(subs.into_iter().map(|submission| HashMatch {
+                submission,
+                distance,
+            }));
+        }
+
+        matches.sort_by_key(|m| (m.distance, m.submission.id));
+
+        Ok(matches)
+    }
+
+    /// Get every recorded attempt to load a submission, oldest first.
+    pub fn fetches(&self, id: SubmissionId) -> Result<Vec<Fetch>, Error> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, fetched_at, outcome, error FROM fetch WHERE id = ?1 ORDER BY rowid",
+        )?;
+
+        let fetches = stmt
+            .query_map(params![id], |row| {
+                let outcome: String = row.get(2)?;
+
+                Ok(Fetch {
+                    id: row.get(0)?,
+                    fetched_at: row.get(1)?,
+                    outcome: FetchOutcome::parse(&outcome),
+                    error: row.get(3)?,
+                })
+            })?
+            .collect::<Result<_, _>>()?;
+
+        Ok(fetches)
+    }
+
+    fn query_submissions<P: rusqlite::Params>(
+        &self,
+        sql: &str,
+        params: P,
+    ) -> Result<Vec<StoredSubmission>, Error> {
+        let mut stmt = self.conn.prepare(sql)?;
+        let subs = stmt
+            .query_map(params, StoredSubmission::from_row)?
+            .collect::<Result<_, _>>()?;
+
+        Ok(subs)
+    }
+
+    /// Add a hash to the bk-tree, walking down from the root until reaching
+    /// a node without a child at the hash's distance.
+    fn insert_hash(&self, hash: i64) -> Result<(), Error> {
+        let mut node: Option<i64> = self
+            .conn
+            .query_row(
+                "SELECT hash FROM hash_tree WHERE parent IS NULL",
+                [],
+                |row| row.get(0),
+            )
+            .optional()?;
+
+        let mut parent = None;
+
+        while let Some(current) = node {
+            let distance = hash.distance(&current).unwrap_or_default();
+            if distance == 0 {
+                return Ok(());
+            }
+
+            parent = Some((current, distance));
+            node = self
+                .conn
+                .query_row(
+                    "SELECT hash FROM hash_tree WHERE parent = ?1 AND distance = ?2",
+                    params![current, distance],
+                    |row| row.get(0),
+                )
+                .optional()?;
+        }
+
+        self.conn.execute(
+            "INSERT INTO hash_tree (hash, parent, distance) VALUES (?1, ?2, ?3)",
+            params![
+                hash,
+                parent.map(|(parent, _)| parent),
+                parent.map(|(_, distance)| distance)
+            ],
+        )?;
+
+        Ok(())
+    }
+
+    fn search_hashes(&self, hash: i64, max_distance: u32) -> Result<Vec<(i64, u32)>, Error> {
+        let root: Option<i64> = self
+            .conn
+            .query_row(
+                "SELECT hash FROM hash_tree WHERE parent IS NULL",
+                [],
+                |row| row.get(0),
+            )
+            .optional()?;
+
+        let mut stmt = self.conn.prepare(
+            "SELECT hash FROM hash_tree WHERE parent = ?1 AND distance BETWEEN ?2 AND ?3",
+        )?;
+
+        let mut found = Vec::new();
+        let mut pending: Vec<i64> = root.into_iter().collect();
+
+        while let Some(node) = pending.pop() {
+            let distance = hash.distance(&node).unwrap_or_default();
+            if distance <= max_distance {
+                found.push((node, distance));
+            }
+
+            let children = stmt
+                .query_map(
+                    params![
+                        node,
+                        distance.saturating_sub(max_distance),
+                        distance + max_distance
+                    ],
+                    |row| row.get(0),
+                )?
+                .collect::<Result<Vec<i64>, _>>()?;
+
+            pending.extend(children);
+        }
+
+        Ok(found)
+    }
+}
+
+/// IDs are stored as SQLite's signed integers.
+impl ToSql for SubmissionId {
+    fn to_sql(&self) -> rusqlite::Result<ToSqlOutput<'_>> {
+        Ok(self.to_i64().into())
+    }
+}
+
+impl FromSql for SubmissionId {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        let id = i64::column_result(value)?;
+
+        std::convert::TryFrom::try_from(id)
+            .map(SubmissionId)
+            .map_err(|_| FromSqlError::OutOfRange(id))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::test_submission;
+
+    #[test]
+    fn test_store() {
+        let store = Store::open_in_memory().unwrap();
+
+        let sub = Submission {
+            hash_num: Some(0b1111),
+            file_sha256: Some(vec![0xab; 32]),
+            ..test_submission("Sketch", 1617235200, &["fox", "sketch"])
+        };
+        store.save_submission(&sub).unwrap();
+
+        let updated = Submission {
+            id: SubmissionId(41234568),
+            hash_num: Some(0b1100),
+            ..test_submission("Sketch (colored)", 1617235300, &["fox"])
+        };
+        store
+            .record_fetch(&Polled::Submission(Box::new(updated)))
+            .unwrap();
+        store
+            .record_fetch(&Polled::Missing(SubmissionId(41234569)))
+            .unwrap();
+
+        let renamed = test_submission("Renamed Sketch", 1617235200, &["fox"]);
+        store.save_submission(&renamed).unwrap();
+
+        let saved = store.submission(SubmissionId(41234567)).unwrap().unwrap();
+        assert_eq!(saved.title, "Renamed Sketch");
+        assert_eq!(saved.tags, vec!["fox"]);
+        assert_eq!(saved.hash_num, Some(0b1111));
+        assert_eq!(saved.posted_at, sub.posted_at);
+        assert_eq!(saved.url, sub.content.url());
+
+        let by_artist = store.submissions_by_artist("example").unwrap();
+        assert_eq!(
+            by_artist.iter().map(|sub| sub.id).collect::<Vec<_>>(),
+            vec![SubmissionId(41234568), SubmissionId(41234567)]
+        );
+
+        assert_eq!(store.submissions_by_sha256(&[0xab; 32]).unwrap().len(), 1);
+
+        let similar = store.similar_submissions(0b0111, 1).unwrap();
+        assert_eq!(similar.len(), 1);
+        assert_eq!(similar[0].submission.id, SubmissionId(41234567));
+        assert_eq!(similar[0].distance, 1);
+        assert_eq!(store.similar_submissions(0b0111, 3).unwrap().len(), 2);
+
+        let fetches = store.fetches(SubmissionId(41234569)).unwrap();
+        assert_eq!(fetches.len(), 1);
+        assert_eq!(fetches[0].outcome, FetchOutcome::Missing);
+    }
+}
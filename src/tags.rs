@@ -0,0 +1,94 @@
+//! Normalizing tags so they can be matched across submissions.
+
+use unicode_normalization::UnicodeNormalization;
+
+/// How to normalize tags. Every step is enabled by default.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct TagNormalization {
+    /// Remove whitespace from the start and end of tags, dropping tags that
+    /// are left empty.
+    pub trim: bool,
+    /// Convert tags to lowercase.
+    pub lowercase: bool,
+    /// Convert tags to Unicode Normalization Form C, so the same characters
+    /// are always encoded the same way.
+    pub nfc: bool,
+    /// Remove tags that are the same as an earlier tag after normalizing.
+    pub dedup: bool,
+}
+
+impl Default for TagNormalization {
+    fn default() -> Self {
+        Self {
+            trim: true,
+            lowercase: true,
+            nfc: true,
+            dedup: true,
+        }
+    }
+}
+
+impl TagNormalization {
+    /// Normalize a single tag.
+    pub fn normalize(&self, tag: &str) -> String {
+        let tag = if self.trim { tag.trim() } else { tag };
+        let tag = if self.nfc {
+            tag.nfc().collect()
+        } else {
+            tag.to_string()
+        };
+
+        if self.lowercase {
+            tag.to_lowercase()
+        } else {
+            tag
+        }
+    }
+
+    /// Normalize a list of tags, keeping their order.
+    pub fn apply<S: AsRef<str>>(&self, tags: &[S]) -> Vec<String> {
+        let mut normalized: Vec<String> = Vec::with_capacity(tags.len());
+
+        for tag in tags {
+            let tag = self.normalize(tag.as_ref());
+
+            if self.trim && tag.is_empty() {
+                continue;
+            }
+            if self.dedup && normalized.contains(&tag) {
+                continue;
+            }
+
+            normalized.push(tag);
+        }
+
+        normalized
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_tags() {
+        let tags = ["Fox", " fox ", "cafe\u{301}", "Caf\u{e9}", "", "Winter"];
+
+        assert_eq!(
+            TagNormalization::default().apply(&tags),
+            vec!["fox", "caf\u{e9}", "winter"]
+        );
+
+        let keep_case = TagNormalization {
+            lowercase: false,
+            dedup: false,
+            ..Default::default()
+        };
+        assert_eq!(
+            keep_case.apply(&tags),
+            vec!["Fox", "fox", "caf\u{e9}", "Caf\u{e9}", "Winter"]
+        );
+    }
+}
@@ -0,0 +1,249 @@
+//! Detecting which of FurAffinity's templates a page was rendered with.
+//!
+//! Users choose between the modern and classic templates in their account
+//! settings, so pages loaded with different cookies can have entirely
+//! different markup.
+
+use crate::anomaly::Anomaly;
+use crate::{join_text_nodes, parse_date, Error, ParseFields, Rating, Submission, SubmissionId};
+use lazy_static::lazy_static;
+use scraper::Selector;
+
+lazy_static! {
+    static ref TITLE: Selector = Selector::parse("#page-submission td.cat b").unwrap();
+    static ref ARTIST: Selector =
+        Selector::parse(r#"#page-submission td.cat a[href^="/user/"]"#).unwrap();
+    static ref STATS: Selector = Selector::parse("#page-submission .stats-container").unwrap();
+    static ref POSTED_AT: Selector = Selector::parse(".popup_date").unwrap();
+    static ref RATING: Selector = Selector::parse(r#"img[alt$=" rating"]"#).unwrap();
+    static ref LABEL: Selector = Selector::parse("b").unwrap();
+    static ref TAGS: Selector = Selector::parse("#keywords a").unwrap();
+    static ref DESCRIPTION: Selector =
+        Selector::parse("#page-submission .maintable td.alt1:not(.stats-container)").unwrap();
+}
+
+/// A template FurAffinity renders pages with.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub enum Template {
+    /// The current template, called beta in FurAffinity's paths.
+    #[default]
+    Modern,
+    Classic,
+}
+
+impl Template {
+    /// Detect the template from the paths of the theme's files linked in a
+    /// page, assuming the modern template if there are none.
+    pub fn detect(page: &str) -> Self {
+        if page.contains("/themes/classic/") {
+            Template::Classic
+        } else {
+            Template::Modern
+        }
+    }
+}
+
+/// Parse a submission page rendered with the classic template.
+pub(crate) fn parse_classic_submission(
+    id: SubmissionId,
+    document: &scraper::Html,
+    fields: ParseFields,
+    anomalies: &mut Vec<Anomaly>,
+) -> Result<Submission, Error> {
+    let title = match document.select(&TITLE).next() {
+        Some(title) => join_text_nodes(title),
+        None => return Err(Error::parse("title")),
+    };
+
+    let (artist, artist_info) = match document.select(&ARTIST).next() {
+        Some(artist) => {
+            let name = join_text_nodes(artist);
+            let info = crate::username::Artist::parse(&name, artist.value().attr("href"));
+
+            (name, info)
+        }
+        None => return Err(Error::parse("artist")),
+    };
+
+    let stats = document
+        .select(&STATS)
+        .next()
+        .ok_or_else(|| Error::parse("submission information"))?;
+
+    let rating = match stats.select(&RATING).next() {
+        Some(rating) => rating
+            .value()
+            .attr("alt")
+            .and_then(|alt| alt.split_whitespace().next())
+            .and_then(Rating::parse)
+            .ok_or_else(|| Error::parse("rating"))?,
+        None => return Err(Error::parse("submission rating")),
+    };
+
+    let posted_at = match stats.select(&POSTED_AT).next() {
+        Some(posted_at) => posted_at
+            .value()
+            .attr("title")
+            .ok_or_else(|| Error::parse("posted at"))?
+            .to_string(),
+        None => return Err(Error::parse("posted at")),
+    };
+
+    // Categories are text after their labels, like `<b>Category:</b> Story`.
+    let ai_generated = stats
+        .select(&LABEL)
+        .filter(|label| matches!(join_text_nodes(*label).as_str(), "Category:" | "Theme:"))
+        .filter_map(|label| {
+            label
+                .next_sibling()?
+                .value()
+                .as_text()
+                .map(|text| text.to_string())
+        })
+        .any(|category| crate::AI_LABEL.is_match(&category));
+
+    let mut counts = crate::SubmissionStats::default();
+    let mut info = crate::info::SubmissionInfo::default();
+    for label in stats.select(&LABEL) {
+        let value = match label.next_sibling().and_then(|text| text.value().as_text()) {
+            Some(value) => value,
+            None => continue,
+        };
+
+        let label = join_text_nodes(label);
+        let field = match label.as_str() {
+            "Views:" => &mut counts.views,
+            "Favorites:" => &mut counts.favorites,
+            "Comments:" => &mut counts.comments,
+            _ => {
+                info.set(&label, value);
+                continue;
+            }
+        };
+        *field = crate::SubmissionStats::parse_count(value)?;
+    }
+
+    let tags: Vec<String> = if fields.contains(ParseFields::TAGS) {
+        document.select(&TAGS).map(join_text_nodes).collect()
+    } else {
+        Vec::new()
+    };
+
+    let description = if fields.contains(ParseFields::DESCRIPTION) {
+        match document.select(&DESCRIPTION).next() {
+            Some(description) => description.inner_html(),
+            None => return Err(Error::parse("description")),
+        }
+    } else {
+        String::new()
+    };
+
+    let (content, url_ext, filename) = crate::parse_content(document, anomalies)?;
+
+    let avatar_url = crate::url::parse_avatar_url_document(document, &artist_info.slug);
+
+    let mut builder = crate::SubmissionBuilder::new(id, content);
+    if let Some(avatar_url) = avatar_url {
+        builder = builder.with_avatar_url(avatar_url);
+    }
+
+    let sub = builder
+        .with_title(title)
+        .with_artist(artist)
+        .with_artist_info(artist_info)
+        .with_rating(rating)
+        .with_ai_generated(ai_generated)
+        .with_stats(counts)
+        .with_info(info)
+        .with_posted_at(parse_date(&posted_at)?)
+        .with_tags(tags)
+        .with_description(description)
+        .build();
+
+    Ok(Submission {
+        ext: url_ext,
+        filename,
+        ..sub
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{CLASSIC, SUBMISSION};
+
+    #[test]
+    fn test_detect() {
+        assert_eq!(Template::detect(SUBMISSION), Template::Modern);
+        assert_eq!(Template::detect(CLASSIC), Template::Classic);
+    }
+
+    #[test]
+    fn test_parse_classic_submission() {
+        let modern = crate::parse_submission(SubmissionId(31209021), SUBMISSION)
+            .unwrap()
+            .unwrap();
+        let classic = crate::parse_submission(SubmissionId(31209021), CLASSIC)
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(classic.title, modern.title);
+        assert_eq!(classic.artist, modern.artist);
+        assert_eq!(classic.content, modern.content);
+        assert_eq!(classic.ext, modern.ext);
+        assert_eq!(classic.rating, modern.rating);
+        assert_eq!(classic.posted_at, modern.posted_at);
+        assert_eq!(classic.stats, modern.stats);
+        assert_eq!(classic.info.category, modern.info.category);
+        assert_eq!(classic.info.kind, modern.info.kind);
+        assert_eq!(classic.info.species, modern.info.species);
+        assert_eq!(classic.info.gender, modern.info.gender);
+        assert_eq!(classic.tags, modern.tags);
+        assert_eq!(classic.description.trim(), "Fox eating bilberries.");
+        assert!(!classic.ai_generated);
+    }
+
+    #[cfg(feature = "client")]
+    #[tokio::test]
+    async fn test_submission_page_template() {
+        let server = crate::test_utils::MockServer::start().await.unwrap();
+        server.route("/view/31209021", 200, CLASSIC);
+
+        let page = server
+            .client()
+            .get_submission_with(SubmissionId(31209021), &Default::default())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(page.template, Template::Classic);
+        assert_eq!(page.submission.title, "Bilberry fox");
+
+        // Classic pages without the theme's files linked can't be detected.
+        server.route(
+            "/view/31209021",
+            200,
+            CLASSIC.replace("/themes/classic/", "/themes/"),
+        );
+        let fa = server.client();
+        assert!(fa.get_submission(SubmissionId(31209021)).await.is_err());
+
+        let fa = fa.with_template(Template::Classic);
+        let sub = fa
+            .get_submission(SubmissionId(31209021))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(sub.title, "Bilberry fox");
+
+        server.route("/view/31209021", 200, SUBMISSION);
+        assert!(fa.get_submission(SubmissionId(31209021)).await.is_err());
+        let fa = server.client().with_template(Template::Modern);
+        assert!(fa
+            .get_submission(SubmissionId(31209021))
+            .await
+            .unwrap()
+            .is_some());
+    }
+}
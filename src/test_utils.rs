@@ -0,0 +1,403 @@
+//! Saved FurAffinity pages and a mock server for testing without making
+//! requests to FurAffinity.
+//!
+//! Pages are trimmed to the markup the parsers use, with the theme and
+//! structure of the real pages.
+
+#[cfg(feature = "client")]
+pub use self::mock::MockServer;
+
+/// Submission 31209021, an image with comments.
+pub const SUBMISSION: &str = include_str!("../fixtures/submission.html");
+/// The error shown for a submission that was deleted or never existed.
+pub const DELETED: &str = include_str!("../fixtures/deleted.html");
+/// The notice shown to guests for mature or adult submissions.
+pub const AGE_GATED: &str = include_str!("../fixtures/age_gated.html");
+/// Submission 31209021 in the classic theme.
+pub const CLASSIC: &str = include_str!("../fixtures/classic.html");
+/// Submission 41234567, a story.
+pub const STORY: &str = include_str!("../fixtures/story.html");
+/// Submission 41234568, music.
+pub const MUSIC: &str = include_str!("../fixtures/music.html");
+/// The front page, with 41234568 as the latest submission.
+pub const FRONT_PAGE: &str = include_str!("../fixtures/front_page.html");
+/// The first page of Example's gallery.
+pub const GALLERY: &str = include_str!("../fixtures/gallery.html");
+/// The profile of deadrussiansoul.
+pub const PROFILE: &str = include_str!("../fixtures/profile.html");
+/// Journal 10123456 by deadrussiansoul, with a comment.
+pub const JOURNAL: &str = include_str!("../fixtures/journal.html");
+
+#[cfg(feature = "client")]
+mod mock {
+    use super::*;
+    use crate::FurAffinity;
+    use std::collections::HashMap;
+    use std::sync::{Arc, Mutex};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    type Routes = HashMap<String, (u16, Vec<(String, String)>, String)>;
+    type Requests = Vec<(String, HashMap<String, String>, String)>;
+    type Delays = HashMap<String, std::time::Duration>;
+
+    /// An HTTP server on localhost responding to paths with fixed pages.
+    ///
+    /// Paths without a page get an empty 404 response.
+    pub struct MockServer {
+        addr: std::net::SocketAddr,
+        routes: Arc<Mutex<Routes>>,
+        requests: Arc<Mutex<Requests>>,
+        delays: Arc<Mutex<Delays>>,
+        /// Requests being responded to, and the most there have been at once.
+        in_flight: Arc<Mutex<(usize, usize)>>,
+        task: tokio::task::JoinHandle<()>,
+    }
+
+    impl MockServer {
+        /// Start a server with no pages.
+        pub async fn start() -> std::io::Result<Self> {
+            let listener = TcpListener::bind("127.0.0.1:0").await?;
+            let addr = listener.local_addr()?;
+
+            let routes: Arc<Mutex<Routes>> = Default::default();
+            let requests: Arc<Mutex<Requests>> = Default::default();
+            let delays: Arc<Mutex<Delays>> = Default::default();
+            let in_flight: Arc<Mutex<(usize, usize)>> = Default::default();
+
+            let task = tokio::spawn({
+                let routes = routes.clone();
+                let requests = requests.clone();
+                let delays = delays.clone();
+                let in_flight = in_flight.clone();
+
+                async move {
+                    while let Ok((stream, _addr)) = listener.accept().await {
+                        tokio::spawn(respond(
+                            stream,
+                            routes.clone(),
+                            requests.clone(),
+                            delays.clone(),
+                            in_flight.clone(),
+                        ));
+                    }
+                }
+            });
+
+            Ok(Self {
+                addr,
+                routes,
+                requests,
+                delays,
+                in_flight,
+                task,
+            })
+        }
+
+        /// Start a server with every fixture at the path FurAffinity serves it
+        /// from.
+        pub async fn with_fixtures() -> std::io::Result<Self> {
+            let server = Self::start().await?;
+
+            server.route("/", 200, FRONT_PAGE);
+            server.route("/view/31209021", 200, SUBMISSION);
+            server.route("/view/34426892", 200, DELETED);
+            server.route("/view/34999322", 200, AGE_GATED);
+            server.route("/view/41234567", 200, STORY);
+            server.route("/view/41234568", 200, MUSIC);
+            server.route("/gallery/example/1/", 200, GALLERY);
+            server.route("/user/deadrussiansoul/", 200, PROFILE);
+            server.route("/journal/10123456/", 200, JOURNAL);
+
+            Ok(server)
+        }
+
+        /// Respond to a path with a status and body.
+        pub fn route<P, B>(&self, path: P, status: u16, body: B)
+        where
+            P: Into<String>,
+            B: Into<String>,
+        {
+            self.route_with_headers(path, status, &[], body);
+        }
+
+        /// Respond to a path with a status, extra headers, and body.
+        pub fn route_with_headers<P, B>(
+            &self,
+            path: P,
+            status: u16,
+            headers: &[(&str, &str)],
+            body: B,
+        ) where
+            P: Into<String>,
+            B: Into<String>,
+        {
+            let headers = headers
+                .iter()
+                .map(|(name, value)| (name.to_string(), value.to_string()))
+                .collect();
+
+            self.routes
+                .lock()
+                .unwrap()
+                .insert(path.into(), (status, headers, body.into()));
+        }
+
+        /// Wait before responding to a path, such as to finish requests in a
+        /// different order than they were sent.
+        pub fn delay<P: Into<String>>(&self, path: P, delay: std::time::Duration) {
+            self.delays.lock().unwrap().insert(path.into(), delay);
+        }
+
+        /// The most requests that were being responded to at once.
+        pub fn max_in_flight(&self) -> usize {
+            self.in_flight.lock().unwrap().1
+        }
+
+        /// Base URL of the server.
+        pub fn url(&self) -> String {
+            format!("http://{}", self.addr)
+        }
+
+        /// A client that loads pages from this server.
+        pub fn client(&self) -> FurAffinity {
+            FurAffinity::new("", "", "furaffinity-rs test", None).with_base_url(self.url())
+        }
+
+        /// Paths that have been requested, in order.
+        pub fn requests(&self) -> Vec<String> {
+            self.requests
+                .lock()
+                .unwrap()
+                .iter()
+                .map(|(path, _headers, _body)| path.clone())
+                .collect()
+        }
+
+        /// Headers of each request, in order, with lowercase names.
+        pub fn request_headers(&self) -> Vec<HashMap<String, String>> {
+            self.requests
+                .lock()
+                .unwrap()
+                .iter()
+                .map(|(_path, headers, _body)| headers.clone())
+                .collect()
+        }
+
+        /// Bodies of each request, in order.
+        pub fn request_bodies(&self) -> Vec<String> {
+            self.requests
+                .lock()
+                .unwrap()
+                .iter()
+                .map(|(_path, _headers, body)| body.clone())
+                .collect()
+        }
+    }
+
+    impl Drop for MockServer {
+        fn drop(&mut self) {
+            self.task.abort();
+        }
+    }
+
+    async fn respond(
+        mut stream: tokio::net::TcpStream,
+        routes: Arc<Mutex<Routes>>,
+        requests: Arc<Mutex<Requests>>,
+        delays: Arc<Mutex<Delays>>,
+        in_flight: Arc<Mutex<(usize, usize)>>,
+    ) -> std::io::Result<()> {
+        let mut request = Vec::new();
+        let mut buf = [0; 1024];
+
+        while !request.windows(4).any(|window| window == b"\r\n\r\n") {
+            let len = stream.read(&mut buf).await?;
+            if len == 0 {
+                return Ok(());
+            }
+            request.extend_from_slice(&buf[..len]);
+        }
+
+        let header_len = request
+            .windows(4)
+            .position(|window| window == b"\r\n\r\n")
+            .unwrap_or_default()
+            + 4;
+        let head = String::from_utf8_lossy(&request[..header_len]).into_owned();
+        let mut lines = head.lines();
+        let path = lines
+            .next()
+            .and_then(|line| line.split_whitespace().nth(1))
+            .unwrap_or_default()
+            .to_string();
+        let headers: HashMap<String, String> = lines
+            .filter_map(|line| line.split_once(':'))
+            .map(|(name, value)| (name.trim().to_lowercase(), value.trim().to_string()))
+            .collect();
+
+        let content_length = headers
+            .get("content-length")
+            .and_then(|len| len.parse().ok())
+            .unwrap_or(0);
+        let mut request_body = request[header_len..].to_vec();
+        while request_body.len() < content_length {
+            let len = stream.read(&mut buf).await?;
+            if len == 0 {
+                break;
+            }
+            request_body.extend_from_slice(&buf[..len]);
+        }
+        let request_body = String::from_utf8_lossy(&request_body).into_owned();
+
+        let (status, extra_headers, body) =
+            routes
+                .lock()
+                .unwrap()
+                .get(&path)
+                .cloned()
+                .unwrap_or((404, Vec::new(), String::new()));
+        let delay = delays.lock().unwrap().get(&path).copied();
+        requests.lock().unwrap().push((path, headers, request_body));
+
+        let extra_headers: String = extra_headers
+            .iter()
+            .map(|(name, value)| format!("{}: {}\r\n", name, value))
+            .collect();
+        let response = format!(
+            "HTTP/1.1 {} Mock\r\nContent-Type: text/html; charset=UTF-8\r\nContent-Length: {}\r\nDate: Thu, 01 Apr 2021 14:00:30 GMT\r\n{}Connection: close\r\n\r\n{}",
+            status,
+            body.len(),
+            extra_headers,
+            body
+        );
+
+        {
+            let mut in_flight = in_flight.lock().unwrap();
+            in_flight.0 += 1;
+            in_flight.1 = in_flight.1.max(in_flight.0);
+        }
+        if let Some(delay) = delay {
+            tokio::time::sleep(delay).await;
+        }
+        let result = stream.write_all(response.as_bytes()).await;
+        in_flight.lock().unwrap().0 -= 1;
+        result?;
+
+        stream.shutdown().await
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use crate::gallery::PreviewStreamExt;
+        use crate::{Content, GetSubmissionOptions, NavLinks, ParseFields, Rating, SubmissionId};
+        use futures::StreamExt;
+
+        #[tokio::test]
+        async fn test_mock_server() {
+            let server = MockServer::with_fixtures().await.unwrap();
+            let fa = server.client();
+
+            let sub = fa
+                .get_submission(SubmissionId(31209021))
+                .await
+                .unwrap()
+                .unwrap();
+            assert_eq!(sub.title, "Bilberry fox");
+            assert_eq!(sub.artist, "deadrussiansoul");
+            assert_eq!(sub.content, Content::Image("https://d.furaffinity.net/art/deadrussiansoul/1555431774/1555431774.deadrussiansoul_Скан_20190411__7_.png".into()));
+            assert_eq!(sub.tags, vec!["fox", "bilberry"]);
+            assert_eq!(sub.rating, Rating::General);
+            assert_eq!(
+                sub.nav_links(),
+                Some(NavLinks {
+                    prev: None,
+                    first: Some(SubmissionId(31200000)),
+                    next: Some(SubmissionId(31300000)),
+                    first_label: Some("FIRST".into()),
+                    next_label: Some("NEXT >>>".into()),
+                    ..Default::default()
+                })
+            );
+
+            let (_sub, comments) = fa
+                .get_submission_with_comments(SubmissionId(31209021))
+                .await
+                .unwrap()
+                .unwrap();
+            assert_eq!(comments.len(), 3);
+
+            let options = GetSubmissionOptions::new()
+                .with_fields(ParseFields::NONE)
+                .with_comments(true)
+                .with_raw_html(true);
+            let page = fa
+                .get_submission_with(SubmissionId(31209021), &options)
+                .await
+                .unwrap()
+                .unwrap();
+            assert!(page.submission.tags.is_empty());
+            assert_eq!(page.comments.map(|comments| comments.len()), Some(3));
+            assert_eq!(page.html.as_deref(), Some(SUBMISSION));
+
+            let link = crate::comment::CommentLink::parse(
+                "https://www.furaffinity.net/view/31209021/#cid:152000001",
+            )
+            .unwrap();
+            let thread = fa.get_comment_thread(&link).await.unwrap().unwrap();
+            assert_eq!(thread.replies.len(), 1);
+
+            let journal = fa
+                .get_journal(crate::JournalId(10123456))
+                .await
+                .unwrap()
+                .unwrap();
+            assert_eq!(journal.comment_count, 1);
+
+            let profile = fa.get_user("Dead_Russian_Soul").await.unwrap().unwrap();
+            assert_eq!(profile.user_id, Some(3456789));
+
+            assert!(fa
+                .get_submission(SubmissionId(34426892))
+                .await
+                .unwrap()
+                .is_none());
+            assert!(fa
+                .get_submission(SubmissionId(34999322))
+                .await
+                .unwrap()
+                .is_none());
+            assert_eq!(
+                fa.get_submission_status(SubmissionId(34999322))
+                    .await
+                    .unwrap(),
+                crate::status::SubmissionStatus::AdultFilterBlocked
+            );
+            assert!(fa
+                .get_submission_status(SubmissionId(31209021))
+                .await
+                .unwrap()
+                .submission()
+                .is_some());
+
+            assert_eq!(fa.latest_id().await.unwrap(), SubmissionId(41234568));
+            assert_eq!(fa.online_stats().await.unwrap().other, 6888);
+
+            let (latest, online) = fa.latest_id_and_online().await.unwrap();
+            assert_eq!(latest, SubmissionId(41234568));
+            assert_eq!(online.total, 12345);
+            assert_eq!(fa.recent_submissions().await.unwrap().len(), 2);
+
+            let gallery = fa.get_gallery("example", 1).await.unwrap();
+            assert_eq!(gallery.len(), 2);
+            assert_eq!(gallery[1].rating, Rating::Mature);
+            assert!(fa.get_gallery("example", 2).await.unwrap().is_empty());
+
+            let sfw: Vec<_> = fa.gallery_stream("example").only_sfw().collect().await;
+            assert_eq!(sfw.len(), 1);
+
+            assert_eq!(server.requests()[0], "/view/31209021");
+        }
+    }
+}
@@ -0,0 +1,334 @@
+//! Sending requests, so they can go through something other than reqwest,
+//! such as a mock in tests or a client with extra middleware.
+
+use crate::Error;
+use futures::future::BoxFuture;
+use futures::stream::{BoxStream, StreamExt};
+use reqwest::header::HeaderMap;
+use reqwest::StatusCode;
+use std::time::Duration;
+
+/// Method of a request.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Method {
+    Get,
+    Post,
+}
+
+/// A request for a page or file, with every header already set.
+#[derive(Clone, Debug)]
+pub struct Request {
+    pub method: Method,
+    pub url: String,
+    pub headers: HeaderMap,
+    /// Fields to send as a urlencoded form.
+    pub form: Option<Vec<(String, String)>>,
+    /// If redirects should be followed. Logging in needs the cookies set by
+    /// the response before the redirect.
+    pub follow_redirects: bool,
+    /// How long the request may take, including reading the body.
+    pub timeout: Option<Duration>,
+}
+
+impl Request {
+    pub fn get<T: Into<String>>(url: T) -> Self {
+        Self {
+            method: Method::Get,
+            url: url.into(),
+            headers: Default::default(),
+            form: None,
+            follow_redirects: true,
+            timeout: None,
+        }
+    }
+
+    pub fn post<T: Into<String>>(url: T, form: Vec<(String, String)>) -> Self {
+        Self {
+            method: Method::Post,
+            form: Some(form),
+            ..Self::get(url)
+        }
+    }
+}
+
+/// A response, with a body that is read as it arrives.
+pub struct Response {
+    status: StatusCode,
+    headers: HeaderMap,
+    url: Option<String>,
+    /// Session from the client's session pool the request was sent with.
+    pub(crate) session: Option<usize>,
+    body: BoxStream<'static, Result<Vec<u8>, Error>>,
+}
+
+impl std::fmt::Debug for Response {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Response")
+            .field("url", &self.url)
+            .field("status", &self.status)
+            .field("headers", &self.headers)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Response {
+    /// Create a response with all of its body available.
+    pub fn new<B: Into<Vec<u8>>>(status: StatusCode, headers: HeaderMap, body: B) -> Self {
+        let body = body.into();
+
+        Self::from_stream(
+            status,
+            headers,
+            futures::stream::once(async move { Ok(body) }),
+        )
+    }
+
+    /// Create a response with a body that arrives in chunks.
+    pub fn from_stream<S>(status: StatusCode, headers: HeaderMap, body: S) -> Self
+    where
+        S: futures::Stream<Item = Result<Vec<u8>, Error>> + Send + 'static,
+    {
+        Self {
+            status,
+            headers,
+            url: None,
+            session: None,
+            body: body.boxed(),
+        }
+    }
+
+    /// Set the URL the response came from, after following redirects.
+    pub fn with_url<T: Into<String>>(mut self, url: T) -> Self {
+        self.url = Some(url.into());
+        self
+    }
+
+    /// URL the response came from, if the transport set it.
+    pub fn url(&self) -> Option<&str> {
+        self.url.as_deref()
+    }
+
+    /// Call a function with each chunk of the body as it is read.
+    pub(crate) fn inspect_body<F>(mut self, mut f: F) -> Self
+    where
+        F: FnMut(&[u8]) + Send + 'static,
+    {
+        self.body = self
+            .body
+            .inspect(move |chunk| {
+                if let Ok(chunk) = chunk {
+                    f(chunk);
+                }
+            })
+            .boxed();
+        self
+    }
+
+    pub fn status(&self) -> StatusCode {
+        self.status
+    }
+
+    pub fn headers(&self) -> &HeaderMap {
+        &self.headers
+    }
+
+    /// The next chunk of the body, or `None` once it has all been read.
+    pub async fn chunk(&mut self) -> Result<Option<Vec<u8>>, Error> {
+        loop {
+            match self.body.next().await.transpose()? {
+                Some(chunk) if chunk.is_empty() => continue,
+                chunk => return Ok(chunk),
+            }
+        }
+    }
+
+    /// Read the rest of the body.
+    pub async fn bytes(mut self) -> Result<Vec<u8>, Error> {
+        let mut data = Vec::new();
+        while let Some(chunk) = self.chunk().await? {
+            data.extend_from_slice(&chunk);
+        }
+
+        Ok(data)
+    }
+
+    /// Read the rest of the body as text, replacing invalid UTF-8.
+    pub async fn text(self) -> Result<String, Error> {
+        let data = self.bytes().await?;
+
+        Ok(String::from_utf8_lossy(&data).into_owned())
+    }
+}
+
+/// Something that can send requests.
+pub trait HttpTransport: Send + Sync {
+    /// Send a request, returning the response once its headers arrive.
+    fn send(&self, request: Request) -> BoxFuture<'_, Result<Response, Error>>;
+}
+
+/// Sends requests with reqwest.
+#[derive(Clone, Debug)]
+pub struct ReqwestTransport {
+    client: reqwest::Client,
+    /// Used for requests that shouldn't follow redirects, as the policy is
+    /// set for an entire client.
+    no_redirect: reqwest::Client,
+}
+
+impl ReqwestTransport {
+    /// Send requests with an existing client. Requests that don't follow
+    /// redirects use a client with the default options.
+    pub fn new(client: reqwest::Client) -> Self {
+        let no_redirect = reqwest::Client::builder()
+            .redirect(reqwest::redirect::Policy::none())
+            .build()
+            .unwrap_or_default();
+
+        Self {
+            client,
+            no_redirect,
+        }
+    }
+
+    /// Create clients with the same options, such as a proxy, for every
+    /// request.
+    pub fn with_options<F>(configure: F) -> Result<Self, Error>
+    where
+        F: Fn(reqwest::ClientBuilder) -> reqwest::ClientBuilder,
+    {
+        let client = configure(reqwest::Client::builder()).build()?;
+        let no_redirect = configure(reqwest::Client::builder())
+            .redirect(reqwest::redirect::Policy::none())
+            .build()?;
+
+        Ok(Self {
+            client,
+            no_redirect,
+        })
+    }
+}
+
+impl Default for ReqwestTransport {
+    fn default() -> Self {
+        Self::new(Default::default())
+    }
+}
+
+impl HttpTransport for ReqwestTransport {
+    fn send(&self, request: Request) -> BoxFuture<'_, Result<Response, Error>> {
+        let client = if request.follow_redirects {
+            &self.client
+        } else {
+            &self.no_redirect
+        };
+
+        let builder = match request.method {
+            Method::Get => client.get(&request.url),
+            Method::Post => client.post(&request.url),
+        };
+        let builder = match &request.form {
+            Some(form) => builder.form(form),
+            None => builder,
+        };
+        let builder = match request.timeout {
+            Some(timeout) => builder.timeout(timeout),
+            None => builder,
+        };
+        let builder = builder.headers(request.headers);
+
+        Box::pin(async move {
+            let resp = builder.send().await?;
+            let status = resp.status();
+            let headers = resp.headers().clone();
+            let url = resp.url().to_string();
+
+            let body = futures::stream::unfold(resp, |mut resp| async move {
+                match resp.chunk().await {
+                    Ok(Some(chunk)) => Some((Ok(chunk.to_vec()), resp)),
+                    Ok(None) => None,
+                    Err(err) => Some((Err(err.into()), resp)),
+                }
+            });
+
+            Ok(Response::from_stream(status, headers, body).with_url(url))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::SUBMISSION;
+    use crate::{FurAffinity, SubmissionId};
+    use std::sync::{Arc, Mutex};
+
+    /// Answers every request with a submission page, remembering requests.
+    #[derive(Default)]
+    struct MockTransport {
+        requests: Mutex<Vec<Request>>,
+    }
+
+    impl HttpTransport for MockTransport {
+        fn send(&self, request: Request) -> BoxFuture<'_, Result<Response, Error>> {
+            self.requests.lock().unwrap().push(request);
+
+            Box::pin(async {
+                Ok(Response::new(
+                    StatusCode::OK,
+                    Default::default(),
+                    SUBMISSION,
+                ))
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_transport() {
+        let transport = Arc::new(MockTransport::default());
+        let fa = FurAffinity::new("a", "b", "furaffinity-rs test", None)
+            .with_transport(transport.clone());
+
+        let sub = fa
+            .get_submission(SubmissionId(31209021))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(sub.title, "Bilberry fox");
+
+        let requests = transport.requests.lock().unwrap();
+        assert_eq!(requests.len(), 1);
+        assert_eq!(requests[0].method, Method::Get);
+        assert_eq!(requests[0].url, "https://www.furaffinity.net/view/31209021");
+        assert_eq!(
+            requests[0].headers[reqwest::header::USER_AGENT],
+            "furaffinity-rs test"
+        );
+        let mut cookies: Vec<_> = requests[0].headers[reqwest::header::COOKIE]
+            .to_str()
+            .unwrap()
+            .split(';')
+            .collect();
+        cookies.sort_unstable();
+        assert_eq!(cookies, vec!["a=a", "b=b"]);
+    }
+
+    #[tokio::test]
+    async fn test_timeouts() {
+        let transport = Arc::new(MockTransport::default());
+        let fa = FurAffinity::new("", "", "furaffinity-rs test", None)
+            .with_transport(transport.clone())
+            .with_page_timeout(Duration::from_secs(10))
+            .with_download_timeout(Duration::from_secs(300));
+
+        let sub = fa
+            .get_submission(SubmissionId(31209021))
+            .await
+            .unwrap()
+            .unwrap();
+        fa.calc_image_hash(sub).await.unwrap();
+
+        let requests = transport.requests.lock().unwrap();
+        assert_eq!(requests[0].timeout, Some(Duration::from_secs(10)));
+        assert_eq!(requests[1].timeout, Some(Duration::from_secs(300)));
+    }
+}
@@ -0,0 +1,410 @@
+//! Recognizing links to FurAffinity pages and files.
+
+use crate::{JournalId, SubmissionId};
+use lazy_static::lazy_static;
+use scraper::Selector;
+
+lazy_static! {
+    static ref IMAGE: Selector = Selector::parse("img").unwrap();
+}
+
+/// Avatar shown for users who have not uploaded one.
+pub const DEFAULT_AVATAR: &str = "https://a.furaffinity.net/default.gif";
+
+/// URL of a user's avatar, built without loading their profile.
+///
+/// FurAffinity includes when the avatar was last changed in the URLs it
+/// links to, and caches may serve an old avatar for URLs without it. Use
+/// [`FurAffinity::get_avatar_url`](crate::FurAffinity::get_avatar_url) to
+/// get the URL with the timestamp from the user's profile.
+pub fn avatar_url(username: &str) -> String {
+    FaUrl::Avatar {
+        user: crate::username::slug(username),
+        timestamp: None,
+    }
+    .canonical()
+}
+
+/// URL of a submission's thumbnail at a given size, such as 600 pixels.
+///
+/// The timestamp is when the thumbnail was generated, which is in the
+/// thumbnail URLs FurAffinity links to. It is usually when the file was
+/// uploaded, but can differ for stories, music, and replaced files.
+pub fn thumbnail_url(id: SubmissionId, size: u32, timestamp: i64) -> String {
+    FaUrl::Thumbnail {
+        id,
+        size,
+        timestamp,
+    }
+    .canonical()
+}
+
+/// Parse the URL of a user's current avatar from their profile, or any
+/// other page showing it. Returns `None` if it is not on the page.
+pub fn parse_avatar_url(page: &str, username: &str) -> Option<String> {
+    let username = crate::username::slug(username);
+    let document = scraper::Html::parse_document(page);
+
+    parse_avatar_url_document(&document, &username)
+}
+
+pub(crate) fn parse_avatar_url_document(document: &scraper::Html, slug: &str) -> Option<String> {
+    document
+        .select(&IMAGE)
+        .find_map(|img| avatar_src(img.value().attr("src")?, slug))
+}
+
+/// The canonical URL of an image's source, if it is the avatar of the user
+/// with `slug`.
+pub(crate) fn avatar_src(src: &str, slug: &str) -> Option<String> {
+    FaUrl::parse(src)
+        .filter(|url| matches!(url, FaUrl::Avatar { user, .. } if user == slug))
+        .map(|url| url.canonical())
+}
+
+/// A page or file on FurAffinity, parsed from a link.
+///
+/// Usernames are slugs, as they appear in FurAffinity's URLs.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub enum FaUrl {
+    /// A submission, from `/view/` or `/full/`.
+    View(SubmissionId),
+    /// A submission's page showing only the full size file. Links to it are
+    /// parsed as [`FaUrl::View`].
+    Full(SubmissionId),
+    /// A user's profile.
+    User(String),
+    /// A page of a user's gallery, starting at 1.
+    Gallery { user: String, page: u32 },
+    /// A page of a user's scraps, starting at 1.
+    Scraps { user: String, page: u32 },
+    /// A user's favorites.
+    Favorites(String),
+    /// A page of a user's journals, starting at 1.
+    Journals { user: String, page: u32 },
+    /// A journal.
+    Journal(JournalId),
+    /// A submission's file on the CDN.
+    File {
+        artist: String,
+        /// Timestamp from the path, when the file was uploaded.
+        timestamp: i64,
+        /// Path after the timestamp.
+        filename: String,
+    },
+    /// A thumbnail of a submission on the CDN.
+    Thumbnail {
+        id: SubmissionId,
+        size: u32,
+        timestamp: i64,
+    },
+    /// A user's avatar on the CDN.
+    Avatar {
+        user: String,
+        /// When the avatar was changed, if included in the URL.
+        timestamp: Option<i64>,
+    },
+}
+
+impl FaUrl {
+    /// Parse a link, which may be missing its scheme. Returns `None` if it is
+    /// not a FurAffinity link or is not to a recognized page.
+    pub fn parse(link: &str) -> Option<Self> {
+        let link = link.trim();
+        let url = if link.contains("://") {
+            ::url::Url::parse(link)
+        } else {
+            ::url::Url::parse(&format!("https://{}", link.trim_start_matches('/')))
+        }
+        .ok()?;
+
+        let host = url.host_str()?.to_ascii_lowercase();
+        let mut segments = url
+            .path_segments()?
+            .filter(|segment| !segment.is_empty())
+            .map(percent_decode);
+
+        match host.as_str() {
+            "furaffinity.net" | "www.furaffinity.net" | "sfw.furaffinity.net" => {
+                let kind = segments.next()?;
+                let mut next = || segments.next();
+
+                match kind.as_str() {
+                    "view" | "full" => Some(FaUrl::View(next()?.parse().ok()?)),
+                    "journal" => Some(FaUrl::Journal(next()?.parse().ok()?)),
+                    "user" => Some(FaUrl::User(crate::username::slug(&next()?))),
+                    "favorites" => Some(FaUrl::Favorites(crate::username::slug(&next()?))),
+                    "gallery" | "scraps" | "journals" => {
+                        let user = crate::username::slug(&next()?);
+                        let page = match next() {
+                            Some(page) => page.parse().ok().filter(|page| *page > 0)?,
+                            None => 1,
+                        };
+
+                        Some(match kind.as_str() {
+                            "gallery" => FaUrl::Gallery { user, page },
+                            "scraps" => FaUrl::Scraps { user, page },
+                            _ => FaUrl::Journals { user, page },
+                        })
+                    }
+                    _ => None,
+                }
+            }
+            "d.furaffinity.net" | "d2.facdn.net" | "d.facdn.net" => {
+                if segments.next()? != "art" {
+                    return None;
+                }
+
+                let artist = crate::username::slug(&segments.next()?);
+                let rest: Vec<_> = segments.collect();
+                // Stories and music have a folder before the timestamp.
+                let position = rest
+                    .iter()
+                    .position(|segment| segment.parse::<i64>().is_ok())?;
+
+                let filename = rest.get(position + 1..)?.join("/");
+                if filename.is_empty() {
+                    return None;
+                }
+
+                Some(FaUrl::File {
+                    artist,
+                    timestamp: rest[position].parse().ok()?,
+                    filename,
+                })
+            }
+            "a.furaffinity.net" | "a.facdn.net" => {
+                let rest: Vec<_> = segments.collect();
+                let (timestamp, name) = match rest.as_slice() {
+                    [name] => (None, name),
+                    [timestamp, name] => (Some(timestamp.parse().ok()?), name),
+                    _ => return None,
+                };
+
+                Some(FaUrl::Avatar {
+                    user: crate::username::slug(name.strip_suffix(".gif")?),
+                    timestamp,
+                })
+            }
+            "t.furaffinity.net" | "t.facdn.net" => {
+                let name = segments.next()?;
+                let (id, rest) = name.split_once('@')?;
+                let (size, rest) = rest.split_once('-')?;
+                let timestamp = rest.split('.').next()?;
+
+                Some(FaUrl::Thumbnail {
+                    id: id.parse().ok()?,
+                    size: size.parse().ok()?,
+                    timestamp: timestamp.parse().ok()?,
+                })
+            }
+            _ => None,
+        }
+    }
+
+    /// The canonical URL for this page or file.
+    pub fn canonical(&self) -> String {
+        const SITE: &str = "https://www.furaffinity.net";
+
+        match self {
+            FaUrl::View(id) => format!("{}/view/{}/", SITE, id),
+            FaUrl::Full(id) => format!("{}/full/{}/", SITE, id),
+            FaUrl::User(user) => format!("{}/user/{}/", SITE, user),
+            FaUrl::Gallery { user, page } => format!("{}/gallery/{}/{}/", SITE, user, page),
+            FaUrl::Scraps { user, page } => format!("{}/scraps/{}/{}/", SITE, user, page),
+            FaUrl::Favorites(user) => format!("{}/favorites/{}/", SITE, user),
+            FaUrl::Journals { user, page } => format!("{}/journals/{}/{}/", SITE, user, page),
+            FaUrl::Journal(id) => format!("{}/journal/{}/", SITE, id),
+            FaUrl::File {
+                artist,
+                timestamp,
+                filename,
+            } => format!(
+                "https://d.furaffinity.net/art/{}/{}/{}",
+                artist, timestamp, filename
+            ),
+            FaUrl::Thumbnail {
+                id,
+                size,
+                timestamp,
+            } => format!(
+                "https://t.furaffinity.net/{}@{}-{}.jpg",
+                id, size, timestamp
+            ),
+            FaUrl::Avatar {
+                user,
+                timestamp: Some(timestamp),
+            } => format!("https://a.furaffinity.net/{}/{}.gif", timestamp, user),
+            FaUrl::Avatar {
+                user,
+                timestamp: None,
+            } => format!("https://a.furaffinity.net/{}.gif", user),
+        }
+    }
+
+    /// The submission this URL is for, if it is a submission or one of its
+    /// thumbnails.
+    pub fn submission_id(&self) -> Option<SubmissionId> {
+        match self {
+            FaUrl::View(id) | FaUrl::Full(id) | FaUrl::Thumbnail { id, .. } => Some(*id),
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Display for FaUrl {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.canonical())
+    }
+}
+
+fn percent_decode(segment: &str) -> String {
+    let bytes = segment.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes
+            .get(i + 1..i + 3)
+            .filter(|_| bytes[i] == b'%')
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+
+        match hex {
+            Some(byte) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            None => {
+                decoded.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let cases = [
+            ("https://www.furaffinity.net/view/31209021/", FaUrl::View(SubmissionId(31209021))),
+            ("furaffinity.net/full/31209021", FaUrl::View(SubmissionId(31209021))),
+            (
+                "http://sfw.furaffinity.net/user/DeadRussianSoul",
+                FaUrl::User("deadrussiansoul".into()),
+            ),
+            (
+                "https://www.furaffinity.net/gallery/example/",
+                FaUrl::Gallery {
+                    user: "example".into(),
+                    page: 1,
+                },
+            ),
+            (
+                "https://www.furaffinity.net/scraps/example/3/?",
+                FaUrl::Scraps {
+                    user: "example".into(),
+                    page: 3,
+                },
+            ),
+            (
+                "https://www.furaffinity.net/journal/10123456/#cid:1",
+                FaUrl::Journal(JournalId(10123456)),
+            ),
+            (
+                "https://d.furaffinity.net/art/example/stories/1617285600/1617285600.example_story.txt",
+                FaUrl::File {
+                    artist: "example".into(),
+                    timestamp: 1617285600,
+                    filename: "1617285600.example_story.txt".into(),
+                },
+            ),
+            (
+                "//t.furaffinity.net/41234567@200-1617285600.jpg",
+                FaUrl::Thumbnail {
+                    id: SubmissionId(41234567),
+                    size: 200,
+                    timestamp: 1617285600,
+                },
+            ),
+            (
+                "https://a.furaffinity.net/1555431774/deadrussiansoul.gif",
+                FaUrl::Avatar {
+                    user: "deadrussiansoul".into(),
+                    timestamp: Some(1555431774),
+                },
+            ),
+        ];
+
+        for (link, expected) in cases.iter() {
+            assert_eq!(FaUrl::parse(link).as_ref(), Some(expected), "{}", link);
+        }
+
+        assert_eq!(FaUrl::parse("https://example.com/view/1/"), None);
+        assert_eq!(FaUrl::parse("https://www.furaffinity.net/view/abc/"), None);
+        assert_eq!(
+            FaUrl::parse("https://www.furaffinity.net/gallery/example/0/"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_canonical() {
+        let url = FaUrl::parse("furaffinity.net/full/31209021").unwrap();
+        assert_eq!(
+            url.to_string(),
+            "https://www.furaffinity.net/view/31209021/"
+        );
+        assert_eq!(url.submission_id(), Some(SubmissionId(31209021)));
+
+        let full = FaUrl::Full(SubmissionId(31209021));
+        assert_eq!(
+            full.canonical(),
+            "https://www.furaffinity.net/full/31209021/"
+        );
+        assert_eq!(FaUrl::parse(&full.canonical()), Some(url));
+
+        assert_eq!(
+            avatar_url("Dead_Russian_Soul"),
+            "https://a.furaffinity.net/deadrussiansoul.gif"
+        );
+    }
+
+    #[cfg(feature = "client")]
+    #[tokio::test]
+    async fn test_get_avatar_url() {
+        let server = crate::test_utils::MockServer::start().await.unwrap();
+        server.route(
+            "/user/deadrussiansoul/",
+            200,
+            r#"<img src="//a.furaffinity.net/1600000000/someoneelse.gif">
+            <img class="user-nav-avatar" src="//a.furaffinity.net/1555431774/deadrussiansoul.gif">"#,
+        );
+
+        let fa = server.client();
+        assert_eq!(
+            fa.get_avatar_url("Dead_Russian_Soul")
+                .await
+                .unwrap()
+                .as_deref(),
+            Some("https://a.furaffinity.net/1555431774/deadrussiansoul.gif")
+        );
+        assert_eq!(fa.get_avatar_url("example").await.unwrap(), None);
+
+        let url = FaUrl::parse(
+            "https://d.furaffinity.net/art/example/1617285600/1617285600.example_%D0%A1.png",
+        )
+        .unwrap();
+        assert_eq!(
+            url.canonical(),
+            "https://d.furaffinity.net/art/example/1617285600/1617285600.example_С.png"
+        );
+    }
+}
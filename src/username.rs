@@ -0,0 +1,115 @@
+//! Converting between display names and the usernames in FurAffinity's URLs.
+//!
+//! FurAffinity shows names as users typed them, such as `Dead_Russian_Soul`,
+//! but its URLs use a slug that is lowercase with underscores removed, such
+//! as `deadrussiansoul`. Pages must be requested using the slug.
+
+/// Symbols FurAffinity shows before names to indicate account status.
+const STATUS_PREFIXES: &[char] = &['~', '!', '@', '∞'];
+
+/// Convert a display name, or a name that is already a slug, to the slug
+/// used in URLs.
+pub fn slug(name: &str) -> String {
+    name.trim()
+        .trim_start_matches(STATUS_PREFIXES)
+        .chars()
+        .filter(|c| *c != '_')
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// If two names refer to the same user.
+pub fn same_user(a: &str, b: &str) -> bool {
+    slug(a) == slug(b)
+}
+
+/// A user as they appear on a page, such as the artist of a submission.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Artist {
+    /// Username as it appears in URLs.
+    pub slug: String,
+    /// Name as the user typed it, without a status symbol.
+    pub display_name: String,
+    /// Symbol shown before the name for the account's status, such as `!`
+    /// for suspended accounts.
+    pub status_symbol: Option<char>,
+}
+
+impl Artist {
+    /// Parse a name as shown on a page, which may start with a status
+    /// symbol, along with the link to the user's page if there is one.
+    pub fn parse(name: &str, link: Option<&str>) -> Self {
+        let name = name.trim();
+        let status_symbol = name.chars().next().filter(|c| STATUS_PREFIXES.contains(c));
+        let display_name = name.trim_start_matches(STATUS_PREFIXES).trim().to_string();
+
+        let slug = link
+            .and_then(|link| {
+                link.split('/')
+                    .skip_while(|segment| *segment != "user")
+                    .nth(1)
+            })
+            .filter(|slug| !slug.is_empty())
+            .map(|slug| slug.to_lowercase())
+            .unwrap_or_else(|| self::slug(&display_name));
+
+        Self {
+            slug,
+            display_name,
+            status_symbol,
+        }
+    }
+
+    /// URL of the user's profile.
+    pub fn profile_url(&self) -> String {
+        crate::url::FaUrl::User(self.slug.clone()).canonical()
+    }
+}
+
+/// If a name, with any status symbol removed, only contains characters
+/// FurAffinity allows in usernames.
+pub fn is_valid(name: &str) -> bool {
+    let name = name.trim_start_matches(STATUS_PREFIXES);
+
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | '~'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_slug() {
+        assert_eq!(slug("Dead_Russian_Soul"), "deadrussiansoul");
+        assert_eq!(slug(" ~Berry_Picker"), "berrypicker");
+        assert_eq!(slug("mr.fox-1"), "mr.fox-1");
+        assert!(same_user("Berry_Picker", "berrypicker"));
+
+        assert!(is_valid("Dead_Russian_Soul"));
+        assert!(is_valid("!example"));
+        assert!(!is_valid("two words"));
+        assert!(!is_valid("../admin"));
+        assert!(!is_valid(""));
+    }
+
+    #[test]
+    fn test_artist() {
+        let artist = Artist::parse(" !Dead_Russian_Soul", Some("/user/deadrussiansoul/"));
+        assert_eq!(artist.slug, "deadrussiansoul");
+        assert_eq!(artist.display_name, "Dead_Russian_Soul");
+        assert_eq!(artist.status_symbol, Some('!'));
+        assert_eq!(
+            artist.profile_url(),
+            "https://www.furaffinity.net/user/deadrussiansoul/"
+        );
+
+        let artist = Artist::parse("Berry_Picker", None);
+        assert_eq!(artist.slug, "berrypicker");
+        assert_eq!(artist.status_symbol, None);
+    }
+}
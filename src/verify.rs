@@ -0,0 +1,160 @@
+//! Checking an archive against what is currently on FurAffinity.
+
+use crate::diff::missing_from;
+use crate::rate_limit::RateLimiter;
+use crate::{Error, FurAffinity, Submission, SubmissionId};
+use futures::{Stream, StreamExt};
+
+/// What an archive knows about a submission.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct ArchivedSubmission {
+    pub id: SubmissionId,
+    /// SHA-256 digest of the archived file. If set, the live file is
+    /// downloaded to compare against it.
+    pub sha256: Option<Vec<u8>>,
+    pub tags: Vec<String>,
+}
+
+impl From<&Submission> for ArchivedSubmission {
+    fn from(sub: &Submission) -> Self {
+        Self {
+            id: sub.id,
+            sha256: sub.file_sha256.clone(),
+            tags: sub.tags.clone(),
+        }
+    }
+}
+
+/// A way a live submission differs from the archive.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub enum Divergence {
+    /// The submission can no longer be viewed.
+    Deleted,
+    /// The file's digest no longer matches.
+    FileReplaced,
+    /// Tags were added or removed.
+    TagsChanged {
+        added: Vec<String>,
+        removed: Vec<String>,
+    },
+}
+
+/// The result of verifying one archived submission.
+#[derive(Debug)]
+pub struct Verification {
+    pub id: SubmissionId,
+    /// How the submission differs, or the error that prevented checking it.
+    pub result: Result<Vec<Divergence>, Error>,
+}
+
+impl Verification {
+    /// If the submission was checked and nothing differed.
+    pub fn is_unchanged(&self) -> bool {
+        matches!(&self.result, Ok(divergences) if divergences.is_empty())
+    }
+}
+
+/// Load each archived submission again, one at a time within the rate
+/// limit, and report how it differs from the archive.
+pub fn verify_archive<'a, I>(
+    fa: &'a FurAffinity,
+    archived: I,
+    limiter: RateLimiter,
+) -> impl Stream<Item = Verification> + 'a
+where
+    I: IntoIterator<Item = ArchivedSubmission>,
+    I::IntoIter: 'a,
+{
+    futures::stream::iter(archived).then(move |archived| {
+        let limiter = limiter.clone();
+
+        async move {
+            let id = archived.id;
+            let result = verify(fa, &limiter, archived).await;
+
+            Verification { id, result }
+        }
+    })
+}
+
+async fn verify(
+    fa: &FurAffinity,
+    limiter: &RateLimiter,
+    archived: ArchivedSubmission,
+) -> Result<Vec<Divergence>, Error> {
+    limiter.wait().await;
+    let live = match fa.get_submission(archived.id).await? {
+        Some(live) => live,
+        None => return Ok(compare(&archived, None)),
+    };
+
+    let live = if archived.sha256.is_some() {
+        limiter.wait().await;
+        fa.calc_image_hash(live).await?
+    } else {
+        live
+    };
+
+    Ok(compare(&archived, Some(&live)))
+}
+
+fn compare(archived: &ArchivedSubmission, live: Option<&Submission>) -> Vec<Divergence> {
+    let live = match live {
+        Some(live) => live,
+        None => return vec![Divergence::Deleted],
+    };
+
+    let mut divergences = Vec::new();
+
+    if let (Some(archived), Some(live)) = (&archived.sha256, &live.file_sha256) {
+        if archived != live {
+            divergences.push(Divergence::FileReplaced);
+        }
+    }
+
+    let added = missing_from(&live.tags, &archived.tags);
+    let removed = missing_from(&archived.tags, &live.tags);
+    if !added.is_empty() || !removed.is_empty() {
+        divergences.push(Divergence::TagsChanged { added, removed });
+    }
+
+    divergences
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::test_submission;
+
+    #[test]
+    fn test_compare() {
+        let live = Submission {
+            file_sha256: Some(vec![0xab; 32]),
+            ..test_submission("Sketch", 1617235200, &["fox", "sketch"])
+        };
+
+        let archived = ArchivedSubmission::from(&live);
+        assert!(compare(&archived, Some(&live)).is_empty());
+        assert_eq!(compare(&archived, None), vec![Divergence::Deleted]);
+
+        let archived = ArchivedSubmission {
+            sha256: Some(vec![0xcd; 32]),
+            tags: vec!["fox".into(), "wip".into()],
+            ..archived
+        };
+        assert_eq!(
+            compare(&archived, Some(&live)),
+            vec![
+                Divergence::FileReplaced,
+                Divergence::TagsChanged {
+                    added: vec!["sketch".into()],
+                    removed: vec!["wip".into()],
+                }
+            ]
+        );
+    }
+}
@@ -0,0 +1,268 @@
+//! Watching users for newly posted content.
+
+use crate::gallery::SubmissionPreview;
+use crate::journal::JournalPreview;
+use crate::{Error, FurAffinity, JournalId, SubmissionId};
+use futures::Stream;
+use std::collections::{HashSet, VecDeque};
+use std::time::Duration;
+use tokio::time::Instant;
+
+/// A submission that appeared in a watched user's gallery.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct NewSubmission {
+    pub username: String,
+    pub submission: SubmissionPreview,
+}
+
+/// A journal that was posted by a watched user.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct NewJournal {
+    pub username: String,
+    pub journal: JournalPreview,
+}
+
+/// Periodically checks the first page of users' galleries for submissions
+/// that have not been seen before.
+///
+/// Submissions already in a gallery the first time it is successfully
+/// checked are recorded without being yielded, unless previously seen IDs
+/// were provided with [`GalleryWatcher::with_seen`].
+pub struct GalleryWatcher<'a> {
+    fa: &'a FurAffinity,
+    usernames: Vec<String>,
+    interval: Duration,
+
+    seen: Seen<SubmissionId>,
+    pending: VecDeque<Result<NewSubmission, Error>>,
+    next_check: Option<Instant>,
+}
+
+impl<'a> GalleryWatcher<'a> {
+    /// Create a new watcher for the galleries of the given users.
+    pub fn new<I, S>(fa: &'a FurAffinity, usernames: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Self {
+            fa,
+            usernames: usernames.into_iter().map(Into::into).collect(),
+            interval: Duration::from_secs(600),
+            seen: Seen::default(),
+            pending: VecDeque::new(),
+            next_check: None,
+        }
+    }
+
+    /// How long to wait between checking all galleries. Defaults to ten
+    /// minutes.
+    pub fn with_interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// IDs of submissions that have already been seen, such as from before
+    /// a restart. Any other submissions found on the first check are
+    /// yielded.
+    pub fn with_seen<I: IntoIterator<Item = SubmissionId>>(mut self, ids: I) -> Self {
+        self.seen.extend(ids);
+        self
+    }
+
+    /// Stream of newly posted submissions, oldest first for each user.
+    /// Errors are from checking a gallery, and watching continues after
+    /// them.
+    pub fn submissions(self) -> impl Stream<Item = Result<NewSubmission, Error>> + 'a {
+        futures::stream::unfold(self, |mut watcher| async move {
+            loop {
+                if let Some(event) = watcher.pending.pop_front() {
+                    return Some((event, watcher));
+                }
+
+                watcher.check().await;
+            }
+        })
+    }
+
+    async fn check(&mut self) {
+        wait_for_check(&mut self.next_check, self.interval).await;
+
+        for username in &self.usernames {
+            let submissions = match self.fa.get_gallery(username, 1).await {
+                Ok(submissions) => submissions,
+                Err(err) => {
+                    self.pending.push_back(Err(err));
+                    continue;
+                }
+            };
+
+            let submissions = self.seen.filter_new(username, submissions, |sub| sub.id);
+            self.pending
+                .extend(submissions.into_iter().map(|submission| {
+                    Ok(NewSubmission {
+                        username: username.clone(),
+                        submission,
+                    })
+                }));
+        }
+    }
+}
+
+/// Periodically checks users' journals for journals that have not been
+/// seen before.
+///
+/// Journals that already exist the first time a user is successfully
+/// checked are recorded without being yielded, unless previously seen IDs
+/// were provided with [`JournalWatcher::with_seen`].
+pub struct JournalWatcher<'a> {
+    fa: &'a FurAffinity,
+    usernames: Vec<String>,
+    interval: Duration,
+
+    seen: Seen<JournalId>,
+    pending: VecDeque<Result<NewJournal, Error>>,
+    next_check: Option<Instant>,
+}
+
+impl<'a> JournalWatcher<'a> {
+    /// Create a new watcher for the journals of the given users.
+    pub fn new<I, S>(fa: &'a FurAffinity, usernames: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Self {
+            fa,
+            usernames: usernames.into_iter().map(Into::into).collect(),
+            interval: Duration::from_secs(600),
+            seen: Seen::default(),
+            pending: VecDeque::new(),
+            next_check: None,
+        }
+    }
+
+    /// How long to wait between checking all users. Defaults to ten
+    /// minutes.
+    pub fn with_interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// IDs of journals that have already been seen, such as from before a
+    /// restart. Any other journals found on the first check are yielded.
+    pub fn with_seen<I: IntoIterator<Item = JournalId>>(mut self, ids: I) -> Self {
+        self.seen.extend(ids);
+        self
+    }
+
+    /// Stream of newly posted journals, oldest first for each user. Errors
+    /// are from checking a user's journals, and watching continues after
+    /// them.
+    pub fn journals(self) -> impl Stream<Item = Result<NewJournal, Error>> + 'a {
+        futures::stream::unfold(self, |mut watcher| async move {
+            loop {
+                if let Some(event) = watcher.pending.pop_front() {
+                    return Some((event, watcher));
+                }
+
+                watcher.check().await;
+            }
+        })
+    }
+
+    async fn check(&mut self) {
+        wait_for_check(&mut self.next_check, self.interval).await;
+
+        for username in &self.usernames {
+            let journals = match self.fa.get_user_journals(username, 1).await {
+                Ok(journals) => journals,
+                Err(err) => {
+                    self.pending.push_back(Err(err));
+                    continue;
+                }
+            };
+
+            let journals = self
+                .seen
+                .filter_new(username, journals, |journal| journal.id);
+            self.pending.extend(journals.into_iter().map(|journal| {
+                Ok(NewJournal {
+                    username: username.clone(),
+                    journal,
+                })
+            }));
+        }
+    }
+}
+
+async fn wait_for_check(next_check: &mut Option<Instant>, interval: Duration) {
+    if let Some(next_check) = *next_check {
+        tokio::time::sleep_until(next_check).await;
+    }
+
+    *next_check = Some(Instant::now() + interval);
+}
+
+/// IDs that have been seen, and which users have been checked at least once.
+struct Seen<Id> {
+    ids: HashSet<Id>,
+    users: HashSet<String>,
+    yield_existing: bool,
+}
+
+impl<Id> Default for Seen<Id> {
+    fn default() -> Self {
+        Self {
+            ids: HashSet::new(),
+            users: HashSet::new(),
+            yield_existing: false,
+        }
+    }
+}
+
+impl<Id: Eq + std::hash::Hash> Seen<Id> {
+    fn extend<I: IntoIterator<Item = Id>>(&mut self, ids: I) {
+        self.ids.extend(ids);
+        self.yield_existing = true;
+    }
+
+    /// Record items listed for a user, newest first, returning the ones that
+    /// are new, oldest first.
+    fn filter_new<T, F>(&mut self, username: &str, items: Vec<T>, id: F) -> Vec<T>
+    where
+        F: Fn(&T) -> Id,
+    {
+        let primed = self.yield_existing || self.users.contains(username);
+        self.users.insert(username.to_string());
+
+        items
+            .into_iter()
+            .rev()
+            .filter(|item| self.ids.insert(id(item)) && primed)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_seen() {
+        let mut seen = Seen::default();
+
+        assert!(seen.filter_new("a", vec![2, 1], |id| *id).is_empty());
+        assert_eq!(seen.filter_new("a", vec![4, 3, 2, 1], |id| *id), vec![3, 4]);
+        assert!(seen.filter_new("b", vec![5], |id| *id).is_empty());
+
+        let mut seen = Seen::default();
+        seen.extend(vec![1]);
+
+        assert_eq!(seen.filter_new("a", vec![2, 1], |id| *id), vec![2]);
+    }
+}
@@ -0,0 +1,102 @@
+//! Users a user watches, and users watching them, from
+//! `/watchlist/by/{user}/` and `/watchlist/to/{user}/`.
+
+use crate::username::Artist;
+use crate::Error;
+use lazy_static::lazy_static;
+use scraper::Selector;
+
+lazy_static! {
+    static ref WATCH_LIST: Selector = Selector::parse(".watch-list, #watchlist").unwrap();
+    static ref USER: Selector =
+        Selector::parse(r#".watch-list-items a[href^="/user/"], td a[href^="/user/"]"#).unwrap();
+    static ref NAV_LINK: Selector =
+        Selector::parse(r#"form[action*="/watchlist/"], a[href*="/watchlist/"]"#).unwrap();
+    static ref PAGE_NUMBER: regex::Regex =
+        regex::Regex::new(r"/watchlist/(?:by|to)/[^/]+/(\d+)").unwrap();
+}
+
+/// A page of users from a watch list.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct WatchList {
+    pub users: Vec<Artist>,
+    /// Number of the next page, if the page links to one.
+    pub next_page: Option<u32>,
+}
+
+/// Parse a page of a watch list.
+pub fn parse_watchlist(page: &str) -> Result<WatchList, Error> {
+    let document = scraper::Html::parse_document(page);
+
+    let list = document
+        .select(&WATCH_LIST)
+        .next()
+        .ok_or_else(|| Error::parse("watch list"))?;
+
+    let users = list
+        .select(&USER)
+        .map(|link| Artist::parse(&crate::join_text_nodes(link), link.value().attr("href")))
+        .collect();
+
+    let next_page = document
+        .select(&NAV_LINK)
+        .filter(|link| {
+            crate::join_text_nodes(*link)
+                .to_lowercase()
+                .contains("next")
+        })
+        .filter_map(|link| {
+            let value = link.value();
+            let target = value.attr("action").or_else(|| value.attr("href"))?;
+
+            PAGE_NUMBER.captures(target)?[1].parse().ok()
+        })
+        .next();
+
+    Ok(WatchList { users, next_page })
+}
+
+#[cfg(all(test, feature = "client"))]
+mod tests {
+    use super::*;
+    use crate::test_utils::MockServer;
+
+    const WATCHLIST: &str = r#"<html><body>
+        <div id="standardpage"><section class="watch-list">
+            <h2>Watched by deadrussiansoul</h2>
+            <div class="watch-list-items"><a href="/user/berrypicker/">Berry_Picker</a></div>
+            <div class="watch-list-items"><a href="/user/example/">!Example</a></div>
+            <div class="floatright">
+                <form method="get" action="/watchlist/by/deadrussiansoul/2/"><button type="submit">Next 200</button></form>
+            </div>
+        </section></div>
+    </body></html>"#;
+
+    #[tokio::test]
+    async fn test_watchlist() {
+        let server = MockServer::start().await.unwrap();
+        server.route("/watchlist/by/deadrussiansoul/1/", 200, WATCHLIST);
+        server.route(
+            "/watchlist/to/deadrussiansoul/2/",
+            200,
+            WATCHLIST.replace("Next 200", "Back 200"),
+        );
+
+        let fa = server.client();
+
+        let watching = fa.get_watching("Dead_Russian_Soul", 1).await.unwrap();
+        assert_eq!(watching.users.len(), 2);
+        assert_eq!(watching.users[0].slug, "berrypicker");
+        assert_eq!(watching.users[1].display_name, "Example");
+        assert_eq!(watching.users[1].status_symbol, Some('!'));
+        assert_eq!(watching.next_page, Some(2));
+
+        let watchers = fa.get_watchers("deadrussiansoul", 2).await.unwrap();
+        assert_eq!(watchers.users.len(), 2);
+        assert_eq!(watchers.next_page, None);
+
+        assert!(parse_watchlist("<html></html>").is_err());
+    }
+}